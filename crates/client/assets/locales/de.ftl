@@ -0,0 +1,169 @@
+# German. Messages missing here fall back to English.
+
+-game-name = Moonlit
+
+app-title = { -game-name }
+
+loading-assets = Lade Ressourcen...
+loading-world = Erzeuge die Welt...
+
+menu-new-game = Neues Spiel
+menu-load-world = Welt laden
+menu-settings = Einstellungen
+menu-quit = Beenden
+
+pause-title = Pausiert
+pause-resume = Weiter
+pause-save-quit = Speichern & beenden
+
+death-title = Du bist gestorben
+death-respawn = Wiederbeleben
+
+worlds-title = Welten
+worlds-new-title = Neue Welt
+worlds-back = Zurück
+worlds-none = Noch keine Welten.
+worlds-play = Spielen
+worlds-details = Seed { $seed } · { $difficulty }{ $survival ->
+        [yes] { " " }· Überleben
+       *[no] {""}
+    } · erstellt { $created } · gespielt { $playtime }
+worlds-playtime = { $hours ->
+        [0] { $minutes ->
+            [one] { $minutes } Minute
+           *[other] { $minutes } Minuten
+        }
+       *[other] { $hours } Std. { $minutes } Min.
+    }
+worlds-health = Leben { $health }/{ $max }
+worlds-name = Name
+worlds-seed = Seed
+worlds-noise = Rauschen
+worlds-difficulty = Schwierigkeit
+worlds-survival-mode = Überleben
+worlds-survival-hint = Kälte und Hitze setzen dem Spieler zu
+worlds-create = Erstellen
+worlds-create-failed = Welt konnte nicht erstellt werden: { $error }
+worlds-bad-seed = Der Seed muss eine ganze Zahl sein
+
+settings-title = Einstellungen
+settings-graphics = Grafik
+settings-audio = Audio
+settings-controls = Steuerung
+settings-language = Sprache
+settings-window = Fenster
+settings-vsync = VSync
+settings-pixel-resolution = Pixelauflösung
+settings-render-distance-across = Sichtweite horizontal
+settings-render-distance-down = Sichtweite vertikal
+settings-crt-filter = Röhrenfilter
+settings-crt-curvature = Krümmung
+settings-crt-scanlines = Zeilen
+settings-crt-aberration = Farbsäume
+settings-master-volume = Gesamt
+settings-music-volume = Musik
+settings-effects-volume = Effekte
+settings-rumble = Vibration
+
+controls-action = Aktion
+controls-keyboard = Tastatur
+controls-controller = Controller
+controls-conflict = Teilt eine Belegung mit einer anderen Aktion
+controls-waiting = Taste drücken...
+controls-reset = Zurücksetzen
+control-move-up = Nach oben
+control-move-left = Nach links
+control-move-down = Nach unten
+control-move-right = Nach rechts
+control-sprint = Sprinten
+control-interact = Interagieren
+control-attack = Angreifen
+control-next-slot = Nächster Platz
+control-previous-slot = Voriger Platz
+control-screenshot = Bildschirmfoto
+
+crafting-title = Handwerk
+crafting-craft = Herstellen
+crafting-reload = Rezepte neu laden
+inventory-title = Inventar
+inventory-give = Geben
+
+chest-title = Truhe
+chest-hint = Klicke einen Stapel an, um ihn auf die andere Seite zu legen.
+chest-inventory = Inventar
+
+map-waypoint-name = Name des Wegpunkts
+map-waypoint-default = Wegpunkt { $number }
+map-hint = Rechtsklick setzt oder entfernt einen Wegpunkt. Ziehen verschiebt, Scrollen zoomt, M schließt.
+
+screenshot-saved = Bildschirmfoto gespeichert unter { $path }
+screenshot-failed = Bildschirmfoto konnte nicht gespeichert werden
+
+changelog-title = Neuigkeiten
+
+notification-saved = Gespeichert
+notification-picked-up = +{ $count } { $item }
+notification-quest-complete = Aufgabe erledigt: { $quest }
+
+objective-collect = Sammle { $item }: { $carried }/{ $count }
+objective-reach = Erreiche die markierte Stelle
+objective-talk-to = Sprich mit { $name }
+
+locked-needs-key = Braucht: { $item }
+locked-unlocked = Aufgeschlossen
+trade-needs = Braucht { $count } { $item }
+
+item-turf = Rasensode
+item-gravel = Kies
+item-stone = Stein
+item-snow = Schnee
+item-stick = Stock
+item-iron_ingot = Eisenbarren
+item-wooden_pickaxe = Holzspitzhacke
+item-stone_pickaxe = Steinspitzhacke
+item-iron_pickaxe = Eisenspitzhacke
+item-chest = Truhe
+item-seeds = Samen
+item-wheat = Weizen
+item-wooden_hoe = Holzhacke
+item-bow = Bogen
+item-arrow = Pfeil
+item-sling = Schleuder
+item-dungeon_key = Verliesschlüssel
+item-herbal_tonic = Kräutertrank
+item-torch = Fackel
+item-campfire = Lagerfeuer
+item-straw_cloak = Strohumhang
+item-straw_hat = Strohhut
+
+quest-first_harvest = Erste Ernte
+quest-wayfinder = Wegfinder
+talker-signpost = dem Wegweiser
+
+dialogue-signpost-welcome-speaker = Wegweiser
+dialogue-signpost-welcome = Willkommen, Reisender. Unter ihrem Blätterdach werden die Wälder dunkel, und dort lauern Schleime.
+dialogue-signpost-welcome-choice-0 = Wie bleibe ich sicher?
+dialogue-signpost-welcome-choice-1 = Wo finde ich Essen?
+dialogue-signpost-welcome-choice-2 = Auf Wiedersehen.
+dialogue-signpost-safety-speaker = Wegweiser
+dialogue-signpost-safety = Halte Abstand und lauf, wenn dein Herz zu pochen beginnt. Schleime geben die Jagd auf, wenn du weit genug wegkommst.
+dialogue-signpost-safety-choice-0 = Noch etwas?
+dialogue-signpost-safety-choice-1 = Danke.
+dialogue-signpost-food-speaker = Wegweiser
+dialogue-signpost-food = Bearbeite Gras mit einer Hacke und säe Samen auf das Ackerland. Weizen reift in wenigen Minuten.
+dialogue-signpost-food-choice-0 = Ich baue etwas Weizen an.
+dialogue-signpost-food-choice-1 = Noch etwas?
+dialogue-signpost-food-choice-2 = Danke.
+
+dialogue-shop_open-welcome-speaker = Ladenbesitzer
+dialogue-shop_open-welcome = Herein! Für alles in den Regalen nehme ich Weizen.
+dialogue-shop_open-welcome-choice-0 = 3 Samen für 1 Weizen.
+dialogue-shop_open-welcome-choice-1 = Ein Kräutertrank für 5 Weizen.
+dialogue-shop_open-welcome-choice-2 = Ich schaue nur.
+dialogue-shop_open-thanks-speaker = Ladenbesitzer
+dialogue-shop_open-thanks = Es war mir ein Vergnügen. Sonst noch etwas?
+dialogue-shop_open-thanks-choice-0 = Zeig mir noch einmal die Regale.
+dialogue-shop_open-thanks-choice-1 = Das ist alles.
+
+dialogue-shop_closed-closed-speaker = Ladenbesitzer
+dialogue-shop_closed-closed = Tut mir leid, wir haben geschlossen. Komm morgen früh wieder, wenn ich an der Theke stehe.
@@ -0,0 +1,115 @@
+# English, built into the game as the fallback for every other language. Item names and dialogue
+# are in items.ron and dialogue.ron.
+
+-game-name = Moonlit
+
+app-title = { -game-name }
+
+loading-assets = Loading assets...
+loading-world = Generating the world...
+
+menu-new-game = New Game
+menu-load-world = Load World
+menu-settings = Settings
+menu-quit = Quit
+
+pause-title = Paused
+pause-resume = Resume
+pause-save-quit = Save & Quit
+
+death-title = You died
+death-respawn = Respawn
+
+worlds-title = Worlds
+worlds-new-title = New world
+worlds-back = Back
+worlds-none = No worlds yet.
+worlds-play = Play
+worlds-details = Seed { $seed } · { $difficulty }{ $survival ->
+        [yes] { " " }· Survival
+       *[no] {""}
+    } · created { $created } · played { $playtime }
+worlds-playtime = { $hours ->
+        [0] { $minutes ->
+            [one] { $minutes } minute
+           *[other] { $minutes } minutes
+        }
+       *[other] { $hours }h { $minutes }m
+    }
+worlds-health = Health { $health }/{ $max }
+worlds-name = Name
+worlds-seed = Seed
+worlds-noise = Noise
+worlds-difficulty = Difficulty
+worlds-survival-mode = Survival
+worlds-survival-hint = The cold and the heat wear the player down
+worlds-create = Create
+worlds-create-failed = Could not create world: { $error }
+worlds-bad-seed = Seed must be a whole number
+
+settings-title = Settings
+settings-graphics = Graphics
+settings-audio = Audio
+settings-controls = Controls
+settings-language = Language
+settings-window = Window
+settings-vsync = VSync
+settings-pixel-resolution = Pixel resolution
+settings-render-distance-across = Render distance across
+settings-render-distance-down = Render distance down
+settings-crt-filter = CRT filter
+settings-crt-curvature = Curvature
+settings-crt-scanlines = Scanlines
+settings-crt-aberration = Color fringing
+settings-master-volume = Master
+settings-music-volume = Music
+settings-effects-volume = Effects
+settings-rumble = Rumble
+
+controls-action = Action
+controls-keyboard = Keyboard
+controls-controller = Controller
+controls-conflict = Shares a binding with another action
+controls-waiting = Press...
+controls-reset = Reset to defaults
+control-move-up = Move up
+control-move-left = Move left
+control-move-down = Move down
+control-move-right = Move right
+control-sprint = Sprint
+control-interact = Interact
+control-attack = Attack
+control-next-slot = Next slot
+control-previous-slot = Previous slot
+control-screenshot = Screenshot
+
+crafting-title = Crafting
+crafting-craft = Craft
+crafting-reload = Reload recipes
+inventory-title = Inventory
+inventory-give = Give
+
+chest-title = Chest
+chest-hint = Click a stack to move it to the other side.
+chest-inventory = Inventory
+
+map-waypoint-name = Waypoint name
+map-waypoint-default = Waypoint { $number }
+map-hint = Right-click to place a waypoint or remove one. Drag to pan, scroll to zoom, M to close.
+
+screenshot-saved = Screenshot saved to { $path }
+screenshot-failed = Could not save screenshot
+
+changelog-title = What's new
+
+notification-saved = Saved
+notification-picked-up = +{ $count } { $item }
+notification-quest-complete = Quest complete: { $quest }
+
+objective-collect = Collect { $item }: { $carried }/{ $count }
+objective-reach = Reach the marked spot
+objective-talk-to = Talk to { $name }
+
+locked-needs-key = Needs a { $item }
+locked-unlocked = Unlocked
+trade-needs = Needs { $count } { $item }
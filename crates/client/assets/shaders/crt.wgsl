@@ -0,0 +1,49 @@
+// An old CRT look over the upscaled view: bulging glass, dark lines between the rows of game pixels
+// and color fringes towards the edges.
+
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct CrtFilter {
+    // The left, top, width and height of the camera's viewport, in physical pixels.
+    viewport: vec4<f32>,
+    curvature: f32,
+    scanlines: f32,
+    aberration: f32,
+    // How many rows of game pixels the viewport shows.
+    rows: f32,
+}
+
+@group(0) @binding(0) var screen: texture_2d<f32>;
+@group(0) @binding(1) var screen_sampler: sampler;
+@group(0) @binding(2) var<uniform> crt: CrtFilter;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(screen));
+    let local = (in.uv * size - crt.viewport.xy) / crt.viewport.zw;
+    // The letterbox bars around the viewport stay as they are.
+    if any(local < vec2(0.0)) || any(local > vec2(1.0)) {
+        return textureSampleLevel(screen, screen_sampler, in.uv, 0.0);
+    }
+
+    let centered = local * 2.0 - 1.0;
+    let bent = centered + centered * centered.yx * centered.yx * crt.curvature;
+    if any(abs(bent) > vec2(1.0)) {
+        return vec4(0.0, 0.0, 0.0, 1.0);
+    }
+    let bent_local = bent * 0.5 + 0.5;
+    let uv = (crt.viewport.xy + bent_local * crt.viewport.zw) / size;
+
+    // Red and blue drift apart the further out from the center they are.
+    let fringe = bent * crt.aberration / size;
+    let color = vec3(
+        textureSampleLevel(screen, screen_sampler, uv + fringe, 0.0).r,
+        textureSampleLevel(screen, screen_sampler, uv, 0.0).g,
+        textureSampleLevel(screen, screen_sampler, uv - fringe, 0.0).b,
+    );
+
+    // Darkest on the edges between rows, full brightness through their middles.
+    let row = bent_local.y * crt.rows;
+    let scanline = 1.0 - crt.scanlines * (0.5 + 0.5 * cos(row * 6.2831855));
+    return vec4(color * scanline, 1.0);
+}
@@ -0,0 +1,22 @@
+// The night tint over the world, thinned wherever the light map says a tile is lit.
+
+#import bevy_ui::ui_vertex_output::UiVertexOutput
+
+@group(1) @binding(0) var<uniform> tint: vec4<f32>;
+// The world positions of the top left and bottom right corners of the view.
+@group(1) @binding(1) var<uniform> view_corners: vec4<f32>;
+// The bottom left corner and size of the world area the light map covers.
+@group(1) @binding(2) var<uniform> light_rect: vec4<f32>;
+@group(1) @binding(3) var light_map: texture_2d<f32>;
+@group(1) @binding(4) var light_sampler: sampler;
+
+@fragment
+fn fragment(in: UiVertexOutput) -> @location(0) vec4<f32> {
+    let world = mix(view_corners.xy, view_corners.zw, in.uv);
+    let uv = (world - light_rect.xy) / light_rect.zw;
+    let sampled = textureSample(light_map, light_sampler, uv).r;
+    // Past the loaded chunks nothing is lit.
+    let inside = all(uv >= vec2(0.0)) && all(uv <= vec2(1.0));
+    let light = select(0.0, sampled, inside);
+    return vec4(tint.rgb, tint.a * (1.0 - light));
+}
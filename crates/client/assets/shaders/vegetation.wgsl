@@ -0,0 +1,84 @@
+#import bevy_ecs_tilemap::common::{process_fragment, mesh, tilemap_data}
+#import bevy_ecs_tilemap::vertex_output::MeshVertexOutput
+#import bevy_sprite::mesh2d_view_bindings::globals
+
+// How far the tops of plants lean in x, in tile widths, and the wave vector of the gusts, in
+// radians per world unit.
+@group(3) @binding(0) var<uniform> wind: vec4<f32>;
+// One bit for each texture index in tiles.png that sways in the wind.
+@group(3) @binding(1) var<uniform> swaying: vec4<u32>;
+// One bit for each texture index in tiles.png that is water.
+@group(3) @binding(2) var<uniform> water: vec4<u32>;
+
+// How fast gusts roll over the world, in radians per second.
+const GUST_SPEED: f32 = 2.5;
+
+// How fast ripples roll across water, in radians per second.
+const RIPPLE_SPEED: f32 = 1.8;
+
+const RIPPLE_COLOR: vec3<f32> = vec3(0.55, 0.75, 0.95);
+const FOAM_COLOR: vec3<f32> = vec3(0.9, 0.96, 1.0);
+
+fn has_bit(bits: vec4<u32>, tile_id: i32) -> bool {
+    let id = u32(tile_id);
+    return id < 128u && ((bits[id / 32u] >> (id % 32u)) & 1u) == 1u;
+}
+
+// Ripples rolling over water and foam lapping at the edges the tile color marks as land: its
+// red, green, blue and alpha are 1 for land to the north, east, south and west. Worked out on
+// whole texels so it stays as blocky as the rest of the art.
+fn shade_water(in: MeshVertexOutput, color: vec4<f32>) -> vec4<f32> {
+    let texels = tilemap_data.tile_size;
+    let texel = min(floor(in.uv.zw * texels), texels - 1.0);
+    let world = tile_center(in) + (texel - texels / 2.0) * vec2(1.0, -1.0);
+
+    var shaded = color.rgb;
+    let wave = sin(world.x * 0.35 + world.y * 0.2 + globals.time * RIPPLE_SPEED)
+        + sin(world.y * 0.45 - world.x * 0.1 - globals.time * RIPPLE_SPEED * 0.7);
+    if wave > 1.5 {
+        shaded = mix(shaded, RIPPLE_COLOR, 0.5);
+    }
+
+    // How many texels in from the north, east, south and west edges this one is, and where along
+    // each edge it is.
+    let inset = vec4(texel.y, texels.x - 1.0 - texel.x, texels.y - 1.0 - texel.y, texel.x);
+    let along = vec4(world.x, world.y, world.x, world.y);
+    // Foam washes in and out, one or two texels deep.
+    let depth = 1.0 + step(vec4(0.0), sin(along * 0.7 + globals.time * 2.0));
+    if any((in.color > vec4(0.5)) & (inset < depth)) {
+        shaded = FOAM_COLOR;
+    }
+    return vec4(shaded, color.a);
+}
+
+fn tile_center(in: MeshVertexOutput) -> vec2<f32> {
+    return mesh.model[3].xy + vec2<f32>(in.storage_position) * tilemap_data.grid_size;
+}
+
+@fragment
+fn fragment(in: MeshVertexOutput) -> @location(0) vec4<f32> {
+#ifdef ATLAS
+    return process_fragment(in);
+#else
+    var drawn = in;
+    let is_water = has_bit(water, in.tile_id);
+    if is_water {
+        // The water texture wobbles sideways a texel at a time. The tile color is the shoreline,
+        // not a tint.
+        let row = tile_center(in).y - floor(in.uv.w * tilemap_data.tile_size.y);
+        let wobble = round(sin(row * 0.6 + globals.time * RIPPLE_SPEED));
+        drawn.uv.x = fract(in.uv.x + wobble / tilemap_data.tile_size.x);
+        drawn.color = vec4(1.0);
+    } else if has_bit(swaying, in.tile_id) {
+        let gust = 0.6 + 0.4 * sin(dot(tile_center(in), wind.zw) - globals.time * GUST_SPEED);
+        // The bottom of the tile stays put and its top leans the furthest.
+        let lean = wind.x * gust * (1.0 - in.uv.w);
+        drawn.uv.x = clamp(in.uv.x - lean, 0.0, 1.0);
+    }
+    let color = process_fragment(drawn);
+    if is_water {
+        return shade_water(in, color);
+    }
+    return color;
+#endif
+}
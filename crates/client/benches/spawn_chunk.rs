@@ -0,0 +1,31 @@
+use bevy::ecs::world::CommandQueue;
+use bevy::prelude::*;
+use criterion::{Criterion, criterion_group, criterion_main};
+use moonlit_client::biome_assets::BiomeAssetGroup;
+use moonlit_client::chunk::{generate_chunk, spawn_chunk};
+use moonlit_client::worldgen::WorldgenPreset;
+
+fn spawn_chunk_benchmark(c: &mut Criterion) {
+    let biome_assets = BiomeAssetGroup::default();
+
+    c.bench_function("spawn_chunk", |b| {
+        let mut world = World::new();
+        let mut x = 0;
+        b.iter(|| {
+            let mut queue = CommandQueue::default();
+            let mut commands = Commands::new(&mut queue, &world);
+            let chunk_pos = IVec2::new(x, 0);
+            spawn_chunk(
+                &mut commands,
+                &biome_assets,
+                chunk_pos,
+                generate_chunk(42, &WorldgenPreset::default(), chunk_pos),
+            );
+            queue.apply(&mut world);
+            x += 1;
+        });
+    });
+}
+
+criterion_group!(benches, spawn_chunk_benchmark);
+criterion_main!(benches);
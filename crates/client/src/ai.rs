@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+
+use crate::GameState;
+use crate::pause::paused;
+
+/// Runs the shared clock of every [`Behavior`] registered with [`AddBehavior::add_behavior`].
+/// Systems that decide on transitions go after [`AiSystems`], so they see how long the current
+/// state has lasted this frame.
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(
+            Update,
+            AiSystems
+                .run_if(in_state(GameState::Playing))
+                .run_if(not(paused)),
+        );
+    }
+}
+
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AiSystems;
+
+/// A small state machine driving an entity's AI, like an animal's wandering or an enemy's
+/// chase. `S` is the set of states it can be in. A state can be entered with a timeout, after
+/// which the behavior's systems pick what comes next.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct Behavior<S> {
+    state: S,
+    /// Seconds spent in the current state.
+    elapsed: f32,
+    timeout: Option<f32>,
+}
+
+impl<S: Default> Default for Behavior<S> {
+    fn default() -> Self {
+        Self::new(S::default())
+    }
+}
+
+impl<S> Behavior<S> {
+    pub fn new(state: S) -> Self {
+        Self {
+            state,
+            elapsed: 0.0,
+            timeout: None,
+        }
+    }
+
+    /// Starts in `state`, timing out after `secs`.
+    pub fn timed(state: S, secs: f32) -> Self {
+        Self {
+            timeout: Some(secs),
+            ..Self::new(state)
+        }
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Seconds spent in the current state.
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// Whether the current state has lasted as long as it was entered for.
+    pub fn timed_out(&self) -> bool {
+        self.timeout.is_some_and(|timeout| self.elapsed >= timeout)
+    }
+
+    /// Enters `state` for `secs`, even if it is the current state already.
+    pub fn enter_for(&mut self, state: S, secs: f32) {
+        *self = Self::timed(state, secs);
+    }
+
+    pub fn advance(&mut self, secs: f32) {
+        self.elapsed += secs;
+    }
+}
+
+impl<S: PartialEq> Behavior<S> {
+    /// Enters `state` without a timeout, unless it is the current state already. Returns whether
+    /// the state changed.
+    pub fn enter(&mut self, state: S) -> bool {
+        let changed = self.state != state;
+        if changed {
+            *self = Self::new(state);
+        }
+        changed
+    }
+}
+
+pub trait AddBehavior {
+    /// Advances the [`Behavior<S>`] of every entity in [`AiSystems`].
+    fn add_behavior<S: Send + Sync + 'static>(&mut self) -> &mut Self;
+}
+
+impl AddBehavior for App {
+    fn add_behavior<S: Send + Sync + 'static>(&mut self) -> &mut Self {
+        self.add_systems(Update, advance_behaviors::<S>.in_set(AiSystems))
+    }
+}
+
+fn advance_behaviors<S: Send + Sync + 'static>(
+    time: Res<Time>,
+    mut behaviors: Query<&mut Behavior<S>>,
+) {
+    for mut behavior in &mut behaviors {
+        behavior.advance(time.delta_secs());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    enum Guard {
+        #[default]
+        Resting,
+        Patrolling,
+    }
+
+    #[test]
+    fn behaviors_time_out_and_only_restart_when_their_state_changes() {
+        let mut behavior = Behavior::timed(Guard::Patrolling, 2.0);
+        behavior.advance(1.5);
+        assert!(!behavior.timed_out());
+        // Entering the current state again keeps the clock running.
+        assert!(!behavior.enter(Guard::Patrolling));
+        behavior.advance(0.5);
+        assert!(behavior.timed_out());
+
+        assert!(behavior.enter(Guard::Resting));
+        assert_eq!(behavior.elapsed(), 0.0);
+        behavior.advance(100.0);
+        assert!(!behavior.timed_out());
+
+        behavior.enter_for(Guard::Resting, 1.0);
+        assert_eq!(
+            (*behavior.state(), behavior.elapsed()),
+            (Guard::Resting, 0.0)
+        );
+        assert_eq!(Behavior::<Guard>::default(), Behavior::new(Guard::Resting));
+    }
+}
@@ -0,0 +1,227 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::GameState;
+use crate::clock::{GameClock, MoonPhase};
+
+const HOURS_PER_DAY: f32 = 24.0;
+
+/// The least the ambient light falls to under a full moon. Other phases of the moon give off
+/// less, down to none at the new moon.
+const MOONLIGHT: f32 = 0.35;
+
+/// How close to a keyframe, in points, the pointer has to be to grab it in the editor.
+const GRAB_RADIUS: f32 = 6.0;
+
+/// Keeps the [`AmbientLight`] in step with the [`GameClock`] by sampling the [`AmbientCurve`],
+/// brightening the night as far as the moon lights it up, and adds a window for editing the curve live: drag keyframes around, double-click to add one
+/// and right-click to remove one.
+pub struct AmbientLightPlugin;
+
+impl Plugin for AmbientLightPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AmbientCurve>()
+            .init_resource::<AmbientLight>()
+            .add_systems(
+                Update,
+                update_ambient_light.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                curve_window.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// How bright the world is over the day, from 0 in the dark of night to 1 in full daylight.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct AmbientCurve {
+    /// The hour of the day and the light at that hour, in order of the hour. The light eases in
+    /// and out between keyframes, wrapping around from the last one to the first at midnight.
+    pub keyframes: Vec<Vec2>,
+}
+
+impl Default for AmbientCurve {
+    /// Dark until the sun comes up around dawn and after it sets around dusk.
+    fn default() -> Self {
+        Self {
+            keyframes: vec![
+                Vec2::new(5.0, 0.0),
+                Vec2::new(7.0, 1.0),
+                Vec2::new(17.0, 1.0),
+                Vec2::new(19.0, 0.0),
+            ],
+        }
+    }
+}
+
+impl AmbientCurve {
+    /// The light at `hour`, between 0 and [`HOURS_PER_DAY`]. Without keyframes, it is always day.
+    pub fn sample(&self, hour: f32) -> f32 {
+        let keyframes = &self.keyframes;
+        let (Some(first), Some(last)) = (keyframes.first(), keyframes.last()) else {
+            return 1.0;
+        };
+        let next = keyframes.partition_point(|keyframe| keyframe.x <= hour);
+        let before = next
+            .checked_sub(1)
+            .map_or(*last - Vec2::X * HOURS_PER_DAY, |index| keyframes[index]);
+        let after = keyframes
+            .get(next)
+            .copied()
+            .unwrap_or(*first + Vec2::X * HOURS_PER_DAY);
+        let span = after.x - before.x;
+        if span <= 0.0 {
+            return after.y;
+        }
+        let t = ((hour - before.x) / span).clamp(0.0, 1.0);
+        before.y.lerp(after.y, t * t * (3.0 - 2.0 * t))
+    }
+
+    /// The light of the sun at the time of `clock`, without the moon.
+    pub fn daylight(&self, clock: &GameClock) -> f32 {
+        self.sample(clock.time_of_day() * HOURS_PER_DAY)
+    }
+}
+
+/// The light in the world right now, from the [`AmbientCurve`] at the time of the [`GameClock`]
+/// or from the moon, whichever is brighter.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct AmbientLight(pub f32);
+
+impl Default for AmbientLight {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+fn update_ambient_light(
+    clock: Res<GameClock>,
+    moon_phase: Res<MoonPhase>,
+    curve: Res<AmbientCurve>,
+    mut ambient: ResMut<AmbientLight>,
+) {
+    let light = curve.daylight(&clock);
+    let moonlight = MOONLIGHT * moon_phase.brightness();
+    ambient.set_if_neq(AmbientLight(light.max(moonlight)));
+}
+
+fn curve_window(
+    mut contexts: EguiContexts,
+    clock: Res<GameClock>,
+    ambient: Res<AmbientLight>,
+    mut curve: ResMut<AmbientCurve>,
+) -> Result {
+    egui::Window::new("Ambient light")
+        .default_open(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.label("Drag: move, double-click: add, right-click: remove");
+            let size = egui::vec2(ui.available_width().max(240.0), 120.0);
+            let (response, painter) = ui.allocate_painter(size, egui::Sense::click());
+            let rect = response.rect;
+            let to_screen = |keyframe: Vec2| {
+                egui::pos2(
+                    egui::lerp(rect.x_range(), keyframe.x / HOURS_PER_DAY),
+                    egui::lerp(rect.bottom_up_range(), keyframe.y),
+                )
+            };
+            let from_screen = |pos: egui::Pos2| {
+                Vec2::new(
+                    egui::remap_clamp(pos.x, rect.x_range(), 0.0..=HOURS_PER_DAY),
+                    egui::remap_clamp(pos.y, rect.bottom_up_range(), 0.0..=1.0),
+                )
+            };
+
+            let stroke = ui.visuals().widgets.inactive.fg_stroke;
+            painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+            let points = (0..=96)
+                .map(|step| {
+                    let hour = step as f32 / 96.0 * HOURS_PER_DAY;
+                    to_screen(Vec2::new(hour, curve.sample(hour)))
+                })
+                .collect();
+            painter.add(egui::Shape::line(points, stroke));
+            let now = to_screen(Vec2::new(clock.time_of_day() * HOURS_PER_DAY, 0.0)).x;
+            painter.vline(now, rect.y_range(), (1.0, egui::Color32::YELLOW));
+
+            let mut removed = None;
+            let count = curve.keyframes.len();
+            for index in 0..count {
+                let keyframe = curve.keyframes[index];
+                let handle = egui::Rect::from_center_size(
+                    to_screen(keyframe),
+                    egui::Vec2::splat(GRAB_RADIUS * 2.0),
+                );
+                let handle = ui.interact(
+                    handle,
+                    response.id.with(index),
+                    egui::Sense::click_and_drag(),
+                );
+                if handle.dragged()
+                    && let Some(pointer) = handle.interact_pointer_pos()
+                {
+                    // Keep keyframes between their neighbours, so they stay in order.
+                    let min = index.checked_sub(1).map_or(0.0, |i| curve.keyframes[i].x);
+                    let max = curve
+                        .keyframes
+                        .get(index + 1)
+                        .map_or(HOURS_PER_DAY, |k| k.x);
+                    let moved = from_screen(pointer);
+                    curve.keyframes[index] = Vec2::new(moved.x.clamp(min, max), moved.y);
+                }
+                if handle.secondary_clicked() {
+                    removed = Some(index);
+                }
+                let color = if handle.hovered() || handle.dragged() {
+                    ui.visuals().widgets.hovered.fg_stroke.color
+                } else {
+                    stroke.color
+                };
+                painter.circle_filled(to_screen(curve.keyframes[index]), GRAB_RADIUS / 2.0, color);
+            }
+            if let Some(index) = removed {
+                curve.keyframes.remove(index);
+            }
+            if response.double_clicked()
+                && let Some(pointer) = response.interact_pointer_pos()
+            {
+                let added = from_screen(pointer);
+                let index = curve
+                    .keyframes
+                    .partition_point(|keyframe| keyframe.x <= added.x);
+                curve.keyframes.insert(index, added);
+            }
+
+            ui.horizontal(|ui| {
+                ui.label(format!("Light now: {:.0}%", ambient.0 * 100.0));
+                if ui.button("Reset").clicked() {
+                    *curve = AmbientCurve::default();
+                }
+            });
+        });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn light_eases_between_keyframes_and_wraps_at_midnight() {
+        let curve = AmbientCurve::default();
+        assert_eq!(curve.sample(12.0), 1.0);
+        assert_eq!(curve.sample(0.0), 0.0);
+        assert_eq!(curve.sample(23.9), 0.0);
+        // Dusk is halfway between day and night, and gets darker as it goes.
+        assert!((curve.sample(18.0) - 0.5).abs() < 1e-4);
+        assert!(curve.sample(18.5) < curve.sample(18.0));
+
+        // Going from the last keyframe of the day to the first of the next.
+        let curve = AmbientCurve {
+            keyframes: vec![Vec2::new(4.0, 0.6), Vec2::new(20.0, 0.2)],
+        };
+        assert!((curve.sample(0.0) - 0.4).abs() < 1e-4);
+        assert_eq!(curve.sample(4.0), 0.6);
+        assert_eq!(AmbientCurve { keyframes: vec![] }.sample(3.0), 1.0);
+    }
+}
@@ -0,0 +1,306 @@
+use std::ops::Range;
+
+use bevy::ecs::query::QueryData;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_rand::prelude::*;
+use rand::Rng;
+
+use crate::GameState;
+use crate::ai::{AddBehavior, AiSystems, Behavior};
+use crate::chunk::{CHUNK_SIZE, ChunkLoaded, TILE_SIZE};
+use crate::collision::{TileCollider, walk};
+use crate::debug_placer::{RegisterSpawnable, SpawnCategory};
+use crate::persistence::WorldSave;
+use crate::player::Velocity;
+use crate::save::{Persist, ReflectSaveableComponent};
+use crate::tiles::{WorldTiles, chunk_tile_to_world, tile_to_world_pos, world_pos_to_tile};
+use crate::worldgen::{Biome, WorldSeed, WorldgenPreset, biome_at_chunk};
+use crate::y_sort::YSort;
+
+/// Walking speed of animals on grass, in world units per second.
+pub const ANIMAL_SPEED: f32 = 24.0;
+
+/// Chance that a newly loaded chunk has a herd in it.
+pub const HERD_CHANCE: f64 = 0.3;
+
+/// How many animals a herd starts with.
+pub const HERD_SIZE: Range<u32> = 2..5;
+
+/// How far an animal strays from the middle of its herd before it heads back, in world units.
+pub const HERD_RADIUS: f32 = 3.0 * TILE_SIZE.x;
+
+/// How long animals stand around and walk for, in seconds.
+const IDLE_SECS: Range<f32> = 1.0..5.0;
+const WALK_SECS: Range<f32> = 0.5..2.5;
+
+/// Spawns herds of passive animals in chunks as they load, picking the animal from the chunk's
+/// biome. Animals idle and wander about at random while keeping close to their herd. They are
+/// saved with the chunk they are in, which doesn't get a new herd while it has saved animals.
+pub struct AnimalsPlugin;
+
+impl Plugin for AnimalsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_behavior::<Wander>()
+            .add_observer(add_animal_sprite)
+            .register_spawnable("Sheep", SpawnCategory::Mob, |commands, pos| {
+                spawn_animal(commands, AnimalKind::Sheep, 0, pos);
+            })
+            .register_spawnable("Deer", SpawnCategory::Mob, |commands, pos| {
+                spawn_animal(commands, AnimalKind::Deer, 0, pos);
+            })
+            .register_spawnable("Goat", SpawnCategory::Mob, |commands, pos| {
+                spawn_animal(commands, AnimalKind::Goat, 0, pos);
+            })
+            .register_spawnable("Penguin", SpawnCategory::Mob, |commands, pos| {
+                spawn_animal(commands, AnimalKind::Penguin, 0, pos);
+            })
+            .add_systems(
+                Update,
+                (
+                    spawn_herds.run_if(on_message::<ChunkLoaded>),
+                    wander,
+                    move_animals,
+                )
+                    .chain()
+                    .after(AiSystems)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct AnimalAssets {
+    /// One frame per [`AnimalKind`], facing right.
+    #[asset(path = "animals.png")]
+    pub animals: Handle<Image>,
+    #[asset(texture_atlas_layout(tile_size_x = 16, tile_size_y = 16, columns = 4, rows = 1))]
+    pub animals_layout: Handle<TextureAtlasLayout>,
+}
+
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AnimalKind {
+    Sheep,
+    Deer,
+    Goat,
+    Penguin,
+}
+
+impl AnimalKind {
+    /// The animal that roams `biome`, if any.
+    pub fn of_biome(biome: Biome) -> Option<Self> {
+        match biome {
+            Biome::Ocean => None,
+            Biome::Plains => Some(AnimalKind::Sheep),
+            Biome::Forest => Some(AnimalKind::Deer),
+            Biome::Mountains => Some(AnimalKind::Goat),
+            Biome::Tundra => Some(AnimalKind::Penguin),
+        }
+    }
+
+    /// Index of the animal in `animals.png`.
+    fn texture_index(self) -> usize {
+        match self {
+            AnimalKind::Sheep => 0,
+            AnimalKind::Deer => 1,
+            AnimalKind::Goat => 2,
+            AnimalKind::Penguin => 3,
+        }
+    }
+}
+
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, SaveableComponent)]
+#[require(Velocity, YSort, Behavior<Wander> = Behavior::timed(Wander::Idle, 0.0))]
+pub struct Animal {
+    pub kind: AnimalKind,
+    /// Animals with the same herd keep close to each other.
+    pub herd: u32,
+}
+
+/// What an [`Animal`] is doing. Each lasts a random while before the animal does the other.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Wander {
+    #[default]
+    Idle,
+    Walking {
+        direction: Vec2,
+    },
+}
+
+pub fn spawn_animal(commands: &mut Commands, kind: AnimalKind, herd: u32, pos: Vec2) {
+    commands.spawn((
+        Name::new(format!("{kind:?}")),
+        Animal { kind, herd },
+        Persist,
+        Transform::from_translation(pos.extend(0.9)),
+        TileCollider {
+            half_size: Vec2::new(5.0, 4.0),
+        },
+    ));
+}
+
+/// The velocity an animal at `pos` wants: its wandering, or straight back towards the middle of
+/// its herd once it has strayed further than [`HERD_RADIUS`].
+pub fn herd_velocity(pos: Vec2, wander: Wander, herd_center: Vec2) -> Vec2 {
+    let to_center = herd_center - pos;
+    if to_center.length() > HERD_RADIUS {
+        return to_center.normalize() * ANIMAL_SPEED;
+    }
+    match wander {
+        Wander::Idle => Vec2::ZERO,
+        Wander::Walking { direction } => direction * ANIMAL_SPEED,
+    }
+}
+
+fn add_animal_sprite(
+    add: On<Add, Animal>,
+    mut commands: Commands,
+    animal_assets: Res<AnimalAssets>,
+    animals: Query<&Animal>,
+) {
+    let Ok(animal) = animals.get(add.entity) else {
+        return;
+    };
+    commands.entity(add.entity).insert(Sprite::from_atlas_image(
+        animal_assets.animals.clone(),
+        TextureAtlas {
+            layout: animal_assets.animals_layout.clone(),
+            index: animal.kind.texture_index(),
+        },
+    ));
+}
+
+fn spawn_herds(
+    mut commands: Commands,
+    mut loaded: MessageReader<ChunkLoaded>,
+    (world_seed, preset): (Res<WorldSeed>, Res<WorldgenPreset>),
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+    mut world_save: ResMut<WorldSave>,
+    tiles: WorldTiles,
+) {
+    for ChunkLoaded(chunk_pos, _) in loaded.read().copied() {
+        let Some(kind) = AnimalKind::of_biome(biome_at_chunk(chunk_pos, world_seed.seed, &preset))
+        else {
+            continue;
+        };
+        if world_save.chunk_entities(chunk_pos).is_some() || !global_rng.random_bool(HERD_CHANCE) {
+            continue;
+        }
+        let herd = global_rng.random();
+        let tile_pos = UVec2::new(
+            global_rng.random_range(0..CHUNK_SIZE.x),
+            global_rng.random_range(0..CHUNK_SIZE.y),
+        );
+        let center = tile_to_world_pos(chunk_tile_to_world(chunk_pos, tile_pos.into()));
+        for _ in 0..global_rng.random_range(HERD_SIZE) {
+            let offset = Vec2::new(
+                global_rng.random_range(-1.0..1.0),
+                global_rng.random_range(-1.0..1.0),
+            ) * TILE_SIZE.x;
+            let pos = center + offset;
+            let walkable = tiles
+                .properties(world_pos_to_tile(pos))
+                .is_some_and(|properties| !properties.solid);
+            if walkable {
+                spawn_animal(&mut commands, kind, herd, pos);
+            }
+        }
+    }
+}
+
+fn wander(
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+    mut animals: Query<&mut Behavior<Wander>>,
+) {
+    for mut behavior in &mut animals {
+        if !behavior.timed_out() {
+            continue;
+        }
+        match behavior.state() {
+            Wander::Idle => {
+                let direction =
+                    Vec2::from_angle(global_rng.random_range(0.0..std::f32::consts::TAU));
+                behavior.enter_for(
+                    Wander::Walking { direction },
+                    global_rng.random_range(WALK_SECS),
+                );
+            }
+            Wander::Walking { .. } => {
+                behavior.enter_for(Wander::Idle, global_rng.random_range(IDLE_SECS));
+            }
+        }
+    }
+}
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct AnimalMovement {
+    animal: &'static Animal,
+    behavior: &'static Behavior<Wander>,
+    collider: &'static TileCollider,
+    velocity: &'static mut Velocity,
+    transform: &'static mut Transform,
+    sprite: &'static mut Sprite,
+}
+
+fn move_animals(time: Res<Time>, mut animals: Query<AnimalMovement>, tiles: WorldTiles) {
+    let secs = time.delta_secs();
+    if secs == 0.0 {
+        return;
+    }
+    let mut herds: HashMap<u32, (Vec2, f32)> = HashMap::default();
+    for animal in &animals {
+        let (sum, count) = herds.entry(animal.animal.herd).or_default();
+        *sum += animal.transform.translation.xy();
+        *count += 1.0;
+    }
+
+    for mut animal in &mut animals {
+        let pos = animal.transform.translation.xy();
+        let (sum, count) = herds[&animal.animal.herd];
+        let wanted = herd_velocity(pos, *animal.behavior.state(), sum / count);
+        let moved = walk(
+            &tiles,
+            pos,
+            &mut animal.velocity.0,
+            wanted,
+            ANIMAL_SPEED,
+            animal.collider,
+            secs,
+        );
+        animal.transform.translation = moved.extend(animal.transform.translation.z);
+        if animal.velocity.0.x != 0.0 {
+            animal.sprite.flip_x = animal.velocity.0.x < 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animals_wander_near_their_herd_and_head_back_when_they_stray() {
+        let walking = Wander::Walking { direction: Vec2::X };
+        let idle = Wander::Idle;
+        let center = Vec2::new(100.0, 50.0);
+
+        assert_eq!(herd_velocity(center, idle, center), Vec2::ZERO);
+        assert_eq!(
+            herd_velocity(center - Vec2::Y * HERD_RADIUS, walking, center),
+            Vec2::X * ANIMAL_SPEED
+        );
+        let strayed = center + Vec2::new(0.0, HERD_RADIUS + 1.0);
+        assert_eq!(
+            herd_velocity(strayed, idle, center),
+            Vec2::NEG_Y * ANIMAL_SPEED
+        );
+        assert_eq!(
+            herd_velocity(strayed, walking, center),
+            Vec2::NEG_Y * ANIMAL_SPEED
+        );
+        assert_eq!(AnimalKind::of_biome(Biome::Ocean), None);
+    }
+}
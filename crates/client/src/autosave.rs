@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::GameState;
+use crate::chunk::{ChunkManager, ChunkMarker, ChunkPosition, SavedTile, collect_chunk_data};
+use crate::clock::GameClock;
+use crate::health::Health;
+use crate::inventory::Inventory;
+use crate::localization::Localization;
+use crate::notifications::Notifications;
+use crate::persistence::WorldSave;
+use crate::player::Player;
+use crate::quests::QuestLog;
+use crate::save::store_loaded_chunk_entities;
+use crate::waypoints::Waypoints;
+use crate::world_map::ExploredMap;
+
+/// How often the world is saved while playing.
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Saves modified chunks, persisted entities, the player's position, health and quests, the time,
+/// the world map, the waypoints and the world metadata every [`AUTOSAVE_INTERVAL`], when the app
+/// exits and on [`SaveAndQuit`]. Autosaves put up a notification.
+pub struct AutosavePlugin;
+
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Autosave>()
+            .add_systems(
+                Update,
+                autosave
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(resource_exists::<WorldSave>),
+            )
+            .add_systems(
+                Last,
+                save_on_exit
+                    .run_if(on_message::<AppExit>)
+                    .run_if(resource_exists::<WorldSave>),
+            )
+            .add_observer(save_and_quit);
+    }
+}
+
+/// Saves the world and goes back to the main menu.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SaveAndQuit;
+
+#[derive(Resource)]
+struct Autosave {
+    timer: Timer,
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self {
+            timer: Timer::new(AUTOSAVE_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Writes the current state of the loaded world into the [`WorldSave`] so it can be flushed to
+/// disk.
+#[derive(SystemParam)]
+struct SaveWorld<'w, 's> {
+    commands: Commands<'w, 's>,
+    world_save: ResMut<'w, WorldSave>,
+    chunk_manager: ResMut<'w, ChunkManager>,
+    chunks: Query<'w, 's, (&'static ChunkPosition, &'static TileStorage), With<ChunkMarker>>,
+    tiles: Query<'w, 's, SavedTile>,
+    player: Query<'w, 's, (&'static Transform, &'static Health, &'static Inventory), With<Player>>,
+    quest_log: Res<'w, QuestLog>,
+    clock: Res<'w, GameClock>,
+    explored_map: Res<'w, ExploredMap>,
+    waypoints: Res<'w, Waypoints>,
+}
+
+impl SaveWorld<'_, '_> {
+    /// Stores the loaded world, then calls `flush` with the [`WorldSave`]. Entities can only be
+    /// saved with exclusive access to the world, so this happens once commands are applied.
+    fn store(&mut self, flush: impl FnOnce(&mut WorldSave) + Send + 'static) {
+        for (ChunkPosition(chunk_pos), tile_storage) in &self.chunks {
+            if !self.chunk_manager.dirty_chunks.remove(chunk_pos) {
+                continue;
+            }
+            match collect_chunk_data(tile_storage, &self.tiles) {
+                Some(data) => self.world_save.store_chunk(*chunk_pos, data),
+                None => error!("Chunk {chunk_pos} is missing tiles, not saving it"),
+            }
+        }
+        if let Ok((transform, health, inventory)) = self.player.single() {
+            self.world_save.metadata.player_pos = transform.translation.xy();
+            self.world_save.metadata.player_health = Some(health.current);
+            self.world_save.metadata.player_inventory = Some(inventory.clone());
+        }
+        self.world_save.metadata.quest_log = Some(self.quest_log.clone());
+        self.world_save.metadata.clock_days = Some(self.clock.days);
+        self.world_save.explored_map = self.explored_map.clone();
+        self.world_save.metadata.waypoints = Some(self.waypoints.clone());
+        self.commands.queue(|world: &mut World| {
+            store_loaded_chunk_entities(world);
+            flush(&mut world.resource_mut::<WorldSave>());
+        });
+    }
+}
+
+fn autosave(
+    time: Res<Time<Real>>,
+    mut autosave: ResMut<Autosave>,
+    mut save_world: SaveWorld,
+    mut notifications: ResMut<Notifications>,
+    localization: Res<Localization>,
+) {
+    if !autosave.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    info!(
+        "Autosaving world to {}",
+        save_world.world_save.dir.display()
+    );
+    save_world.store(WorldSave::flush_async);
+    notifications.push(localization.get("notification-saved"));
+}
+
+fn save_on_exit(mut save_world: SaveWorld) {
+    save_world.store(|world_save| {
+        if let Err(err) = world_save.flush() {
+            error!("Failed to save world on exit: {err}");
+        }
+    });
+}
+
+fn save_and_quit(
+    _: On<SaveAndQuit>,
+    mut save_world: SaveWorld,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    save_world.store(|world_save| {
+        if let Err(err) = world_save.flush() {
+            error!("Failed to save world: {err}");
+        }
+    });
+    next_state.set(GameState::MainMenu);
+}
@@ -0,0 +1,165 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+use crate::GameState;
+use crate::chunk::{ChunkManager, ChunkSpawnSystems};
+use crate::player::Player;
+use crate::tiles::{world_pos_to_tile, world_tile_to_chunk};
+use crate::worldgen::{Biome, WorldSeed, WorldgenPreset, biome_at_chunk};
+
+/// How many chunks beyond the render distance biomes are looked up to preload their assets.
+pub const BIOME_PRELOAD_MARGIN: i32 = 3;
+
+/// Starts loading each biome's tiles, props and music once the biome shows up just outside the
+/// loaded area, so the assets are ready by the time its chunks spawn. The biomes around the
+/// player when a world opens are held behind its loading screen.
+pub struct BiomeAssetsPlugin;
+
+impl Plugin for BiomeAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BiomeAssets>()
+            .add_systems(OnExit(GameState::Playing), unload_biome_assets)
+            .add_systems(
+                Update,
+                preload_nearby_biomes
+                    .before(ChunkSpawnSystems)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+impl Biome {
+    /// The folder under `biomes/` holding the biome's `tiles.png`, `props.png` and `calm.wav`.
+    pub fn asset_dir(self) -> &'static str {
+        match self {
+            Biome::Ocean => "ocean",
+            Biome::Plains => "plains",
+            Biome::Forest => "forest",
+            Biome::Mountains => "mountains",
+            Biome::Tundra => "tundra",
+        }
+    }
+}
+
+/// The assets a biome's chunks are drawn and scored with. Both sheets follow the layout of
+/// [`TileKind::texture_index`](crate::tiles::TileKind::texture_index): the ground layer draws
+/// from `tiles` and the [`ChunkLayer::STACKED`](crate::chunk::ChunkLayer::STACKED) layers from
+/// `props`.
+#[derive(Clone, Default)]
+pub struct BiomeAssetGroup {
+    pub tiles: Handle<Image>,
+    pub props: Handle<Image>,
+    /// Plays on the calm cue while the player is in the biome.
+    pub music: Handle<AudioSample>,
+}
+
+impl BiomeAssetGroup {
+    fn load(biome: Biome, asset_server: &AssetServer) -> Self {
+        let dir = biome.asset_dir();
+        Self {
+            tiles: asset_server.load(format!("biomes/{dir}/tiles.png")),
+            props: asset_server.load(format!("biomes/{dir}/props.png")),
+            music: asset_server.load(format!("biomes/{dir}/calm.wav")),
+        }
+    }
+
+    pub fn is_loaded(&self, asset_server: &AssetServer) -> bool {
+        asset_server.is_loaded_with_dependencies(&self.tiles)
+            && asset_server.is_loaded_with_dependencies(&self.props)
+            && asset_server.is_loaded_with_dependencies(&self.music)
+    }
+}
+
+/// Asset groups of the biomes near the player. Groups stay loaded for the rest of the session
+/// in the world.
+#[derive(Resource, Default)]
+pub struct BiomeAssets {
+    pub groups: HashMap<Biome, BiomeAssetGroup>,
+    /// The biome of the chunk the player is in.
+    pub player_biome: Option<Biome>,
+    last_player_chunk: Option<IVec2>,
+}
+
+impl BiomeAssets {
+    /// Whether the assets of `biome` have finished loading, along with their dependencies.
+    pub fn is_loaded(&self, biome: Biome, asset_server: &AssetServer) -> bool {
+        self.groups
+            .get(&biome)
+            .is_some_and(|group| group.is_loaded(asset_server))
+    }
+
+    /// The music of the biome the player is in.
+    pub fn player_music(&self) -> Option<&Handle<AudioSample>> {
+        let biome = self.player_biome?;
+        self.groups.get(&biome).map(|group| &group.music)
+    }
+
+    fn preload(&mut self, biome: Biome, asset_server: &AssetServer) {
+        if !self.groups.contains_key(&biome) {
+            debug!("Preloading assets for {biome:?}");
+            self.groups
+                .insert(biome, BiomeAssetGroup::load(biome, asset_server));
+        }
+    }
+}
+
+// Runs before the chunks spawn, so every chunk the spawning wants already has its biome's group.
+fn preload_nearby_biomes(
+    player: Single<&Transform, With<Player>>,
+    asset_server: Res<AssetServer>,
+    (world_seed, preset): (Res<WorldSeed>, Res<WorldgenPreset>),
+    chunk_manager: Res<ChunkManager>,
+    mut biome_assets: ResMut<BiomeAssets>,
+) {
+    // Chunks kept loaded elsewhere, such as a boss arena, spawn without the player nearby.
+    for chunk_pos in &chunk_manager.forced {
+        let biome = biome_at_chunk(*chunk_pos, world_seed.seed, &preset);
+        biome_assets.preload(biome, &asset_server);
+    }
+
+    let player_chunk = world_tile_to_chunk(world_pos_to_tile(player.translation.xy())).0;
+    if biome_assets.last_player_chunk == Some(player_chunk) {
+        return;
+    }
+    biome_assets.last_player_chunk = Some(player_chunk);
+    biome_assets.player_biome = Some(biome_at_chunk(player_chunk, world_seed.seed, &preset));
+
+    // Everything up to a few chunks past the render distance: the biomes in the margin are at
+    // most a few chunks from being entered, and the player may have arrived by teleporting.
+    let radius = chunk_manager.render_distance.as_ivec2() + IVec2::splat(BIOME_PRELOAD_MARGIN);
+    for y in -radius.y..=radius.y {
+        for x in -radius.x..=radius.x {
+            let biome = biome_at_chunk(player_chunk + IVec2::new(x, y), world_seed.seed, &preset);
+            biome_assets.preload(biome, &asset_server);
+        }
+    }
+}
+
+fn unload_biome_assets(mut biome_assets: ResMut<BiomeAssets>) {
+    *biome_assets = BiomeAssets::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn every_biome_ships_its_assets() {
+        let biomes = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/biomes");
+        for biome in [
+            Biome::Ocean,
+            Biome::Plains,
+            Biome::Forest,
+            Biome::Mountains,
+            Biome::Tundra,
+        ] {
+            for file in ["tiles.png", "props.png", "calm.wav"] {
+                let path = biomes.join(biome.asset_dir()).join(file);
+                assert!(path.exists(), "missing {}", path.display());
+            }
+        }
+    }
+}
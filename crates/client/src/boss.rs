@@ -0,0 +1,646 @@
+use std::f32::consts::TAU;
+
+use bevy::ecs::query::QueryData;
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use serde::Deserialize;
+
+use crate::GameState;
+use crate::ai::{AddBehavior, AiSystems, Behavior};
+use crate::chunk::{ChunkManager, Freeze, TILE_SIZE};
+use crate::collision::{TileCollider, touching, walk};
+use crate::debug_placer::{RegisterSpawnable, SpawnCategory};
+use crate::enemies::{ATTACK_COOLDOWN_SECS, CONTACT_KNOCKBACK, spawn_enemy};
+use crate::health::{Damage, Health, Killed, LifeState};
+use crate::loot::Loot;
+use crate::player::{Player, Velocity};
+use crate::projectiles::spawn_projectile;
+use crate::ron_asset::RonAssetLoader;
+use crate::spatial::Spatial;
+use crate::tiles::{WorldTiles, world_pos_to_tile, world_tile_to_chunk};
+
+/// How close the player has to come to an idle boss to start the fight, in world units.
+pub const ENGAGE_RADIUS: f32 = 6.0 * TILE_SIZE.x;
+
+/// The script of the boss in the debug placer.
+pub const SLIME_KING: &str = "slime_king";
+
+/// How far from a boss the enemies it summons appear, in world units.
+const SUMMON_DISTANCE: f32 = 20.0;
+
+/// Width of the health bar at full health, in logical pixels.
+const BOSS_BAR_WIDTH: f32 = 240.0;
+
+const BOSS_BAR_COLOR: Color = Color::srgb(0.75, 0.2, 0.3);
+
+/// Loads the boss scripts from `bosses.ron` into [`BossScripts`]. A [`Boss`] waits until the
+/// player comes within [`ENGAGE_RADIUS`], then fights them with the attacks of the phase its
+/// health is down to. The chunks of its arena stay loaded while the fight lasts. If the player
+/// dies or leaves the arena, the boss heals up and waits again. A health bar shows at the top of
+/// the screen during the fight, and every turn it takes is written as a [`BossEvent`].
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<BossScripts>()
+            .register_asset_loader(RonAssetLoader::<BossScripts>::new(&["bosses.ron"]))
+            .init_resource::<BossScripts>()
+            .add_behavior::<BossState>()
+            .add_message::<BossEvent>()
+            .add_observer(add_boss)
+            .add_observer(defeat_boss)
+            .register_spawnable("Slime King", SpawnCategory::Mob, spawn_slime_king)
+            .add_systems(OnEnter(GameState::Playing), spawn_boss_bar)
+            .add_systems(OnEnter(LifeState::Dead), disengage_bosses)
+            .add_systems(OnExit(GameState::Playing), release_arenas)
+            .add_systems(
+                Update,
+                (
+                    update_boss_scripts.run_if(on_message::<AssetEvent<BossScripts>>),
+                    (
+                        (engage_bosses, fight_player)
+                            .chain()
+                            .run_if(in_state(LifeState::Alive)),
+                        lock_arenas,
+                        update_boss_bar,
+                    )
+                        .chain()
+                        .after(AiSystems)
+                        .run_if(in_state(GameState::Playing)),
+                ),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct BossAssets {
+    #[asset(path = "bosses.ron")]
+    pub scripts: Handle<BossScripts>,
+    #[asset(path = "boss.png")]
+    pub sprite: Handle<Image>,
+}
+
+/// Every boss script by name, as defined in `bosses.ron`.
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug, Default)]
+pub struct BossScripts {
+    pub bosses: HashMap<String, BossScript>,
+}
+
+/// How a boss fights. It drops from the loot table named like its script when it is killed.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct BossScript {
+    /// Shown over its health bar.
+    pub name: String,
+    pub health: f32,
+    /// How many chunks around the boss's own make up its arena, in each direction.
+    pub arena_radius: i32,
+    /// Health the boss takes from the player each time it touches them.
+    pub contact_damage: f32,
+    /// The phases of the fight, from full health down.
+    pub phases: Vec<BossPhase>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct BossPhase {
+    /// The phase starts once the boss is down to this fraction of its health.
+    pub below: f32,
+    /// How fast the boss walks towards the player, in world units per second.
+    pub speed: f32,
+    /// Seconds between attacks.
+    pub attack_secs: f32,
+    /// Used one after another, starting over after the last.
+    pub attacks: Vec<BossAttack>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum BossAttack {
+    /// Rushes at the player at `speed` world units per second for `secs`.
+    Charge { speed: f32, secs: f32 },
+    /// Calls `count` slimes to its side.
+    Summon { count: u32 },
+    /// Shoots `count` projectiles out in every direction, with the sprite at index `sprite` in
+    /// `projectiles.png`.
+    Volley {
+        count: u32,
+        speed: f32,
+        damage: f32,
+        sprite: usize,
+    },
+}
+
+impl BossScript {
+    /// The phase of a boss with `fraction` of its health left.
+    pub fn phase_at(&self, fraction: f32) -> usize {
+        self.phases
+            .iter()
+            .rposition(|phase| fraction <= phase.below)
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Component, Clone, Debug)]
+#[require(Velocity, Freeze, Spatial, Behavior<BossState>)]
+pub struct Boss {
+    /// The name of its [`BossScript`] in [`BossScripts`].
+    pub script: String,
+    /// Index of the current phase in [`BossScript::phases`]. It only goes up over a fight.
+    pub phase: usize,
+    /// The chunks kept loaded for the fight, empty while the boss waits.
+    pub arena: Vec<IVec2>,
+    /// Index of the next attack in [`BossPhase::attacks`].
+    next_attack: usize,
+    /// Seconds until the next attack.
+    attack_secs: f32,
+    /// Seconds until the boss can hurt the player again.
+    contact_secs: f32,
+}
+
+impl Boss {
+    pub fn new(script: impl Into<String>) -> Self {
+        Self {
+            script: script.into(),
+            phase: 0,
+            arena: Vec::new(),
+            next_attack: 0,
+            attack_secs: 0.0,
+            contact_secs: 0.0,
+        }
+    }
+
+    /// Ends the fight, healing the boss back up.
+    fn disengage(&mut self, behavior: &mut Behavior<BossState>, health: &mut Health) {
+        *self = Self::new(std::mem::take(&mut self.script));
+        behavior.enter(BossState::Idle);
+        *health = Health::full(health.max);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BossState {
+    /// Waiting for the player.
+    #[default]
+    Idle,
+    Fighting,
+    /// Rushing at the player with this velocity.
+    Charging(Vec2),
+}
+
+/// A turn in a boss fight, for the music and the like to follow.
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct BossEvent {
+    pub boss: Entity,
+    pub kind: BossEventKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BossEventKind {
+    Engaged,
+    /// The boss moved on to the phase at this index.
+    PhaseChanged(usize),
+    Defeated,
+    /// The fight ended with the player dead or out of the arena.
+    Disengaged,
+}
+
+fn chunk_of(pos: Vec2) -> IVec2 {
+    world_tile_to_chunk(world_pos_to_tile(pos)).0
+}
+
+/// The chunks within `radius` of `center` in each direction.
+fn arena_around(center: IVec2, radius: i32) -> Vec<IVec2> {
+    (-radius..=radius)
+        .flat_map(|y| (-radius..=radius).map(move |x| center + IVec2::new(x, y)))
+        .collect()
+}
+
+pub fn spawn_slime_king(commands: &mut Commands, pos: Vec2) {
+    commands.spawn((
+        Name::new("Slime King"),
+        Boss::new(SLIME_KING),
+        DespawnOnExit(GameState::Playing),
+        Transform::from_translation(pos.extend(0.9)),
+        TileCollider {
+            half_size: Vec2::new(12.0, 10.0),
+        },
+    ));
+}
+
+fn add_boss(
+    add: On<Add, Boss>,
+    mut commands: Commands,
+    (scripts, boss_assets): (Res<BossScripts>, Res<BossAssets>),
+    bosses: Query<&Boss>,
+) {
+    let Ok(boss) = bosses.get(add.entity) else {
+        return;
+    };
+    let Some(script) = scripts.bosses.get(&boss.script) else {
+        warn!("No boss script named {}", boss.script);
+        return;
+    };
+    commands.entity(add.entity).insert((
+        Health::full(script.health),
+        Loot(boss.script.clone()),
+        Sprite::from_image(boss_assets.sprite.clone()),
+    ));
+}
+
+fn update_boss_scripts(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<BossScripts>>,
+    scripts: Res<Assets<BossScripts>>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event
+            && let Some(scripts) = scripts.get(*id)
+        {
+            for (name, script) in &scripts.bosses {
+                if script.phases.is_empty() {
+                    warn!("Boss {name} has no phases");
+                }
+                for (index, phase) in script.phases.iter().enumerate() {
+                    if phase.attacks.is_empty() {
+                        warn!("Phase {index} of boss {name} has no attacks");
+                    }
+                }
+            }
+            info!("Loaded {} boss scripts", scripts.bosses.len());
+            commands.insert_resource(scripts.clone());
+        }
+    }
+}
+
+/// Starts the fight with idle bosses the player comes close to, and ends it once the player
+/// leaves the arena.
+fn engage_bosses(
+    player: Single<&Transform, With<Player>>,
+    scripts: Res<BossScripts>,
+    mut bosses: Query<(
+        Entity,
+        &mut Boss,
+        &mut Behavior<BossState>,
+        &mut Health,
+        &Transform,
+    )>,
+    mut events: MessageWriter<BossEvent>,
+) {
+    let player_pos = player.translation.xy();
+    for (entity, mut boss, mut behavior, mut health, transform) in &mut bosses {
+        let Some(script) = scripts.bosses.get(&boss.script) else {
+            continue;
+        };
+        let pos = transform.translation.xy();
+        let kind = if *behavior.state() == BossState::Idle {
+            if pos.distance(player_pos) > ENGAGE_RADIUS {
+                continue;
+            }
+            behavior.enter(BossState::Fighting);
+            boss.arena = arena_around(chunk_of(pos), script.arena_radius);
+            boss.attack_secs = script.phases.first().map_or(0.0, |phase| phase.attack_secs);
+            BossEventKind::Engaged
+        } else if !boss.arena.contains(&chunk_of(player_pos)) {
+            boss.disengage(&mut behavior, &mut health);
+            BossEventKind::Disengaged
+        } else {
+            continue;
+        };
+        events.write(BossEvent { boss: entity, kind });
+    }
+}
+
+fn disengage_bosses(
+    mut bosses: Query<(Entity, &mut Boss, &mut Behavior<BossState>, &mut Health)>,
+    mut events: MessageWriter<BossEvent>,
+) {
+    for (entity, mut boss, mut behavior, mut health) in &mut bosses {
+        if *behavior.state() != BossState::Idle {
+            boss.disengage(&mut behavior, &mut health);
+            events.write(BossEvent {
+                boss: entity,
+                kind: BossEventKind::Disengaged,
+            });
+        }
+    }
+}
+
+/// What [`fight_player`] fights with.
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct BossFighter {
+    entity: Entity,
+    boss: &'static mut Boss,
+    behavior: &'static mut Behavior<BossState>,
+    health: &'static Health,
+    collider: &'static TileCollider,
+    velocity: &'static mut Velocity,
+    transform: &'static mut Transform,
+    sprite: Option<&'static mut Sprite>,
+}
+
+fn fight_player(
+    mut commands: Commands,
+    time: Res<Time>,
+    scripts: Res<BossScripts>,
+    player: Single<(Entity, &Transform, &TileCollider), With<Player>>,
+    mut bosses: Query<BossFighter>,
+    (tiles, mut damage, mut events): (WorldTiles, MessageWriter<Damage>, MessageWriter<BossEvent>),
+) {
+    let secs = time.delta_secs();
+    if secs == 0.0 {
+        return;
+    }
+    let (target, player_transform, player_collider) = player.into_inner();
+    let player_pos = player_transform.translation.xy();
+    for mut fighter in &mut bosses {
+        let Some(script) = scripts.bosses.get(&fighter.boss.script) else {
+            continue;
+        };
+        if *fighter.behavior.state() == BossState::Idle {
+            continue;
+        }
+        let phase = script
+            .phase_at(fighter.health.current / fighter.health.max)
+            .max(fighter.boss.phase);
+        if phase != fighter.boss.phase {
+            fighter.boss.phase = phase;
+            fighter.boss.next_attack = 0;
+            events.write(BossEvent {
+                boss: fighter.entity,
+                kind: BossEventKind::PhaseChanged(phase),
+            });
+        }
+        let Some(current) = script.phases.get(phase) else {
+            continue;
+        };
+
+        let pos = fighter.transform.translation.xy();
+        let towards_player = (player_pos - pos).normalize_or_zero();
+        let wanted = match *fighter.behavior.state() {
+            BossState::Charging(velocity) if !fighter.behavior.timed_out() => velocity,
+            _ => {
+                fighter.behavior.enter(BossState::Fighting);
+                towards_player * current.speed
+            }
+        };
+
+        fighter.boss.attack_secs -= secs;
+        if *fighter.behavior.state() == BossState::Fighting
+            && fighter.boss.attack_secs <= 0.0
+            && !current.attacks.is_empty()
+        {
+            fighter.boss.attack_secs = current.attack_secs;
+            let attack = current.attacks[fighter.boss.next_attack % current.attacks.len()];
+            fighter.boss.next_attack += 1;
+            match attack {
+                BossAttack::Charge { speed, secs } => fighter
+                    .behavior
+                    .enter_for(BossState::Charging(towards_player * speed), secs),
+                BossAttack::Summon { count } => {
+                    for i in 0..count {
+                        let angle = TAU * i as f32 / count as f32;
+                        spawn_enemy(
+                            &mut commands,
+                            pos + Vec2::from_angle(angle) * SUMMON_DISTANCE,
+                        );
+                    }
+                }
+                BossAttack::Volley {
+                    count,
+                    speed,
+                    damage,
+                    sprite,
+                } => {
+                    for i in 0..count {
+                        let direction = Vec2::from_angle(TAU * i as f32 / count as f32);
+                        let velocity = direction * speed;
+                        spawn_projectile(
+                            &mut commands,
+                            fighter.entity,
+                            pos,
+                            velocity,
+                            sprite,
+                            damage,
+                        );
+                    }
+                }
+            }
+        }
+
+        fighter.boss.contact_secs -= secs;
+        if fighter.boss.contact_secs <= 0.0
+            && touching(
+                pos,
+                fighter.collider.half_size,
+                player_pos,
+                player_collider.half_size,
+            )
+        {
+            fighter.boss.contact_secs = ATTACK_COOLDOWN_SECS;
+            damage.write(Damage {
+                target,
+                amount: script.contact_damage,
+                knockback: towards_player * CONTACT_KNOCKBACK,
+            });
+        }
+
+        let max_speed = wanted.length().max(current.speed);
+        let velocity = &mut fighter.velocity.0;
+        let moved = walk(
+            &tiles,
+            pos,
+            velocity,
+            wanted,
+            max_speed,
+            fighter.collider,
+            secs,
+        );
+        let moving_x = velocity.x;
+        fighter.transform.translation = moved.extend(fighter.transform.translation.z);
+        if let Some(sprite) = &mut fighter.sprite
+            && moving_x != 0.0
+        {
+            sprite.flip_x = moving_x < 0.0;
+        }
+    }
+}
+
+fn defeat_boss(
+    killed: On<Killed>,
+    bosses: Query<(), With<Boss>>,
+    mut events: MessageWriter<BossEvent>,
+) {
+    if bosses.contains(killed.entity) {
+        events.write(BossEvent {
+            boss: killed.entity,
+            kind: BossEventKind::Defeated,
+        });
+    }
+}
+
+/// Keeps the arenas of ongoing fights loaded, and only those.
+fn lock_arenas(bosses: Query<&Boss>, mut chunk_manager: ResMut<ChunkManager>) {
+    let forced: HashSet<IVec2> = bosses
+        .iter()
+        .flat_map(|boss| boss.arena.iter().copied())
+        .collect();
+    if chunk_manager.forced != forced {
+        chunk_manager.forced = forced;
+    }
+}
+
+fn release_arenas(mut chunk_manager: ResMut<ChunkManager>) {
+    chunk_manager.forced.clear();
+}
+
+#[derive(Component)]
+struct BossBar;
+
+#[derive(Component)]
+struct BossBarName;
+
+#[derive(Component)]
+struct BossBarFill;
+
+fn spawn_boss_bar(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Boss bar"),
+            BossBar,
+            DespawnOnExit(GameState::Playing),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            Visibility::Hidden,
+            Pickable::IGNORE,
+        ))
+        .with_children(|bar| {
+            bar.spawn((
+                BossBarName,
+                Text::default(),
+                TextFont::from_font_size(14.0),
+                TextShadow::default(),
+            ));
+            bar.spawn((
+                Node {
+                    width: Val::Px(BOSS_BAR_WIDTH),
+                    height: Val::Px(8.0),
+                    border: UiRect::all(Val::Px(1.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+                BorderColor::all(Color::BLACK),
+            ))
+            .with_child((
+                BossBarFill,
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(BOSS_BAR_COLOR),
+            ));
+        });
+}
+
+fn update_boss_bar(
+    scripts: Res<BossScripts>,
+    bosses: Query<(&Boss, &Behavior<BossState>, &Health)>,
+    mut bar: Single<&mut Visibility, With<BossBar>>,
+    mut name: Single<&mut Text, With<BossBarName>>,
+    mut fill: Single<&mut Node, With<BossBarFill>>,
+) {
+    let fight = bosses
+        .iter()
+        .filter(|(_, behavior, _)| *behavior.state() != BossState::Idle)
+        .find_map(|(boss, _, health)| Some((scripts.bosses.get(&boss.script)?, health)));
+    let Some((script, health)) = fight else {
+        **bar = Visibility::Hidden;
+        return;
+    };
+    **bar = Visibility::Inherited;
+    if name.0 != script.name {
+        name.0.clone_from(&script.name);
+    }
+    fill.width = Val::Percent(100.0 * health.current / health.max);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::CHUNK_SIZE;
+
+    #[test]
+    fn bosses_lock_their_arena_and_heal_up_when_the_player_leaves() {
+        let scripts: BossScripts = ron::from_str(include_str!("../assets/bosses.ron")).unwrap();
+        let script = scripts.bosses[SLIME_KING].clone();
+        assert_eq!(script.phase_at(1.0), 0);
+        assert_eq!(script.phase_at(0.5), 1);
+        assert_eq!(script.phase_at(0.1), 2);
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_message::<BossEvent>()
+            .insert_resource(scripts)
+            .init_resource::<ChunkManager>()
+            .add_systems(Update, (engage_bosses, lock_arenas).chain());
+        let chunk_width = CHUNK_SIZE.x as f32 * TILE_SIZE.x;
+        let center = Vec2::splat(chunk_width / 2.0);
+        let player = app
+            .world_mut()
+            .spawn((Player, Transform::from_translation(center.extend(0.0))))
+            .id();
+        let boss_pos = center + Vec2::X * ENGAGE_RADIUS * 2.0;
+        let boss = app
+            .world_mut()
+            .spawn((
+                Boss::new(SLIME_KING),
+                Health::full(script.health),
+                Transform::from_translation(boss_pos.extend(0.0)),
+            ))
+            .id();
+        let mut cursor = app.world().resource::<Messages<BossEvent>>().get_cursor();
+        let mut step = |app: &mut App, player_pos: Vec2| {
+            app.world_mut()
+                .get_mut::<Transform>(player)
+                .unwrap()
+                .translation = player_pos.extend(0.0);
+            app.update();
+            let messages = app.world().resource::<Messages<BossEvent>>();
+            let events: Vec<_> = cursor.read(messages).map(|event| event.kind).collect();
+            (
+                events,
+                app.world().resource::<ChunkManager>().forced.clone(),
+            )
+        };
+
+        let (events, forced) = step(&mut app, center);
+        assert!(events.is_empty() && forced.is_empty());
+
+        // Coming close starts the fight and loads the arena around the boss.
+        let (events, forced) = step(&mut app, center + Vec2::X * ENGAGE_RADIUS * 1.5);
+        assert_eq!(events, vec![BossEventKind::Engaged]);
+        let side = 2 * script.arena_radius as usize + 1;
+        assert_eq!(forced.len(), side * side);
+        assert!(forced.contains(&chunk_of(boss_pos)));
+
+        // Running out of the arena ends it.
+        app.world_mut().get_mut::<Health>(boss).unwrap().current = 5.0;
+        let outside = boss_pos + Vec2::NEG_X * chunk_width * (script.arena_radius + 1) as f32;
+        let (events, forced) = step(&mut app, outside);
+        assert_eq!(events, vec![BossEventKind::Disengaged]);
+        assert!(forced.is_empty());
+        let boss = app.world().entity(boss);
+        assert_eq!(*boss.get::<Health>().unwrap(), Health::full(script.health));
+        assert_eq!(
+            *boss.get::<Behavior<BossState>>().unwrap().state(),
+            BossState::Idle
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy::transform::TransformSystems;
+
+use crate::GameState;
+use crate::health::Hurt;
+use crate::player::{CameraFollow, Player, follow_player};
+
+/// How far from the camera a hit can be and still shake it, in world units.
+const HIT_SHAKE_RANGE: f32 = 160.0;
+
+/// Strength, in world units, below which a shake has died out.
+const MIN_AMPLITUDE: f32 = 0.05;
+
+/// Shakes the camera on [`CameraShake`] messages, on top of where it follows the player and before
+/// [`PixelSnapPlugin`](crate::pixel_snap::PixelSnapPlugin) snaps it, so the shake steps along the
+/// pixel grid like any other camera movement. The offset is taken back off at the start of every
+/// frame, so gameplay never sees the shaken position. Hits the player takes shake it hard, and
+/// hits nearby lightly.
+pub struct CameraShakePlugin;
+
+impl Plugin for CameraShakePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<CameraShake>()
+            .add_systems(PreUpdate, unshake_camera)
+            .add_systems(
+                Update,
+                shake_on_hurt
+                    .run_if(on_message::<Hurt>)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                PostUpdate,
+                shake_camera
+                    .after(follow_player)
+                    .before(TransformSystems::Propagate),
+            );
+    }
+}
+
+/// Shakes the camera by up to `amplitude` world units, `frequency` times a second, fading out at
+/// an exponential `decay` rate per second. Shakes that overlap add up.
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct CameraShake {
+    pub amplitude: f32,
+    pub frequency: f32,
+    pub decay: f32,
+}
+
+impl CameraShake {
+    /// The player getting hit.
+    pub const HIT: Self = Self {
+        amplitude: 3.0,
+        frequency: 18.0,
+        decay: 8.0,
+    };
+    /// Something else getting hit near the camera.
+    pub const NEARBY_HIT: Self = Self {
+        amplitude: 1.0,
+        frequency: 20.0,
+        decay: 12.0,
+    };
+    /// A thunderclap in a storm, a long low rumble.
+    pub const THUNDER: Self = Self {
+        amplitude: 2.0,
+        frequency: 7.0,
+        decay: 1.5,
+    };
+
+    /// How strong the shake still is `secs` after it started.
+    pub fn strength(&self, secs: f32) -> f32 {
+        self.amplitude * (-self.decay * secs).exp()
+    }
+
+    /// How far the shake moves the camera `secs` after it started. The axes swing at slightly
+    /// different rates so the camera wobbles around instead of along a line.
+    pub fn offset(&self, secs: f32) -> Vec2 {
+        let phase = self.frequency * secs * TAU;
+        Vec2::new(phase.sin(), (phase * 1.3 + 1.0).sin()) * self.strength(secs)
+    }
+}
+
+/// The shakes a camera is in the middle of, each with the seconds since it started.
+#[derive(Component, Clone, Debug, Default)]
+pub struct Shaking {
+    shakes: Vec<(CameraShake, f32)>,
+    /// The offset applied to the camera this frame.
+    offset: Vec2,
+}
+
+fn unshake_camera(mut cameras: Query<(&mut Transform, &mut Shaking)>) {
+    for (mut transform, mut shaking) in &mut cameras {
+        if shaking.offset != Vec2::ZERO {
+            transform.translation -= std::mem::take(&mut shaking.offset).extend(0.0);
+        }
+    }
+}
+
+fn shake_on_hurt(
+    mut hurt: MessageReader<Hurt>,
+    players: Query<(), With<Player>>,
+    camera: Single<&Transform, With<CameraFollow>>,
+    mut shakes: MessageWriter<CameraShake>,
+) {
+    let camera_pos = camera.translation.xy();
+    for hurt in hurt.read() {
+        if players.contains(hurt.target) {
+            shakes.write(CameraShake::HIT);
+        } else if hurt.pos.distance(camera_pos) < HIT_SHAKE_RANGE {
+            shakes.write(CameraShake::NEARBY_HIT);
+        }
+    }
+}
+
+fn shake_camera(
+    time: Res<Time>,
+    mut new_shakes: MessageReader<CameraShake>,
+    mut cameras: Query<(&mut Transform, &mut Shaking)>,
+) {
+    let new_shakes: Vec<_> = new_shakes.read().copied().collect();
+    for (mut transform, mut shaking) in &mut cameras {
+        shaking
+            .shakes
+            .extend(new_shakes.iter().map(|&shake| (shake, 0.0)));
+        shaking.shakes.retain_mut(|(shake, secs)| {
+            *secs += time.delta_secs();
+            shake.strength(*secs) > MIN_AMPLITUDE
+        });
+        if shaking.shakes.is_empty() {
+            continue;
+        }
+        let offset = shaking
+            .shakes
+            .iter()
+            .map(|(shake, secs)| shake.offset(*secs))
+            .sum();
+        shaking.offset = offset;
+        transform.translation += offset.extend(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    #[test]
+    fn shakes_fade_out_and_leave_the_camera_where_it_was() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                50,
+            )))
+            .add_message::<CameraShake>()
+            .add_systems(PreUpdate, unshake_camera)
+            .add_systems(PostUpdate, shake_camera);
+        let start = Vec3::new(10.0, 20.0, 5.0);
+        let camera = app
+            .world_mut()
+            .spawn((Transform::from_translation(start), Shaking::default()))
+            .id();
+        app.update();
+        app.world_mut().write_message(CameraShake::HIT);
+
+        let mut moved = Vec::new();
+        for _ in 0..20 {
+            app.update();
+            let translation = app.world().get::<Transform>(camera).unwrap().translation;
+            assert_eq!(translation.z, start.z);
+            let offset = translation.xy() - start.xy();
+            assert!(offset.length() <= CameraShake::HIT.amplitude * 2.0_f32.sqrt());
+            moved.push(offset);
+        }
+        assert!(moved[0] != Vec2::ZERO);
+        // A second's worth of frames later it has died out completely.
+        assert!(
+            app.world()
+                .get::<Shaking>(camera)
+                .unwrap()
+                .shakes
+                .is_empty()
+        );
+        assert!(moved.last().unwrap().length() < 1e-4);
+    }
+}
@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::GameState;
+use crate::localization::Localization;
+use crate::settings::Settings;
+
+/// The changelog shipped with this build.
+const CHANGELOG: &str = include_str!("../CHANGELOG.md");
+
+/// Shows the bundled changelog on the main menu. Opens by itself when the game was
+/// updated since the player last looked, with the new releases highlighted.
+pub struct ChangelogPlugin;
+
+impl Plugin for ChangelogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChangelogPanel>()
+            .add_systems(OnEnter(GameState::MainMenu), open_if_updated)
+            .add_systems(
+                EguiPrimaryContextPass,
+                changelog_ui.run_if(in_state(GameState::MainMenu)),
+            );
+    }
+}
+
+/// A version heading and its notes, as written in `CHANGELOG.md`.
+#[derive(Debug, PartialEq)]
+pub struct Release<'a> {
+    pub version: &'a str,
+    pub notes: Vec<&'a str>,
+}
+
+/// Splits a changelog into releases, newest first.
+pub fn parse_changelog(text: &str) -> Vec<Release<'_>> {
+    let mut releases = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if let Some(version) = line.strip_prefix("## ") {
+            releases.push(Release {
+                version: version.trim(),
+                notes: Vec::new(),
+            });
+        } else if let (Some(note), Some(release)) = (line.strip_prefix("- "), releases.last_mut()) {
+            release.notes.push(note);
+        }
+    }
+    releases
+}
+
+/// Whether `version` is newer than `last_seen`. Every release is new to players who have never
+/// opened the changelog.
+pub fn is_newer(version: &str, last_seen: Option<&str>) -> bool {
+    let Some(last_seen) = last_seen else {
+        return true;
+    };
+    let parse = |version: &str| -> Vec<u32> {
+        version
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    parse(version) > parse(last_seen)
+}
+
+#[derive(Resource, Default)]
+struct ChangelogPanel {
+    open: bool,
+    /// Releases newer than this are highlighted, taken from the settings when the panel opened.
+    last_seen_version: Option<String>,
+}
+
+fn open_if_updated(mut panel: ResMut<ChangelogPanel>, settings: Res<Settings>) {
+    panel.last_seen_version = settings.last_seen_version.clone();
+    panel.open = is_newer(
+        env!("CARGO_PKG_VERSION"),
+        panel.last_seen_version.as_deref(),
+    );
+}
+
+fn changelog_ui(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<ChangelogPanel>,
+    mut settings: ResMut<Settings>,
+    localization: Res<Localization>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    egui::Area::new(egui::Id::new("changelog_button"))
+        .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-8.0, 8.0))
+        .show(ctx, |ui| {
+            if ui.button(localization.get("changelog-title")).clicked() {
+                panel.open = !panel.open;
+            }
+        });
+
+    let mut open = panel.open;
+    egui::Window::new(localization.get("changelog-title"))
+        .open(&mut open)
+        .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-8.0, 40.0))
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for release in parse_changelog(CHANGELOG) {
+                    let heading = egui::RichText::new(release.version).heading();
+                    if is_newer(release.version, panel.last_seen_version.as_deref()) {
+                        ui.label(heading.color(egui::Color32::YELLOW));
+                    } else {
+                        ui.label(heading);
+                    }
+                    for note in release.notes {
+                        ui.label(format!("• {note}"));
+                    }
+                    ui.separator();
+                }
+            });
+        });
+    panel.open = open;
+
+    let version = env!("CARGO_PKG_VERSION");
+    if panel.open && settings.last_seen_version.as_deref() != Some(version) {
+        settings.last_seen_version = Some(version.to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_changelog_lists_this_version() {
+        let releases = parse_changelog(CHANGELOG);
+        assert_eq!(releases[0].version, env!("CARGO_PKG_VERSION"));
+        assert!(!releases[0].notes.is_empty());
+    }
+
+    #[test]
+    fn compares_versions_numerically() {
+        assert!(is_newer("0.10.0", Some("0.9.3")));
+        assert!(is_newer("1.0", Some("0.9")));
+        assert!(!is_newer("0.1.0", Some("0.1.0")));
+        assert!(!is_newer("0.1.0", Some("0.2.0")));
+        assert!(is_newer("0.1.0", None));
+    }
+}
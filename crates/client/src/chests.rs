@@ -0,0 +1,254 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_egui::input::egui_wants_any_pointer_input;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::GameState;
+use crate::chunk::ChunkManager;
+use crate::debug_placer::placing;
+use crate::dungeons::Locked;
+use crate::health::LifeState;
+use crate::inventory::{Inventory, ItemRegistry};
+use crate::item_drops::spawn_item_drop;
+use crate::localization::Localization;
+use crate::picking::{PickedTile, TilePicking};
+use crate::player::Player;
+use crate::terraform::brush_selected;
+use crate::tile_highlight::in_reach;
+use crate::tiles::{TileKind, TileLocator, tile_to_world_pos};
+
+/// Slots in a chest.
+pub const CHEST_SLOTS: usize = 18;
+
+/// How far spilled items land from the center of their chest, in world units.
+const SPILL_RADIUS: f32 = 4.0;
+
+/// Gives every [`TileKind::Chest`] tile its own [`Inventory`], saved with its chunk. Right-clicking
+/// a chest within reach opens a window for moving stacks between it and the player's inventory.
+/// A chest that is broken or replaced spills what it held as item drops. [`Locked`] chests don't
+/// open.
+pub struct ChestsPlugin;
+
+impl Plugin for ChestsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OpenChest>()
+            .add_systems(
+                Update,
+                (
+                    sync_chest_inventories,
+                    open_chests
+                        .run_if(in_state(LifeState::Alive))
+                        .run_if(not(egui_wants_any_pointer_input))
+                        .run_if(not(brush_selected))
+                        .run_if(not(placing)),
+                    close_chests,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnEnter(LifeState::Dead), close_chest)
+            .add_systems(OnExit(GameState::Playing), close_chest)
+            .add_systems(
+                EguiPrimaryContextPass,
+                chest_window.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// The chest whose window is open.
+#[derive(Resource, Default)]
+struct OpenChest(Option<PickedTile>);
+
+fn sync_chest_inventories(
+    mut commands: Commands,
+    registry: Res<ItemRegistry>,
+    tiles: Query<(Entity, &TileKind, Option<&Inventory>), Changed<TileKind>>,
+    locator: TileLocator,
+) {
+    for (entity, kind, inventory) in &tiles {
+        match (kind, inventory) {
+            (TileKind::Chest, None) => {
+                commands.entity(entity).insert(Inventory::new(CHEST_SLOTS));
+            }
+            (TileKind::Chest, Some(_)) | (_, None) => {}
+            (_, Some(inventory)) => {
+                commands.entity(entity).remove::<Inventory>();
+                let Some(world_tile) = locator.world_tile(entity) else {
+                    continue;
+                };
+                let center = tile_to_world_pos(world_tile);
+                let stacks = inventory.slots.iter().flatten();
+                let count = stacks.clone().count();
+                for (index, stack) in stacks.enumerate() {
+                    let angle = index as f32 / count as f32 * TAU;
+                    let origin = center + Vec2::from_angle(angle) * SPILL_RADIUS;
+                    spawn_item_drop(&mut commands, &registry, stack.clone(), origin);
+                }
+            }
+        }
+    }
+}
+
+type UnlockedChest = (With<Inventory>, With<TileKind>, Without<Locked>);
+
+fn open_chests(
+    mouse: Res<ButtonInput<MouseButton>>,
+    picking: TilePicking,
+    chests: Query<(), UnlockedChest>,
+    player: Single<&Transform, With<Player>>,
+    mut open_chest: ResMut<OpenChest>,
+) {
+    if !mouse.just_pressed(MouseButton::Right) {
+        return;
+    }
+    if let Some(picked) = picking.under_cursor().filter(|picked| {
+        chests.contains(picked.entity) && in_reach(player.translation.xy(), picked.world_tile)
+    }) {
+        open_chest.0 = Some(picked);
+    }
+}
+
+fn close_chests(
+    mut open_chest: ResMut<OpenChest>,
+    chests: Query<(), (With<Inventory>, With<TileKind>)>,
+    player: Single<&Transform, With<Player>>,
+) {
+    if let Some(picked) = open_chest.0
+        && (!chests.contains(picked.entity)
+            || !in_reach(player.translation.xy(), picked.world_tile))
+    {
+        open_chest.0 = None;
+    }
+}
+
+fn close_chest(mut open_chest: ResMut<OpenChest>) {
+    open_chest.0 = None;
+}
+
+fn chest_window(
+    mut contexts: EguiContexts,
+    registry: Res<ItemRegistry>,
+    localization: Res<Localization>,
+    mut open_chest: ResMut<OpenChest>,
+    mut chunk_manager: ResMut<ChunkManager>,
+    mut player: Single<&mut Inventory, With<Player>>,
+    mut chests: Query<&mut Inventory, (With<TileKind>, Without<Player>)>,
+) -> Result {
+    let Some(picked) = open_chest.0 else {
+        return Ok(());
+    };
+    let Ok(mut chest) = chests.get_mut(picked.entity) else {
+        return Ok(());
+    };
+
+    let mut open = true;
+    let mut moved = false;
+    egui::Window::new(localization.get("chest-title"))
+        .id(egui::Id::new("chest"))
+        .open(&mut open)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.label(localization.get("chest-hint"));
+            ui.columns(2, |columns| {
+                columns[0].label(localization.get("chest-title"));
+                moved |= stack_buttons(
+                    &mut columns[0],
+                    &mut chest,
+                    &mut player,
+                    &registry,
+                    &localization,
+                );
+                columns[1].label(localization.get("chest-inventory"));
+                moved |= stack_buttons(
+                    &mut columns[1],
+                    &mut player,
+                    &mut chest,
+                    &registry,
+                    &localization,
+                );
+            });
+        });
+    if moved {
+        chunk_manager.dirty_chunks.insert(picked.chunk_pos);
+    }
+    if !open {
+        open_chest.0 = None;
+    }
+    Ok(())
+}
+
+/// A button for each stack in `from` that moves it into `to`. Returns whether a stack was moved.
+fn stack_buttons(
+    ui: &mut egui::Ui,
+    from: &mut Inventory,
+    to: &mut Inventory,
+    registry: &ItemRegistry,
+    localization: &Localization,
+) -> bool {
+    let mut clicked = None;
+    for (index, slot) in from.slots.iter().enumerate() {
+        let Some(stack) = slot else {
+            continue;
+        };
+        let name = localization.item_name(registry, &stack.item);
+        if ui.button(format!("{name} × {}", stack.count)).clicked() {
+            clicked = Some(index);
+        }
+    }
+    clicked.is_some_and(|index| from.move_stack(index, to, registry))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs_tilemap::prelude::*;
+
+    use super::*;
+    use crate::chunk::ChunkPosition;
+    use crate::inventory::ItemId;
+    use crate::item_drops::ItemDrop;
+    use crate::tiles::{world_pos_to_tile, world_tile_to_chunk};
+
+    #[test]
+    fn chests_get_an_inventory_and_spill_it_when_broken() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(
+                ron::from_str::<ItemRegistry>(include_str!("../assets/items.ron")).unwrap(),
+            )
+            .add_systems(Update, sync_chest_inventories);
+        let chunk = app.world_mut().spawn(ChunkPosition(IVec2::new(-1, 0))).id();
+        let chest = app
+            .world_mut()
+            .spawn((TileKind::Chest, TilePos::new(9, 2), TilemapId(chunk)))
+            .id();
+        app.update();
+
+        let registry = app.world().resource::<ItemRegistry>().clone();
+        let stick = ItemId::from("stick");
+        let mut player = Inventory::new(1);
+        player.add(&stick, 99, &registry);
+        let mut inventory = app.world_mut().get_mut::<Inventory>(chest).unwrap();
+        assert_eq!(inventory.slots.len(), CHEST_SLOTS);
+        // Only what fits in the chest is moved.
+        inventory.add(&stick, 99 * (CHEST_SLOTS as u32 - 1) + 19, &registry);
+        assert!(player.move_stack(0, &mut inventory, &registry));
+        assert_eq!(player.count(&stick), 19);
+        assert!(!player.move_stack(0, &mut inventory, &registry));
+
+        *app.world_mut().get_mut::<TileKind>(chest).unwrap() = TileKind::Grass;
+        app.update();
+        assert!(app.world().get::<Inventory>(chest).is_none());
+        let mut drops = app.world_mut().query::<(&ItemDrop, &Transform)>();
+        let drops: Vec<_> = drops.iter(app.world()).collect();
+        assert_eq!(drops.len(), CHEST_SLOTS);
+        assert_eq!(
+            drops.iter().map(|(drop, _)| drop.count).sum::<u32>(),
+            99 * CHEST_SLOTS as u32
+        );
+        // Tile (-1, 2) is at the right edge of chunk (-1, 0).
+        assert!(drops.iter().all(|(_, transform)| {
+            world_tile_to_chunk(world_pos_to_tile(transform.translation.xy())).0
+                == IVec2::new(-1, 0)
+        }));
+    }
+}
@@ -0,0 +1,622 @@
+use bevy::ecs::entity_disabling::Disabled;
+use bevy::ecs::query::QueryData;
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::GameState;
+use crate::biome_assets::{BiomeAssetGroup, BiomeAssets};
+use crate::console::{RegisterConsoleCommand, player_tile};
+use crate::dungeons::{Locked, build_dungeon};
+use crate::farming::Crop;
+#[cfg(feature = "gpu_worldgen")]
+use crate::gpu_worldgen::GpuWorldgen;
+use crate::inventory::Inventory;
+use crate::loot::Loot;
+use crate::pause::paused;
+use crate::persistence::{ChunkData, WorldSave};
+use crate::player::Player;
+use crate::save::{restore_chunk_entities, unload_chunk_entities};
+use crate::tiles::{
+    TileKind, WorldTiles, chunk_tile_to_world, tile_to_world_pos, update_shorelines,
+    update_tile_textures, world_pos_to_tile, world_tile_to_chunk,
+};
+use crate::villagers::build_village;
+use crate::wind::{Vegetation, VegetationMaterial};
+use crate::worldgen::{WorldSeed, WorldgenPreset, biome_at_chunk, get_tile_type};
+
+pub const TILE_SIZE: TilemapTileSize = TilemapTileSize { x: 16.0, y: 16.0 };
+pub const CHUNK_SIZE: UVec2 = UVec2 { x: 10, y: 10 };
+pub const CHUNK_RENDER_DISTANCE: UVec2 = UVec2 { x: 2, y: 2 };
+
+pub struct ChunkPlugin;
+
+impl Plugin for ChunkPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ChunkManager::default())
+            .add_message::<ChunkLoaded>()
+            .add_message::<ChunkUnloaded>()
+            .add_systems(
+                Update,
+                spawn_chunks_around_player
+                    .in_set(ChunkSpawnSystems)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(not(paused)),
+            )
+            .add_systems(
+                Update,
+                despawn_outofrange_chunks
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(not(paused)),
+            )
+            .add_systems(
+                OnExit(GameState::Playing),
+                (despawn_frozen_entities, despawn_chunks),
+            )
+            .add_systems(PostUpdate, (update_tile_textures, update_shorelines))
+            .add_observer(write_chunk_loaded)
+            .register_console_command("chunk regen", "chunk regen", regenerate_chunk);
+    }
+}
+
+/// Where the chunks around the player spawn. Systems preparing what new chunks need go before it.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChunkSpawnSystems;
+
+/// Puts the tiles of the chunk the player is in back the way the world generated them.
+fn regenerate_chunk(world: &mut World, _: &[&str]) -> Result<String, String> {
+    let (chunk_pos, _) = world_tile_to_chunk(player_tile(world)?);
+    world
+        .run_system_cached_with(
+            |In(chunk_pos): In<IVec2>,
+             mut tiles: WorldTiles,
+             (world_seed, preset): (Res<WorldSeed>, Res<WorldgenPreset>)| {
+                let data = generate_chunk(world_seed.seed, &preset, chunk_pos);
+                for (index, kind) in data.tiles.into_iter().enumerate() {
+                    tiles.set_tile(
+                        chunk_tile_to_world(chunk_pos, tile_pos_from_index(index)),
+                        kind,
+                    );
+                }
+            },
+            chunk_pos,
+        )
+        .map_err(|err| err.to_string())?;
+    Ok(format!("Regenerated chunk {chunk_pos}"))
+}
+
+#[derive(Debug, Resource)]
+pub struct ChunkManager {
+    /// The tilemap entity of each loaded chunk, which draws its [`ChunkLayer::Ground`].
+    pub spawned_chunks: HashMap<IVec2, Entity>,
+    /// The tilemaps of the [`ChunkLayer::STACKED`] layers of each loaded chunk, in order. They
+    /// are children of the chunk's tilemap entity.
+    pub layers: HashMap<IVec2, [Entity; 3]>,
+    /// Loaded chunks whose tiles were modified since they were last stored. They are stored in
+    /// the [`WorldSave`] when they unload or the world is autosaved.
+    pub dirty_chunks: HashSet<IVec2>,
+    /// How many chunks are kept loaded around the player in each direction.
+    pub render_distance: UVec2,
+    /// The [`Freeze`] entities of unloaded chunks, disabled until their chunk loads again.
+    pub frozen: HashMap<IVec2, Vec<Entity>>,
+    /// Chunks kept loaded wherever the player is, like the arena of a boss fight.
+    pub forced: HashSet<IVec2>,
+}
+
+impl Default for ChunkManager {
+    fn default() -> Self {
+        Self {
+            spawned_chunks: HashMap::default(),
+            layers: HashMap::default(),
+            dirty_chunks: HashSet::default(),
+            render_distance: CHUNK_RENDER_DISTANCE,
+            frozen: HashMap::default(),
+            forced: HashSet::default(),
+        }
+    }
+}
+
+impl ChunkManager {
+    /// The tilemap entity drawing `layer` of the chunk at `chunk_pos`, or `None` if the chunk
+    /// isn't loaded.
+    pub fn layer(&self, chunk_pos: IVec2, layer: ChunkLayer) -> Option<Entity> {
+        match layer {
+            ChunkLayer::Ground => self.spawned_chunks.get(&chunk_pos).copied(),
+            _ => Some(self.layers.get(&chunk_pos)?[layer as usize - 1]),
+        }
+    }
+
+    /// Parks the frozen `entity` in the chunk at `chunk_pos` instead of wherever it was, for a
+    /// frozen entity that has been moved. Returns `true` if that chunk is loaded, in which case
+    /// the entity isn't parked anywhere and should be thawed by removing its [`Disabled`].
+    pub fn move_frozen(&mut self, entity: Entity, chunk_pos: IVec2) -> bool {
+        for entities in self.frozen.values_mut() {
+            entities.retain(|frozen| *frozen != entity);
+        }
+        self.frozen.retain(|_, entities| !entities.is_empty());
+        if self.spawned_chunks.contains_key(&chunk_pos) {
+            return true;
+        }
+        self.frozen.entry(chunk_pos).or_default().push(entity);
+        false
+    }
+}
+
+/// The tilemaps stacked up in every chunk, from the bottom up, so that what grows, stands and
+/// settles on a tile is drawn over the ground instead of in its place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChunkLayer {
+    /// The chunk's own tilemap, whose tiles carry the [`TileKind`] and any other state of the
+    /// tile. There is a ground tile at every position of a chunk.
+    Ground,
+    /// Crops and flowers.
+    Decoration,
+    /// Chests, torches and campfires.
+    Object,
+    /// Snow settling over everything else in winter.
+    Overlay,
+}
+
+impl ChunkLayer {
+    pub const ALL: [Self; 4] = [Self::Ground, Self::Decoration, Self::Object, Self::Overlay];
+
+    /// The layers over the ground, whose tiles are only there where something is drawn in them.
+    pub const STACKED: [Self; 3] = [Self::Decoration, Self::Object, Self::Overlay];
+
+    /// How far above the ground the layer is drawn. Everything in the layers stays below tile
+    /// highlights and anything walking around.
+    pub fn z(self) -> f32 {
+        self as usize as f32 * 0.1
+    }
+}
+
+/// Written once a chunk's tilemap and tiles exist at the given chunk position.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct ChunkLoaded(pub IVec2, pub Entity);
+
+/// Written when the chunk at the given chunk position has been despawned.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct ChunkUnloaded(pub IVec2);
+
+#[derive(Component)]
+pub struct ChunkMarker;
+
+/// The chunk coordinate of a chunk's tilemap entity.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ChunkPosition(pub IVec2);
+
+#[derive(Component)]
+pub struct TerrainChunk;
+
+/// Marks an entity to be frozen when the chunk it is in unloads, instead of running on without
+/// any tiles around it. It is parked in [`ChunkManager::frozen`] with the [`Disabled`] component
+/// and carries on where it left off once the chunk has loaded again. Frozen entities aren't
+/// saved, unlike [`Persist`](crate::save::Persist) ones.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Freeze;
+
+/// The chunk `world_pos` is in.
+pub fn world_pos_to_chunk_pos(world_pos: &Vec2) -> IVec2 {
+    world_tile_to_chunk(world_pos_to_tile(*world_pos)).0
+}
+
+fn tile_pos_from_index(index: usize) -> TilePos {
+    let index = index as u32;
+    TilePos {
+        x: index % CHUNK_SIZE.x,
+        y: index / CHUNK_SIZE.x,
+    }
+}
+
+/// Generates the tiles of the chunk at `chunk_pos`, indexed by `TilePos::to_index`, along with
+/// any dungeon or village built into it.
+pub fn generate_chunk(world_seed: u64, preset: &WorldgenPreset, chunk_pos: IVec2) -> ChunkData {
+    let tiles = (0..(CHUNK_SIZE.x * CHUNK_SIZE.y) as usize)
+        .map(|index| {
+            let tile_pos = tile_pos_from_index(index);
+            let world_x = chunk_pos.x * CHUNK_SIZE.x as i32 + tile_pos.x as i32;
+            let world_y = chunk_pos.y * CHUNK_SIZE.y as i32 + tile_pos.y as i32;
+            get_tile_type(world_x, world_y, world_seed, preset)
+        })
+        .collect();
+    let mut data = ChunkData { tiles, ..default() };
+    build_dungeon(world_seed, preset, chunk_pos, &mut data);
+    build_village(world_seed, preset, chunk_pos, &mut data);
+    data
+}
+
+/// Spawns the chunk at `chunk_pos` with the given tiles, drawn with the assets of its biome, and
+/// returns its tilemap entity.
+pub fn spawn_chunk(
+    commands: &mut Commands,
+    biome_assets: &BiomeAssetGroup,
+    chunk_pos: IVec2,
+    chunk_data: ChunkData,
+) -> Entity {
+    let tilemap_entity = commands.spawn_empty().id();
+    insert_chunk(
+        commands,
+        tilemap_entity,
+        biome_assets,
+        chunk_pos,
+        chunk_data,
+    );
+    tilemap_entity
+}
+
+/// Turns `tilemap_entity` into the chunk at `chunk_pos`. The tiles and tilemap components are
+/// added by a single queued command that batch-spawns the ground tiles, along with the empty
+/// tilemaps of the [`ChunkLayer::STACKED`] layers, and chests get their [`Inventory`] back.
+/// [`update_tile_textures`] fills the layers in as the new tiles call for it. The ground is drawn
+/// from the biome's tiles and the stacked layers from its props, all with the shared
+/// [`Vegetation`] material.
+pub fn insert_chunk(
+    commands: &mut Commands,
+    tilemap_entity: Entity,
+    biome_assets: &BiomeAssetGroup,
+    chunk_pos: IVec2,
+    chunk_data: ChunkData,
+) {
+    let tile_positions: Vec<TilePos> = (0..chunk_data.tiles.len())
+        .map(tile_pos_from_index)
+        .collect();
+    let containers = chunk_data.containers;
+    let crops = chunk_data.crops;
+    let locks = chunk_data.locks;
+    let loot = chunk_data.loot;
+    let tiles: Vec<_> = tile_positions
+        .iter()
+        .zip(chunk_data.tiles)
+        .map(|(tile_pos, tile_kind)| {
+            (
+                TileBundle {
+                    position: *tile_pos,
+                    tilemap_id: TilemapId(tilemap_entity),
+                    texture_index: TileTextureIndex(tile_kind.texture_index()),
+                    ..default()
+                },
+                tile_kind,
+            )
+        })
+        .collect();
+
+    let origin = tile_to_world_pos(chunk_pos * CHUNK_SIZE.as_ivec2());
+    let transform = Transform::from_translation(origin.extend(0.0));
+    let texture = TilemapTexture::Single(biome_assets.tiles.clone());
+    let props = TilemapTexture::Single(biome_assets.props.clone());
+
+    commands.queue(move |world: &mut World| {
+        let tile_entities: Vec<Entity> = world.spawn_batch(tiles).collect();
+        let mut tile_storage = TileStorage::empty(CHUNK_SIZE.into());
+        for (tile_pos, tile_entity) in tile_positions.iter().zip(&tile_entities) {
+            tile_storage.set(tile_pos, *tile_entity);
+        }
+        insert_tile_state(world, &tile_entities, chunk_pos, containers);
+        insert_tile_state(world, &tile_entities, chunk_pos, crops);
+        insert_tile_state(world, &tile_entities, chunk_pos, locks);
+        insert_tile_state(world, &tile_entities, chunk_pos, loot);
+        let material = world.resource::<Vegetation>().0.clone();
+
+        let layers = ChunkLayer::STACKED.map(|layer| {
+            let storage = TileStorage::empty(CHUNK_SIZE.into());
+            let transform = Transform::from_xyz(0.0, 0.0, layer.z());
+            world
+                .spawn((
+                    chunk_tilemap(storage, transform, &props, &material),
+                    ChildOf(tilemap_entity),
+                ))
+                .id()
+        });
+        world
+            .resource_mut::<ChunkManager>()
+            .layers
+            .insert(chunk_pos, layers);
+        world
+            .entity_mut(tilemap_entity)
+            .add_children(&tile_entities)
+            .insert((
+                chunk_tilemap(tile_storage, transform, &texture, &material),
+                ChunkMarker,
+                ChunkPosition(chunk_pos),
+                TerrainChunk,
+            ));
+    });
+}
+
+/// The tilemap of one [`ChunkLayer`] of a chunk, holding the tiles in `storage`.
+fn chunk_tilemap(
+    storage: TileStorage,
+    transform: Transform,
+    texture: &TilemapTexture,
+    material: &Handle<VegetationMaterial>,
+) -> MaterialTilemapBundle<VegetationMaterial> {
+    MaterialTilemapBundle {
+        grid_size: TILE_SIZE.into(),
+        size: CHUNK_SIZE.into(),
+        storage,
+        texture: texture.clone(),
+        tile_size: TILE_SIZE,
+        transform,
+        render_settings: TilemapRenderSettings {
+            render_chunk_size: CHUNK_SIZE,
+            ..Default::default()
+        },
+        material: MaterialTilemapHandle::from(material.clone()),
+        ..Default::default()
+    }
+}
+
+/// Adds saved per-tile components, such as a chest's [`Inventory`], back to the tiles of a chunk.
+fn insert_tile_state<C: Component>(
+    world: &mut World,
+    tile_entities: &[Entity],
+    chunk_pos: IVec2,
+    states: Vec<(u16, C)>,
+) {
+    for (index, state) in states {
+        match tile_entities.get(index as usize) {
+            Some(tile_entity) => {
+                world.entity_mut(*tile_entity).insert(state);
+            }
+            None => warn!("Chunk {chunk_pos} has saved tile state outside of it at {index}"),
+        }
+    }
+}
+
+/// The components of a tile that are saved with its chunk.
+#[derive(QueryData)]
+pub struct SavedTile {
+    kind: &'static TileKind,
+    inventory: Option<&'static Inventory>,
+    crop: Option<&'static Crop>,
+    locked: Option<&'static Locked>,
+    loot: Option<&'static Loot>,
+}
+
+/// Reads the current tiles of a spawned chunk and their [`SavedTile`] state back into
+/// [`ChunkData`], or `None` if any tile entity is missing.
+pub fn collect_chunk_data(
+    tile_storage: &TileStorage,
+    tiles_query: &Query<SavedTile>,
+) -> Option<ChunkData> {
+    let mut data = ChunkData::default();
+    for (index, tile) in tile_storage.iter().enumerate() {
+        let tile = tiles_query.get((*tile)?).ok()?;
+        let index = index as u16;
+        data.tiles.push(*tile.kind);
+        if let Some(inventory) = tile.inventory {
+            data.containers.push((index, inventory.clone()));
+        }
+        if let Some(crop) = tile.crop {
+            data.crops.push((index, *crop));
+        }
+        if let Some(locked) = tile.locked {
+            data.locks.push((index, locked.clone()));
+        }
+        if let Some(loot) = tile.loot {
+            data.loot.push((index, loot.clone()));
+        }
+    }
+    Some(data)
+}
+
+fn spawn_chunks_around_player(
+    mut commands: Commands,
+    biome_assets: Res<BiomeAssets>,
+    (world_seed, preset): (Res<WorldSeed>, Res<WorldgenPreset>),
+    player_query: Query<&Transform, With<Player>>,
+    mut chunk_manager: ResMut<ChunkManager>,
+    mut world_save: ResMut<WorldSave>,
+    #[cfg(feature = "gpu_worldgen")] mut gpu_worldgen: GpuWorldgen,
+) {
+    for transform in player_query.iter() {
+        let player_chunk_pos = world_pos_to_chunk_pos(&transform.translation.xy());
+        let distance = chunk_manager.render_distance.as_ivec2();
+        let around_player = (-distance.y..=distance.y).flat_map(|y| {
+            (-distance.x..=distance.x).map(move |x| player_chunk_pos + IVec2::new(x, y))
+        });
+        let wanted: Vec<IVec2> = around_player
+            .chain(chunk_manager.forced.iter().copied())
+            .collect();
+
+        for chunk_pos in wanted {
+            if !chunk_manager.spawned_chunks.contains_key(&chunk_pos) {
+                // The chunk waits until its biome's assets are on their way.
+                let biome = biome_at_chunk(chunk_pos, world_seed.seed, &preset);
+                let Some(assets) = biome_assets.groups.get(&biome) else {
+                    continue;
+                };
+                let entity = match world_save.load_chunk(chunk_pos) {
+                    Some(chunk_data) => spawn_chunk(&mut commands, assets, chunk_pos, chunk_data),
+                    #[cfg(feature = "gpu_worldgen")]
+                    None if GpuWorldgen::supports(&preset) => {
+                        gpu_worldgen.request(&mut commands, chunk_pos, world_seed.seed)
+                    }
+                    None => spawn_chunk(
+                        &mut commands,
+                        assets,
+                        chunk_pos,
+                        generate_chunk(world_seed.seed, &preset, chunk_pos),
+                    ),
+                };
+                chunk_manager.spawned_chunks.insert(chunk_pos, entity);
+                if world_save.chunk_entities(chunk_pos).is_some() {
+                    commands.queue(move |world: &mut World| {
+                        restore_chunk_entities(world, chunk_pos);
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn despawn_outofrange_chunks(
+    mut commands: Commands,
+    player_query: Query<&Transform, With<Player>>,
+    chunks_query: Query<(Entity, &Transform, &TileStorage), With<ChunkMarker>>,
+    tiles_query: Query<SavedTile>,
+    mut chunk_manager: ResMut<ChunkManager>,
+    mut world_save: ResMut<WorldSave>,
+    mut chunk_unloaded: MessageWriter<ChunkUnloaded>,
+) {
+    for player_transform in player_query.iter() {
+        let player_chunk_pos = world_pos_to_chunk_pos(&player_transform.translation.xy());
+
+        for (entity, chunk_transform, tile_storage) in chunks_query.iter() {
+            let chunk_pos = chunk_transform.translation.xy();
+            let x = (chunk_pos.x / (CHUNK_SIZE.x as f32 * TILE_SIZE.x)).floor() as i32;
+            let y = (chunk_pos.y / (CHUNK_SIZE.y as f32 * TILE_SIZE.y)).floor() as i32;
+            let chunk_coord = IVec2::new(x, y);
+
+            let out_of_range = (chunk_coord.x - player_chunk_pos.x).abs()
+                > chunk_manager.render_distance.x as i32
+                || (chunk_coord.y - player_chunk_pos.y).abs()
+                    > chunk_manager.render_distance.y as i32;
+            if out_of_range && !chunk_manager.forced.contains(&chunk_coord) {
+                chunk_manager.spawned_chunks.remove(&chunk_coord);
+                chunk_manager.layers.remove(&chunk_coord);
+                if chunk_manager.dirty_chunks.remove(&chunk_coord) {
+                    match collect_chunk_data(tile_storage, &tiles_query) {
+                        Some(data) => world_save.store_chunk(chunk_coord, data),
+                        None => error!("Chunk {chunk_coord} is missing tiles, not saving it"),
+                    }
+                }
+                commands.queue(move |world: &mut World| {
+                    unload_chunk_entities(world, chunk_coord);
+                    freeze_chunk_entities(world, chunk_coord);
+                });
+                commands.entity(entity).despawn();
+                chunk_unloaded.write(ChunkUnloaded(chunk_coord));
+            }
+        }
+    }
+}
+
+/// Disables the [`Freeze`] entities positioned inside the unloading chunk at `chunk_pos` and
+/// parks them in [`ChunkManager::frozen`].
+pub fn freeze_chunk_entities(world: &mut World, chunk_pos: IVec2) {
+    let mut query = world.query_filtered::<(Entity, &Transform), With<Freeze>>();
+    let entities: Vec<Entity> = query
+        .iter(world)
+        .filter(|(_, transform)| world_pos_to_chunk_pos(&transform.translation.xy()) == chunk_pos)
+        .map(|(entity, _)| entity)
+        .collect();
+    if entities.is_empty() {
+        return;
+    }
+    for &entity in &entities {
+        world.entity_mut(entity).insert(Disabled);
+    }
+    world
+        .resource_mut::<ChunkManager>()
+        .frozen
+        .entry(chunk_pos)
+        .or_default()
+        .extend(entities);
+}
+
+// The tilemap components are inserted in a single command after the tiles are spawned, so by
+// the time `ChunkMarker` is added the whole chunk is queryable. Its frozen entities come back
+// at the same time.
+fn write_chunk_loaded(
+    add: On<Add, ChunkMarker>,
+    mut commands: Commands,
+    chunks: Query<&ChunkPosition>,
+    mut chunk_manager: ResMut<ChunkManager>,
+    mut chunk_loaded: MessageWriter<ChunkLoaded>,
+) {
+    let Ok(ChunkPosition(chunk_pos)) = chunks.get(add.entity) else {
+        return;
+    };
+    for entity in chunk_manager.frozen.remove(chunk_pos).into_iter().flatten() {
+        if let Ok(mut entity) = commands.get_entity(entity) {
+            entity.remove::<Disabled>();
+        }
+    }
+    chunk_loaded.write(ChunkLoaded(*chunk_pos, add.entity));
+}
+
+/// Frozen entities are disabled, so `DespawnOnExit` doesn't see them.
+fn despawn_frozen_entities(mut commands: Commands, mut chunk_manager: ResMut<ChunkManager>) {
+    for entity in chunk_manager
+        .frozen
+        .drain()
+        .flat_map(|(_, entities)| entities)
+    {
+        if let Ok(mut entity) = commands.get_entity(entity) {
+            entity.despawn();
+        }
+    }
+}
+
+/// Chunks are kept between frames rather than tied to the state, so leaving the world clears
+/// them out for the next one.
+fn despawn_chunks(
+    mut commands: Commands,
+    mut chunk_manager: ResMut<ChunkManager>,
+    chunks: Query<Entity, With<ChunkMarker>>,
+) {
+    for chunk in &chunks {
+        commands.entity(chunk).despawn();
+    }
+    chunk_manager.spawned_chunks.clear();
+    chunk_manager.layers.clear();
+    chunk_manager.dirty_chunks.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frozen_entities_wait_for_their_chunk_to_load_again() {
+        let mut app = App::new();
+        app.init_resource::<ChunkManager>()
+            .add_message::<ChunkLoaded>()
+            .add_observer(write_chunk_loaded);
+        let frozen = app
+            .world_mut()
+            .spawn((Freeze, Transform::from_xyz(20.0, 30.0, 0.0)))
+            .id();
+        let neighbor = app
+            .world_mut()
+            .spawn((Freeze, Transform::from_xyz(-20.0, 30.0, 0.0)))
+            .id();
+        freeze_chunk_entities(app.world_mut(), IVec2::ZERO);
+        let disabled =
+            |app: &App, entity: Entity| app.world().entity(entity).contains::<Disabled>();
+        assert!(disabled(&app, frozen) && !disabled(&app, neighbor));
+        assert_eq!(
+            app.world().resource::<ChunkManager>().frozen[&IVec2::ZERO],
+            vec![frozen]
+        );
+
+        app.world_mut()
+            .spawn((ChunkPosition(IVec2::X), ChunkMarker));
+        app.world_mut().flush();
+        assert!(disabled(&app, frozen));
+        app.world_mut()
+            .spawn((ChunkPosition(IVec2::ZERO), ChunkMarker));
+        app.world_mut().flush();
+        assert!(!disabled(&app, frozen));
+        assert!(app.world().resource::<ChunkManager>().frozen.is_empty());
+    }
+
+    #[test]
+    fn frozen_entities_can_move_to_other_chunks() {
+        let mut world = World::new();
+        let (entity, tilemap) = (world.spawn_empty().id(), world.spawn_empty().id());
+        let mut chunk_manager = ChunkManager::default();
+        chunk_manager.frozen.insert(IVec2::ZERO, vec![entity]);
+        chunk_manager.spawned_chunks.insert(IVec2::X, tilemap);
+
+        assert!(!chunk_manager.move_frozen(entity, IVec2::Y));
+        assert_eq!(
+            chunk_manager.frozen,
+            HashMap::from_iter([(IVec2::Y, vec![entity])])
+        );
+        // Into a loaded chunk, where it isn't frozen any more.
+        assert!(chunk_manager.move_frozen(entity, IVec2::X));
+        assert!(chunk_manager.frozen.is_empty());
+    }
+}
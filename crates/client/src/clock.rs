@@ -0,0 +1,320 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::GameState;
+use crate::console::{RegisterConsoleCommand, console_arg};
+use crate::dialogue::talking;
+use crate::health::LifeState;
+use crate::pause::paused;
+use crate::persistence::WorldSave;
+
+/// Real-time seconds a day lasts unless [`Settings::day_length_secs`] says otherwise.
+///
+/// [`Settings::day_length_secs`]: crate::settings::Settings::day_length_secs
+pub const DEFAULT_DAY_SECS: f32 = 600.0;
+
+/// The time of day new worlds start at, mid-morning.
+pub const START_TIME: f64 = 0.3;
+
+/// How many days each [`Season`] lasts.
+pub const DAYS_PER_SEASON: u64 = 7;
+
+/// Every this many full moons rises as a blood moon.
+pub const BLOOD_MOON_CYCLES: u64 = 3;
+
+/// Advances the [`GameClock`] while playing, writing a [`ClockEvent`] whenever it passes dawn,
+/// dusk or midnight and keeping the [`Season`] and [`MoonPhase`] up to date. The clock stands still during
+/// conversations, on the death screen and while the window is in the background. It is saved with
+/// the world.
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameClock>()
+            .init_resource::<Season>()
+            .init_resource::<MoonPhase>()
+            .add_message::<ClockEvent>()
+            .register_console_command("time set", "time set <hour>", set_time)
+            .add_systems(OnEnter(GameState::Playing), load_clock)
+            .add_systems(
+                Update,
+                (
+                    tick_clock.run_if(not(talking
+                        .or(in_state(LifeState::Dead))
+                        .or(in_background)
+                        .or(paused))),
+                    (update_season, update_moon_phase),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// How long the world has been going, in days. The fraction is the time of day, counted from
+/// midnight, and the whole days make up the seasons and years of the calendar.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct GameClock {
+    pub days: f64,
+    /// Real-time seconds a day lasts.
+    pub day_secs: f32,
+    /// How many times faster than `day_secs` says the clock runs.
+    pub scale: f32,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        Self {
+            days: START_TIME,
+            day_secs: DEFAULT_DAY_SECS,
+            scale: 1.0,
+        }
+    }
+}
+
+/// The seasons of a year, [`DAYS_PER_SEASON`] days each. As a resource, the season the
+/// [`GameClock`] is in.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Season {
+    #[default]
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    pub const ALL: [Self; 4] = [Self::Spring, Self::Summer, Self::Autumn, Self::Winter];
+}
+
+/// The phases of the moon, one a day through a cycle of [`MoonPhase::ALL`]. As a resource, the
+/// phase of the moon the [`GameClock`] is in.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MoonPhase {
+    #[default]
+    New,
+    WaxingCrescent,
+    FirstQuarter,
+    WaxingGibbous,
+    Full,
+    WaningGibbous,
+    LastQuarter,
+    WaningCrescent,
+}
+
+impl MoonPhase {
+    pub const ALL: [Self; 8] = [
+        Self::New,
+        Self::WaxingCrescent,
+        Self::FirstQuarter,
+        Self::WaxingGibbous,
+        Self::Full,
+        Self::WaningGibbous,
+        Self::LastQuarter,
+        Self::WaningCrescent,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::New => "New moon",
+            Self::WaxingCrescent => "Waxing crescent",
+            Self::FirstQuarter => "First quarter",
+            Self::WaxingGibbous => "Waxing gibbous",
+            Self::Full => "Full moon",
+            Self::WaningGibbous => "Waning gibbous",
+            Self::LastQuarter => "Last quarter",
+            Self::WaningCrescent => "Waning crescent",
+        }
+    }
+
+    /// How much of the moon is lit, from 0 at the new moon to 1 at the full moon.
+    pub fn brightness(self) -> f32 {
+        let angle = TAU * self as u8 as f32 / Self::ALL.len() as f32;
+        (1.0 - angle.cos()) / 2.0
+    }
+}
+
+/// Written when the [`GameClock`] passes one of these times of day.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockEvent {
+    Dawn,
+    Dusk,
+    Midnight,
+}
+
+impl ClockEvent {
+    pub const ALL: [Self; 3] = [Self::Dawn, Self::Dusk, Self::Midnight];
+
+    /// The [`GameClock::time_of_day`] it happens at. Dawn and dusk are when the world is halfway
+    /// between light and dark.
+    pub fn time_of_day(self) -> f32 {
+        match self {
+            Self::Dawn => 0.25,
+            Self::Dusk => 0.75,
+            Self::Midnight => 0.0,
+        }
+    }
+}
+
+impl GameClock {
+    /// The time of day from 0 at midnight through 0.5 at noon to 1 at the next midnight.
+    pub fn time_of_day(&self) -> f32 {
+        self.days.fract() as f32
+    }
+
+    /// The whole days gone by, counting from 0.
+    pub fn day(&self) -> u64 {
+        self.days as u64
+    }
+
+    pub fn season(&self) -> Season {
+        Season::ALL[(self.day() / DAYS_PER_SEASON % 4) as usize]
+    }
+
+    /// The year, counting from 1.
+    pub fn year(&self) -> u64 {
+        self.day() / (DAYS_PER_SEASON * 4) + 1
+    }
+
+    /// The phase of the moon, which changes at noon so each night has a single one.
+    pub fn moon_phase(&self) -> MoonPhase {
+        MoonPhase::ALL[(self.moon_day() % MoonPhase::ALL.len() as u64) as usize]
+    }
+
+    /// The days since the first noon, by which the moon goes through its phases.
+    fn moon_day(&self) -> u64 {
+        (self.days + 0.5) as u64
+    }
+
+    /// The calendar date, like "Spring 3, year 1".
+    pub fn date(&self) -> String {
+        let day_of_season = self.day() % DAYS_PER_SEASON + 1;
+        format!("{:?} {day_of_season}, year {}", self.season(), self.year())
+    }
+
+    /// Advances by `secs` real-time seconds, returning the [`ClockEvent`]s passed on the way.
+    pub fn advance(&mut self, secs: f32) -> impl Iterator<Item = ClockEvent> + use<> {
+        let before = self.days;
+        self.days += f64::from(secs * self.scale / self.day_secs);
+        let after = self.days;
+        ClockEvent::ALL.into_iter().filter(move |event| {
+            let at = f64::from(event.time_of_day());
+            (after - at).floor() > (before - at).floor()
+        })
+    }
+
+    /// Whether it is between dusk and dawn.
+    pub fn is_night(&self) -> bool {
+        let time = self.time_of_day();
+        !(ClockEvent::Dawn.time_of_day()..ClockEvent::Dusk.time_of_day()).contains(&time)
+    }
+
+    /// Whether it is the night of a full moon.
+    pub fn is_full_moon(&self) -> bool {
+        self.is_night() && self.moon_phase() == MoonPhase::Full
+    }
+
+    /// Whether it is the night of a full moon that rose as a blood moon, the last full moon of
+    /// every [`BLOOD_MOON_CYCLES`].
+    pub fn is_blood_moon(&self) -> bool {
+        let cycle = self.moon_day() / MoonPhase::ALL.len() as u64;
+        self.is_full_moon() && cycle % BLOOD_MOON_CYCLES == BLOOD_MOON_CYCLES - 1
+    }
+}
+
+fn load_clock(mut clock: ResMut<GameClock>, world_save: Option<Res<WorldSave>>) {
+    clock.days = world_save
+        .and_then(|world_save| world_save.metadata.clock_days)
+        .unwrap_or(START_TIME);
+}
+
+/// Sets the time of day to `hour`, from 0 at midnight to 24, keeping the day.
+fn set_time(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let hour: f64 = console_arg(args, 0, "hour")?;
+    if !(0.0..24.0).contains(&hour) {
+        return Err(format!("Hour {hour} is not between 0 and 24"));
+    }
+    let mut clock = world.resource_mut::<GameClock>();
+    clock.days = clock.days.floor() + hour / 24.0;
+    Ok(clock.date())
+}
+
+fn tick_clock(
+    time: Res<Time>,
+    mut clock: ResMut<GameClock>,
+    mut clock_events: MessageWriter<ClockEvent>,
+) {
+    clock_events.write_batch(clock.advance(time.delta_secs()));
+}
+
+fn update_season(clock: Res<GameClock>, mut season: ResMut<Season>) {
+    season.set_if_neq(clock.season());
+}
+
+fn update_moon_phase(clock: Res<GameClock>, mut moon_phase: ResMut<MoonPhase>) {
+    moon_phase.set_if_neq(clock.moon_phase());
+}
+
+/// Whether the game window is in the background.
+fn in_background(windows: Query<&Window, With<PrimaryWindow>>) -> bool {
+    windows.iter().any(|window| !window.focused)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nights_fall_at_dusk_and_days_wrap_around() {
+        let at = |days: f64| GameClock { days, ..default() };
+        assert!(at(0.8).is_night() && at(0.1).is_night() && !at(0.5).is_night());
+
+        let mut clock = at(0.9);
+        let passed: Vec<_> = clock.advance(DEFAULT_DAY_SECS * 0.2).collect();
+        assert_eq!(passed, vec![ClockEvent::Midnight]);
+        assert!((clock.days - 1.1).abs() < 1e-6);
+        assert!((clock.time_of_day() - 0.1).abs() < 1e-6);
+        // A faster clock passes more of the day in the same time.
+        clock.scale = 4.0;
+        let passed: Vec<_> = clock.advance(DEFAULT_DAY_SECS * 0.2).collect();
+        assert_eq!(passed, vec![ClockEvent::Dawn, ClockEvent::Dusk]);
+        assert_eq!(clock.advance(0.0).count(), 0);
+    }
+
+    #[test]
+    fn days_make_up_seasons_and_years() {
+        let at = |days: f64| GameClock { days, ..default() };
+        assert_eq!(at(START_TIME).date(), "Spring 1, year 1");
+        assert_eq!(
+            at(DAYS_PER_SEASON as f64 * 2.0 + 2.5).date(),
+            "Autumn 3, year 1"
+        );
+        let last = at(DAYS_PER_SEASON as f64 * 4.0 - 0.1);
+        assert_eq!((last.season(), last.year()), (Season::Winter, 1));
+        assert_eq!(at(DAYS_PER_SEASON as f64 * 4.0).date(), "Spring 1, year 2");
+    }
+
+    #[test]
+    fn the_moon_waxes_and_wanes_a_phase_a_night() {
+        let at = |days: f64| GameClock { days, ..default() };
+        assert_eq!(at(START_TIME).moon_phase(), MoonPhase::New);
+        // A night keeps its phase past midnight, and the next one has the next phase.
+        assert_eq!(at(3.8).moon_phase(), MoonPhase::Full);
+        assert_eq!(at(4.2).moon_phase(), MoonPhase::Full);
+        assert_eq!(at(4.8).moon_phase(), MoonPhase::WaningGibbous);
+        assert_eq!(at(8.0).moon_phase(), MoonPhase::New);
+        // Every third full moon is a blood moon, through the whole night.
+        assert!(at(3.8).is_full_moon() && !at(3.8).is_blood_moon());
+        assert!(at(19.8).is_blood_moon() && at(20.2).is_blood_moon());
+        assert!(!at(20.5).is_blood_moon() && !at(35.8).is_blood_moon());
+        assert!(at(43.8).is_blood_moon());
+
+        assert_eq!(MoonPhase::New.brightness(), 0.0);
+        assert_eq!(MoonPhase::Full.brightness(), 1.0);
+        assert!((MoonPhase::FirstQuarter.brightness() - 0.5).abs() < 1e-6);
+        let crescent = MoonPhase::WaxingCrescent.brightness();
+        assert!((crescent - MoonPhase::WaningCrescent.brightness()).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+
+use crate::chunk::TILE_SIZE;
+use crate::player::steer;
+use crate::save::ReflectSaveableComponent;
+use crate::tiles::{Surface, WorldTiles, tile_to_world_pos, world_pos_to_tile};
+
+/// Keeps small gaps between a collider and the tiles it touches, so a collider resting against a
+/// wall doesn't count as overlapping the wall's row or column.
+const SKIN: f32 = 0.01;
+
+/// An axis-aligned box that can't move into [solid](crate::tiles::TileProperties::solid) tiles,
+/// centered on the entity's translation.
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[reflect(Component, SaveableComponent)]
+pub struct TileCollider {
+    pub half_size: Vec2,
+}
+
+/// Moves a box centered at `pos` by `delta`, stopping it at the edges of tiles `is_solid` returns
+/// `true` for. Each axis is resolved separately, so the box slides along walls.
+///
+/// A box that starts inside solid tiles, for example after spawning in water, moves freely until
+/// it is out.
+pub fn resolve_movement(
+    pos: Vec2,
+    delta: Vec2,
+    half_size: Vec2,
+    is_solid: impl Fn(IVec2) -> bool,
+) -> Vec2 {
+    if overlaps_solid(pos, half_size, &is_solid) {
+        return pos + delta;
+    }
+
+    // Step at most half a tile at a time so fast movement can't skip over a tile.
+    let max_step = TILE_SIZE.x.min(TILE_SIZE.y) / 2.0;
+    let steps = (delta.abs().max_element() / max_step).ceil().max(1.0);
+    let step = delta / steps;
+    let mut pos = pos;
+    for _ in 0..steps as u32 {
+        pos.x = resolve_axis(pos, step.x, half_size, 0, &is_solid);
+        pos.y = resolve_axis(pos, step.y, half_size, 1, &is_solid);
+    }
+    pos
+}
+
+fn resolve_axis(
+    pos: Vec2,
+    delta: f32,
+    half_size: Vec2,
+    axis: usize,
+    is_solid: &impl Fn(IVec2) -> bool,
+) -> f32 {
+    let moved = pos[axis] + delta;
+    if delta == 0.0 {
+        return moved;
+    }
+    let mut moved_pos = pos;
+    moved_pos[axis] = moved;
+    if !overlaps_solid(moved_pos, half_size, is_solid) {
+        return moved;
+    }
+
+    // Put the leading edge against the side of the tile it ran into.
+    let tile_size = Vec2::new(TILE_SIZE.x, TILE_SIZE.y);
+    let leading_edge = moved + delta.signum() * half_size[axis];
+    let mut edge_pos = pos;
+    edge_pos[axis] = leading_edge;
+    let tile_center = tile_to_world_pos(world_pos_to_tile(edge_pos))[axis];
+    let tile_edge = tile_center - delta.signum() * tile_size[axis] / 2.0;
+    tile_edge - delta.signum() * (half_size[axis] + SKIN)
+}
+
+/// Whether a box centered at `pos` overlaps any tile `is_solid` returns `true` for.
+pub fn overlaps_solid(pos: Vec2, half_size: Vec2, is_solid: &impl Fn(IVec2) -> bool) -> bool {
+    let min = world_pos_to_tile(pos - half_size);
+    let max = world_pos_to_tile(pos + half_size);
+    (min.y..=max.y).any(|y| (min.x..=max.x).any(|x| is_solid(IVec2::new(x, y))))
+}
+
+/// Whether the boxes centered at `a` and `b` overlap.
+pub fn touching(a: Vec2, a_half_size: Vec2, b: Vec2, b_half_size: Vec2) -> bool {
+    let gap = (a - b).abs() - (a_half_size + b_half_size);
+    gap.max_element() < 0.0
+}
+
+/// Walks a [`TileCollider`] at `pos` for `secs` seconds the way creatures other than the player
+/// do. `velocity` is steered towards `wanted`, the velocity they'd like on grass up to
+/// `max_speed`, which the tile underfoot slows down and its surface makes harder to change. Returns the new position and
+/// leaves `velocity` as how fast the collider actually moved. Like the player, nothing walks into
+/// chunks that haven't loaded.
+pub fn walk(
+    tiles: &WorldTiles,
+    pos: Vec2,
+    velocity: &mut Vec2,
+    wanted: Vec2,
+    max_speed: f32,
+    collider: &TileCollider,
+    secs: f32,
+) -> Vec2 {
+    let properties = tiles.properties(world_pos_to_tile(pos));
+    let surface = properties.map_or(Surface::Ground, |properties| properties.surface);
+    let speed = properties.map_or(1.0, |properties| properties.speed);
+    let profile = surface.profile();
+    *velocity = steer(*velocity, wanted * speed, max_speed * speed, &profile, secs);
+    if *velocity == Vec2::ZERO {
+        return pos;
+    }
+    let moved = resolve_movement(pos, *velocity * secs, collider.half_size, |world_tile| {
+        tiles
+            .properties(world_tile)
+            .is_none_or(|properties| properties.solid)
+    });
+    *velocity = (moved - pos) / secs;
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Walls in the tile column x = 2 and the tile row y = -3.
+    fn walls(world_tile: IVec2) -> bool {
+        world_tile.x == 2 || world_tile.y == -3
+    }
+
+    #[test]
+    fn stops_at_walls_and_slides_along_them() {
+        let half_size = Vec2::splat(5.0);
+        // The wall column starts at x = 24.
+        let pos = resolve_movement(Vec2::ZERO, Vec2::new(40.0, 3.0), half_size, walls);
+        assert!((pos.x - (24.0 - 5.0)).abs() < 0.1, "{pos}");
+        assert!((pos.y - 3.0).abs() < 1e-4, "{pos}");
+
+        // Moving away from a wall it rests against is not blocked.
+        let back = resolve_movement(pos, Vec2::new(-4.0, 0.0), half_size, walls);
+        assert_eq!(back, pos - Vec2::new(4.0, 0.0));
+
+        // The wall row ends at y = -40, in the chunk below the start.
+        let down = resolve_movement(Vec2::ZERO, Vec2::new(0.0, -100.0), half_size, walls);
+        assert!((down.y - (-40.0 + 5.0)).abs() < 0.1, "{down}");
+    }
+
+    #[test]
+    fn colliders_stuck_in_walls_can_walk_out() {
+        let pos = Vec2::new(32.0, 0.0);
+        assert_eq!(
+            resolve_movement(pos, Vec2::new(-2.0, 0.0), Vec2::splat(5.0), walls),
+            Vec2::new(30.0, 0.0)
+        );
+    }
+}
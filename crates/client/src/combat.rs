@@ -0,0 +1,338 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_enhanced_input::prelude::*;
+use bevy_seedling::prelude::*;
+
+use crate::GameState;
+use crate::collision::{TileCollider, touching};
+use crate::dialogue::Conversation;
+use crate::health::{Damage, Health, LifeState};
+use crate::hotbar::Hotbar;
+use crate::inventory::{Inventory, ItemRegistry};
+use crate::player::{Attack, Player};
+use crate::player_animation::PlayerAnimation;
+use crate::projectiles::held_weapon;
+use crate::spatial::SpatialIndex;
+use crate::sprite_animation::{AnimatedSprite, AnimationFinished};
+use crate::status_effects::held_effect;
+
+/// Health a swing takes from everything it hits.
+pub const MELEE_DAMAGE: f32 = 1.0;
+
+/// How hard a swing knocks back what it hits, in world units per second.
+pub const MELEE_KNOCKBACK: f32 = 180.0;
+
+/// How long a swing lasts, in seconds. The player can't swing again until it is over.
+pub const SWING_SECS: f32 = 0.25;
+
+/// How far in front of the player a swing's hitbox is centered, in world units.
+const SWING_REACH: f32 = 12.0;
+
+const SWING_HALF_SIZE: Vec2 = Vec2::splat(8.0);
+
+/// How far a swing's slash sprite sweeps on either side of the facing direction, in radians.
+const SWING_ARC: f32 = 1.0;
+
+/// How fast a hit spark plays its frames.
+const SPARK_FPS: f32 = 15.0;
+
+/// The biggest [`TileCollider`] a hitbox looks for, so it can find overlapping entities in the
+/// [`SpatialIndex`] by their centers.
+const MAX_TARGET_HALF_SIZE: f32 = 12.0;
+
+/// Swings at whatever is in front of the player with the attack key, unless they are holding a
+/// [ranged weapon](crate::projectiles::RangedWeapon). Each swing spawns a short-lived
+/// [`Hitbox`], which damages every entity with [`Health`] it overlaps once and throws off a
+/// spark.
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<Hit>()
+            .configure_sets(Update, HitboxSystems.run_if(in_state(GameState::Playing)))
+            .add_observer(swing)
+            .add_systems(
+                Update,
+                (
+                    hit_overlapping.in_set(HitboxSystems),
+                    (animate_swings, expire_lifetimes).after(HitboxSystems),
+                    spawn_hit_sparks
+                        .after(HitboxSystems)
+                        .run_if(resource_exists::<CombatAssets>),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Where [`Hitbox`]es find what they overlap. Systems moving hitboxes go before it.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HitboxSystems;
+
+#[derive(AssetCollection, Resource)]
+pub struct CombatAssets {
+    #[asset(path = "swing.png")]
+    pub swing: Handle<Image>,
+    #[asset(path = "sfx/swing.wav")]
+    pub swing_sound: Handle<AudioSample>,
+    #[asset(path = "spark.png")]
+    pub spark: Handle<Image>,
+    #[asset(texture_atlas_layout(tile_size_x = 8, tile_size_y = 8, columns = 3, rows = 1))]
+    pub spark_layout: Handle<TextureAtlasLayout>,
+}
+
+/// An area centered on the entity's translation that damages every [`Spatial`] entity with
+/// [`Health`] it overlaps, once each, until it despawns.
+///
+/// [`Spatial`]: crate::spatial::Spatial
+#[derive(Component, Clone, Debug)]
+#[require(Transform)]
+pub struct Hitbox {
+    /// Who the hitbox belongs to, which it never hurts.
+    pub owner: Entity,
+    pub damage: f32,
+    /// How hard the hitbox knocks what it hits away from its center, in world units per second.
+    pub knockback: f32,
+    pub half_size: Vec2,
+    hit: Vec<Entity>,
+}
+
+impl Hitbox {
+    pub fn new(owner: Entity, damage: f32, knockback: f32, half_size: Vec2) -> Self {
+        Self {
+            owner,
+            damage,
+            knockback,
+            half_size,
+            hit: Vec::new(),
+        }
+    }
+}
+
+/// A [`Hitbox`] overlapped `target` and damaged it.
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    pub hitbox: Entity,
+    pub target: Entity,
+}
+
+/// Whether the player can attack: they are alive and not talking to anyone.
+#[derive(SystemParam)]
+pub struct AttackReady<'w> {
+    life_state: Option<Res<'w, State<LifeState>>>,
+    conversation: Option<Res<'w, Conversation>>,
+}
+
+impl AttackReady<'_> {
+    pub fn get(&self) -> bool {
+        self.life_state
+            .as_ref()
+            .is_some_and(|state| *state.get() == LifeState::Alive)
+            && self.conversation.is_none()
+    }
+}
+
+/// Despawns the entity once the timer finishes.
+#[derive(Component, Clone, Debug)]
+pub struct Lifetime(pub Timer);
+
+impl Lifetime {
+    pub fn from_secs(secs: f32) -> Self {
+        Self(Timer::from_seconds(secs, TimerMode::Once))
+    }
+}
+
+/// The slash sprite of a swing, sweeping across the direction it was swung in.
+#[derive(Component, Clone, Copy, Debug)]
+#[require(Lifetime = Lifetime::from_secs(SWING_SECS))]
+struct Swing {
+    /// The angle of the swing's direction, in radians.
+    angle: f32,
+}
+
+impl Swing {
+    /// The rotation of the slash sprite a `fraction` of the way through the swing.
+    fn rotation(&self, fraction: f32) -> Quat {
+        Quat::from_rotation_z(self.angle + SWING_ARC * (1.0 - 2.0 * fraction))
+    }
+}
+
+fn swing(
+    _: On<Start<Attack>>,
+    mut commands: Commands,
+    (assets, ready): (Res<CombatAssets>, AttackReady),
+    (hotbar, registry): (Res<Hotbar>, Res<ItemRegistry>),
+    player: Single<(Entity, &Transform, &PlayerAnimation, &Inventory), With<Player>>,
+    swings: Query<&Hitbox, With<Swing>>,
+) {
+    let (player, transform, animation, inventory) = player.into_inner();
+    if !ready.get()
+        || held_weapon(&hotbar, inventory, &registry).is_some()
+        || held_effect(&hotbar, inventory, &registry).is_some()
+        || swings.iter().any(|hitbox| hitbox.owner == player)
+    {
+        return;
+    }
+    let direction = animation.facing.direction();
+    let swing = Swing {
+        angle: direction.to_angle(),
+    };
+    let pos = transform.translation.xy() + direction * SWING_REACH;
+    commands.spawn((
+        Name::new("Swing"),
+        Hitbox::new(player, MELEE_DAMAGE, MELEE_KNOCKBACK, SWING_HALF_SIZE),
+        Sprite::from_image(assets.swing.clone()),
+        Transform::from_translation(pos.extend(transform.translation.z + 0.5))
+            .with_rotation(swing.rotation(0.0)),
+        swing,
+        DespawnOnExit(GameState::Playing),
+    ));
+    commands.spawn(SamplePlayer::new(assets.swing_sound.clone()));
+}
+
+fn hit_overlapping(
+    index: Res<SpatialIndex>,
+    mut hitboxes: Query<(Entity, &mut Hitbox, &Transform)>,
+    targets: Query<(&Transform, Option<&TileCollider>), With<Health>>,
+    mut damage: MessageWriter<Damage>,
+    mut hits: MessageWriter<Hit>,
+) {
+    for (entity, mut hitbox, transform) in &mut hitboxes {
+        let pos = transform.translation.xy();
+        let radius = (hitbox.half_size + MAX_TARGET_HALF_SIZE).length();
+        for (target, _) in index.within(pos, radius) {
+            if target == hitbox.owner || hitbox.hit.contains(&target) {
+                continue;
+            }
+            let Ok((target_transform, collider)) = targets.get(target) else {
+                continue;
+            };
+            let target_pos = target_transform.translation.xy();
+            let target_half_size = collider.map_or(Vec2::ZERO, |collider| collider.half_size);
+            if touching(pos, hitbox.half_size, target_pos, target_half_size) {
+                hitbox.hit.push(target);
+                damage.write(Damage {
+                    target,
+                    amount: hitbox.damage,
+                    knockback: (target_pos - pos).normalize_or_zero() * hitbox.knockback,
+                });
+                hits.write(Hit {
+                    hitbox: entity,
+                    target,
+                });
+            }
+        }
+    }
+}
+
+fn animate_swings(mut swings: Query<(&Swing, &Lifetime, &mut Transform, &mut Sprite)>) {
+    for (swing, lifetime, mut transform, mut sprite) in &mut swings {
+        let fraction = lifetime.0.fraction();
+        transform.rotation = swing.rotation(fraction);
+        // Fade out over the second half of the swing.
+        sprite.color = Color::WHITE.with_alpha((2.0 - 2.0 * fraction).min(1.0));
+    }
+}
+
+fn spawn_hit_sparks(
+    mut commands: Commands,
+    assets: Res<CombatAssets>,
+    mut hits: MessageReader<Hit>,
+    targets: Query<&Transform>,
+) {
+    for hit in hits.read() {
+        let Ok(transform) = targets.get(hit.target) else {
+            continue;
+        };
+        commands
+            .spawn((
+                Name::new("Hit spark"),
+                Sprite::from_atlas_image(
+                    assets.spark.clone(),
+                    TextureAtlas::from(assets.spark_layout.clone()),
+                ),
+                AnimatedSprite::once(0, 3, SPARK_FPS),
+                // Over the player and what they hit, under floating numbers.
+                Transform::from_translation(transform.translation.xy().extend(2.0)),
+                DespawnOnExit(GameState::Playing),
+            ))
+            .observe(|finished: On<AnimationFinished>, mut commands: Commands| {
+                commands.entity(finished.entity).despawn();
+            });
+    }
+}
+
+fn expire_lifetimes(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut lifetimes: Query<(Entity, &mut Lifetime)>,
+) {
+    for (entity, mut lifetime) in &mut lifetimes {
+        if lifetime.0.tick(time.delta()).is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::state::app::StatesPlugin;
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::spatial::{Spatial, SpatialPlugin};
+
+    #[test]
+    fn hitboxes_hurt_what_they_overlap_once_and_expire() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin, SpatialPlugin, CombatPlugin))
+            .add_message::<Damage>()
+            .insert_state(GameState::Playing)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                100,
+            )));
+        let target = |app: &mut App, x: f32| {
+            app.world_mut()
+                .spawn((
+                    Spatial,
+                    Health::full(3.0),
+                    TileCollider {
+                        half_size: Vec2::splat(4.0),
+                    },
+                    Transform::from_xyz(x, 0.0, 0.0),
+                ))
+                .id()
+        };
+        let owner = target(&mut app, 0.0);
+        let near = target(&mut app, 10.0);
+        target(&mut app, 40.0);
+        let hitbox = app
+            .world_mut()
+            .spawn((
+                Hitbox::new(owner, 2.0, 50.0, Vec2::splat(8.0)),
+                Transform::from_xyz(4.0, 0.0, 0.0),
+                Lifetime::from_secs(0.25),
+            ))
+            .id();
+
+        let mut cursor = app.world().resource::<Messages<Damage>>().get_cursor();
+        let mut hits = Vec::new();
+        for _ in 0..6 {
+            app.update();
+            let messages = app.world().resource::<Messages<Damage>>();
+            hits.extend(cursor.read(messages).copied());
+        }
+        assert_eq!(
+            hits,
+            vec![Damage {
+                target: near,
+                amount: 2.0,
+                knockback: Vec2::X * 50.0,
+            }]
+        );
+        assert!(app.world().get_entity(hitbox).is_err());
+    }
+}
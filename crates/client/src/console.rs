@@ -0,0 +1,286 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use bevy_enhanced_input::prelude::*;
+
+use crate::GameState;
+use crate::pause::{PauseState, paused};
+use crate::player::{CameraFollow, Player};
+use crate::tiles::{tile_to_world_pos, world_pos_to_tile};
+use crate::world_map::world_map_open;
+use crate::worldgen::WorldSeed;
+
+/// How many lines of output the console keeps.
+const CONSOLE_LINES: usize = 200;
+
+/// A drop-down console, opened with the backtick key, for running the commands in the
+/// [`ConsoleCommands`] registry. Other modules add their own with
+/// [`RegisterConsoleCommand::register_console_command`]. The player's controls are off while it
+/// is open, and pausing closes it.
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConsoleCommands>()
+            .init_resource::<Console>()
+            .register_console_command("help", "help", help)
+            .register_console_command("tp", "tp <x> <y>", teleport)
+            .register_console_command("seed", "seed", seed)
+            .add_systems(OnEnter(PauseState::Paused), close_console)
+            .add_systems(OnExit(GameState::Playing), close_console)
+            .add_systems(
+                Update,
+                (
+                    toggle_console
+                        .run_if(not(paused.or(world_map_open)))
+                        .run_if(resource_exists::<ButtonInput<KeyCode>>),
+                    run_console_commands
+                        .run_if(|console: Res<Console>| !console.pending.is_empty()),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                console_ui
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(console_open),
+            );
+    }
+}
+
+/// Runs a console command with the words typed after its name, returning what to print.
+pub type ConsoleFn = fn(&mut World, &[&str]) -> Result<String, String>;
+
+pub struct ConsoleCommand {
+    /// What to type to run it. May be more than one word, like `time set`.
+    pub name: &'static str,
+    /// How to use it, shown by `help` and when it fails.
+    pub usage: &'static str,
+    pub run: ConsoleFn,
+}
+
+/// Every command the console can run.
+#[derive(Resource, Default)]
+pub struct ConsoleCommands {
+    pub commands: Vec<ConsoleCommand>,
+}
+
+impl ConsoleCommands {
+    /// The command `line` runs, with the words after its name. The longest name wins, so
+    /// `time set` can sit next to `time`.
+    pub fn find<'a>(&self, line: &'a str) -> Option<(&ConsoleCommand, Vec<&'a str>)> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        self.commands
+            .iter()
+            .filter_map(|command| {
+                let name_words = command.name.split_whitespace().count();
+                let matches = words.len() >= name_words
+                    && command
+                        .name
+                        .split_whitespace()
+                        .eq(words[..name_words].iter().copied());
+                matches.then(|| (command, words[name_words..].to_vec()))
+            })
+            .max_by_key(|(command, _)| command.name.len())
+    }
+}
+
+pub trait RegisterConsoleCommand {
+    fn register_console_command(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        run: ConsoleFn,
+    ) -> &mut Self;
+}
+
+impl RegisterConsoleCommand for App {
+    fn register_console_command(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        run: ConsoleFn,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<ConsoleCommands>()
+            .commands
+            .push(ConsoleCommand { name, usage, run });
+        self
+    }
+}
+
+/// Runs `line` as a console command.
+pub fn run_console_line(world: &mut World, line: &str) -> Result<String, String> {
+    let commands = world.resource::<ConsoleCommands>();
+    let Some((command, args)) = commands.find(line) else {
+        return Err(format!("Unknown command {line:?}, try help"));
+    };
+    let (run, usage) = (command.run, command.usage);
+    run(world, &args).map_err(|err| format!("{err}\nUsage: {usage}"))
+}
+
+/// Parses the console argument at `index`, named `name` in the error if it is missing or
+/// malformed.
+pub fn console_arg<T: std::str::FromStr>(
+    args: &[&str],
+    index: usize,
+    name: &str,
+) -> Result<T, String> {
+    let arg = args.get(index).ok_or_else(|| format!("Missing {name}"))?;
+    arg.parse().map_err(|_| format!("Bad {name} {arg:?}"))
+}
+
+#[derive(Resource, Default)]
+pub struct Console {
+    open: bool,
+    input: String,
+    /// Lines typed but not yet run.
+    pending: Vec<String>,
+    /// The commands run and what they printed, oldest first. Errors are flagged `true`.
+    output: Vec<(String, bool)>,
+}
+
+/// Whether the console is open.
+pub fn console_open(console: Res<Console>) -> bool {
+    console.open
+}
+
+fn toggle_console(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut console: ResMut<Console>,
+    player: Single<Entity, With<Player>>,
+) {
+    if !keys.just_pressed(KeyCode::Backquote) {
+        return;
+    }
+    console.open = !console.open;
+    let activity = if console.open {
+        ContextActivity::<Player>::INACTIVE
+    } else {
+        ContextActivity::<Player>::ACTIVE
+    };
+    commands.entity(*player).insert(activity);
+}
+
+// Resuming or the next world turns the player's controls back on.
+fn close_console(mut console: ResMut<Console>) {
+    console.open = false;
+}
+
+fn run_console_commands(world: &mut World) {
+    let lines = std::mem::take(&mut world.resource_mut::<Console>().pending);
+    for line in lines {
+        let result = run_console_line(world, &line);
+        let mut console = world.resource_mut::<Console>();
+        console.output.push((format!("> {line}"), false));
+        match result {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => console.output.push((output, false)),
+            Err(err) => console.output.push((err, true)),
+        }
+        let overflow = console.output.len().saturating_sub(CONSOLE_LINES);
+        console.output.drain(..overflow);
+    }
+}
+
+fn console_ui(mut contexts: EguiContexts, mut console: ResMut<Console>) -> Result {
+    egui::TopBottomPanel::top("console").show(contexts.ctx_mut()?, |ui| {
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for (line, error) in &console.output {
+                    let color = if *error {
+                        egui::Color32::LIGHT_RED
+                    } else {
+                        egui::Color32::LIGHT_GRAY
+                    };
+                    ui.label(egui::RichText::new(line).monospace().color(color));
+                }
+            });
+        let input = ui.add(
+            egui::TextEdit::singleline(&mut console.input)
+                .desired_width(f32::INFINITY)
+                .font(egui::TextStyle::Monospace),
+        );
+        input.request_focus();
+        // The key that opens the console shouldn't end up in it.
+        console.input.retain(|c| c != '`');
+        if input.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+            let line = std::mem::take(&mut console.input);
+            if !line.trim().is_empty() {
+                console.pending.push(line.trim().to_string());
+            }
+        }
+    });
+    Ok(())
+}
+
+fn help(world: &mut World, _: &[&str]) -> Result<String, String> {
+    let commands = world.resource::<ConsoleCommands>();
+    let usages: Vec<&str> = commands
+        .commands
+        .iter()
+        .map(|command| command.usage)
+        .collect();
+    Ok(usages.join("\n"))
+}
+
+fn teleport(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let world_tile = IVec2::new(console_arg(args, 0, "x")?, console_arg(args, 1, "y")?);
+    let pos = tile_to_world_pos(world_tile);
+    let mut players = world.query_filtered::<&mut Transform, With<Player>>();
+    let mut transform = players.single_mut(world).map_err(|err| err.to_string())?;
+    transform.translation = pos.extend(transform.translation.z);
+    let mut cameras =
+        world.query_filtered::<&mut Transform, (With<CameraFollow>, Without<Player>)>();
+    for mut camera in cameras.iter_mut(world) {
+        camera.translation = pos.extend(camera.translation.z);
+    }
+    Ok(format!("Teleported to {world_tile}"))
+}
+
+fn seed(world: &mut World, _: &[&str]) -> Result<String, String> {
+    Ok(world.resource::<WorldSeed>().seed.to_string())
+}
+
+/// The world tile the player stands on, for commands that act around them.
+pub fn player_tile(world: &mut World) -> Result<IVec2, String> {
+    let mut players = world.query_filtered::<&Transform, With<Player>>();
+    let transform = players.single(world).map_err(|err| err.to_string())?;
+    Ok(world_pos_to_tile(transform.translation.xy()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn echo(_: &mut World, args: &[&str]) -> Result<String, String> {
+        Ok(args.join(" "))
+    }
+
+    fn add(_: &mut World, args: &[&str]) -> Result<String, String> {
+        let sum: i32 = console_arg::<i32>(args, 0, "a")? + console_arg::<i32>(args, 1, "b")?;
+        Ok(sum.to_string())
+    }
+
+    #[test]
+    fn lines_run_the_command_with_the_longest_matching_name() {
+        let mut app = App::new();
+        app.register_console_command("echo", "echo <words>", echo)
+            .register_console_command("echo add", "echo add <a> <b>", add);
+        let world = app.world_mut();
+
+        assert_eq!(
+            run_console_line(world, "echo  hi there").unwrap(),
+            "hi there"
+        );
+        assert_eq!(run_console_line(world, "echo add 2 -5").unwrap(), "-3");
+        let err = run_console_line(world, "echo add 2")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "Missing b\nUsage: echo add <a> <b>");
+        assert!(run_console_line(world, "ech").is_err());
+    }
+}
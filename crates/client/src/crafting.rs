@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use serde::Deserialize;
+
+use crate::GameState;
+use crate::inventory::{Inventory, ItemRegistry, ItemStack};
+use crate::localization::Localization;
+use crate::player::Player;
+use crate::ron_asset::RonAssetLoader;
+
+/// Loads the recipes from `recipes.ron` into the [`RecipeBook`] and adds a crafting window that
+/// crafts from the player's [`Inventory`]. The window can reload the recipes for balancing, and
+/// they also reload by themselves when Bevy's file watcher is enabled.
+pub struct CraftingPlugin;
+
+impl Plugin for CraftingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<RecipeBook>()
+            .register_asset_loader(RonAssetLoader::<RecipeBook>::new(&["recipes.ron"]))
+            .init_resource::<RecipeBook>()
+            .add_systems(
+                Update,
+                update_recipe_book.run_if(on_message::<AssetEvent<RecipeBook>>),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                crafting_window.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct RecipeAssets {
+    #[asset(path = "recipes.ron")]
+    pub recipes: Handle<RecipeBook>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Recipe {
+    pub inputs: Vec<ItemStack>,
+    pub output: ItemStack,
+}
+
+impl Recipe {
+    /// Whether `inventory` holds all the inputs and has room for the output once they are used
+    /// up.
+    pub fn can_craft(&self, inventory: &Inventory, registry: &ItemRegistry) -> bool {
+        self.crafted(inventory, registry).is_some()
+    }
+
+    /// Uses up the inputs from `inventory` and adds the output, or changes nothing and returns
+    /// `false` if the recipe can't be crafted.
+    pub fn craft(&self, inventory: &mut Inventory, registry: &ItemRegistry) -> bool {
+        let Some(crafted) = self.crafted(inventory, registry) else {
+            return false;
+        };
+        *inventory = crafted;
+        true
+    }
+
+    fn crafted(&self, inventory: &Inventory, registry: &ItemRegistry) -> Option<Inventory> {
+        let mut crafted = inventory.clone();
+        for input in &self.inputs {
+            if !crafted.consume(&input.item, input.count) {
+                return None;
+            }
+        }
+        let output = &self.output;
+        (crafted.add(&output.item, output.count, registry) == 0).then_some(crafted)
+    }
+
+    /// Item ids the recipe uses that aren't in `registry`.
+    pub fn unknown_items<'a>(
+        &'a self,
+        registry: &'a ItemRegistry,
+    ) -> impl Iterator<Item = &'a str> + 'a {
+        self.inputs
+            .iter()
+            .chain([&self.output])
+            .filter(|stack| registry.get(&stack.item).is_none())
+            .map(|stack| stack.item.0.as_str())
+    }
+}
+
+/// Every crafting recipe, as defined in `recipes.ron`.
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug, Default)]
+pub struct RecipeBook {
+    pub recipes: Vec<Recipe>,
+}
+
+fn update_recipe_book(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<RecipeBook>>,
+    recipe_books: Res<Assets<RecipeBook>>,
+    registry: Res<ItemRegistry>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event
+            && let Some(recipe_book) = recipe_books.get(*id)
+        {
+            for recipe in &recipe_book.recipes {
+                for item in recipe.unknown_items(&registry) {
+                    warn!(
+                        "Recipe for {} uses unknown item {item}",
+                        recipe.output.item.0
+                    );
+                }
+            }
+            info!("Loaded {} recipes", recipe_book.recipes.len());
+            commands.insert_resource(recipe_book.clone());
+        }
+    }
+}
+
+fn crafting_window(
+    mut contexts: EguiContexts,
+    asset_server: Res<AssetServer>,
+    (recipe_book, recipe_assets): (Res<RecipeBook>, Res<RecipeAssets>),
+    registry: Res<ItemRegistry>,
+    localization: Res<Localization>,
+    mut inventory: Single<&mut Inventory, With<Player>>,
+) -> Result {
+    let name = |stack: &ItemStack| {
+        let name = localization.item_name(&registry, &stack.item);
+        format!("{name} × {}", stack.count)
+    };
+    egui::Window::new(localization.get("crafting-title"))
+        .default_open(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            for recipe in &recipe_book.recipes {
+                ui.horizontal(|ui| {
+                    let inputs: Vec<_> = recipe.inputs.iter().map(name).collect();
+                    ui.label(format!("{} → {}", inputs.join(", "), name(&recipe.output)));
+                    let craftable = recipe.can_craft(&inventory, &registry);
+                    if ui
+                        .add_enabled(
+                            craftable,
+                            egui::Button::new(localization.get("crafting-craft")),
+                        )
+                        .clicked()
+                    {
+                        recipe.craft(&mut inventory, &registry);
+                    }
+                });
+            }
+            ui.separator();
+            if ui.button(localization.get("crafting-reload")).clicked() {
+                asset_server.reload(recipe_assets.recipes.path().cloned().unwrap_or_default());
+            }
+        });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::ItemId;
+
+    #[test]
+    fn crafting_uses_up_inputs_only_when_the_output_fits() {
+        let registry: ItemRegistry = ron::from_str(include_str!("../assets/items.ron")).unwrap();
+        let recipe_book: RecipeBook = ron::from_str(include_str!("../assets/recipes.ron")).unwrap();
+        for recipe in &recipe_book.recipes {
+            assert_eq!(recipe.unknown_items(&registry).count(), 0, "{recipe:?}");
+        }
+
+        let gravel = ItemId::from("gravel");
+        let stone = ItemId::from("stone");
+        let recipe = Recipe {
+            inputs: vec![ItemStack {
+                item: gravel.clone(),
+                count: 4,
+            }],
+            output: ItemStack {
+                item: stone.clone(),
+                count: 1,
+            },
+        };
+        let mut inventory = Inventory::new(2);
+        inventory.add(&gravel, 8, &registry);
+        inventory.add(&ItemId::from("stick"), 1, &registry);
+        // There's no room for the stone while gravel is left over.
+        assert!(!recipe.craft(&mut inventory, &registry));
+        assert_eq!(inventory.count(&gravel), 8);
+
+        // Using up the last of the gravel frees its slot.
+        inventory.consume(&gravel, 4);
+        assert!(recipe.craft(&mut inventory, &registry));
+        assert_eq!(inventory.count(&gravel), 0);
+        assert_eq!(inventory.count(&stone), 1);
+        assert!(!recipe.can_craft(&inventory, &registry));
+    }
+}
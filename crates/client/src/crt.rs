@@ -0,0 +1,271 @@
+use bevy::core_pipeline::FullscreenShader;
+use bevy::core_pipeline::core_2d::graph::{Core2d, Node2d};
+use bevy::ecs::query::QueryItem;
+use bevy::prelude::*;
+use bevy::render::extract_component::{
+    ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+    UniformComponentPlugin,
+};
+use bevy::render::render_graph::{
+    NodeRunError, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner,
+};
+use bevy::render::render_resource::binding_types::{sampler, texture_2d, uniform_buffer};
+use bevy::render::render_resource::*;
+use bevy::render::renderer::{RenderContext, RenderDevice};
+use bevy::render::view::ViewTarget;
+use bevy::render::{RenderApp, RenderStartup};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+const SHADER_PATH: &str = "shaders/crt.wgsl";
+
+/// Draws the upscaled view of the world like an old CRT when [`Settings::crt_filter`] is on:
+/// bulging out at the center, with scanlines between the rows of game pixels and colors
+/// fringing towards the edges. Runs as a pass after tonemapping on every camera with a
+/// [`CrtFilter`], so the UI on top stays flat and sharp.
+pub struct CrtPlugin;
+
+impl Plugin for CrtPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<CrtFilter>::default(),
+            UniformComponentPlugin::<CrtUniform>::default(),
+        ))
+        .add_systems(
+            Update,
+            apply_crt_settings.run_if(resource_changed::<Settings>),
+        );
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_systems(RenderStartup, init_crt_pipeline)
+            .add_render_graph_node::<ViewNodeRunner<CrtNode>>(Core2d, CrtLabel)
+            .add_render_graph_edges(
+                Core2d,
+                (
+                    Node2d::Tonemapping,
+                    CrtLabel,
+                    Node2d::EndMainPassPostProcessing,
+                ),
+            );
+    }
+}
+
+/// How strong each part of the CRT look is, from 0 for none.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct CrtFilter {
+    /// How far the corners of the view bend inwards, as a fraction of its size.
+    pub curvature: f32,
+    /// How dark the lines between rows get, up to 1 for black.
+    pub scanlines: f32,
+    /// How far apart red and blue are at the edges of the view, in physical pixels.
+    pub aberration: f32,
+}
+
+impl Default for CrtFilter {
+    fn default() -> Self {
+        Self {
+            curvature: 0.08,
+            scanlines: 0.35,
+            aberration: 1.5,
+        }
+    }
+}
+
+impl ExtractComponent for CrtFilter {
+    type QueryData = (&'static CrtFilter, &'static Camera, &'static Projection);
+    type QueryFilter = ();
+    type Out = CrtUniform;
+
+    fn extract_component(
+        (filter, camera, projection): QueryItem<'_, '_, Self::QueryData>,
+    ) -> Option<CrtUniform> {
+        let viewport = camera.physical_viewport_rect()?;
+        // World units are game pixels, so the projection's height is how many rows there are.
+        let Projection::Orthographic(projection) = projection else {
+            return None;
+        };
+        Some(CrtUniform {
+            viewport: Vec4::new(
+                viewport.min.x as f32,
+                viewport.min.y as f32,
+                viewport.width() as f32,
+                viewport.height() as f32,
+            ),
+            curvature: filter.curvature,
+            scanlines: filter.scanlines,
+            aberration: filter.aberration,
+            rows: projection.area.height(),
+        })
+    }
+}
+
+/// A [`CrtFilter`] as the shader sees it, along with the viewport it is drawn over.
+#[derive(Component, ShaderType, Clone, Copy, Debug)]
+pub struct CrtUniform {
+    viewport: Vec4,
+    curvature: f32,
+    scanlines: f32,
+    aberration: f32,
+    rows: f32,
+}
+
+fn apply_crt_settings(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    cameras: Query<Entity, With<Camera2d>>,
+) {
+    for camera in &cameras {
+        if settings.crt_filter {
+            commands.entity(camera).insert(settings.crt);
+        } else {
+            commands.entity(camera).remove::<CrtFilter>();
+        }
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct CrtLabel;
+
+#[derive(Resource)]
+struct CrtPipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    pipeline_id: CachedRenderPipelineId,
+}
+
+fn init_crt_pipeline(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    asset_server: Res<AssetServer>,
+    fullscreen_shader: Res<FullscreenShader>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    let layout = render_device.create_bind_group_layout(
+        "crt_bind_group_layout",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::FRAGMENT,
+            (
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                sampler(SamplerBindingType::Filtering),
+                uniform_buffer::<CrtUniform>(true),
+            ),
+        ),
+    );
+    // Linear, so the bent rows of pixels blend smoothly instead of stair-stepping.
+    let sampler = render_device.create_sampler(&SamplerDescriptor {
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        ..default()
+    });
+    let pipeline_id = pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+        label: Some("crt_pipeline".into()),
+        layout: vec![layout.clone()],
+        vertex: fullscreen_shader.to_vertex_state(),
+        fragment: Some(FragmentState {
+            shader: asset_server.load(SHADER_PATH),
+            targets: vec![Some(ColorTargetState {
+                format: TextureFormat::bevy_default(),
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            })],
+            ..default()
+        }),
+        ..default()
+    });
+    commands.insert_resource(CrtPipeline {
+        layout,
+        sampler,
+        pipeline_id,
+    });
+}
+
+#[derive(Default)]
+struct CrtNode;
+
+impl ViewNode for CrtNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static DynamicUniformIndex<CrtUniform>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, uniform_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let crt_pipeline = world.resource::<CrtPipeline>();
+        let Some(pipeline) = world
+            .resource::<PipelineCache>()
+            .get_render_pipeline(crt_pipeline.pipeline_id)
+        else {
+            return Ok(());
+        };
+        let Some(uniforms) = world
+            .resource::<ComponentUniforms<CrtUniform>>()
+            .uniforms()
+            .binding()
+        else {
+            return Ok(());
+        };
+
+        // Reads the view as drawn so far and writes the filtered one, which becomes the view.
+        let post_process = view_target.post_process_write();
+        let bind_group = render_context.render_device().create_bind_group(
+            "crt_bind_group",
+            &crt_pipeline.layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &crt_pipeline.sampler,
+                uniforms.clone(),
+            )),
+        );
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("crt_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                depth_slice: None,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[uniform_index.index()]);
+        render_pass.draw(0..3, 0..1);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_setting_toggles_the_filter_on_cameras() {
+        let mut app = App::new();
+        app.init_resource::<Settings>()
+            .add_systems(Update, apply_crt_settings);
+        let camera = app.world_mut().spawn(Camera2d).id();
+        app.update();
+        assert!(app.world().get::<CrtFilter>(camera).is_none());
+
+        let mut settings = app.world_mut().resource_mut::<Settings>();
+        settings.crt_filter = true;
+        settings.crt.scanlines = 0.5;
+        app.update();
+        assert_eq!(app.world().get::<CrtFilter>(camera).unwrap().scanlines, 0.5);
+
+        app.world_mut().resource_mut::<Settings>().crt_filter = false;
+        app.update();
+        assert!(app.world().get::<CrtFilter>(camera).is_none());
+    }
+}
@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+use bevy_egui::input::egui_wants_any_pointer_input;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use bevy_rand::prelude::*;
+use noisy_bevy::simplex_noise_2d_seeded;
+use rand::Rng;
+
+use crate::GameState;
+use crate::chunk::TILE_SIZE;
+use crate::picking::CursorWorldPos;
+
+/// Dev tool for placing any registered entity type at the cursor.
+///
+/// Click places one entity, Shift+click scatters a batch at random within the scatter radius
+/// and Ctrl+click scatters along a noise field so batches form natural-looking clusters.
+pub struct DebugPlacerPlugin;
+
+impl Plugin for DebugPlacerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpawnRegistry>()
+            .init_resource::<DebugPlacer>()
+            .register_spawnable("Debug marker", SpawnCategory::Prop, spawn_debug_marker)
+            .add_systems(
+                EguiPrimaryContextPass,
+                placer_window.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                place_at_cursor
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(not(egui_wants_any_pointer_input)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpawnCategory {
+    Npc,
+    Mob,
+    Prop,
+    Prefab,
+}
+
+pub type SpawnFn = fn(&mut Commands, Vec2);
+
+pub struct Spawnable {
+    pub name: &'static str,
+    pub category: SpawnCategory,
+    pub spawn: SpawnFn,
+}
+
+/// Every entity type that can be placed with the debug placer.
+#[derive(Resource, Default)]
+pub struct SpawnRegistry {
+    pub entries: Vec<Spawnable>,
+}
+
+pub trait RegisterSpawnable {
+    fn register_spawnable(
+        &mut self,
+        name: &'static str,
+        category: SpawnCategory,
+        spawn: SpawnFn,
+    ) -> &mut Self;
+}
+
+impl RegisterSpawnable for App {
+    fn register_spawnable(
+        &mut self,
+        name: &'static str,
+        category: SpawnCategory,
+        spawn: SpawnFn,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<SpawnRegistry>()
+            .entries
+            .push(Spawnable {
+                name,
+                category,
+                spawn,
+            });
+        self
+    }
+}
+
+#[derive(Resource)]
+pub struct DebugPlacer {
+    search: String,
+    selected: Option<usize>,
+    scatter_count: u32,
+    scatter_radius: f32,
+}
+
+impl Default for DebugPlacer {
+    fn default() -> Self {
+        Self {
+            search: String::new(),
+            selected: None,
+            scatter_count: 10,
+            scatter_radius: 64.0,
+        }
+    }
+}
+
+/// Run condition for whether an entity type is selected, so clicks place it.
+pub fn placing(placer: Res<DebugPlacer>) -> bool {
+    placer.selected.is_some()
+}
+
+fn placer_window(
+    mut contexts: EguiContexts,
+    registry: Res<SpawnRegistry>,
+    mut placer: ResMut<DebugPlacer>,
+) -> Result {
+    egui::Window::new("Spawn")
+        .default_open(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.text_edit_singleline(&mut placer.search);
+            ui.add(egui::Slider::new(&mut placer.scatter_count, 1..=100).text("Scatter count"));
+            ui.add(
+                egui::Slider::new(&mut placer.scatter_radius, 8.0..=256.0).text("Scatter radius"),
+            );
+            ui.label("Click: place, Shift: scatter, Ctrl: noise scatter");
+            ui.separator();
+
+            let search = placer.search.to_lowercase();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (index, entry) in registry.entries.iter().enumerate() {
+                    if !entry.name.to_lowercase().contains(&search) {
+                        continue;
+                    }
+                    let label = format!("{} ({:?})", entry.name, entry.category);
+                    if ui
+                        .selectable_label(placer.selected == Some(index), label)
+                        .clicked()
+                    {
+                        placer.selected = Some(index);
+                    }
+                }
+            });
+        });
+    Ok(())
+}
+
+fn place_at_cursor(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    cursor: CursorWorldPos,
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+    registry: Res<SpawnRegistry>,
+    placer: Res<DebugPlacer>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(entry) = placer
+        .selected
+        .and_then(|index| registry.entries.get(index))
+    else {
+        return;
+    };
+    let Some(cursor_pos) = cursor.get() else {
+        return;
+    };
+
+    if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        for _ in 0..placer.scatter_count {
+            let angle = global_rng.random_range(0.0..std::f32::consts::TAU);
+            let distance = placer.scatter_radius * global_rng.random::<f32>().sqrt();
+            (entry.spawn)(
+                &mut commands,
+                cursor_pos + Vec2::from_angle(angle) * distance,
+            );
+        }
+    } else if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        // Sample a tile-spaced grid and keep the points where the noise peaks, so each batch
+        // forms a few organic clumps instead of an even spread.
+        let seed = global_rng.random::<f32>() * 1000.0;
+        let step = TILE_SIZE.x / 2.0;
+        let steps = (placer.scatter_radius / step) as i32;
+        let mut candidates = Vec::new();
+        for y in -steps..=steps {
+            for x in -steps..=steps {
+                let offset = Vec2::new(x as f32, y as f32) * step;
+                if offset.length() > placer.scatter_radius {
+                    continue;
+                }
+                let noise = simplex_noise_2d_seeded(offset / (TILE_SIZE.x * 4.0), seed);
+                candidates.push((noise, cursor_pos + offset));
+            }
+        }
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        for (_, pos) in candidates.into_iter().take(placer.scatter_count as usize) {
+            (entry.spawn)(&mut commands, pos);
+        }
+    } else {
+        (entry.spawn)(&mut commands, cursor_pos);
+    }
+}
+
+fn spawn_debug_marker(commands: &mut Commands, pos: Vec2) {
+    commands.spawn((
+        Name::new("Debug marker"),
+        Sprite::from_color(Color::srgb(1.0, 0.0, 1.0), Vec2::splat(4.0)),
+        Transform::from_translation(pos.extend(1.0)),
+    ));
+}
@@ -0,0 +1,398 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_enhanced_input::prelude::*;
+use serde::Deserialize;
+
+use crate::GameState;
+use crate::chunk::TILE_SIZE;
+use crate::debug_placer::{RegisterSpawnable, SpawnCategory};
+use crate::health::LifeState;
+use crate::localization::Localization;
+use crate::player::{Interact, Player, PlayerMovement, Velocity};
+use crate::ron_asset::RonAssetLoader;
+use crate::save::{Persist, ReflectSaveableComponent};
+use crate::spatial::{Spatial, SpatialIndex};
+
+/// How close the player has to be to a [`Talker`] to talk to it, in world units.
+pub const TALK_RADIUS: f32 = 1.5 * TILE_SIZE.x;
+
+/// Loads the dialogue trees from `dialogue.ron` into the [`DialogueBook`]. Pressing the interact
+/// key next to a [`Talker`] opens its tree in a dialogue box, where the movement keys or stick
+/// pick a choice and the interact key confirms it. Choices with an event write a
+/// [`DialogueEvent`] for gameplay to react to. The player stands still while talking, and
+/// [`Signpost`]s are the simplest things to talk to.
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<DialogueBook>()
+            .register_asset_loader(RonAssetLoader::<DialogueBook>::new(&["dialogue.ron"]))
+            .init_resource::<DialogueBook>()
+            .add_message::<DialogueEvent>()
+            .add_observer(interact)
+            .add_observer(pick_choice)
+            .add_observer(add_signpost_sprite)
+            .register_spawnable("Signpost", SpawnCategory::Prop, spawn_signpost)
+            .add_systems(OnEnter(LifeState::Dead), end_conversation)
+            .add_systems(OnExit(GameState::Playing), end_conversation)
+            .add_systems(
+                Update,
+                (
+                    update_dialogue_book.run_if(on_message::<AssetEvent<DialogueBook>>),
+                    update_dialogue_box.run_if(resource_exists::<Conversation>.and(
+                        resource_changed::<Conversation>.or(resource_changed::<Localization>),
+                    )),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct DialogueAssets {
+    #[asset(path = "dialogue.ron")]
+    pub dialogue: Handle<DialogueBook>,
+    #[asset(path = "signpost.png")]
+    pub signpost: Handle<Image>,
+}
+
+/// Something the player can pick at the end of a [`DialogueNode`].
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct DialogueChoice {
+    pub text: String,
+    /// The node this choice leads to, or `None` if it ends the conversation.
+    #[serde(default)]
+    pub next: Option<String>,
+    /// Written as a [`DialogueEvent`] when the choice is picked.
+    #[serde(default)]
+    pub event: Option<String>,
+}
+
+/// A line of dialogue. Without any choices, confirming it ends the conversation.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct DialogueNode {
+    pub speaker: String,
+    pub text: String,
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+}
+
+/// A branching conversation, starting at the node named by `start`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct DialogueTree {
+    pub start: String,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+impl DialogueTree {
+    /// Node names the tree leads to that aren't in it.
+    pub fn missing_nodes(&self) -> impl Iterator<Item = &str> + '_ {
+        self.nodes
+            .values()
+            .flat_map(|node| &node.choices)
+            .filter_map(|choice| choice.next.as_deref())
+            .chain([self.start.as_str()])
+            .filter(|name| !self.nodes.contains_key(*name))
+    }
+}
+
+/// Every dialogue tree by name, as defined in `dialogue.ron`.
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug, Default)]
+pub struct DialogueBook {
+    pub trees: HashMap<String, DialogueTree>,
+}
+
+/// Lets the player talk to an entity, with the [`DialogueTree`] of the given name.
+#[derive(Component, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Component, SaveableComponent)]
+#[require(Spatial)]
+pub struct Talker {
+    pub dialogue: String,
+}
+
+/// A sign that tells the player about the world.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component, SaveableComponent)]
+pub struct Signpost;
+
+/// A choice with an event was picked while talking to `speaker`.
+#[derive(Message, Clone, Debug, PartialEq)]
+pub struct DialogueEvent {
+    pub speaker: Entity,
+    pub event: String,
+}
+
+/// The conversation the player is having, if any.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct Conversation {
+    pub speaker: Entity,
+    /// The name of the tree in the [`DialogueBook`], which its lines are localized by.
+    pub dialogue: String,
+    pub tree: DialogueTree,
+    /// The node being shown.
+    pub node: String,
+    /// Index of the highlighted choice.
+    pub selected: usize,
+}
+
+impl Conversation {
+    pub fn new(speaker: Entity, dialogue: &str, tree: DialogueTree) -> Self {
+        Self {
+            speaker,
+            dialogue: dialogue.to_string(),
+            node: tree.start.clone(),
+            tree,
+            selected: 0,
+        }
+    }
+
+    pub fn current(&self) -> Option<&DialogueNode> {
+        self.tree.nodes.get(&self.node)
+    }
+
+    /// Moves the highlight `steps` choices down, wrapping around at either end.
+    pub fn select(&mut self, steps: i32) {
+        let count = self.current().map_or(0, |node| node.choices.len());
+        if count > 0 {
+            self.selected = (self.selected as i32 + steps).rem_euclid(count as i32) as usize;
+        }
+    }
+
+    /// Picks the highlighted choice and moves on to the node it leads to. Returns the choice, or
+    /// `None` for a node without choices, and whether the conversation goes on.
+    pub fn confirm(&mut self) -> (Option<DialogueChoice>, bool) {
+        let choice = self
+            .current()
+            .and_then(|node| node.choices.get(self.selected))
+            .cloned();
+        let next = choice.as_ref().and_then(|choice| choice.next.clone());
+        let goes_on = next.is_some_and(|next| {
+            self.node = next;
+            self.selected = 0;
+            self.current().is_some()
+        });
+        (choice, goes_on)
+    }
+}
+
+/// Whether the player is talking to something.
+pub fn talking(conversation: Option<Res<Conversation>>) -> bool {
+    conversation.is_some()
+}
+
+pub fn spawn_signpost(commands: &mut Commands, pos: Vec2) {
+    commands.spawn((
+        Name::new("Signpost"),
+        Signpost,
+        Talker {
+            dialogue: "signpost".to_string(),
+        },
+        Persist,
+        Transform::from_translation(pos.extend(0.8)),
+    ));
+}
+
+fn add_signpost_sprite(
+    add: On<Add, Signpost>,
+    mut commands: Commands,
+    dialogue_assets: Res<DialogueAssets>,
+) {
+    commands
+        .entity(add.entity)
+        .insert(Sprite::from_image(dialogue_assets.signpost.clone()));
+}
+
+fn update_dialogue_book(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<DialogueBook>>,
+    dialogue_books: Res<Assets<DialogueBook>>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event
+            && let Some(dialogue_book) = dialogue_books.get(*id)
+        {
+            for (name, tree) in &dialogue_book.trees {
+                for missing in tree.missing_nodes() {
+                    warn!("Dialogue {name} leads to missing node {missing}");
+                }
+            }
+            info!("Loaded {} dialogue trees", dialogue_book.trees.len());
+            commands.insert_resource(dialogue_book.clone());
+        }
+    }
+}
+
+/// Starts talking to the nearest [`Talker`] in reach, or confirms the highlighted choice.
+fn interact(
+    _: On<Start<Interact>>,
+    mut commands: Commands,
+    (dialogue_book, mut conversation): (Res<DialogueBook>, Option<ResMut<Conversation>>),
+    player: Single<(&Transform, &mut Velocity), With<Player>>,
+    (index, talkers): (Res<SpatialIndex>, Query<&Talker>),
+    mut dialogue_events: MessageWriter<DialogueEvent>,
+) {
+    if let Some(conversation) = &mut conversation {
+        let (choice, goes_on) = conversation.confirm();
+        if let Some(event) = choice.and_then(|choice| choice.event) {
+            dialogue_events.write(DialogueEvent {
+                speaker: conversation.speaker,
+                event,
+            });
+        }
+        if !goes_on {
+            commands.run_system_cached(end_conversation);
+        }
+        return;
+    }
+
+    let (transform, mut velocity) = player.into_inner();
+    let pos = transform.translation.xy();
+    let nearest = index
+        .within(pos, TALK_RADIUS)
+        .filter_map(|(entity, distance)| Some((entity, talkers.get(entity).ok()?, distance)))
+        .min_by(|(.., a), (.., b)| a.total_cmp(b));
+    let Some((speaker, talker, _)) = nearest else {
+        return;
+    };
+    let Some(tree) = dialogue_book.trees.get(&talker.dialogue) else {
+        warn!("No dialogue named {}", talker.dialogue);
+        return;
+    };
+    velocity.0 = Vec2::ZERO;
+    commands.insert_resource(Conversation::new(speaker, &talker.dialogue, tree.clone()));
+    spawn_dialogue_box(&mut commands);
+}
+
+/// Moves the highlight with the movement keys, one choice per press.
+fn pick_choice(movement: On<Start<PlayerMovement>>, conversation: Option<ResMut<Conversation>>) {
+    if let Some(mut conversation) = conversation
+        && movement.value.y != 0.0
+    {
+        conversation.select(-movement.value.y.signum() as i32);
+    }
+}
+
+fn end_conversation(mut commands: Commands, boxes: Query<Entity, With<DialogueBox>>) {
+    commands.remove_resource::<Conversation>();
+    for entity in &boxes {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[derive(Component)]
+struct DialogueBox;
+
+#[derive(Component)]
+struct DialogueSpeaker;
+
+#[derive(Component)]
+struct DialogueText;
+
+fn spawn_dialogue_box(commands: &mut Commands) {
+    commands
+        .spawn((
+            Name::new("Dialogue box"),
+            DialogueBox,
+            DespawnOnExit(GameState::Playing),
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(15.0),
+                right: Val::Percent(15.0),
+                bottom: Val::Px(84.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                row_gap: Val::Px(6.0),
+                flex_direction: FlexDirection::Column,
+                border: UiRect::all(Val::Px(3.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.08, 0.06, 0.05, 0.9)),
+            BorderColor::all(Color::srgb(0.6, 0.4, 0.2)),
+            Pickable::IGNORE,
+        ))
+        .with_children(|dialogue_box| {
+            dialogue_box.spawn((
+                DialogueSpeaker,
+                Text::default(),
+                TextFont::from_font_size(18.0),
+                TextColor(Color::srgb(0.95, 0.8, 0.45)),
+            ));
+            dialogue_box.spawn((
+                DialogueText,
+                Text::default(),
+                TextFont::from_font_size(16.0),
+            ));
+        });
+}
+
+fn update_dialogue_box(
+    conversation: Res<Conversation>,
+    localization: Res<Localization>,
+    mut speakers: Query<&mut Text, (With<DialogueSpeaker>, Without<DialogueText>)>,
+    mut texts: Query<&mut Text, With<DialogueText>>,
+) {
+    let Some(node) = conversation.current() else {
+        return;
+    };
+    let line = format!("dialogue-{}-{}", conversation.dialogue, conversation.node);
+    let speaker_name = localization.get_or(&format!("{line}-speaker"), &node.speaker);
+    for mut speaker in &mut speakers {
+        speaker.0 = speaker_name.to_string();
+    }
+    let mut text = localization.get_or(&line, &node.text).to_string();
+    for (index, choice) in node.choices.iter().enumerate() {
+        let choice_text = localization.get_or(&format!("{line}-choice-{index}"), &choice.text);
+        let marker = if index == conversation.selected {
+            '>'
+        } else {
+            ' '
+        };
+        text.push_str(&format!("\n{marker} {choice_text}"));
+    }
+    for mut line in &mut texts {
+        line.0.clone_from(&text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversations_follow_the_picked_choices_until_one_ends_them() {
+        let dialogue_book: DialogueBook =
+            ron::from_str(include_str!("../assets/dialogue.ron")).unwrap();
+        for (name, tree) in &dialogue_book.trees {
+            assert_eq!(tree.missing_nodes().count(), 0, "{name}");
+        }
+
+        let tree: DialogueTree = ron::from_str(
+            r#"(
+                start: "hello",
+                nodes: {
+                    "hello": (speaker: "Sign", text: "Hello", choices: [
+                        (text: "More", next: Some("more")),
+                        (text: "Bye", event: Some("left")),
+                    ]),
+                    "more": (speaker: "Sign", text: "That's all"),
+                },
+            )"#,
+        )
+        .unwrap();
+        let mut conversation = Conversation::new(Entity::PLACEHOLDER, "sign", tree.clone());
+        conversation.select(-1);
+        assert_eq!(conversation.selected, 1);
+        let (choice, goes_on) = conversation.confirm();
+        assert_eq!(
+            choice.and_then(|choice| choice.event).as_deref(),
+            Some("left")
+        );
+        assert!(!goes_on);
+
+        let mut conversation = Conversation::new(Entity::PLACEHOLDER, "sign", tree);
+        assert!(conversation.confirm().1);
+        assert_eq!(conversation.current().unwrap().text, "That's all");
+        // Lines without choices just end the conversation.
+        assert_eq!(conversation.confirm(), (None, false));
+    }
+}
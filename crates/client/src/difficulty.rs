@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// How hard a world is, picked when it is created and stored with it. It scales how often
+/// enemies spawn, how much damage the player takes and how much loot drops.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Self; 3] = [Self::Easy, Self::Normal, Self::Hard];
+
+    /// What the chance of an enemy spawning is multiplied by.
+    pub fn spawn_multiplier(self) -> f64 {
+        match self {
+            Self::Easy => 0.5,
+            Self::Normal => 1.0,
+            Self::Hard => 1.75,
+        }
+    }
+
+    /// What the damage the player takes is multiplied by.
+    pub fn damage_multiplier(self) -> f32 {
+        match self {
+            Self::Easy => 0.5,
+            Self::Normal => 1.0,
+            Self::Hard => 1.5,
+        }
+    }
+
+    /// What the number of rolls of every loot table is multiplied by.
+    pub fn loot_multiplier(self) -> f32 {
+        match self {
+            Self::Easy => 0.75,
+            Self::Normal => 1.0,
+            Self::Hard => 1.5,
+        }
+    }
+
+    /// How many times a loot table with `rolls` is rolled, at least once.
+    pub fn loot_rolls(self, rolls: u32) -> u32 {
+        ((rolls as f32 * self.loot_multiplier()).round() as u32).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn harder_worlds_roll_more_loot_but_always_at_least_once() {
+        assert_eq!(Difficulty::Normal.loot_rolls(4), 4);
+        assert_eq!(Difficulty::Easy.loot_rolls(4), 3);
+        assert_eq!(Difficulty::Easy.loot_rolls(1), 1);
+        assert_eq!(Difficulty::Hard.loot_rolls(1), 2);
+        assert_eq!(Difficulty::Hard.loot_rolls(4), 6);
+    }
+}
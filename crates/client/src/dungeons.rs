@@ -0,0 +1,271 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::TilePos;
+use bevy_enhanced_input::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+use crate::chunk::{CHUNK_SIZE, ChunkLoaded, TILE_SIZE};
+use crate::combat::AttackReady;
+use crate::health::LifeState;
+use crate::hit_feedback::spawn_floating_text;
+use crate::inventory::{Inventory, ItemId, ItemRegistry};
+use crate::localization::Localization;
+use crate::loot::Loot;
+use crate::persistence::ChunkData;
+use crate::player::{Interact, Player};
+use crate::player_animation::PlayerAnimation;
+use crate::tiles::{
+    TileKind, WorldTiles, chunk_tile_to_world, tile_to_world_pos, world_pos_to_tile,
+};
+use crate::transitions::{ScreenTransition, TransitionStyle};
+use crate::waypoints::Waypoints;
+use crate::worldgen::{Biome, WorldSeed, WorldgenPreset, biome_at_chunk, chunk_hash};
+
+/// One in this many mountain chunks has a dungeon built into it.
+const DUNGEON_RARITY: u32 = 12;
+
+/// The item that opens the doors and chests of dungeons.
+pub const DUNGEON_KEY: &str = "dungeon_key";
+
+/// The loot table a dungeon's chest is filled from.
+pub const DUNGEON_LOOT: &str = "dungeon";
+
+/// How far from a pressure plate the secret walls it opens can be, in tiles.
+const PRESSURE_PLATE_RADIUS: i32 = 8;
+
+/// A dungeon room filling its chunk, top row first: `#` is a wall, `.` floor, `D` a locked door,
+/// `S` a secret wall, `P` the pressure plate that opens it and `C` a locked chest holding a roll
+/// of the [`DUNGEON_LOOT`] table.
+const ROOM: [&str; CHUNK_SIZE.y as usize] = [
+    "##########",
+    "#...#....#",
+    "#.P.#....#",
+    "#...#....#",
+    "D...S..C.#",
+    "#...#....#",
+    "#...#....#",
+    "#...#....#",
+    "#...#....#",
+    "##########",
+];
+
+const LOCKED_COLOR: Color = Color::srgb(1.0, 0.85, 0.4);
+
+/// Builds dungeon rooms into some of the mountain chunks as they are generated. Their doors and
+/// chests are [`Locked`], and their vaults are hidden behind secret walls. Interacting with the
+/// tile the player faces opens doors and secret walls, and uses up a key to unlock it if it is
+/// locked. Stepping on a pressure plate opens the secret walls around it. Walking in through a
+/// dungeon's door dissolves the screen to black and back. Dungeons are marked on the
+/// [`Waypoints`] once found.
+pub struct DungeonsPlugin;
+
+impl Plugin for DungeonsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(use_facing_tile)
+            .add_systems(
+                Update,
+                (press_plates, dissolve_into_dungeons)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(LifeState::Alive)),
+            )
+            .add_systems(
+                Update,
+                mark_dungeons
+                    .run_if(on_message::<ChunkLoaded>)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Keeps a door or chest tile shut until the player uses up a `key` item on it. Saved with its
+/// chunk.
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Locked {
+    pub key: ItemId,
+}
+
+/// Whether the chunk at `chunk_pos` has a dungeon built into it.
+pub fn has_dungeon(world_seed: u64, preset: &WorldgenPreset, chunk_pos: IVec2) -> bool {
+    chunk_hash(chunk_pos, world_seed, 0).is_multiple_of(DUNGEON_RARITY)
+        && biome_at_chunk(chunk_pos, world_seed, preset) == Biome::Mountains
+}
+
+/// Builds the dungeon of `chunk_pos`, if it has one, into `data`, its generated tiles.
+pub fn build_dungeon(
+    world_seed: u64,
+    preset: &WorldgenPreset,
+    chunk_pos: IVec2,
+    data: &mut ChunkData,
+) {
+    if !has_dungeon(world_seed, preset, chunk_pos) {
+        return;
+    }
+    for (row, line) in ROOM.iter().enumerate() {
+        let y = CHUNK_SIZE.y as usize - 1 - row;
+        for (x, symbol) in line.chars().enumerate() {
+            let index = y * CHUNK_SIZE.x as usize + x;
+            data.tiles[index] = match symbol {
+                '#' => TileKind::DungeonWall,
+                'D' => TileKind::Door,
+                'S' => TileKind::SecretWall,
+                'P' => TileKind::PressurePlate,
+                'C' => TileKind::Chest,
+                _ => TileKind::DungeonFloor,
+            };
+            if matches!(symbol, 'D' | 'C') {
+                let locked = Locked {
+                    key: ItemId::from(DUNGEON_KEY),
+                };
+                data.locks.push((index as u16, locked));
+            }
+            if symbol == 'C' {
+                data.loot.push((index as u16, Loot(DUNGEON_LOOT.into())));
+            }
+        }
+    }
+}
+
+fn mark_dungeons(
+    mut loaded: MessageReader<ChunkLoaded>,
+    (world_seed, preset): (Res<WorldSeed>, Res<WorldgenPreset>),
+    mut waypoints: ResMut<Waypoints>,
+) {
+    for ChunkLoaded(chunk_pos, _) in loaded.read().copied() {
+        if has_dungeon(world_seed.seed, &preset, chunk_pos) {
+            let middle = TilePos::new(CHUNK_SIZE.x / 2, CHUNK_SIZE.y / 2);
+            waypoints.mark(
+                &format!("dungeon {chunk_pos}"),
+                "Dungeon",
+                chunk_tile_to_world(chunk_pos, middle),
+            );
+        }
+    }
+}
+
+/// Opens the door or secret wall the player faces, or unlocks the locked tile they face if they
+/// carry its key.
+fn use_facing_tile(
+    _: On<Start<Interact>>,
+    mut commands: Commands,
+    (registry, ready): (Res<ItemRegistry>, AttackReady),
+    localization: Res<Localization>,
+    player: Single<(&Transform, &PlayerAnimation, &mut Inventory), With<Player>>,
+    mut tiles: WorldTiles,
+    locks: Query<&Locked>,
+) {
+    let (transform, animation, mut inventory) = player.into_inner();
+    if !ready.get() {
+        return;
+    }
+    let pos = transform.translation.xy() + animation.facing.direction() * TILE_SIZE.x;
+    let world_tile = world_pos_to_tile(pos);
+    let (Some(kind), Some(entity)) = (tiles.get_tile(world_tile), tiles.tile_entity(world_tile))
+    else {
+        return;
+    };
+    let text_pos = tile_to_world_pos(world_tile);
+    if let Ok(locked) = locks.get(entity) {
+        if !inventory.consume(&locked.key, 1) {
+            let key = localization.item_name(&registry, &locked.key);
+            spawn_floating_text(
+                &mut commands,
+                text_pos,
+                localization.format("locked-needs-key", &[("item", &key)]),
+                LOCKED_COLOR,
+            );
+            return;
+        }
+        commands.entity(entity).remove::<Locked>();
+        tiles.mark_changed(world_tile);
+        let text = localization.get("locked-unlocked").to_string();
+        spawn_floating_text(&mut commands, text_pos, text, LOCKED_COLOR);
+    }
+    if matches!(kind, TileKind::Door | TileKind::SecretWall) {
+        tiles.set_tile(world_tile, TileKind::DungeonFloor);
+    }
+}
+
+/// Opens the secret walls around a pressure plate when the player steps onto it.
+fn press_plates(
+    player: Single<&Transform, With<Player>>,
+    mut standing_on: Local<Option<IVec2>>,
+    mut tiles: WorldTiles,
+) {
+    let world_tile = world_pos_to_tile(player.translation.xy());
+    if standing_on.replace(world_tile) == Some(world_tile)
+        || tiles.get_tile(world_tile) != Some(TileKind::PressurePlate)
+    {
+        return;
+    }
+    for y in -PRESSURE_PLATE_RADIUS..=PRESSURE_PLATE_RADIUS {
+        for x in -PRESSURE_PLATE_RADIUS..=PRESSURE_PLATE_RADIUS {
+            let wall = world_tile + IVec2::new(x, y);
+            if tiles.get_tile(wall) == Some(TileKind::SecretWall) {
+                tiles.set_tile(wall, TileKind::DungeonFloor);
+            }
+        }
+    }
+}
+
+/// Dissolves the screen to black and back when the player steps from outside onto a dungeon's
+/// floor.
+fn dissolve_into_dungeons(
+    player: Single<&Transform, With<Player>>,
+    mut inside: Local<bool>,
+    tiles: WorldTiles,
+    mut transition: ResMut<ScreenTransition>,
+) {
+    let world_tile = world_pos_to_tile(player.translation.xy());
+    let was_inside = std::mem::replace(
+        &mut *inside,
+        tiles.get_tile(world_tile) == Some(TileKind::DungeonFloor),
+    );
+    if *inside && !was_inside {
+        transition.cover_and_reveal(TransitionStyle::Dissolve);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::generate_chunk;
+    use crate::tiles::TileProperties;
+
+    #[test]
+    fn dungeons_are_rare_reproducible_and_locked() {
+        let preset = WorldgenPreset::default();
+        let chunks: Vec<IVec2> = (-40..40)
+            .flat_map(|y| (-40..40).map(move |x| IVec2::new(x, y)))
+            .filter(|&chunk_pos| has_dungeon(7, &preset, chunk_pos))
+            .collect();
+        assert!(!chunks.is_empty());
+        assert!(chunks.len() < 80 * 80 / DUNGEON_RARITY as usize);
+
+        let data = generate_chunk(7, &preset, chunks[0]);
+        assert_eq!(data, generate_chunk(7, &preset, chunks[0]));
+        let count = |kind| data.tiles.iter().filter(|&&tile| tile == kind).count();
+        assert_eq!(count(TileKind::Door), 1);
+        assert_eq!(count(TileKind::SecretWall), 1);
+        assert_eq!(count(TileKind::PressurePlate), 1);
+        assert_eq!(count(TileKind::Chest), 1);
+        // The door and the chest are locked, and only the chest holds loot.
+        let locked: Vec<TileKind> = data
+            .locks
+            .iter()
+            .map(|(index, _)| data.tiles[*index as usize])
+            .collect();
+        assert_eq!(locked, vec![TileKind::Door, TileKind::Chest]);
+        assert_eq!(data.loot.len(), 1);
+        assert_eq!(data.tiles[data.loot[0].0 as usize], TileKind::Chest);
+        // The room is walled in apart from its door.
+        for (index, tile) in data.tiles.iter().enumerate() {
+            let (x, y) = (index as u32 % CHUNK_SIZE.x, index as u32 / CHUNK_SIZE.x);
+            if (x == 0 || y == 0 || x == CHUNK_SIZE.x - 1 || y == CHUNK_SIZE.y - 1)
+                && *tile != TileKind::Door
+            {
+                assert!(TileProperties::of(*tile).solid);
+                assert!(!TileProperties::of(*tile).breakable);
+            }
+        }
+    }
+}
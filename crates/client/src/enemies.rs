@@ -0,0 +1,554 @@
+use bevy::ecs::query::QueryData;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_rand::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::GameState;
+use crate::ai::{AddBehavior, AiSystems, Behavior};
+use crate::chunk::{CHUNK_SIZE, ChunkLoaded, Freeze, TILE_SIZE};
+use crate::clock::{ClockEvent, GameClock, MoonPhase};
+use crate::collision::{TileCollider, touching, walk};
+use crate::debug_placer::{RegisterSpawnable, SpawnCategory};
+use crate::difficulty::Difficulty;
+use crate::health::{Damage, Health, LifeState};
+use crate::hit_feedback::Tint;
+use crate::loot::Loot;
+use crate::pathfinding::{FindPath, Path};
+use crate::player::{Player, Velocity};
+use crate::ron_asset::RonAssetLoader;
+use crate::spatial::{Spatial, SpatialIndex};
+use crate::sprite_animation::AnimatedSprite;
+use crate::status_effects::{ApplyEffect, StatusEffect, StatusEffectKind, StatusEffects};
+use crate::tiles::{WorldTiles, chunk_tile_to_world, tile_to_world_pos};
+use crate::worldgen::{Biome, WorldSeed, WorldgenPreset, biome_at_chunk};
+use crate::y_sort::YSort;
+
+/// How close the player has to come for an enemy to give chase, in world units.
+pub const DETECT_RADIUS: f32 = 8.0 * TILE_SIZE.x;
+
+/// Chasing speed of enemies on grass, in world units per second.
+pub const ENEMY_SPEED: f32 = 45.0;
+
+pub const ENEMY_HEALTH: f32 = 3.0;
+
+/// Health an enemy takes from the player each time it touches them.
+pub const CONTACT_DAMAGE: f32 = 1.0;
+
+/// How hard an enemy knocks the player away when it hurts them, in world units per second.
+pub const CONTACT_KNOCKBACK: f32 = 160.0;
+
+/// Stacked onto the player each time an enemy hurts them.
+pub const CONTACT_POISON: StatusEffect = StatusEffect {
+    kind: StatusEffectKind::Poison,
+    secs: 4.0,
+};
+
+/// Seconds an enemy waits after hurting the player before it can hurt them again.
+pub const ATTACK_COOLDOWN_SECS: f32 = 1.0;
+
+/// Chance that a chunk in a dark biome has an enemy in it when it loads, or any chunk at night,
+/// on [`Difficulty::Normal`].
+pub const ENEMY_CHANCE: f64 = 0.2;
+
+/// How many chunks from the middle of the world the deep danger zones begin.
+pub const DEEP_DANGER_CHUNKS: f32 = 32.0;
+
+/// Seconds between path searches towards the player while chasing.
+const REPATH_SECS: f32 = 0.5;
+
+/// How fast slimes squash and stretch, in frames per second.
+const SLIME_FPS: f32 = 4.0;
+
+/// How close an enemy has to come to a waypoint before heading for the next one.
+const WAYPOINT_REACHED: f32 = 2.0;
+
+/// Spawns hostile slimes in the chunks of dark biomes as they load, and in any chunk at night:
+/// in every loaded chunk as dusk falls and in those that load until dawn, more of them the darker
+/// the moon and the most under the full moon. A slime that notices
+/// the player within [`DETECT_RADIUS`] paths towards them and poisons them on contact. Slimes
+/// freeze while the chunk they are in is unloaded, which doesn't get another slime when it loads
+/// again. Loads the mob variants from `mobs.ron` into the [`MobRegistry`]: under a blood moon and
+/// in the deep danger zones, slimes may spawn as tougher variants with their own stats, tint and
+/// loot.
+pub struct EnemiesPlugin;
+
+impl Plugin for EnemiesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<MobRegistry>()
+            .register_asset_loader(RonAssetLoader::<MobRegistry>::new(&["mobs.ron"]))
+            .init_resource::<MobRegistry>()
+            .add_behavior::<EnemyState>()
+            .add_message::<PlayerSpotted>()
+            .add_observer(add_enemy_sprite)
+            .register_spawnable("Slime", SpawnCategory::Mob, spawn_enemy)
+            .add_systems(
+                Update,
+                (
+                    update_mob_registry.run_if(on_message::<AssetEvent<MobRegistry>>),
+                    (
+                        spawn_enemies
+                            .run_if(on_message::<ChunkLoaded>.or(on_message::<ClockEvent>)),
+                        (chase_player, contact_damage).run_if(in_state(LifeState::Alive)),
+                        move_enemies,
+                    )
+                        .chain()
+                        .after(AiSystems)
+                        .run_if(in_state(GameState::Playing)),
+                ),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct EnemyAssets {
+    #[asset(path = "enemies.png")]
+    pub slime: Handle<Image>,
+    /// Two frames of a slime squashing and stretching.
+    #[asset(texture_atlas_layout(tile_size_x = 16, tile_size_y = 16, columns = 2, rows = 1))]
+    pub slime_layout: Handle<TextureAtlasLayout>,
+    #[asset(path = "mobs.ron")]
+    pub mobs: Handle<MobRegistry>,
+}
+
+/// Every mob variant by name, as defined in `mobs.ron`.
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug, Default)]
+pub struct MobRegistry {
+    pub variants: HashMap<String, MobVariant>,
+}
+
+/// A tougher kind of slime that spawns in place of a plain one while its trigger is met.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct MobVariant {
+    pub name: String,
+    pub trigger: VariantTrigger,
+    /// The chance that a slime spawning while the trigger is met is of this variant.
+    pub chance: f64,
+    /// What the health, speed and contact damage of a plain slime are multiplied by.
+    pub health: f32,
+    pub speed: f32,
+    pub damage: f32,
+    /// The sRGB color its sprite is tinted.
+    pub tint: (f32, f32, f32),
+    /// The loot table it drops from instead of the plain slime's.
+    pub loot: String,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VariantTrigger {
+    /// The slime spawns on the night of a blood moon.
+    BloodMoon,
+    /// The slime spawns in a chunk of the deep danger zones.
+    DeepDanger,
+}
+
+/// Where and when a slime spawns, for checking [`VariantTrigger`]s.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VariantContext {
+    pub blood_moon: bool,
+    pub deep_danger: bool,
+}
+
+impl VariantTrigger {
+    pub fn is_met(self, context: &VariantContext) -> bool {
+        match self {
+            Self::BloodMoon => context.blood_moon,
+            Self::DeepDanger => context.deep_danger,
+        }
+    }
+}
+
+impl MobRegistry {
+    /// Rolls which variant a slime spawning in `context` is of, trying them in order of name.
+    /// `None` means a plain slime.
+    pub fn roll(&self, rng: &mut impl Rng, context: &VariantContext) -> Option<&MobVariant> {
+        let mut names: Vec<_> = self.variants.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| &self.variants[name])
+            .filter(|variant| variant.trigger.is_met(context))
+            .find(|variant| rng.random_bool(variant.chance.clamp(0.0, 1.0)))
+    }
+}
+
+#[derive(Component, Clone, Debug)]
+#[require(
+    Velocity,
+    Freeze,
+    Spatial,
+    Behavior<EnemyState>,
+    YSort,
+    Loot = Loot("slime".into())
+)]
+pub struct Enemy {
+    /// Chasing speed on grass, in world units per second.
+    speed: f32,
+    /// Health it takes from the player each time it touches them.
+    damage: f32,
+    /// Seconds until the enemy looks for a new path to the player.
+    repath_secs: f32,
+    /// Seconds until the enemy can hurt the player again.
+    attack_secs: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EnemyState {
+    #[default]
+    Idle,
+    /// The enemy has noticed the player and is after them.
+    Chasing,
+}
+
+/// An enemy noticed the player and started chasing them.
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct PlayerSpotted {
+    pub enemy: Entity,
+}
+
+/// What the chance of an enemy spawning at night is multiplied by under the moon in `phase`.
+/// Slimes come out more on darker nights, and swarm under the full moon.
+pub fn moon_spawn_multiplier(phase: MoonPhase) -> f64 {
+    match phase {
+        MoonPhase::Full => 2.0,
+        phase => 1.5 - 0.5 * f64::from(phase.brightness()),
+    }
+}
+
+/// Whether `chunk_pos` is in the deep danger zones, [`DEEP_DANGER_CHUNKS`] or more from the
+/// middle of the world.
+pub fn is_deep_danger(chunk_pos: IVec2) -> bool {
+    chunk_pos.as_vec2().length() >= DEEP_DANGER_CHUNKS
+}
+
+/// Whether enemies roam `biome`. Forests are dark under their canopy.
+pub fn is_dark(biome: Biome) -> bool {
+    biome == Biome::Forest
+}
+
+pub fn spawn_enemy(commands: &mut Commands, pos: Vec2) {
+    spawn_slime(commands, pos, None);
+}
+
+/// Spawns a slime at `pos`, of `variant` if it is given or a plain one otherwise.
+fn spawn_slime(commands: &mut Commands, pos: Vec2, variant: Option<&MobVariant>) {
+    let (health, speed, damage) = variant.map_or((1.0, 1.0, 1.0), |variant| {
+        (variant.health, variant.speed, variant.damage)
+    });
+    let tint = variant.map_or(Color::WHITE, |variant| {
+        let (red, green, blue) = variant.tint;
+        Color::srgb(red, green, blue)
+    });
+    commands.spawn((
+        Name::new(variant.map_or("Slime".into(), |variant| variant.name.clone())),
+        Enemy {
+            speed: ENEMY_SPEED * speed,
+            damage: CONTACT_DAMAGE * damage,
+            repath_secs: 0.0,
+            attack_secs: 0.0,
+        },
+        Health::full(ENEMY_HEALTH * health),
+        Tint(tint),
+        Loot(variant.map_or("slime".into(), |variant| variant.loot.clone())),
+        DespawnOnExit(GameState::Playing),
+        Transform::from_translation(pos.extend(0.9)),
+        TileCollider {
+            half_size: Vec2::new(6.0, 4.0),
+        },
+    ));
+}
+
+fn add_enemy_sprite(
+    add: On<Add, Enemy>,
+    mut commands: Commands,
+    enemy_assets: Res<EnemyAssets>,
+    tints: Query<&Tint>,
+) {
+    let color = tints.get(add.entity).map_or(Color::WHITE, |tint| tint.0);
+    commands.entity(add.entity).insert((
+        Sprite {
+            color,
+            ..Sprite::from_atlas_image(
+                enemy_assets.slime.clone(),
+                TextureAtlas::from(enemy_assets.slime_layout.clone()),
+            )
+        },
+        AnimatedSprite::looping(0, 2, SLIME_FPS),
+    ));
+}
+
+fn update_mob_registry(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<MobRegistry>>,
+    registries: Res<Assets<MobRegistry>>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event
+            && let Some(registry) = registries.get(*id)
+        {
+            info!("Loaded {} mob variants", registry.variants.len());
+            commands.insert_resource(registry.clone());
+        }
+    }
+}
+
+fn spawn_enemies(
+    mut commands: Commands,
+    (mut loaded, mut clock_events): (MessageReader<ChunkLoaded>, MessageReader<ClockEvent>),
+    (world_seed, preset, difficulty): (Res<WorldSeed>, Res<WorldgenPreset>, Res<Difficulty>),
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+    player: Single<&Transform, With<Player>>,
+    (index, enemies): (Res<SpatialIndex>, Query<(), With<Enemy>>),
+    (tiles, clock, mobs): (WorldTiles, Res<GameClock>, Res<MobRegistry>),
+) {
+    let dusk = clock_events.read().any(|event| *event == ClockEvent::Dusk);
+    let night = dusk || clock.is_night();
+    let chance = ENEMY_CHANCE
+        * difficulty.spawn_multiplier()
+        * if night {
+            moon_spawn_multiplier(clock.moon_phase())
+        } else {
+            1.0
+        };
+    let mut chunks: Vec<IVec2> = loaded
+        .read()
+        .map(|ChunkLoaded(chunk_pos, _)| *chunk_pos)
+        .collect();
+    if dusk {
+        chunks.extend(tiles.loaded_chunks());
+    }
+    for chunk_pos in chunks {
+        let occupied = index
+            .in_chunk(chunk_pos)
+            .any(|entity| enemies.contains(entity));
+        if occupied
+            || !(night || is_dark(biome_at_chunk(chunk_pos, world_seed.seed, &preset)))
+            || !global_rng.random_bool(chance.min(1.0))
+        {
+            continue;
+        }
+        let tile_pos = UVec2::new(
+            global_rng.random_range(0..CHUNK_SIZE.x),
+            global_rng.random_range(0..CHUNK_SIZE.y),
+        );
+        let world_tile = chunk_tile_to_world(chunk_pos, tile_pos.into());
+        let pos = tile_to_world_pos(world_tile);
+        // Enemies don't appear right in front of the player.
+        let walkable = tiles
+            .properties(world_tile)
+            .is_some_and(|properties| !properties.solid);
+        if walkable && pos.distance(player.translation.xy()) > DETECT_RADIUS {
+            let context = VariantContext {
+                blood_moon: clock.is_blood_moon(),
+                deep_danger: is_deep_danger(chunk_pos),
+            };
+            spawn_slime(&mut commands, pos, mobs.roll(&mut **global_rng, &context));
+        }
+    }
+}
+
+fn chase_player(
+    mut commands: Commands,
+    time: Res<Time>,
+    player: Single<&Transform, With<Player>>,
+    mut enemies: Query<(Entity, &mut Enemy, &mut Behavior<EnemyState>, &Transform)>,
+    mut spotted: MessageWriter<PlayerSpotted>,
+) {
+    let target = player.translation.xy();
+    for (entity, mut enemy, mut behavior, transform) in &mut enemies {
+        enemy.repath_secs -= time.delta_secs();
+        if transform.translation.xy().distance(target) > DETECT_RADIUS {
+            if behavior.enter(EnemyState::Idle) {
+                commands.entity(entity).remove::<(FindPath, Path)>();
+            }
+            continue;
+        }
+        if behavior.enter(EnemyState::Chasing) {
+            spotted.write(PlayerSpotted { enemy: entity });
+        }
+        if enemy.repath_secs <= 0.0 {
+            enemy.repath_secs = REPATH_SECS;
+            commands.entity(entity).insert(FindPath::to(target));
+        }
+    }
+}
+
+fn contact_damage(
+    time: Res<Time>,
+    player: Single<(Entity, &Transform, &TileCollider), With<Player>>,
+    mut enemies: Query<(&mut Enemy, &Transform, &TileCollider)>,
+    (mut damage, mut effects): (MessageWriter<Damage>, MessageWriter<ApplyEffect>),
+) {
+    let (target, player_transform, player_collider) = player.into_inner();
+    let player_pos = player_transform.translation.xy();
+    for (mut enemy, transform, collider) in &mut enemies {
+        enemy.attack_secs -= time.delta_secs();
+        if enemy.attack_secs <= 0.0
+            && touching(
+                transform.translation.xy(),
+                collider.half_size,
+                player_pos,
+                player_collider.half_size,
+            )
+        {
+            enemy.attack_secs = ATTACK_COOLDOWN_SECS;
+            let away = (player_pos - transform.translation.xy()).normalize_or_zero();
+            damage.write(Damage {
+                target,
+                amount: enemy.damage,
+                knockback: away * CONTACT_KNOCKBACK,
+            });
+            effects.write(ApplyEffect {
+                target,
+                effect: CONTACT_POISON,
+            });
+        }
+    }
+}
+
+/// What [`move_enemies`] moves enemies with.
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct EnemyMovement {
+    enemy: &'static Enemy,
+    path: Option<&'static mut Path>,
+    collider: &'static TileCollider,
+    effects: &'static StatusEffects,
+    velocity: &'static mut Velocity,
+    transform: &'static mut Transform,
+    sprite: &'static mut Sprite,
+}
+
+fn move_enemies(
+    time: Res<Time>,
+    mut enemies: Query<EnemyMovement, With<Enemy>>,
+    tiles: WorldTiles,
+) {
+    let secs = time.delta_secs();
+    if secs == 0.0 {
+        return;
+    }
+    for mut enemy in &mut enemies {
+        let pos = enemy.transform.translation.xy();
+        let speed = enemy.enemy.speed * enemy.effects.speed_multiplier();
+        let wanted = enemy
+            .path
+            .as_mut()
+            .and_then(|path| path.next_waypoint(pos, WAYPOINT_REACHED))
+            .map_or(Vec2::ZERO, |waypoint| {
+                (waypoint - pos).normalize_or_zero() * speed
+            });
+        let velocity = &mut enemy.velocity.0;
+        let moved = walk(&tiles, pos, velocity, wanted, speed, enemy.collider, secs);
+        let moving_x = velocity.x;
+        enemy.transform.translation = moved.extend(enemy.transform.translation.z);
+        if moving_x != 0.0 {
+            enemy.sprite.flip_x = moving_x < 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::loot::LootTables;
+
+    #[test]
+    fn enemies_chase_nearby_players_and_hurt_them_on_contact() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_message::<Damage>()
+            .add_message::<PlayerSpotted>()
+            .add_message::<ApplyEffect>()
+            .add_systems(Update, (chase_player, contact_damage));
+        let collider = TileCollider {
+            half_size: Vec2::splat(4.0),
+        };
+        let player = app
+            .world_mut()
+            .spawn((Player, Transform::default(), collider))
+            .id();
+        let spawn = |app: &mut App, pos: Vec2| {
+            let mut commands = app.world_mut().commands();
+            spawn_enemy(&mut commands, pos);
+            app.world_mut().flush();
+        };
+        spawn(&mut app, Vec2::new(6.0, 0.0));
+        spawn(&mut app, Vec2::new(DETECT_RADIUS + 1.0, 0.0));
+        app.update();
+        app.update();
+
+        let mut enemies = app
+            .world_mut()
+            .query::<(Entity, &Behavior<EnemyState>, &Transform, Has<FindPath>)>();
+        let mut chasers = Vec::new();
+        for (entity, behavior, transform, finding_path) in enemies.iter(app.world()) {
+            let near = transform.translation.x < DETECT_RADIUS;
+            let chasing = *behavior.state() == EnemyState::Chasing;
+            assert_eq!((chasing, finding_path), (near, near));
+            if near {
+                chasers.push(PlayerSpotted { enemy: entity });
+            }
+        }
+        // The player is only spotted once however long the chase goes on.
+        let spotted: Vec<_> = app
+            .world_mut()
+            .resource_mut::<Messages<PlayerSpotted>>()
+            .drain()
+            .collect();
+        assert_eq!(spotted, chasers);
+        // Only the touching enemy attacks, and only once within its cooldown.
+        let damage: Vec<_> = app
+            .world_mut()
+            .resource_mut::<Messages<Damage>>()
+            .drain()
+            .collect();
+        assert_eq!(
+            damage,
+            vec![Damage {
+                target: player,
+                amount: CONTACT_DAMAGE,
+                knockback: Vec2::NEG_X * CONTACT_KNOCKBACK,
+            }]
+        );
+    }
+
+    #[test]
+    fn variants_only_spawn_while_their_triggers_are_met() {
+        let mobs: MobRegistry = ron::from_str(include_str!("../assets/mobs.ron")).unwrap();
+        let loot_tables: LootTables = ron::from_str(include_str!("../assets/loot.ron")).unwrap();
+        assert!(
+            mobs.variants
+                .values()
+                .all(|variant| loot_tables.tables.contains_key(&variant.loot))
+        );
+
+        let mut rng = WyRand::from_seed([5; 8]);
+        let mut roll = |context: VariantContext| {
+            (0..100)
+                .filter_map(|_| mobs.roll(&mut rng, &context))
+                .map(|variant| variant.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert!(roll(VariantContext::default()).is_empty());
+        let blood_moon = roll(VariantContext {
+            blood_moon: true,
+            ..default()
+        });
+        assert!(!blood_moon.is_empty() && blood_moon.len() < 100);
+        assert!(blood_moon.iter().all(|name| *name == "Moonlit slime"));
+        let deep_danger = roll(VariantContext {
+            deep_danger: true,
+            ..default()
+        });
+        assert!(!deep_danger.is_empty());
+        assert!(deep_danger.iter().all(|name| *name == "Elite slime"));
+
+        assert!(!is_deep_danger(IVec2::new(3, -20)));
+        assert!(is_deep_danger(IVec2::new(-40, 2)));
+    }
+}
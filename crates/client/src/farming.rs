@@ -0,0 +1,270 @@
+use bevy::prelude::*;
+use bevy_egui::input::egui_wants_any_pointer_input;
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+use crate::chunk::ChunkLayer;
+use crate::debug_placer::placing;
+use crate::health::LifeState;
+use crate::hotbar::Hotbar;
+use crate::inventory::{Inventory, ItemId, ItemRegistry, ItemStack};
+use crate::item_drops::spawn_item_drop;
+use crate::persistence::WorldSave;
+use crate::picking::CursorWorldPos;
+use crate::player::Player;
+use crate::terraform::brush_selected;
+use crate::tile_highlight::in_reach;
+use crate::tiles::{
+    LayerTiles, TileKind, TileLocator, WorldTiles, tile_to_world_pos, update_tile_textures,
+    world_pos_to_tile,
+};
+
+/// Growth stages of a crop, each with its own frame in `tiles.png`. The last one is ripe.
+pub const CROP_STAGES: u32 = 4;
+
+/// Seconds of playtime a crop spends in each growth stage.
+pub const CROP_STAGE_SECS: f64 = 90.0;
+
+/// What a ripe crop gives on top of its seeds when it is harvested.
+const HARVEST: &str = "wheat";
+
+/// Right-clicking grass with a hoe tills it into farmland, where seeds can be planted like any
+/// other placeable item. Crops grow through [`CROP_STAGES`] with the world's playtime, so a crop
+/// whose chunk was unloaded catches up as soon as it is seen again, and they grow faster in the
+/// rain. Ripe crops drop wheat as well as their seeds when harvested.
+pub struct FarmingPlugin;
+
+impl Plugin for FarmingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                till_tiles
+                    .run_if(in_state(LifeState::Alive))
+                    .run_if(not(egui_wants_any_pointer_input))
+                    .run_if(not(brush_selected))
+                    .run_if(not(placing)),
+                sync_crops,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            PostUpdate,
+            grow_crops
+                .after(update_tile_textures)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// A crop planted on a [`TileKind::Crop`] tile, saved with its chunk.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Crop {
+    /// The world's playtime when the crop was planted, in seconds.
+    pub planted_at: f64,
+}
+
+impl Crop {
+    /// The growth stage at `playtime`, from 0 when just planted to `CROP_STAGES - 1` when ripe.
+    pub fn stage(self, playtime: f64) -> u32 {
+        let stage = ((playtime - self.planted_at) / CROP_STAGE_SECS).max(0.0) as u32;
+        stage.min(CROP_STAGES - 1)
+    }
+
+    pub fn is_ripe(self, playtime: f64) -> bool {
+        self.stage(playtime) == CROP_STAGES - 1
+    }
+}
+
+fn till_tiles(
+    mouse: Res<ButtonInput<MouseButton>>,
+    cursor: CursorWorldPos,
+    (hotbar, registry): (Res<Hotbar>, Res<ItemRegistry>),
+    player: Single<(&Transform, &Inventory), With<Player>>,
+    mut tiles: WorldTiles,
+) {
+    if !mouse.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let (transform, inventory) = player.into_inner();
+    let holding_hoe = hotbar
+        .selected_stack(inventory)
+        .and_then(|stack| registry.get(&stack.item))
+        .is_some_and(|definition| definition.tills);
+    let Some(world_tile) = cursor.get().map(world_pos_to_tile) else {
+        return;
+    };
+    if holding_hoe
+        && in_reach(transform.translation.xy(), world_tile)
+        && tiles.get_tile(world_tile) == Some(TileKind::Grass)
+    {
+        tiles.set_tile(world_tile, TileKind::Farmland);
+    }
+}
+
+/// Plants a [`Crop`] on new crop tiles, and harvests the crops of tiles that changed to
+/// something else.
+fn sync_crops(
+    mut commands: Commands,
+    world_save: Res<WorldSave>,
+    registry: Res<ItemRegistry>,
+    tiles: Query<(Entity, &TileKind, Option<&Crop>), Changed<TileKind>>,
+    locator: TileLocator,
+) {
+    let playtime = world_save.metadata.playtime_secs;
+    for (entity, kind, crop) in &tiles {
+        match (kind, crop) {
+            (TileKind::Crop, None) => {
+                commands.entity(entity).insert(Crop {
+                    planted_at: playtime,
+                });
+            }
+            (TileKind::Crop, Some(_)) | (_, None) => {}
+            (_, Some(crop)) => {
+                commands.entity(entity).remove::<Crop>();
+                if let Some(world_tile) = locator.world_tile(entity)
+                    && crop.is_ripe(playtime)
+                {
+                    let stack = ItemStack {
+                        item: ItemId::from(HARVEST),
+                        count: 1,
+                    };
+                    let origin = tile_to_world_pos(world_tile);
+                    spawn_item_drop(&mut commands, &registry, stack, origin);
+                }
+            }
+        }
+    }
+}
+
+/// Crops are drawn in the [`ChunkLayer::Decoration`] over their farmland.
+fn grow_crops(
+    world_save: Res<WorldSave>,
+    crops: Query<(Entity, &Crop)>,
+    mut layer_tiles: LayerTiles,
+) {
+    let playtime = world_save.metadata.playtime_secs;
+    for (entity, crop) in &crops {
+        let index = TileKind::Crop.texture_index() + crop.stage(playtime);
+        if let Some(mut texture_index) = layer_tiles.texture_mut(entity, ChunkLayer::Decoration)
+            && texture_index.0 != index
+        {
+            texture_index.0 = index;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use bevy_ecs_tilemap::prelude::*;
+
+    use super::*;
+    use crate::chunk::{CHUNK_SIZE, ChunkManager, ChunkPosition};
+    use crate::clock::Season;
+    use crate::difficulty::Difficulty;
+    use crate::item_drops::ItemDrop;
+    use crate::persistence::WorldMetadata;
+    use crate::temperature::SurvivalMode;
+    use crate::worldgen::WorldgenPreset;
+
+    #[test]
+    fn crops_grow_with_playtime_and_drop_a_harvest_when_ripe() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(
+                ron::from_str::<ItemRegistry>(include_str!("../assets/items.ron")).unwrap(),
+            )
+            .insert_resource(WorldSave::new(
+                PathBuf::new(),
+                WorldMetadata {
+                    name: "Farm".to_string(),
+                    seed: 1,
+                    preset: WorldgenPreset::default(),
+                    difficulty: Difficulty::default(),
+                    survival: SurvivalMode::default(),
+                    created: 0,
+                    playtime_secs: 100.0,
+                    player_pos: Vec2::ZERO,
+                    player_health: None,
+                    player_inventory: None,
+                    quest_log: None,
+                    clock_days: None,
+                    waypoints: None,
+                },
+            ))
+            .init_resource::<ChunkManager>()
+            .init_resource::<Season>()
+            .add_systems(
+                Update,
+                (sync_crops, update_tile_textures, grow_crops).chain(),
+            );
+        let chunk = app.world_mut().spawn(ChunkPosition(IVec2::ZERO)).id();
+        let layers = ChunkLayer::STACKED.map(|_| {
+            app.world_mut()
+                .spawn(TileStorage::empty(CHUNK_SIZE.into()))
+                .id()
+        });
+        let mut chunk_manager = app.world_mut().resource_mut::<ChunkManager>();
+        chunk_manager.spawned_chunks.insert(IVec2::ZERO, chunk);
+        chunk_manager.layers.insert(IVec2::ZERO, layers);
+        let spawn_crop = |world: &mut World, tile_pos| {
+            world
+                .spawn((
+                    TileKind::Crop,
+                    tile_pos,
+                    TilemapId(chunk),
+                    TileTextureIndex(TileKind::Crop.texture_index()),
+                ))
+                .id()
+        };
+        let early = spawn_crop(app.world_mut(), TilePos::new(3, 4));
+        app.update();
+        let late = spawn_crop(app.world_mut(), TilePos::new(5, 4));
+        app.world_mut().get_mut::<Crop>(early).unwrap().planted_at = 100.0 - CROP_STAGE_SECS;
+        app.update();
+        assert_eq!(
+            app.world().get::<Crop>(late),
+            Some(&Crop { planted_at: 100.0 })
+        );
+
+        // Growth only depends on playtime, so crops catch up however long they went unseen.
+        app.world_mut()
+            .resource_mut::<WorldSave>()
+            .metadata
+            .playtime_secs += CROP_STAGE_SECS * 2.5;
+        app.update();
+        // The crops are drawn over their farmland.
+        let texture = |app: &App, tile_pos| {
+            let storage = app.world().get::<TileStorage>(layers[0]).unwrap();
+            app.world()
+                .get::<TileTextureIndex>(storage.get(&tile_pos).unwrap())
+                .unwrap()
+                .0
+        };
+        assert_eq!(
+            texture(&app, TilePos::new(3, 4)),
+            TileKind::Crop.texture_index() + 3
+        );
+        assert_eq!(
+            texture(&app, TilePos::new(5, 4)),
+            TileKind::Crop.texture_index() + 2
+        );
+        assert_eq!(
+            app.world().get::<TileTextureIndex>(early).unwrap().0,
+            TileKind::Farmland.texture_index()
+        );
+
+        for entity in [early, late] {
+            *app.world_mut().get_mut::<TileKind>(entity).unwrap() = TileKind::Farmland;
+        }
+        app.update();
+        assert!(app.world().get::<Crop>(late).is_none());
+        let mut drops = app.world_mut().query::<&ItemDrop>();
+        let drops: Vec<_> = drops.iter(app.world()).collect();
+        assert_eq!(drops.len(), 1);
+        assert_eq!(drops[0].item, ItemId::from(HARVEST));
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_seedling::prelude::*;
+
+use crate::GameState;
+use crate::player::{Player, Velocity};
+use crate::tiles::{TileKind, WorldTiles, world_pos_to_tile};
+
+/// Distance walked per footstep, in world units.
+pub const STEP_LENGTH: f32 = 18.0;
+
+/// Footsteps never play closer together than this, in seconds, however fast the player moves.
+pub const MIN_STEP_INTERVAL: f32 = 0.15;
+
+/// Plays a footstep each [`STEP_LENGTH`] the player moves, picking the sample set from the tile
+/// underfoot.
+pub struct FootstepsPlugin;
+
+impl Plugin for FootstepsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, play_footsteps.run_if(in_state(GameState::Playing)));
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct FootstepSounds {
+    #[asset(
+        paths("sfx/footsteps/grass_1.wav", "sfx/footsteps/grass_2.wav"),
+        collection(typed)
+    )]
+    pub grass: Vec<Handle<AudioSample>>,
+    #[asset(
+        paths("sfx/footsteps/gravel_1.wav", "sfx/footsteps/gravel_2.wav"),
+        collection(typed)
+    )]
+    pub gravel: Vec<Handle<AudioSample>>,
+    #[asset(
+        paths("sfx/footsteps/snow_1.wav", "sfx/footsteps/snow_2.wav"),
+        collection(typed)
+    )]
+    pub snow: Vec<Handle<AudioSample>>,
+    #[asset(
+        paths("sfx/footsteps/water_1.wav", "sfx/footsteps/water_2.wav"),
+        collection(typed)
+    )]
+    pub water: Vec<Handle<AudioSample>>,
+}
+
+impl FootstepSounds {
+    /// The samples to pick from for a step on `kind`.
+    pub fn for_tile(&self, kind: TileKind) -> &[Handle<AudioSample>] {
+        match kind {
+            TileKind::Grass | TileKind::Forest | TileKind::Crop => &self.grass,
+            TileKind::Gravel
+            | TileKind::Stone
+            | TileKind::Chest
+            | TileKind::Farmland
+            | TileKind::DungeonWall
+            | TileKind::DungeonFloor
+            | TileKind::Door
+            | TileKind::SecretWall
+            | TileKind::PressurePlate
+            | TileKind::HouseWall
+            | TileKind::HouseFloor
+            | TileKind::HouseWindow
+            | TileKind::Torch
+            | TileKind::Campfire => &self.gravel,
+            TileKind::Snow => &self.snow,
+            TileKind::Water => &self.water,
+        }
+    }
+}
+
+/// Tracks the distance walked since the last footstep.
+#[derive(Default)]
+pub struct Stride {
+    travelled: f32,
+    since_step: f32,
+    steps: usize,
+}
+
+impl Stride {
+    /// Advances by `distance` walked over `secs` seconds, returning the number of the step to
+    /// play if one is due.
+    pub fn advance(&mut self, distance: f32, secs: f32) -> Option<usize> {
+        self.since_step += secs;
+        if distance == 0.0 {
+            // Start the next walk with a step.
+            self.travelled = STEP_LENGTH;
+            return None;
+        }
+        self.travelled += distance;
+        if self.travelled < STEP_LENGTH || self.since_step < MIN_STEP_INTERVAL {
+            return None;
+        }
+        self.travelled = 0.0;
+        self.since_step = 0.0;
+        self.steps += 1;
+        Some(self.steps)
+    }
+}
+
+fn play_footsteps(
+    mut commands: Commands,
+    time: Res<Time>,
+    sounds: Res<FootstepSounds>,
+    player: Single<(&Transform, &Velocity), With<Player>>,
+    tiles: WorldTiles,
+    mut stride: Local<Stride>,
+) {
+    let (transform, velocity) = player.into_inner();
+    let distance = velocity.0.length() * time.delta_secs();
+    let Some(step) = stride.advance(distance, time.delta_secs()) else {
+        return;
+    };
+    let Some(kind) = tiles.get_tile(world_pos_to_tile(transform.translation.xy())) else {
+        return;
+    };
+    let samples = sounds.for_tile(kind);
+    if samples.is_empty() {
+        return;
+    }
+    // Alternate samples and nudge the pitch so repeated steps don't sound identical.
+    let pitch = [1.0, 0.94, 1.05][step % 3];
+    commands.spawn((
+        SamplePlayer::new(samples[step % samples.len()].clone()),
+        PlaybackSettings::default().with_speed(pitch),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_follow_distance_but_not_too_quickly() {
+        let mut stride = Stride::default();
+        // Setting off plays a step as soon as the interval allows.
+        assert_eq!(stride.advance(0.0, 1.0), None);
+        assert_eq!(stride.advance(1.0, 0.01), Some(1));
+
+        assert_eq!(stride.advance(STEP_LENGTH / 2.0, 0.2), None);
+        assert_eq!(stride.advance(STEP_LENGTH / 2.0, 0.2), Some(2));
+
+        // Sprinting far in a single frame still waits for the minimum interval.
+        assert_eq!(stride.advance(STEP_LENGTH * 3.0, 0.05), None);
+        assert_eq!(stride.advance(1.0, MIN_STEP_INTERVAL), Some(3));
+    }
+}
@@ -0,0 +1,263 @@
+use bevy::asset::{RenderAssetUsages, embedded_asset, load_embedded_asset};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::render::extract_component::{ExtractComponent, ExtractComponentPlugin};
+use bevy::render::gpu_readback::{Readback, ReadbackComplete};
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_graph::{
+    self, NodeRunError, RenderGraph, RenderGraphContext, RenderLabel,
+};
+use bevy::render::render_resource::binding_types::{storage_buffer, uniform_buffer};
+use bevy::render::render_resource::*;
+use bevy::render::renderer::{RenderContext, RenderDevice, RenderQueue};
+use bevy::render::storage::{GpuShaderStorageBuffer, ShaderStorageBuffer};
+use bevy::render::{Render, RenderApp, RenderSystems};
+use noisy_bevy::NoisyShaderPlugin;
+
+use crate::biome_assets::BiomeAssets;
+use crate::chunk::{CHUNK_SIZE, insert_chunk};
+use crate::dungeons::build_dungeon;
+use crate::noise::NoiseBackend;
+use crate::persistence::ChunkData;
+use crate::tiles::TileKind;
+use crate::villagers::build_village;
+use crate::worldgen::{WorldSeed, WorldgenPreset, biome_at_chunk, noise_seed};
+
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Written to every tile before generation so readbacks that finish before the shader ran can
+/// be told apart from real results.
+const UNGENERATED: u32 = u32::MAX;
+
+/// Generates chunks with a compute shader and reads the tiles back, for render distances where
+/// CPU generation dominates the frame. Only the simplex noise backend is implemented on the GPU;
+/// other presets keep generating on the CPU.
+pub struct GpuWorldgenPlugin;
+
+impl Plugin for GpuWorldgenPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "gpu_worldgen.wgsl");
+
+        app.add_plugins((
+            NoisyShaderPlugin,
+            ExtractComponentPlugin::<GpuChunkRequest>::default(),
+        ))
+        .add_observer(spawn_generated_chunk);
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<GpuWorldgenBindGroups>()
+            .add_systems(
+                Render,
+                prepare_bind_groups.in_set(RenderSystems::PrepareBindGroups),
+            );
+        let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
+        render_graph.add_node(GpuWorldgenLabel, GpuWorldgenNode);
+        render_graph.add_node_edge(GpuWorldgenLabel, bevy::render::graph::CameraDriverLabel);
+    }
+
+    fn finish(&self, app: &mut App) {
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.init_resource::<GpuWorldgenPipeline>();
+        }
+    }
+}
+
+/// A chunk waiting for its tiles to come back from the GPU. Once they do, the chunk's tilemap is
+/// inserted on the same entity.
+#[derive(Component, ExtractComponent, Clone)]
+struct GpuChunkRequest {
+    chunk_pos: IVec2,
+    seed: u64,
+    tiles: Handle<ShaderStorageBuffer>,
+}
+
+/// Queues chunks for generation on the GPU.
+#[derive(SystemParam)]
+pub struct GpuWorldgen<'w> {
+    buffers: ResMut<'w, Assets<ShaderStorageBuffer>>,
+}
+
+impl GpuWorldgen<'_> {
+    pub fn supports(preset: &WorldgenPreset) -> bool {
+        preset.noise == NoiseBackend::Simplex
+    }
+
+    /// Spawns the entity the chunk at `chunk_pos` will be inserted on once generated.
+    pub fn request(&mut self, commands: &mut Commands, chunk_pos: IVec2, seed: u64) -> Entity {
+        let tile_count = (CHUNK_SIZE.x * CHUNK_SIZE.y) as usize;
+        let mut buffer = ShaderStorageBuffer::from(vec![UNGENERATED; tile_count]);
+        buffer.buffer_description.usage |= BufferUsages::COPY_SRC;
+        buffer.asset_usage = RenderAssetUsages::RENDER_WORLD;
+        let tiles = self.buffers.add(buffer);
+
+        commands
+            .spawn((
+                GpuChunkRequest {
+                    chunk_pos,
+                    seed,
+                    tiles: tiles.clone(),
+                },
+                Readback::buffer(tiles),
+            ))
+            .id()
+    }
+}
+
+fn spawn_generated_chunk(
+    readback: On<ReadbackComplete>,
+    mut commands: Commands,
+    biome_assets: Res<BiomeAssets>,
+    (world_seed, preset): (Res<WorldSeed>, Res<WorldgenPreset>),
+    requests: Query<&GpuChunkRequest>,
+) {
+    // Readbacks already in flight can still complete after the chunk was inserted.
+    let Ok(request) = requests.get(readback.entity) else {
+        return;
+    };
+    let tiles = readback
+        .data
+        .chunks_exact(4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .map(TileKind::from_texture_index)
+        .collect::<Option<Vec<_>>>();
+    let Some(tiles) = tiles else {
+        return;
+    };
+    let biome = biome_at_chunk(request.chunk_pos, world_seed.seed, &preset);
+    let Some(assets) = biome_assets.groups.get(&biome) else {
+        return;
+    };
+
+    let mut data = ChunkData { tiles, ..default() };
+    build_dungeon(world_seed.seed, &preset, request.chunk_pos, &mut data);
+    build_village(world_seed.seed, &preset, request.chunk_pos, &mut data);
+
+    commands
+        .entity(readback.entity)
+        .remove::<(GpuChunkRequest, Readback)>();
+    insert_chunk(
+        &mut commands,
+        readback.entity,
+        assets,
+        request.chunk_pos,
+        data,
+    );
+}
+
+#[derive(ShaderType)]
+struct GpuWorldgenParams {
+    chunk_origin: IVec2,
+    chunk_size: UVec2,
+    terrain_seed: f32,
+    moisture_seed: f32,
+}
+
+#[derive(Resource)]
+struct GpuWorldgenPipeline {
+    layout: BindGroupLayout,
+    pipeline: CachedComputePipelineId,
+}
+
+impl FromWorld for GpuWorldgenPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let layout = world.resource::<RenderDevice>().create_bind_group_layout(
+            "gpu_worldgen_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    uniform_buffer::<GpuWorldgenParams>(false),
+                    storage_buffer::<Vec<u32>>(false),
+                ),
+            ),
+        );
+        let shader = load_embedded_asset!(world, "gpu_worldgen.wgsl");
+        let pipeline =
+            world
+                .resource::<PipelineCache>()
+                .queue_compute_pipeline(ComputePipelineDescriptor {
+                    label: Some("gpu_worldgen_pipeline".into()),
+                    layout: vec![layout.clone()],
+                    shader,
+                    ..default()
+                });
+        Self { layout, pipeline }
+    }
+}
+
+#[derive(Resource, Default)]
+struct GpuWorldgenBindGroups(Vec<BindGroup>);
+
+fn prepare_bind_groups(
+    pipeline: Res<GpuWorldgenPipeline>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    buffers: Res<RenderAssets<GpuShaderStorageBuffer>>,
+    requests: Query<&GpuChunkRequest>,
+    mut bind_groups: ResMut<GpuWorldgenBindGroups>,
+) {
+    bind_groups.0.clear();
+    for request in &requests {
+        let Some(tiles) = buffers.get(&request.tiles) else {
+            continue;
+        };
+        let mut params = UniformBuffer::from(GpuWorldgenParams {
+            chunk_origin: request.chunk_pos * CHUNK_SIZE.as_ivec2(),
+            chunk_size: CHUNK_SIZE,
+            terrain_seed: noise_seed(request.seed),
+            moisture_seed: noise_seed(request.seed + 1000),
+        });
+        params.write_buffer(&render_device, &render_queue);
+        let Some(params) = params.binding() else {
+            continue;
+        };
+        bind_groups.0.push(render_device.create_bind_group(
+            "gpu_worldgen_bind_group",
+            &pipeline.layout,
+            &BindGroupEntries::sequential((params, tiles.buffer.as_entire_buffer_binding())),
+        ));
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct GpuWorldgenLabel;
+
+struct GpuWorldgenNode;
+
+impl render_graph::Node for GpuWorldgenNode {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let bind_groups = &world.resource::<GpuWorldgenBindGroups>().0;
+        let pipeline_id = world.resource::<GpuWorldgenPipeline>().pipeline;
+        let Some(pipeline) = world
+            .resource::<PipelineCache>()
+            .get_compute_pipeline(pipeline_id)
+        else {
+            return Ok(());
+        };
+        if bind_groups.is_empty() {
+            return Ok(());
+        }
+
+        let workgroups = (CHUNK_SIZE + UVec2::splat(WORKGROUP_SIZE - 1)) / WORKGROUP_SIZE;
+        let mut pass =
+            render_context
+                .command_encoder()
+                .begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("gpu_worldgen"),
+                    ..default()
+                });
+        pass.set_pipeline(pipeline);
+        for bind_group in bind_groups {
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups(workgroups.x, workgroups.y, 1);
+        }
+        Ok(())
+    }
+}
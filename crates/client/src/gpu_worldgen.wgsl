@@ -0,0 +1,49 @@
+#import noisy_bevy::fbm_simplex_2d_seeded
+
+struct Params {
+    chunk_origin: vec2<i32>,
+    chunk_size: vec2<u32>,
+    terrain_seed: f32,
+    moisture_seed: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> tiles: array<u32>;
+
+const GRASS: u32 = 0u;
+const WATER: u32 = 1u;
+const FOREST: u32 = 2u;
+const STONE: u32 = 3u;
+const GRAVEL: u32 = 4u;
+const SNOW: u32 = 5u;
+
+fn fbm_safe(pos: vec2<f32>, octaves: i32, seed: f32) -> f32 {
+    return clamp(fbm_simplex_2d_seeded(pos / 10.0, octaves, 2.0, 0.5, seed), -1.0, 1.0);
+}
+
+// Mirrors `get_tile_type` in worldgen.rs; keep the thresholds in sync.
+fn tile_type(world_tile: vec2<i32>) -> u32 {
+    let pos = vec2<f32>(world_tile) * 0.08;
+    let terrain = fbm_safe(pos, 4, params.terrain_seed);
+    let moisture = fbm_safe(pos + vec2(100.0), 3, params.moisture_seed);
+
+    if terrain < -0.25 {
+        return WATER;
+    } else if terrain < 0.0 {
+        return select(FOREST, GRASS, moisture > 0.3);
+    } else if terrain < 0.3 {
+        return select(FOREST, GRASS, moisture > 0.1);
+    } else if terrain < 0.55 {
+        return select(STONE, GRAVEL, moisture < -0.2);
+    }
+    return SNOW;
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.chunk_size.x || id.y >= params.chunk_size.y {
+        return;
+    }
+    let world_tile = params.chunk_origin + vec2<i32>(id.xy);
+    tiles[id.y * params.chunk_size.x + id.x] = tile_type(world_tile);
+}
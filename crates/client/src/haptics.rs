@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use bevy::input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest};
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+
+/// Plays [`Haptic`] messages as rumble on every connected gamepad, scaled by
+/// [`Settings::haptics_intensity`]. Without a gamepad, or on gamepads without rumble motors, the
+/// messages are simply dropped.
+pub struct HapticsPlugin;
+
+impl Plugin for HapticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<Haptic>()
+            .add_systems(Update, play_haptics.run_if(on_message::<Haptic>));
+    }
+}
+
+/// A gameplay event to be felt through the controller.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Haptic {
+    Hit,
+    HeavyLanding,
+    FishingBite,
+    Thunder,
+}
+
+/// Rumble strength of each motor, from 0 to 1, and how long it lasts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HapticProfile {
+    pub strong_motor: f32,
+    pub weak_motor: f32,
+    pub duration: Duration,
+}
+
+impl Haptic {
+    pub fn profile(self) -> HapticProfile {
+        let (strong_motor, weak_motor, millis) = match self {
+            Haptic::Hit => (0.6, 0.8, 150),
+            Haptic::HeavyLanding => (1.0, 0.3, 250),
+            Haptic::FishingBite => (0.0, 0.7, 120),
+            Haptic::Thunder => (0.8, 0.2, 900),
+        };
+        HapticProfile {
+            strong_motor,
+            weak_motor,
+            duration: Duration::from_millis(millis),
+        }
+    }
+}
+
+fn play_haptics(
+    mut haptics: MessageReader<Haptic>,
+    settings: Res<Settings>,
+    gamepads: Query<Entity, With<Gamepad>>,
+    mut rumble: MessageWriter<GamepadRumbleRequest>,
+) {
+    let intensity = settings.haptics_intensity.clamp(0.0, 1.0);
+    if intensity == 0.0 || gamepads.is_empty() {
+        haptics.clear();
+        return;
+    }
+    for haptic in haptics.read() {
+        let profile = haptic.profile();
+        for gamepad in &gamepads {
+            rumble.write(GamepadRumbleRequest::Add {
+                duration: profile.duration,
+                intensity: GamepadRumbleIntensity {
+                    strong_motor: profile.strong_motor * intensity,
+                    weak_motor: profile.weak_motor * intensity,
+                },
+                gamepad,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rumbles_every_gamepad_at_the_set_intensity() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, HapticsPlugin))
+            .add_message::<GamepadRumbleRequest>()
+            .insert_resource(Settings {
+                haptics_intensity: 0.5,
+                ..default()
+            });
+        let mut gamepads = vec![
+            app.world_mut().spawn(Gamepad::default()).id(),
+            app.world_mut().spawn(Gamepad::default()).id(),
+        ];
+        gamepads.sort();
+
+        app.world_mut().write_message(Haptic::HeavyLanding);
+        app.update();
+
+        let requests = app.world().resource::<Messages<GamepadRumbleRequest>>();
+        let mut rumbled = Vec::new();
+        for request in requests.iter_current_update_messages() {
+            let GamepadRumbleRequest::Add {
+                intensity, gamepad, ..
+            } = request
+            else {
+                panic!("expected a rumble to be added");
+            };
+            assert_eq!(intensity.strong_motor, 0.5);
+            assert_eq!(intensity.weak_motor, 0.15);
+            rumbled.push(*gamepad);
+        }
+        rumbled.sort();
+        assert_eq!(rumbled, gamepads);
+    }
+}
@@ -0,0 +1,326 @@
+use bevy::ecs::query::QueryData;
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::GameState;
+use crate::difficulty::Difficulty;
+use crate::haptics::Haptic;
+use crate::localization::Localization;
+use crate::player::{CameraFollow, Player, Velocity};
+use crate::status_effects::StatusEffects;
+
+/// Health the player starts with and respawns with.
+pub const PLAYER_MAX_HEALTH: f32 = 10.0;
+
+/// Seconds an entity ignores further damage for after being hurt.
+pub const INVULNERABLE_SECS: f32 = 0.5;
+
+/// Where the player respawns after dying.
+pub const WORLD_SPAWN: Vec2 = Vec2::ZERO;
+
+/// Applies [`Damage`] and [`Heal`]ing to entities with [`Health`]. Damage knocks its target
+/// back, and the target can't be hurt again for [`INVULNERABLE_SECS`]. Damage to the player is
+/// scaled by the world's [`Difficulty`]. Entities other than the player are despawned when their
+/// health runs out, while the player dies: [`LifeState`] switches to
+/// [`Dead`](LifeState::Dead), which shows the death screen until the player chooses to
+/// [`Respawn`].
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<LifeState>()
+            .add_message::<Damage>()
+            .add_message::<Heal>()
+            .add_message::<Hurt>()
+            .add_observer(respawn)
+            .add_systems(OnEnter(LifeState::Dead), hide_player)
+            .add_systems(
+                Update,
+                (
+                    tick_invulnerability,
+                    apply_damage.run_if(on_message::<Damage>),
+                    apply_healing.run_if(on_message::<Heal>),
+                )
+                    .chain()
+                    .run_if(in_state(LifeState::Alive)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                death_screen.run_if(in_state(LifeState::Dead)),
+            );
+    }
+}
+
+/// Whether the player is alive, while [`GameState::Playing`].
+#[derive(SubStates, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[source(GameState = GameState::Playing)]
+pub enum LifeState {
+    #[default]
+    Alive,
+    Dead,
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[require(StatusEffects)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+    /// Seconds left until the entity can be hurt again.
+    pub invulnerable_secs: f32,
+}
+
+impl Health {
+    pub fn full(max: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            invulnerable_secs: 0.0,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+}
+
+/// Takes `amount` health from `target`, adding `knockback` to its [`Velocity`] if it has one.
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct Damage {
+    pub target: Entity,
+    pub amount: f32,
+    pub knockback: Vec2,
+}
+
+/// [`Damage`] that got through to `target`, which was at `pos`. The target may have died and
+/// despawned since.
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct Hurt {
+    pub target: Entity,
+    pub amount: f32,
+    pub pos: Vec2,
+}
+
+/// Gives `amount` health back to `target`, up to its maximum.
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct Heal {
+    pub target: Entity,
+    pub amount: f32,
+}
+
+/// Triggered on an entity other than the player that ran out of health, right before it
+/// despawns.
+#[derive(EntityEvent, Clone, Copy, Debug)]
+pub struct Killed {
+    pub entity: Entity,
+}
+
+/// Brings a dead player back at [`WORLD_SPAWN`] with full health.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Respawn;
+
+fn tick_invulnerability(time: Res<Time>, mut healths: Query<&mut Health>) {
+    for mut health in &mut healths {
+        if health.invulnerable_secs > 0.0 {
+            health.invulnerable_secs = (health.invulnerable_secs - time.delta_secs()).max(0.0);
+        }
+    }
+}
+
+/// What [`apply_damage`] hurts.
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct DamageTarget {
+    health: &'static mut Health,
+    transform: &'static Transform,
+    velocity: Option<&'static mut Velocity>,
+    is_player: Has<Player>,
+}
+
+fn apply_damage(
+    mut commands: Commands,
+    mut damage: MessageReader<Damage>,
+    mut targets: Query<DamageTarget>,
+    (mut hurt, mut haptics): (MessageWriter<Hurt>, MessageWriter<Haptic>),
+    mut life_state: ResMut<NextState<LifeState>>,
+    difficulty: Res<Difficulty>,
+) {
+    for Damage {
+        target,
+        amount,
+        knockback,
+    } in damage.read().copied()
+    {
+        let Ok(mut victim) = targets.get_mut(target) else {
+            continue;
+        };
+        if victim.health.is_dead() || victim.health.invulnerable_secs > 0.0 {
+            continue;
+        }
+        let amount = if victim.is_player {
+            amount * difficulty.damage_multiplier()
+        } else {
+            amount
+        };
+        victim.health.current = (victim.health.current - amount).max(0.0);
+        victim.health.invulnerable_secs = INVULNERABLE_SECS;
+        if let Some(velocity) = &mut victim.velocity {
+            velocity.0 += knockback;
+        }
+        hurt.write(Hurt {
+            target,
+            amount,
+            pos: victim.transform.translation.xy(),
+        });
+        if victim.is_player {
+            haptics.write(Haptic::Hit);
+        }
+        if !victim.health.is_dead() {
+            continue;
+        }
+        if victim.is_player {
+            info!("The player died");
+            life_state.set(LifeState::Dead);
+        } else {
+            commands.trigger(Killed { entity: target });
+            commands.entity(target).despawn();
+        }
+    }
+}
+
+fn apply_healing(mut heal: MessageReader<Heal>, mut healths: Query<&mut Health>) {
+    for Heal { target, amount } in heal.read().copied() {
+        if let Ok(mut health) = healths.get_mut(target)
+            && !health.is_dead()
+        {
+            health.current = (health.current + amount).min(health.max);
+        }
+    }
+}
+
+fn hide_player(mut player: Single<&mut Visibility, With<Player>>) {
+    **player = Visibility::Hidden;
+}
+
+fn respawn(
+    _: On<Respawn>,
+    player: Single<(&mut Transform, &mut Health, &mut Velocity, &mut Visibility), With<Player>>,
+    mut cameras: Query<&mut Transform, (With<CameraFollow>, Without<Player>)>,
+    mut life_state: ResMut<NextState<LifeState>>,
+) {
+    let (mut transform, mut health, mut velocity, mut visibility) = player.into_inner();
+    transform.translation = WORLD_SPAWN.extend(transform.translation.z);
+    *health = Health::full(health.max);
+    velocity.0 = Vec2::ZERO;
+    *visibility = Visibility::Inherited;
+    for mut camera in &mut cameras {
+        camera.translation = WORLD_SPAWN.extend(camera.translation.z);
+    }
+    life_state.set(LifeState::Alive);
+}
+
+fn death_screen(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    localization: Res<Localization>,
+) -> Result {
+    egui::Window::new(localization.get("death-title"))
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            if ui.button(localization.get("death-respawn")).clicked() {
+                commands.trigger(Respawn);
+            }
+        });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::state::app::StatesPlugin;
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    #[test]
+    fn players_die_and_respawn_at_the_world_spawn() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin, HealthPlugin))
+            .init_resource::<Difficulty>()
+            .add_message::<Haptic>()
+            .insert_state(GameState::Playing)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                200,
+            )));
+        let player = app
+            .world_mut()
+            .spawn((
+                Player,
+                Health::full(PLAYER_MAX_HEALTH),
+                Velocity(Vec2::X),
+                Transform::from_xyz(300.0, -40.0, 1.0),
+                Visibility::default(),
+            ))
+            .id();
+        app.update();
+
+        let hit = |app: &mut App, amount: f32| {
+            app.world_mut().write_message(Damage {
+                target: player,
+                amount,
+                knockback: Vec2::Y,
+            });
+        };
+        // Only the first hit lands while the player is invulnerable.
+        hit(&mut app, 4.0);
+        hit(&mut app, 8.0);
+        app.update();
+        hit(&mut app, 8.0);
+        app.update();
+        let health = app.world().get::<Health>(player).unwrap();
+        assert_eq!(health.current, PLAYER_MAX_HEALTH - 4.0);
+        assert_eq!(
+            app.world().get::<Velocity>(player).unwrap().0,
+            Vec2::new(1.0, 1.0)
+        );
+        for _ in 0..3 {
+            app.update();
+        }
+        hit(&mut app, 8.0);
+        app.update();
+        // The state changes on the next update.
+        app.update();
+        assert_eq!(app.world().get::<Health>(player).unwrap().current, 0.0);
+        assert_eq!(
+            *app.world().resource::<State<LifeState>>().get(),
+            LifeState::Dead
+        );
+
+        app.world_mut().trigger(Respawn);
+        app.update();
+        assert_eq!(
+            *app.world().resource::<State<LifeState>>().get(),
+            LifeState::Alive
+        );
+        let player = app.world().entity(player);
+        assert_eq!(player.get::<Health>().unwrap().current, PLAYER_MAX_HEALTH);
+        assert_eq!(player.get::<Transform>().unwrap().translation, Vec3::Z);
+        assert_eq!(player.get::<Velocity>().unwrap().0, Vec2::ZERO);
+        let player = player.id();
+
+        // Healing never goes past the maximum.
+        hit(&mut app, 3.0);
+        app.update();
+        app.world_mut().write_message(Heal {
+            target: player,
+            amount: 5.0,
+        });
+        app.update();
+        assert_eq!(
+            app.world().get::<Health>(player).unwrap().current,
+            PLAYER_MAX_HEALTH
+        );
+    }
+}
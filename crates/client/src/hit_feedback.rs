@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+
+use crate::GameState;
+use crate::combat::Lifetime;
+use crate::health::{Heal, Hurt};
+
+/// Seconds a floating number stays up.
+pub const FLOATING_TEXT_SECS: f32 = 0.8;
+
+/// How far a floating number rises over its lifetime, in world units.
+const FLOATING_TEXT_RISE: f32 = 12.0;
+
+/// How far above an entity's center its numbers appear, in world units.
+const FLOATING_TEXT_OFFSET: f32 = 10.0;
+
+/// Seconds a damaged sprite stays tinted.
+pub const HIT_FLASH_SECS: f32 = 0.2;
+
+const DAMAGE_COLOR: Color = Color::srgb(1.0, 0.35, 0.3);
+const HEAL_COLOR: Color = Color::srgb(0.45, 1.0, 0.45);
+
+/// Pops up a number above every entity that is [`Hurt`] or [`Heal`]ed, which rises
+/// and fades out, and flashes the sprites of damaged entities red before they go back to their
+/// [`Tint`].
+pub struct HitFeedbackPlugin;
+
+impl Plugin for HitFeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                show_damage,
+                show_healing,
+                animate_floating_text,
+                fade_hit_flash,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// World-space text that rises from where it spawned and fades out with its [`Lifetime`].
+#[derive(Component, Clone, Copy, Debug)]
+#[require(Lifetime = Lifetime::from_secs(FLOATING_TEXT_SECS))]
+pub struct FloatingText {
+    start: Vec2,
+    color: Color,
+}
+
+/// The color a sprite is tinted when it isn't flashing.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Tint(pub Color);
+
+/// Tints a damaged sprite until the timer finishes.
+#[derive(Component, Clone, Debug)]
+pub struct HitFlash(Timer);
+
+impl Default for HitFlash {
+    fn default() -> Self {
+        Self(Timer::from_seconds(HIT_FLASH_SECS, TimerMode::Once))
+    }
+}
+
+/// Spawns `text` floating up from `pos`.
+pub fn spawn_floating_text(commands: &mut Commands, pos: Vec2, text: String, color: Color) {
+    commands.spawn((
+        Name::new("Floating text"),
+        FloatingText { start: pos, color },
+        Text2d::new(text),
+        TextFont::from_font_size(8.0),
+        TextColor(color),
+        Transform::from_translation(pos.extend(10.0)),
+        DespawnOnExit(GameState::Playing),
+    ));
+}
+
+fn show_damage(
+    mut commands: Commands,
+    mut hurt: MessageReader<Hurt>,
+    sprites: Query<(), With<Sprite>>,
+) {
+    for Hurt {
+        target,
+        amount,
+        pos,
+    } in hurt.read().copied()
+    {
+        spawn_floating_text(
+            &mut commands,
+            pos + Vec2::Y * FLOATING_TEXT_OFFSET,
+            format!("{amount}"),
+            DAMAGE_COLOR,
+        );
+        if sprites.contains(target) {
+            commands.entity(target).try_insert(HitFlash::default());
+        }
+    }
+}
+
+fn show_healing(mut commands: Commands, mut heal: MessageReader<Heal>, targets: Query<&Transform>) {
+    for Heal { target, amount } in heal.read().copied() {
+        if let Ok(transform) = targets.get(target) {
+            spawn_floating_text(
+                &mut commands,
+                transform.translation.xy() + Vec2::Y * FLOATING_TEXT_OFFSET,
+                format!("+{amount}"),
+                HEAL_COLOR,
+            );
+        }
+    }
+}
+
+fn animate_floating_text(
+    mut texts: Query<(&FloatingText, &Lifetime, &mut Transform, &mut TextColor)>,
+) {
+    for (text, lifetime, mut transform, mut color) in &mut texts {
+        let fraction = lifetime.0.fraction();
+        // Rise quickly at first, then slow down.
+        let rise = FLOATING_TEXT_RISE * (1.0 - (1.0 - fraction).powi(2));
+        transform.translation = (text.start + Vec2::Y * rise).extend(transform.translation.z);
+        color.0 = text.color.with_alpha(1.0 - fraction.powi(2));
+    }
+}
+
+fn fade_hit_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut flashing: Query<(Entity, &mut HitFlash, &mut Sprite, Option<&Tint>)>,
+) {
+    for (entity, mut flash, mut sprite, tint) in &mut flashing {
+        let tint = tint.map_or(Color::WHITE, |tint| tint.0);
+        if flash.0.tick(time.delta()).is_finished() {
+            sprite.color = tint;
+            commands.entity(entity).remove::<HitFlash>();
+        } else {
+            sprite.color = DAMAGE_COLOR.mix(&tint, flash.0.fraction());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::state::app::StatesPlugin;
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::combat::CombatPlugin;
+    use crate::health::Damage;
+    use crate::spatial::SpatialPlugin;
+
+    #[test]
+    fn hurting_pops_up_a_number_and_flashes_the_sprite() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            StatesPlugin,
+            SpatialPlugin,
+            CombatPlugin,
+            HitFeedbackPlugin,
+        ))
+        .add_message::<Damage>()
+        .add_message::<Hurt>()
+        .add_message::<Heal>()
+        .insert_state(GameState::Playing)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            100,
+        )));
+        let target = app
+            .world_mut()
+            .spawn((Sprite::default(), Transform::from_xyz(20.0, 0.0, 0.0)))
+            .id();
+        app.update();
+        app.world_mut().write_message(Hurt {
+            target,
+            amount: 2.0,
+            pos: Vec2::new(20.0, 0.0),
+        });
+        app.update();
+
+        let mut texts = app.world_mut().query::<(&Text2d, &Transform)>();
+        let (text, transform) = texts.single(app.world()).unwrap();
+        assert_eq!(text.0, "2");
+        assert!(transform.translation.y >= FLOATING_TEXT_OFFSET);
+        assert_ne!(
+            app.world().get::<Sprite>(target).unwrap().color,
+            Color::WHITE
+        );
+
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(texts.iter(app.world()).count(), 0);
+        let target = app.world().entity(target);
+        assert!(!target.contains::<HitFlash>());
+        assert_eq!(target.get::<Sprite>().unwrap().color, Color::WHITE);
+    }
+}
@@ -0,0 +1,223 @@
+use bevy::ecs::spawn::SpawnIter;
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_enhanced_input::prelude::*;
+
+use crate::GameState;
+use crate::inventory::{Inventory, ItemAssets, ItemRegistry, ItemStack};
+use crate::player::Player;
+
+/// Number of hotbar slots. They show the first slots of the player's [`Inventory`].
+pub const HOTBAR_SLOTS: usize = 9;
+
+/// Screen pixels per pixel of hotbar art.
+const HUD_SCALE: f32 = 3.0;
+
+/// Size of a slot frame in `ui/hotbar.png`, in art pixels.
+const SLOT_SIZE: f32 = 20.0;
+
+/// Shows the first [`HOTBAR_SLOTS`] inventory slots at the bottom of the screen. The number keys
+/// select a slot and the gamepad bumpers cycle through them, and the selected item is what
+/// right-click places.
+pub struct HotbarPlugin;
+
+impl Plugin for HotbarPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hotbar>()
+            .add_observer(select_slot)
+            .add_observer(cycle_slots)
+            .add_systems(OnEnter(GameState::Playing), spawn_hotbar)
+            .add_systems(Update, update_hotbar.run_if(in_state(GameState::Playing)));
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct HotbarAssets {
+    /// The slot frame, then the selected slot frame.
+    #[asset(path = "ui/hotbar.png")]
+    pub frames: Handle<Image>,
+    #[asset(texture_atlas_layout(tile_size_x = 20, tile_size_y = 20, columns = 2, rows = 1))]
+    pub frames_layout: Handle<TextureAtlasLayout>,
+}
+
+/// The selected hotbar slot.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Hotbar {
+    pub selected: usize,
+}
+
+impl Hotbar {
+    /// The stack in the selected slot of `inventory`.
+    pub fn selected_stack<'a>(&self, inventory: &'a Inventory) -> Option<&'a ItemStack> {
+        inventory.slots.get(self.selected)?.as_ref()
+    }
+
+    /// Moves the selection `steps` slots to the right, wrapping around at either end.
+    pub fn cycle(&mut self, steps: i32) {
+        self.selected = (self.selected as i32 + steps).rem_euclid(HOTBAR_SLOTS as i32) as usize;
+    }
+}
+
+/// Selects the hotbar slot given by the action value, starting at 1.
+#[derive(InputAction)]
+#[action_output(f32)]
+pub struct SelectHotbarSlot;
+
+/// Moves the hotbar selection one slot in the direction of the action value.
+#[derive(InputAction)]
+#[action_output(f32)]
+pub struct CycleHotbar;
+
+/// Number keys 1 to 9 for [`SelectHotbarSlot`], each scaled to its slot number.
+pub fn slot_key_bindings() -> SpawnIter<impl Iterator<Item = (Binding, Scale)> + Send + Sync> {
+    const KEYS: [KeyCode; HOTBAR_SLOTS] = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    SpawnIter(
+        KEYS.into_iter()
+            .zip(1..)
+            .map(|(key, slot)| (Binding::from(key), Scale::splat(slot as f32))),
+    )
+}
+
+fn select_slot(select: On<Start<SelectHotbarSlot>>, mut hotbar: ResMut<Hotbar>) {
+    let slot = select.value.round() as usize;
+    if (1..=HOTBAR_SLOTS).contains(&slot) {
+        hotbar.selected = slot - 1;
+    }
+}
+
+fn cycle_slots(cycle: On<Start<CycleHotbar>>, mut hotbar: ResMut<Hotbar>) {
+    hotbar.cycle(cycle.value.signum() as i32);
+}
+
+#[derive(Component)]
+struct HotbarSlot(usize);
+
+#[derive(Component)]
+struct HotbarIcon(usize);
+
+#[derive(Component)]
+struct HotbarCount(usize);
+
+fn spawn_hotbar(mut commands: Commands, hotbar_assets: Res<HotbarAssets>) {
+    let slot_size = Val::Px(SLOT_SIZE * HUD_SCALE);
+    let icon_size = Val::Px(16.0 * HUD_SCALE);
+    commands
+        .spawn((
+            Name::new("Hotbar"),
+            DespawnOnExit(GameState::Playing),
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(4.0 * HUD_SCALE),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                column_gap: Val::Px(HUD_SCALE),
+                ..default()
+            },
+            Pickable::IGNORE,
+        ))
+        .with_children(|strip| {
+            for index in 0..HOTBAR_SLOTS {
+                strip
+                    .spawn((
+                        HotbarSlot(index),
+                        ImageNode::from_atlas_image(
+                            hotbar_assets.frames.clone(),
+                            TextureAtlas::from(hotbar_assets.frames_layout.clone()),
+                        ),
+                        Node {
+                            width: slot_size,
+                            height: slot_size,
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                    ))
+                    .with_children(|slot| {
+                        slot.spawn((
+                            HotbarIcon(index),
+                            ImageNode::default(),
+                            Node {
+                                width: icon_size,
+                                height: icon_size,
+                                ..default()
+                            },
+                            Visibility::Hidden,
+                        ));
+                        slot.spawn((
+                            HotbarCount(index),
+                            Text::default(),
+                            TextFont::from_font_size(6.0 * HUD_SCALE),
+                            TextShadow::default(),
+                            Node {
+                                position_type: PositionType::Absolute,
+                                right: Val::Px(2.0 * HUD_SCALE),
+                                bottom: Val::Px(HUD_SCALE),
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn update_hotbar(
+    hotbar: Res<Hotbar>,
+    (registry, item_assets): (Res<ItemRegistry>, Res<ItemAssets>),
+    inventory: Single<Ref<Inventory>, With<Player>>,
+    mut frames: Query<(&HotbarSlot, &mut ImageNode), Without<HotbarIcon>>,
+    mut icons: Query<(&HotbarIcon, &mut ImageNode, &mut Visibility)>,
+    mut counts: Query<(&HotbarCount, &mut Text)>,
+) {
+    if !hotbar.is_changed() && !inventory.is_changed() && !registry.is_changed() {
+        return;
+    }
+    for (HotbarSlot(index), mut frame) in &mut frames {
+        if let Some(atlas) = &mut frame.texture_atlas {
+            atlas.index = usize::from(*index == hotbar.selected);
+        }
+    }
+
+    let stack = |index: usize| inventory.slots.get(index).and_then(Option::as_ref);
+    for (HotbarIcon(index), mut image, mut visibility) in &mut icons {
+        let Some(definition) = stack(*index).and_then(|stack| registry.get(&stack.item)) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        let (icon, atlas) = item_assets.icon(definition);
+        image.image = icon;
+        image.texture_atlas = Some(atlas);
+        *visibility = Visibility::Inherited;
+    }
+    for (HotbarCount(index), mut text) in &mut counts {
+        text.0 = match stack(*index) {
+            Some(stack) if stack.count > 1 => stack.count.to_string(),
+            _ => String::new(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycling_wraps_around_the_hotbar() {
+        let mut hotbar = Hotbar::default();
+        hotbar.cycle(-1);
+        assert_eq!(hotbar.selected, HOTBAR_SLOTS - 1);
+        hotbar.cycle(1);
+        assert_eq!(hotbar.selected, 0);
+        hotbar.cycle(3);
+        assert_eq!(hotbar.selected, 3);
+    }
+}
@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+
+/// Smooths the movement of entities simulated in `FixedUpdate` by blending their rendered
+/// [`Transform`] between the last two fixed ticks.
+///
+/// Entities marked [`Interpolated`] must only be moved from `FixedUpdate`: before the fixed loop
+/// runs, their `Transform` is reset to the simulated one, so changes made elsewhere are lost.
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(init_interpolation)
+            .add_systems(
+                RunFixedMainLoop,
+                (
+                    restore_simulated_transform
+                        .in_set(RunFixedMainLoopSystems::BeforeFixedMainLoop),
+                    interpolate_transform.in_set(RunFixedMainLoopSystems::AfterFixedMainLoop),
+                ),
+            )
+            .add_systems(FixedFirst, store_previous_transform)
+            .add_systems(FixedLast, store_simulated_transform);
+    }
+}
+
+/// Marks an entity whose `Transform` is simulated in `FixedUpdate` and interpolated for rendering.
+#[derive(Component, Default)]
+#[require(PreviousTransform, SimulatedTransform)]
+pub struct Interpolated;
+
+/// The simulated transform at the start of the latest fixed tick.
+#[derive(Component, Default, Clone, Copy, Debug, PartialEq)]
+pub struct PreviousTransform(pub Transform);
+
+/// The simulated transform at the end of the latest fixed tick.
+#[derive(Component, Default, Clone, Copy, Debug, PartialEq)]
+pub struct SimulatedTransform(pub Transform);
+
+fn init_interpolation(
+    add: On<Add, Interpolated>,
+    mut entities: Query<(&Transform, &mut PreviousTransform, &mut SimulatedTransform)>,
+) {
+    if let Ok((transform, mut previous, mut simulated)) = entities.get_mut(add.entity) {
+        previous.0 = *transform;
+        simulated.0 = *transform;
+    }
+}
+
+fn restore_simulated_transform(
+    mut entities: Query<(&mut Transform, &SimulatedTransform), With<Interpolated>>,
+) {
+    for (mut transform, simulated) in &mut entities {
+        *transform = simulated.0;
+    }
+}
+
+fn store_previous_transform(
+    mut entities: Query<(&Transform, &mut PreviousTransform), With<Interpolated>>,
+) {
+    for (transform, mut previous) in &mut entities {
+        previous.0 = *transform;
+    }
+}
+
+fn store_simulated_transform(
+    mut entities: Query<(&Transform, &mut SimulatedTransform), With<Interpolated>>,
+) {
+    for (transform, mut simulated) in &mut entities {
+        simulated.0 = *transform;
+    }
+}
+
+fn interpolate_transform(
+    fixed_time: Res<Time<Fixed>>,
+    mut entities: Query<
+        (&mut Transform, &PreviousTransform, &SimulatedTransform),
+        With<Interpolated>,
+    >,
+) {
+    let alpha = fixed_time.overstep_fraction();
+    for (mut transform, previous, simulated) in &mut entities {
+        let (previous, simulated) = (previous.0, simulated.0);
+        *transform = Transform {
+            translation: previous.translation.lerp(simulated.translation, alpha),
+            rotation: previous.rotation.slerp(simulated.rotation, alpha),
+            scale: previous.scale.lerp(simulated.scale, alpha),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    #[test]
+    fn renders_between_fixed_ticks() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, InterpolationPlugin))
+            .insert_resource(Time::<Fixed>::from_hz(60.0))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+                1.0 / 240.0,
+            )))
+            .add_systems(
+                FixedUpdate,
+                |mut transforms: Query<&mut Transform, With<Interpolated>>| {
+                    for mut transform in &mut transforms {
+                        transform.translation.x += 1.0;
+                    }
+                },
+            );
+        let entity = app
+            .world_mut()
+            .spawn((Transform::default(), Interpolated))
+            .id();
+
+        let mut seen_between_ticks = false;
+        for _ in 0..20 {
+            app.update();
+            let x = app.world().get::<Transform>(entity).unwrap().translation.x;
+            let simulated = app.world().get::<SimulatedTransform>(entity).unwrap().0;
+            let alpha = app.world().resource::<Time<Fixed>>().overstep_fraction();
+            let expected = if simulated.translation.x == 0.0 {
+                0.0
+            } else {
+                simulated.translation.x - 1.0 + alpha
+            };
+            assert!((x - expected).abs() < 1e-4, "{x} != {expected}");
+            seen_between_ticks |= x.fract() != 0.0;
+        }
+        assert!(seen_between_ticks);
+    }
+}
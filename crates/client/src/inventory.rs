@@ -0,0 +1,325 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+use crate::console::{RegisterConsoleCommand, console_arg};
+use crate::localization::Localization;
+use crate::player::Player;
+use crate::projectiles::RangedWeapon;
+use crate::ron_asset::RonAssetLoader;
+use crate::status_effects::StatusEffect;
+use crate::tiles::TileKind;
+use crate::tools::ToolTier;
+
+/// Slots in the player's inventory.
+pub const PLAYER_INVENTORY_SLOTS: usize = 27;
+
+/// Loads the item definitions from `items.ron` into the [`ItemRegistry`], reloading them when
+/// the file changes, and adds a debug window for the player's [`Inventory`].
+pub struct InventoryPlugin;
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<ItemRegistry>()
+            .register_asset_loader(RonAssetLoader::<ItemRegistry>::new(&["items.ron"]))
+            .init_resource::<ItemRegistry>()
+            .register_console_command("give", "give <item> [count]", give)
+            .add_systems(
+                Update,
+                update_item_registry.run_if(on_message::<AssetEvent<ItemRegistry>>),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                inventory_window.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct ItemAssets {
+    #[asset(path = "items.ron")]
+    pub items: Handle<ItemRegistry>,
+    /// Item icons, indexed by [`ItemDefinition::icon`].
+    #[asset(path = "items.png")]
+    pub icons: Handle<Image>,
+    #[asset(texture_atlas_layout(tile_size_x = 16, tile_size_y = 16, columns = 22, rows = 1))]
+    pub icons_layout: Handle<TextureAtlasLayout>,
+}
+
+impl ItemAssets {
+    /// The icon of an item, for sprites and UI images.
+    pub fn icon(&self, definition: &ItemDefinition) -> (Handle<Image>, TextureAtlas) {
+        (
+            self.icons.clone(),
+            TextureAtlas {
+                layout: self.icons_layout.clone(),
+                index: definition.icon,
+            },
+        )
+    }
+}
+
+/// Identifies an item type, e.g. `"stone"`.
+#[derive(Serialize, Deserialize, Reflect, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct ItemId(pub String);
+
+impl From<&str> for ItemId {
+    fn from(id: &str) -> Self {
+        Self(id.to_string())
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ItemDefinition {
+    /// Index of the item's icon in `items.png`.
+    pub icon: usize,
+    pub name: String,
+    /// How many fit in one inventory slot.
+    pub max_stack: u32,
+    /// The tile the item places, if any.
+    #[serde(default)]
+    pub tile: Option<TileKind>,
+    /// The tool tier of the item when it is used to break tiles, if it is a tool.
+    #[serde(default)]
+    pub tool: Option<ToolTier>,
+    /// Whether right-clicking grass with the item tills it into farmland.
+    #[serde(default)]
+    pub tills: bool,
+    /// What the item shoots with the attack key, if it is a ranged weapon.
+    #[serde(default)]
+    pub ranged: Option<RangedWeapon>,
+    /// The effect of drinking or eating the item with the attack key, which uses it up.
+    #[serde(default)]
+    pub effect: Option<StatusEffect>,
+    /// How many degrees warmer the item keeps the player in survival mode, or cooler if it is
+    /// negative. Clothing is worn while it is in the inventory, see
+    /// [`TemperaturePlugin`](crate::temperature::TemperaturePlugin).
+    #[serde(default)]
+    pub warmth: f32,
+}
+
+/// Every item type, as defined in `items.ron`.
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug, Default)]
+pub struct ItemRegistry {
+    pub items: HashMap<ItemId, ItemDefinition>,
+    /// The item each tile drops when broken.
+    #[serde(default)]
+    pub drops: HashMap<TileKind, ItemId>,
+}
+
+impl ItemRegistry {
+    pub fn get(&self, item: &ItemId) -> Option<&ItemDefinition> {
+        self.items.get(item)
+    }
+
+    fn max_stack(&self, item: &ItemId) -> u32 {
+        self.get(item).map_or(1, |definition| definition.max_stack)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item: ItemId,
+    pub count: u32,
+}
+
+/// Item slots, each holding up to a stack of one item type.
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Inventory {
+    pub slots: Vec<Option<ItemStack>>,
+}
+
+impl Inventory {
+    pub fn new(size: usize) -> Self {
+        Self {
+            slots: vec![None; size],
+        }
+    }
+
+    /// Adds `count` of `item`, topping up existing stacks before filling empty slots. Returns
+    /// how many didn't fit.
+    pub fn add(&mut self, item: &ItemId, count: u32, registry: &ItemRegistry) -> u32 {
+        let max_stack = registry.max_stack(item);
+        let mut remaining = count;
+        for stack in self.slots.iter_mut().flatten() {
+            if remaining == 0 {
+                return 0;
+            }
+            if stack.item == *item && stack.count < max_stack {
+                let added = remaining.min(max_stack - stack.count);
+                stack.count += added;
+                remaining -= added;
+            }
+        }
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if remaining == 0 {
+                break;
+            }
+            let added = remaining.min(max_stack);
+            *slot = Some(ItemStack {
+                item: item.clone(),
+                count: added,
+            });
+            remaining -= added;
+        }
+        remaining
+    }
+
+    /// How many of `item` the inventory holds.
+    pub fn count(&self, item: &ItemId) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item == *item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    /// Removes `count` of `item`, taking from the last stacks first. Does nothing and returns
+    /// `false` if there aren't enough.
+    pub fn consume(&mut self, item: &ItemId, count: u32) -> bool {
+        if self.count(item) < count {
+            return false;
+        }
+        let mut remaining = count;
+        for slot in self.slots.iter_mut().rev() {
+            let Some(stack) = slot.as_mut().filter(|stack| stack.item == *item) else {
+                continue;
+            };
+            let taken = remaining.min(stack.count);
+            stack.count -= taken;
+            remaining -= taken;
+            if stack.count == 0 {
+                *slot = None;
+            }
+            if remaining == 0 {
+                break;
+            }
+        }
+        true
+    }
+
+    /// Moves as much of the stack in `slot` into `other` as fits. Returns whether anything was
+    /// moved.
+    pub fn move_stack(
+        &mut self,
+        slot: usize,
+        other: &mut Inventory,
+        registry: &ItemRegistry,
+    ) -> bool {
+        let Some(stack) = self.slots.get_mut(slot).and_then(Option::as_mut) else {
+            return false;
+        };
+        let left = other.add(&stack.item, stack.count, registry);
+        let moved = left < stack.count;
+        if left == 0 {
+            self.slots[slot] = None;
+        } else {
+            stack.count = left;
+        }
+        moved
+    }
+}
+
+fn update_item_registry(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<ItemRegistry>>,
+    registries: Res<Assets<ItemRegistry>>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event
+            && let Some(registry) = registries.get(*id)
+        {
+            info!("Loaded {} item definitions", registry.items.len());
+            commands.insert_resource(registry.clone());
+        }
+    }
+}
+
+/// Gives the player `count` of an item, one if no count is given.
+fn give(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let item = ItemId::from(*args.first().ok_or("Missing item")?);
+    let count: u32 = if args.len() > 1 {
+        console_arg(args, 1, "count")?
+    } else {
+        1
+    };
+    world.resource_scope(|world, registry: Mut<ItemRegistry>| {
+        let name = &registry.get(&item).ok_or("Unknown item")?.name;
+        let mut players = world.query_filtered::<&mut Inventory, With<Player>>();
+        let mut inventory = players.single_mut(world).map_err(|err| err.to_string())?;
+        let left_over = inventory.add(&item, count, &registry);
+        Ok(format!("Gave {} {name}", count - left_over))
+    })
+}
+
+fn inventory_window(
+    mut contexts: EguiContexts,
+    registry: Res<ItemRegistry>,
+    localization: Res<Localization>,
+    mut inventory: Single<&mut Inventory, With<Player>>,
+) -> Result {
+    egui::Window::new(localization.get("inventory-title"))
+        .default_open(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            for (index, slot) in inventory.slots.iter().enumerate() {
+                let Some(stack) = slot else {
+                    continue;
+                };
+                let name = localization.item_name(&registry, &stack.item);
+                ui.label(format!("{}: {name} × {}", index + 1, stack.count));
+            }
+            ui.separator();
+
+            ui.label(localization.get("inventory-give"));
+            let mut items: Vec<_> = registry.items.iter().collect();
+            items.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+            for (item, _) in items {
+                ui.horizontal(|ui| {
+                    ui.label(localization.item_name(&registry, item));
+                    for count in [1, 10] {
+                        if ui.button(format!("+{count}")).clicked() {
+                            inventory.add(item, count, &registry);
+                        }
+                    }
+                });
+            }
+        });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> ItemRegistry {
+        ron::from_str(include_str!("../assets/items.ron")).unwrap()
+    }
+
+    #[test]
+    fn items_stack_up_to_their_limit_and_are_consumed() {
+        let registry = registry();
+        let stone = ItemId::from("stone");
+        let stick = ItemId::from("stick");
+        assert_eq!(registry.get(&stone).unwrap().tile, Some(TileKind::Stone));
+        assert_eq!(registry.drops[&TileKind::Forest], stick);
+
+        let mut inventory = Inventory::new(3);
+        assert_eq!(inventory.add(&stone, 150, &registry), 0);
+        assert_eq!(inventory.add(&stick, 2, &registry), 0);
+        assert_eq!(inventory.count(&stone), 150);
+        // The second stack is topped up and the rest doesn't fit.
+        assert_eq!(inventory.add(&stone, 100, &registry), 52);
+        assert_eq!(inventory.count(&stone), 198);
+
+        assert!(!inventory.consume(&stick, 3));
+        assert!(inventory.consume(&stick, 2));
+        assert_eq!(inventory.slots[2], None);
+        assert!(inventory.consume(&stone, 100));
+        assert_eq!(inventory.count(&stone), 98);
+    }
+}
@@ -0,0 +1,253 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::GameState;
+use crate::health::LifeState;
+use crate::inventory::{Inventory, ItemAssets, ItemId, ItemRegistry, ItemStack};
+use crate::localization::Localization;
+use crate::notifications::Notifications;
+use crate::player::Player;
+use crate::save::{Persist, ReflectSaveableComponent};
+use crate::spatial::{Spatial, SpatialIndex};
+use crate::tile_editing::TileBroken;
+use crate::tiles::tile_to_world_pos;
+
+/// Seconds a drop lies around before it disappears.
+pub const DROP_LIFETIME: f32 = 300.0;
+
+/// How close the player has to get to a drop to collect it, in world units.
+pub const PICKUP_RADIUS: f32 = 10.0;
+
+/// Height of the bob, in world units.
+const BOB_HEIGHT: f32 = 1.5;
+
+/// Bobs per second.
+const BOB_RATE: f32 = 0.8;
+
+/// Drops the item each broken tile gives in the [`ItemRegistry`], which bobs in place until the
+/// player walks over it with room in their [`Inventory`]. Drops are saved with their chunk and
+/// despawn after [`DROP_LIFETIME`]. Other items can be dropped with [`spawn_item_drop`].
+pub struct ItemDropsPlugin;
+
+impl Plugin for ItemDropsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<ItemPickedUp>()
+            .add_observer(add_drop_sprite)
+            .add_systems(
+                Update,
+                (
+                    spawn_drops.run_if(on_message::<TileBroken>),
+                    age_drops,
+                    pick_up_drops.run_if(in_state(LifeState::Alive)),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, SaveableComponent)]
+#[require(Spatial)]
+pub struct ItemDrop {
+    pub item: ItemId,
+    pub count: u32,
+    /// Where the drop rests, at the bottom of its bob.
+    origin: Vec2,
+    age: f32,
+}
+
+/// Written when the player collects some or all of an [`ItemDrop`].
+#[derive(Message, Clone, Debug, PartialEq)]
+pub struct ItemPickedUp {
+    pub item: ItemId,
+    pub count: u32,
+}
+
+/// Drops `stack` at `origin`, or does nothing if the item isn't in the `registry`.
+pub fn spawn_item_drop(
+    commands: &mut Commands,
+    registry: &ItemRegistry,
+    stack: ItemStack,
+    origin: Vec2,
+) {
+    if registry.get(&stack.item).is_none() {
+        return;
+    }
+    commands.spawn((
+        ItemDrop {
+            item: stack.item,
+            count: stack.count,
+            origin,
+            age: 0.0,
+        },
+        Persist,
+        Transform::from_translation(origin.extend(0.6)),
+    ));
+}
+
+fn add_drop_sprite(
+    add: On<Add, ItemDrop>,
+    mut commands: Commands,
+    (item_assets, registry): (Res<ItemAssets>, Res<ItemRegistry>),
+    drops: Query<&ItemDrop>,
+) {
+    let Some(definition) = drops
+        .get(add.entity)
+        .ok()
+        .and_then(|drop| registry.get(&drop.item))
+    else {
+        return;
+    };
+    let (image, atlas) = item_assets.icon(definition);
+    commands
+        .entity(add.entity)
+        .insert(Sprite::from_atlas_image(image, atlas));
+}
+
+fn spawn_drops(
+    mut commands: Commands,
+    registry: Res<ItemRegistry>,
+    mut broken_tiles: MessageReader<TileBroken>,
+) {
+    for TileBroken {
+        world_tile, kind, ..
+    } in broken_tiles.read().copied()
+    {
+        if let Some(item) = registry.drops.get(&kind) {
+            let stack = ItemStack {
+                item: item.clone(),
+                count: 1,
+            };
+            let origin = tile_to_world_pos(world_tile);
+            spawn_item_drop(&mut commands, &registry, stack, origin);
+        }
+    }
+}
+
+fn age_drops(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut drops: Query<(Entity, &mut ItemDrop, &mut Transform)>,
+) {
+    for (entity, mut drop, mut transform) in &mut drops {
+        drop.age += time.delta_secs();
+        if drop.age >= DROP_LIFETIME {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let bob = (1.0 - (drop.age * BOB_RATE * TAU).cos()) / 2.0 * BOB_HEIGHT;
+        transform.translation.x = drop.origin.x;
+        transform.translation.y = drop.origin.y + bob;
+    }
+}
+
+fn pick_up_drops(
+    mut commands: Commands,
+    (registry, index): (Res<ItemRegistry>, Res<SpatialIndex>),
+    localization: Res<Localization>,
+    player: Single<(&Transform, &mut Inventory), With<Player>>,
+    mut drops: Query<&mut ItemDrop>,
+    mut picked_up: MessageWriter<ItemPickedUp>,
+    mut notifications: ResMut<Notifications>,
+) {
+    let (transform, mut inventory) = player.into_inner();
+    let player_pos = transform.translation.xy();
+    // Drops bob above their origin, so look a bit further out than the pickup radius.
+    for (entity, _) in index.within(player_pos, PICKUP_RADIUS + BOB_HEIGHT) {
+        let Ok(mut drop) = drops.get_mut(entity) else {
+            continue;
+        };
+        if drop.origin.distance(player_pos) > PICKUP_RADIUS {
+            continue;
+        }
+        let left = inventory.add(&drop.item, drop.count, &registry);
+        if left < drop.count {
+            let count = drop.count - left;
+            let name = localization.item_name(&registry, &drop.item);
+            notifications.push(localization.format(
+                "notification-picked-up",
+                &[("count", &count), ("item", &name)],
+            ));
+            picked_up.write(ItemPickedUp {
+                item: drop.item.clone(),
+                count,
+            });
+        }
+        if left == 0 {
+            commands.entity(entity).despawn();
+        } else {
+            drop.count = left;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use super::*;
+    use crate::spatial::SpatialPlugin;
+    use crate::tiles::TileKind;
+    use crate::tools::ToolTier;
+
+    #[test]
+    fn broken_tiles_drop_items_that_the_player_collects() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin, SpatialPlugin, ItemDropsPlugin))
+            .add_sub_state::<LifeState>()
+            .add_message::<TileBroken>()
+            .init_resource::<Notifications>()
+            .init_resource::<Localization>()
+            .insert_resource(ItemAssets {
+                items: Handle::default(),
+                icons: Handle::default(),
+                icons_layout: Handle::default(),
+            })
+            .insert_resource(
+                ron::from_str::<ItemRegistry>(include_str!("../assets/items.ron")).unwrap(),
+            )
+            .insert_state(GameState::Playing);
+        let player = app
+            .world_mut()
+            .spawn((
+                Player,
+                Inventory::new(1),
+                Transform::from_xyz(200.0, 0.0, 1.0),
+            ))
+            .id();
+        app.update();
+
+        for world_tile in [IVec2::new(0, 0), IVec2::new(40, 0)] {
+            app.world_mut().write_message(TileBroken {
+                world_tile,
+                kind: TileKind::Stone,
+                tool: ToolTier::Wood,
+            });
+        }
+        app.update();
+        let mut drops = app.world_mut().query::<(&ItemDrop, Has<Sprite>)>();
+        assert_eq!(drops.iter(app.world()).count(), 2);
+        assert!(drops.iter(app.world()).all(|(_, has_sprite)| has_sprite));
+
+        app.world_mut()
+            .get_mut::<Transform>(player)
+            .unwrap()
+            .translation = Vec3::new(4.0, 6.0, 1.0);
+        app.update();
+        assert_eq!(drops.iter(app.world()).count(), 1);
+        let picked_up = app.world().resource::<Messages<ItemPickedUp>>();
+        let picked_up: Vec<_> = picked_up.iter_current_update_messages().cloned().collect();
+        let stone = ItemId::from("stone");
+        assert_eq!(
+            picked_up,
+            [ItemPickedUp {
+                item: stone.clone(),
+                count: 1,
+            }]
+        );
+        let inventory = app.world().get::<Inventory>(player).unwrap();
+        assert_eq!(inventory.count(&stone), 1);
+    }
+}
@@ -0,0 +1,312 @@
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_modern_pixel_camera::prelude::*;
+
+use crate::ai::AiPlugin;
+use crate::ambient_light::AmbientLightPlugin;
+use crate::animals::{AnimalAssets, AnimalsPlugin};
+use crate::autosave::AutosavePlugin;
+use crate::biome_assets::BiomeAssetsPlugin;
+use crate::boss::{BossAssets, BossPlugin};
+use crate::camera_shake::{CameraShakePlugin, Shaking};
+use crate::changelog::ChangelogPlugin;
+use crate::chests::ChestsPlugin;
+use crate::chunk::ChunkPlugin;
+use crate::clock::ClockPlugin;
+use crate::combat::{CombatAssets, CombatPlugin};
+use crate::console::ConsolePlugin;
+use crate::crafting::{CraftingPlugin, RecipeAssets};
+use crate::crt::CrtPlugin;
+use crate::debug_placer::DebugPlacerPlugin;
+use crate::dialogue::{DialogueAssets, DialoguePlugin};
+use crate::difficulty::Difficulty;
+use crate::dungeons::DungeonsPlugin;
+use crate::enemies::{EnemiesPlugin, EnemyAssets};
+use crate::farming::FarmingPlugin;
+use crate::footsteps::{FootstepSounds, FootstepsPlugin};
+use crate::haptics::HapticsPlugin;
+use crate::health::HealthPlugin;
+use crate::hit_feedback::HitFeedbackPlugin;
+use crate::hotbar::{HotbarAssets, HotbarPlugin};
+use crate::interpolation::InterpolationPlugin;
+use crate::inventory::{InventoryPlugin, ItemAssets};
+use crate::item_drops::ItemDropsPlugin;
+use crate::lighting::LightingPlugin;
+use crate::loading_screen::{LoadAndTrack, LoadingScreenPlugin};
+use crate::localization::LocalizationPlugin;
+use crate::loot::{LootAssets, LootPlugin};
+use crate::main_menu::MainMenuPlugin;
+use crate::map_export::MapExportPlugin;
+use crate::minimap::MinimapPlugin;
+use crate::moon::{MoonAssets, MoonPlugin};
+use crate::music::{MusicAssets, MusicDirectorPlugin};
+use crate::notifications::NotificationsPlugin;
+use crate::parallax::{ParallaxAssets, ParallaxPlugin};
+use crate::particles::{ParticleAssets, ParticlesPlugin};
+use crate::pathfinding::PathfindingPlugin;
+use crate::paths::AppPaths;
+use crate::pause::PausePlugin;
+use crate::persistence::PersistencePlugin;
+use crate::pixel_snap::PixelSnapPlugin;
+use crate::player::{CameraFollow, PlayerPlugin};
+use crate::player_animation::PlayerAnimationPlugin;
+use crate::projectiles::{ProjectileAssets, ProjectilesPlugin};
+use crate::quests::{QuestAssets, QuestsPlugin};
+use crate::save::SavePlugin;
+use crate::screenshot::ScreenshotPlugin;
+use crate::settings::{Settings, SettingsPlugin};
+use crate::spatial::SpatialPlugin;
+use crate::sprite_animation::SpriteAnimationPlugin;
+use crate::stamina::StaminaPlugin;
+use crate::status_effects::{StatusEffectAssets, StatusEffectsPlugin};
+use crate::surface_particles::SurfaceParticlesPlugin;
+use crate::temperature::TemperaturePlugin;
+use crate::terraform::TerraformPlugin;
+use crate::tile_editing::{CrackAssets, TileEditingPlugin};
+use crate::tile_highlight::TileHighlightPlugin;
+use crate::transitions::TransitionsPlugin;
+use crate::villagers::{VillagerAssets, VillagersPlugin};
+use crate::waypoints::WaypointsPlugin;
+use crate::weather::{WeatherAssets, WeatherPlugin};
+use crate::wind::WindPlugin;
+use crate::world_map::WorldMapPlugin;
+use crate::world_select::WorldSelectPlugin;
+use crate::worldgen::{WorldSeed, WorldgenPreset};
+use crate::y_sort::YSortPlugin;
+
+pub mod ai;
+pub mod ambient_light;
+pub mod animals;
+pub mod autosave;
+pub mod biome_assets;
+pub mod boss;
+pub mod camera_shake;
+pub mod changelog;
+pub mod chests;
+pub mod chunk;
+pub mod clock;
+pub mod collision;
+pub mod combat;
+pub mod console;
+pub mod crafting;
+pub mod crt;
+pub mod debug_placer;
+pub mod dialogue;
+pub mod difficulty;
+pub mod dungeons;
+pub mod enemies;
+pub mod farming;
+pub mod footsteps;
+#[cfg(feature = "gpu_worldgen")]
+pub mod gpu_worldgen;
+pub mod haptics;
+pub mod health;
+pub mod hit_feedback;
+pub mod hotbar;
+pub mod interpolation;
+pub mod inventory;
+pub mod item_drops;
+pub mod lighting;
+pub mod loading_screen;
+pub mod localization;
+pub mod loot;
+pub mod main_menu;
+pub mod map_export;
+pub mod minimap;
+pub mod moon;
+pub mod music;
+pub mod noise;
+pub mod notifications;
+pub mod parallax;
+pub mod particles;
+pub mod pathfinding;
+pub mod paths;
+pub mod pause;
+pub mod persistence;
+pub mod picking;
+pub mod pixel_snap;
+pub mod player;
+pub mod player_animation;
+pub mod projectiles;
+pub mod quests;
+pub mod ron_asset;
+pub mod save;
+pub mod screenshot;
+pub mod settings;
+pub mod spatial;
+pub mod sprite_animation;
+pub mod stamina;
+pub mod status_effects;
+pub mod surface_particles;
+pub mod temperature;
+pub mod terraform;
+pub mod tile_editing;
+pub mod tile_highlight;
+pub mod tiles;
+pub mod tools;
+pub mod transitions;
+pub mod villagers;
+pub mod waypoints;
+pub mod weather;
+pub mod wind;
+pub mod world_map;
+pub mod world_select;
+pub mod worldgen;
+pub mod y_sort;
+
+/// Game states, chunk streaming, save data and the camera. Expects the third-party plugins set up in
+/// `main` (tilemap, input, entropy, ...) to already be added.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<GameState>()
+            .insert_resource(WorldSeed::default())
+            .init_resource::<WorldgenPreset>()
+            .init_resource::<Difficulty>()
+            .init_resource::<AppPaths>()
+            .add_plugins((
+                (
+                    ChunkPlugin,
+                    PlayerPlugin,
+                    PausePlugin,
+                    PersistencePlugin,
+                    AutosavePlugin,
+                    SavePlugin,
+                    InterpolationPlugin,
+                    BiomeAssetsPlugin,
+                    ClockPlugin,
+                    AmbientLightPlugin,
+                    WeatherPlugin,
+                    WindPlugin,
+                    LightingPlugin,
+                    MoonPlugin,
+                    TemperaturePlugin,
+                ),
+                (
+                    MainMenuPlugin,
+                    WorldSelectPlugin,
+                    SettingsPlugin,
+                    HapticsPlugin,
+                    PixelSnapPlugin,
+                    YSortPlugin,
+                    SpriteAnimationPlugin,
+                    ParallaxPlugin,
+                    ParticlesPlugin,
+                    CameraShakePlugin,
+                    TransitionsPlugin,
+                    CrtPlugin,
+                    ChangelogPlugin,
+                    ScreenshotPlugin,
+                    MapExportPlugin,
+                ),
+                (
+                    DebugPlacerPlugin,
+                    TerraformPlugin,
+                    StaminaPlugin,
+                    PlayerAnimationPlugin,
+                    SurfaceParticlesPlugin,
+                    FootstepsPlugin,
+                    HealthPlugin,
+                    TileHighlightPlugin,
+                    TileEditingPlugin,
+                    ItemDropsPlugin,
+                    InventoryPlugin,
+                    HotbarPlugin,
+                    CraftingPlugin,
+                    ChestsPlugin,
+                    FarmingPlugin,
+                ),
+                (
+                    AiPlugin,
+                    AnimalsPlugin,
+                    PathfindingPlugin,
+                    EnemiesPlugin,
+                    MusicDirectorPlugin,
+                    DialoguePlugin,
+                    QuestsPlugin,
+                    SpatialPlugin,
+                    CombatPlugin,
+                    ProjectilesPlugin,
+                    HitFeedbackPlugin,
+                    LootPlugin,
+                    DungeonsPlugin,
+                    BossPlugin,
+                    StatusEffectsPlugin,
+                ),
+                VillagersPlugin,
+                LoadingScreenPlugin,
+                MinimapPlugin,
+                WorldMapPlugin,
+                WaypointsPlugin,
+                ConsolePlugin,
+                NotificationsPlugin,
+                LocalizationPlugin,
+            ));
+
+        // Every collection the game needs before the main menu, tracked by the loading screen.
+        let loading_state = LoadingState::new(GameState::Loading)
+            .continue_to_state(GameState::MainMenu)
+            .load_and_track::<GameAssets>(app)
+            .load_and_track::<FootstepSounds>(app)
+            .load_and_track::<ItemAssets>(app)
+            .load_and_track::<HotbarAssets>(app)
+            .load_and_track::<RecipeAssets>(app)
+            .load_and_track::<CrackAssets>(app)
+            .load_and_track::<AnimalAssets>(app)
+            .load_and_track::<EnemyAssets>(app)
+            .load_and_track::<MusicAssets>(app)
+            .load_and_track::<DialogueAssets>(app)
+            .load_and_track::<QuestAssets>(app)
+            .load_and_track::<CombatAssets>(app)
+            .load_and_track::<MoonAssets>(app)
+            .load_and_track::<ProjectileAssets>(app)
+            .load_and_track::<LootAssets>(app)
+            .load_and_track::<BossAssets>(app)
+            .load_and_track::<StatusEffectAssets>(app)
+            .load_and_track::<VillagerAssets>(app)
+            .load_and_track::<WeatherAssets>(app)
+            .load_and_track::<ParallaxAssets>(app)
+            .load_and_track::<ParticleAssets>(app);
+        app.add_loading_state(loading_state)
+            .add_systems(Startup, spawn_camera);
+
+        #[cfg(feature = "gpu_worldgen")]
+        app.add_plugins(gpu_worldgen::GpuWorldgenPlugin);
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Hash, Default, States)]
+pub enum GameState {
+    #[default]
+    Loading,
+    MainMenu,
+    WorldSelect,
+    Playing,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct GameAssets {
+    /// Idle, walk and swim frames for each [`Facing`](player_animation::Facing), one row each. The
+    /// column count has to match [`PLAYER_SHEET_COLUMNS`](player_animation::PLAYER_SHEET_COLUMNS).
+    #[asset(path = "player.png")]
+    pub player: Handle<Image>,
+    #[asset(texture_atlas_layout(tile_size_x = 16, tile_size_y = 16, columns = 8, rows = 4))]
+    pub player_layout: Handle<TextureAtlasLayout>,
+}
+
+// The camera lives for the whole session because egui only attaches its primary context to the
+// first camera spawned; menus and the world share it.
+fn spawn_camera(mut commands: Commands, settings: Res<Settings>) {
+    commands.spawn((
+        Camera2d,
+        Msaa::Off,
+        PixelZoom::FitSize {
+            width: settings.pixel_resolution.x as i32,
+            height: settings.pixel_resolution.y as i32,
+        },
+        PixelViewport,
+        CameraFollow::default(),
+        Shaking::default(),
+    ));
+}
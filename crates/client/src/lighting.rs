@@ -0,0 +1,447 @@
+use std::time::Duration;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::image::ImageSampler;
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, Extent3d, TextureDimension, TextureFormat};
+use bevy::shader::ShaderRef;
+use bevy::time::common_conditions::on_timer;
+use bevy::transform::TransformSystems;
+use bevy_rand::prelude::*;
+use rand::Rng;
+
+use crate::GameState;
+use crate::ambient_light::AmbientLight;
+use crate::chunk::{CHUNK_SIZE, ChunkUnloaded, TILE_SIZE};
+use crate::tiles::{
+    TileKind, TileLocator, TileProperties, WorldTiles, world_pos_to_tile, world_tile_to_chunk,
+};
+
+/// The brightest light level. Light dims by one level per tile, so a light source shines at
+/// most this many tiles away.
+pub const MAX_LIGHT: u8 = 8;
+
+/// How brightly the player glows, just enough to see their surroundings on the darkest nights.
+pub const PLAYER_LIGHT: u8 = 4;
+
+/// How brightly [`TileKind::Torch`]es burn.
+pub const TORCH_LIGHT: u8 = 7;
+
+/// How brightly [`TileKind::Campfire`]s burn.
+pub const CAMPFIRE_LIGHT: u8 = MAX_LIGHT;
+
+/// Seconds between flickers of the tiles that give off light.
+const FLICKER_SECS: u64 = 150;
+
+/// How likely a tile that gives off light is to burn a level dimmer after each flicker.
+const FLICKER_CHANCE: f64 = 0.3;
+
+/// The tint over the world in the middle of the night.
+const NIGHT_COLOR: Color = Color::srgba(0.05, 0.08, 0.3, 0.55);
+
+const SHADER_PATH: &str = "shaders/night.wgsl";
+
+/// Tints the world towards blue at night with a fullscreen overlay under the HUD, except where
+/// [`LightSource`]s and tiles that give off [`light`](TileProperties::light), like torches, shine.
+/// Light spreads from each source over the tile grid, dimming with every tile and stopping at
+/// opaque tiles, and the light of each loaded chunk is recomputed whenever a source near it moves
+/// or a tile near it changes. Tiles flicker like flames, randomly burning a level dimmer now and
+/// then. The light levels of all loaded chunks make up a light map that the overlay's shader thins
+/// the tint with.
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(UiMaterialPlugin::<NightMaterial>::default())
+            .init_resource::<LightMap>()
+            .add_systems(OnEnter(GameState::Playing), spawn_night_tint)
+            .add_systems(OnExit(GameState::Playing), clear_light_map)
+            .add_systems(
+                Update,
+                (
+                    track_light_sources,
+                    track_tiles,
+                    forget_unloaded_chunks,
+                    flicker_tiles.run_if(on_timer(Duration::from_millis(FLICKER_SECS))),
+                    relight_chunks,
+                    update_light_map_image,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                PostUpdate,
+                tint_night
+                    .after(TransformSystems::Propagate)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Lights up the tiles around it, [`level`](Self::level) tiles far at its brightest.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+#[require(Transform)]
+pub struct LightSource {
+    /// Up to [`MAX_LIGHT`].
+    pub level: u8,
+}
+
+/// The light levels of the loaded chunks.
+#[derive(Resource, Default)]
+struct LightMap {
+    /// Each chunk's light levels, one per tile in the order of
+    /// [`ChunkData::tiles`](crate::persistence::ChunkData::tiles).
+    chunks: HashMap<IVec2, Vec<u8>>,
+    /// Where each light source shone from when the chunks were last lit, and how brightly.
+    sources: HashMap<Entity, (IVec2, u8)>,
+    /// The loaded tiles that give off light, with their level and how many levels dimmer their
+    /// flicker has them burn right now.
+    glowing_tiles: HashMap<IVec2, (u8, u8)>,
+    /// Chunks to recompute the light of.
+    dirty: HashSet<IVec2>,
+    /// Whether the levels changed since the light map image was last made.
+    changed: bool,
+}
+
+impl LightMap {
+    /// Marks every chunk that light from `world_tile` can reach for relighting.
+    fn mark_around(&mut self, world_tile: IVec2) {
+        let reach = IVec2::splat(i32::from(MAX_LIGHT));
+        let min = world_tile_to_chunk(world_tile - reach).0;
+        let max = world_tile_to_chunk(world_tile + reach).0;
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                self.dirty.insert(IVec2::new(x, y));
+            }
+        }
+    }
+}
+
+/// Spreads the light of `sources`, each a world tile and its level, over the tiles of the chunk
+/// at `chunk_pos`. Light dims by one level with every step to a neighbouring tile. `opaque`
+/// tiles are lit, but light doesn't shine past them. Returns a level for each tile of the
+/// chunk, in the order of [`ChunkData::tiles`](crate::persistence::ChunkData::tiles).
+pub fn light_chunk(
+    chunk_pos: IVec2,
+    sources: impl IntoIterator<Item = (IVec2, u8)>,
+    opaque: impl Fn(IVec2) -> bool,
+) -> Vec<u8> {
+    // Light from outside the chunk can shine into it, so spread it over a margin around it.
+    let margin = IVec2::splat(i32::from(MAX_LIGHT));
+    let chunk_size = CHUNK_SIZE.as_ivec2();
+    let min = chunk_pos * chunk_size - margin;
+    let size = chunk_size + margin * 2;
+    let index = |tile: IVec2| {
+        let local = tile - min;
+        (local.cmpge(IVec2::ZERO).all() && local.cmplt(size).all())
+            .then(|| (local.y * size.x + local.x) as usize)
+    };
+
+    let mut levels = vec![0; (size.x * size.y) as usize];
+    // The tiles to spread light from, by their level, so the brightest light spreads first and
+    // every tile is reached by its brightest light before it spreads any further.
+    let mut frontier = vec![Vec::new(); usize::from(MAX_LIGHT) + 1];
+    for (tile, level) in sources {
+        let level = level.min(MAX_LIGHT);
+        if let Some(i) = index(tile)
+            && level > levels[i]
+        {
+            levels[i] = level;
+            frontier[usize::from(level)].push(tile);
+        }
+    }
+    for level in (2..=MAX_LIGHT).rev() {
+        for tile in std::mem::take(&mut frontier[usize::from(level)]) {
+            if levels[index(tile).unwrap()] != level || opaque(tile) {
+                continue;
+            }
+            for step in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                let next = tile + step;
+                if let Some(i) = index(next)
+                    && levels[i] < level - 1
+                {
+                    levels[i] = level - 1;
+                    frontier[usize::from(level - 1)].push(next);
+                }
+            }
+        }
+    }
+
+    (0..chunk_size.y)
+        .flat_map(|y| (0..chunk_size.x).map(move |x| IVec2::new(x, y)))
+        .map(|local| levels[index(min + margin + local).unwrap()])
+        .collect()
+}
+
+/// The night tint, drawn by `night.wgsl`.
+#[derive(AsBindGroup, Asset, TypePath, Debug, Clone)]
+struct NightMaterial {
+    /// The tint color, its alpha faded in as the [`AmbientLight`] fades out.
+    #[uniform(0)]
+    tint: Vec4,
+    /// The world positions of the top left and bottom right corners of the view.
+    #[uniform(1)]
+    view: Vec4,
+    /// The bottom left corner and size of the world area covered by `light_map`.
+    #[uniform(2)]
+    light_rect: Vec4,
+    /// The light level of each loaded tile, bottom row first.
+    #[texture(3)]
+    #[sampler(4)]
+    light_map: Handle<Image>,
+}
+
+impl UiMaterial for NightMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+}
+
+/// An image of `size` light levels, filtered linearly so light fades smoothly between tiles.
+fn light_map_image(size: UVec2, data: Vec<u8>) -> Image {
+    let mut image = Image::new(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::R8Unorm,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::linear();
+    image
+}
+
+fn spawn_night_tint(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<NightMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut light_map: ResMut<LightMap>,
+) {
+    light_map.changed = true;
+    let material = NightMaterial {
+        tint: Vec4::ZERO,
+        view: Vec4::ZERO,
+        light_rect: Vec4::ZERO,
+        light_map: images.add(light_map_image(UVec2::ONE, vec![0])),
+    };
+    commands.spawn((
+        Name::new("Night tint"),
+        DespawnOnExit(GameState::Playing),
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        MaterialNode(materials.add(material)),
+        // Under every other UI node, so only the world is tinted.
+        GlobalZIndex(i32::MIN),
+        Pickable::IGNORE,
+    ));
+}
+
+fn clear_light_map(mut light_map: ResMut<LightMap>) {
+    *light_map = LightMap::default();
+}
+
+/// Marks the chunks around light sources that appeared, moved, changed or went away.
+fn track_light_sources(
+    mut light_map: ResMut<LightMap>,
+    sources: Query<(Entity, &LightSource, &GlobalTransform)>,
+) {
+    for (entity, source, transform) in &sources {
+        let tile = world_pos_to_tile(transform.translation().xy());
+        match light_map.sources.insert(entity, (tile, source.level)) {
+            Some(before) if before == (tile, source.level) => continue,
+            Some((before, _)) => light_map.mark_around(before),
+            None => {}
+        }
+        light_map.mark_around(tile);
+    }
+    let gone: Vec<(Entity, IVec2)> = light_map
+        .sources
+        .iter()
+        .filter(|(entity, _)| !sources.contains(**entity))
+        .map(|(entity, (tile, _))| (*entity, *tile))
+        .collect();
+    for (entity, tile) in gone {
+        light_map.sources.remove(&entity);
+        light_map.mark_around(tile);
+    }
+}
+
+/// Marks the chunks around tiles that changed or were loaded, as they may now block light, let
+/// it through or give it off.
+fn track_tiles(
+    mut light_map: ResMut<LightMap>,
+    tiles: Query<(Entity, &TileKind), Changed<TileKind>>,
+    locator: TileLocator,
+) {
+    for (entity, kind) in &tiles {
+        let Some(world_tile) = locator.world_tile(entity) else {
+            continue;
+        };
+        match TileProperties::of(*kind).light {
+            0 => light_map.glowing_tiles.remove(&world_tile),
+            light => light_map.glowing_tiles.insert(world_tile, (light, 0)),
+        };
+        light_map.mark_around(world_tile);
+    }
+}
+
+fn forget_unloaded_chunks(
+    mut light_map: ResMut<LightMap>,
+    mut unloaded: MessageReader<ChunkUnloaded>,
+) {
+    for ChunkUnloaded(chunk_pos) in unloaded.read() {
+        if light_map.chunks.remove(chunk_pos).is_some() {
+            light_map.changed = true;
+        }
+        light_map
+            .glowing_tiles
+            .retain(|world_tile, _| world_tile_to_chunk(*world_tile).0 != *chunk_pos);
+    }
+}
+
+fn flicker_tiles(
+    mut light_map: ResMut<LightMap>,
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+) {
+    let mut flickered = Vec::new();
+    for (world_tile, (_, dimmed)) in &mut light_map.glowing_tiles {
+        let dims = u8::from(global_rng.random_bool(FLICKER_CHANCE));
+        if *dimmed != dims {
+            *dimmed = dims;
+            flickered.push(*world_tile);
+        }
+    }
+    for world_tile in flickered {
+        light_map.mark_around(world_tile);
+    }
+}
+
+fn relight_chunks(mut light_map: ResMut<LightMap>, tiles: WorldTiles) {
+    if light_map.dirty.is_empty() {
+        return;
+    }
+    let loaded: HashSet<IVec2> = tiles.loaded_chunks().collect();
+    let reach = i32::from(MAX_LIGHT);
+    for chunk_pos in std::mem::take(&mut light_map.dirty) {
+        if !loaded.contains(&chunk_pos) {
+            continue;
+        }
+        let min = chunk_pos * CHUNK_SIZE.as_ivec2() - IVec2::splat(reach);
+        let max = (chunk_pos + IVec2::ONE) * CHUNK_SIZE.as_ivec2() + IVec2::splat(reach);
+        let glowing_tiles = light_map
+            .glowing_tiles
+            .iter()
+            .map(|(tile, (light, dimmed))| (*tile, light - dimmed));
+        let sources = light_map
+            .sources
+            .values()
+            .copied()
+            .chain(glowing_tiles)
+            .filter(|(tile, _)| tile.cmpge(min).all() && tile.cmplt(max).all());
+        let opaque = |tile| {
+            tiles
+                .properties(tile)
+                .is_some_and(|properties| properties.opaque)
+        };
+        let levels = light_chunk(chunk_pos, sources, opaque);
+        if light_map.chunks.insert(chunk_pos, levels.clone()) != Some(levels) {
+            light_map.changed = true;
+        }
+    }
+}
+
+/// Redraws the light map image from the light levels of the loaded chunks.
+fn update_light_map_image(
+    mut light_map: ResMut<LightMap>,
+    tint: Single<&MaterialNode<NightMaterial>>,
+    mut materials: ResMut<Assets<NightMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if !light_map.changed {
+        return;
+    }
+    light_map.changed = false;
+    let Some(material) = materials.get_mut(&tint.0) else {
+        return;
+    };
+    let (Some(min), Some(max)) = (
+        light_map.chunks.keys().copied().reduce(IVec2::min),
+        light_map.chunks.keys().copied().reduce(IVec2::max),
+    ) else {
+        return;
+    };
+    let size = ((max - min + IVec2::ONE) * CHUNK_SIZE.as_ivec2()).as_uvec2();
+    let mut data = vec![0; (size.x * size.y) as usize];
+    for (chunk_pos, levels) in &light_map.chunks {
+        let origin = ((chunk_pos - min) * CHUNK_SIZE.as_ivec2()).as_uvec2();
+        for (i, level) in levels.iter().enumerate() {
+            let x = origin.x + i as u32 % CHUNK_SIZE.x;
+            let y = origin.y + i as u32 / CHUNK_SIZE.x;
+            data[(y * size.x + x) as usize] =
+                (u32::from(*level) * 255 / u32::from(MAX_LIGHT)) as u8;
+        }
+    }
+    // Tile centers sit on multiples of the tile size, so each tile's texel starts half a tile
+    // before its center.
+    let tile_size = Vec2::new(TILE_SIZE.x, TILE_SIZE.y);
+    let corner = ((min * CHUNK_SIZE.as_ivec2()).as_vec2() - 0.5) * tile_size;
+    let extent = size.as_vec2() * tile_size;
+    material.light_rect = Vec4::new(corner.x, corner.y, extent.x, extent.y);
+    if let Some(image) = images.get_mut(&material.light_map) {
+        *image = light_map_image(size, data);
+    }
+}
+
+fn tint_night(
+    ambient: Res<AmbientLight>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+    tint: Single<&MaterialNode<NightMaterial>>,
+    mut materials: ResMut<Assets<NightMaterial>>,
+) {
+    let (camera, camera_transform) = *camera;
+    let Some(material) = materials.get_mut(&tint.0) else {
+        return;
+    };
+    let alpha = NIGHT_COLOR.alpha() * (1.0 - ambient.0);
+    material.tint = NIGHT_COLOR.with_alpha(alpha).to_linear().to_vec4();
+    if let Some(viewport) = camera.logical_viewport_rect()
+        && let Ok(top_left) = camera.viewport_to_world_2d(camera_transform, viewport.min)
+        && let Ok(bottom_right) = camera.viewport_to_world_2d(camera_transform, viewport.max)
+    {
+        material.view = top_left.extend(bottom_right.x).extend(bottom_right.y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn light_dims_with_distance_and_stops_at_walls() {
+        // A wall runs down the middle of the chunk, at x = 5.
+        let wall = |tile: IVec2| tile.x == 5;
+        let levels = light_chunk(IVec2::ZERO, [(IVec2::new(2, 5), 6)], wall);
+        let level = |x: u32, y: u32| levels[(y * CHUNK_SIZE.x + x) as usize];
+        assert_eq!(level(2, 5), 6);
+        assert_eq!(level(3, 5), 5);
+        // Light goes around corners, one level per step.
+        assert_eq!(level(0, 3), 2);
+        // The wall is lit, but nothing behind it.
+        assert_eq!(level(5, 5), 3);
+        assert_eq!(level(6, 5), 0);
+
+        // Light shines in from a source in the chunk next door, and is capped at MAX_LIGHT.
+        let plain = |_| false;
+        let levels = light_chunk(IVec2::X, [(IVec2::new(9, 0), u8::MAX)], plain);
+        assert_eq!(levels[0], MAX_LIGHT - 1);
+        assert_eq!(levels[CHUNK_SIZE.x as usize - 1], 0);
+    }
+}
@@ -0,0 +1,165 @@
+use bevy::asset::RecursiveDependencyLoadState;
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::GameState;
+use crate::biome_assets::BiomeAssets;
+use crate::chunk::{ChunkManager, ChunkMarker, world_pos_to_chunk_pos};
+use crate::localization::Localization;
+use crate::player::Player;
+use crate::worldgen::{WorldSeed, WorldgenPreset, biome_at_chunk};
+
+/// Shows how far along loading is: the assets of every collection loaded with
+/// [`LoadAndTrack::load_and_track`] while [`GameState::Loading`], then the chunks around the
+/// player and their [`BiomeAssets`] when a world opens, until they are all in.
+pub struct LoadingScreenPlugin;
+
+impl Plugin for LoadingScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoadingAssets>()
+            .init_resource::<WorldLoaded>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                |mut loaded: ResMut<WorldLoaded>| {
+                    loaded.0 = false;
+                },
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                (
+                    asset_loading_ui.run_if(in_state(GameState::Loading)),
+                    world_loading_ui
+                        .run_if(in_state(GameState::Playing))
+                        .run_if(resource_equals(WorldLoaded(false))),
+                ),
+            );
+    }
+}
+
+/// Adds an [`AssetCollection`] to a [`LoadingState`] and counts its assets towards the loading
+/// screen's progress.
+pub trait LoadAndTrack {
+    fn load_and_track<A: AssetCollection>(self, app: &mut App) -> Self;
+}
+
+impl LoadAndTrack for LoadingState<GameState> {
+    fn load_and_track<A: AssetCollection>(self, app: &mut App) -> Self {
+        app.add_systems(OnEnter(GameState::Loading), track_collection::<A>);
+        self.load_collection::<A>()
+    }
+}
+
+/// The assets the loading screen waits for.
+#[derive(Resource, Default)]
+struct LoadingAssets(Vec<UntypedHandle>);
+
+/// Whether every chunk around the player had come in since the world opened.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct WorldLoaded(bool);
+
+// The asset server hands back the handles the loading state is already loading, so this only
+// looks them up.
+fn track_collection<A: AssetCollection>(world: &mut World) {
+    let handles = A::load(world);
+    world.resource_mut::<LoadingAssets>().0.extend(handles);
+}
+
+/// The fraction of the chunks within `distance` chunks of `center` that are `loaded`.
+pub fn chunk_progress(center: IVec2, distance: UVec2, loaded: impl Fn(IVec2) -> bool) -> f32 {
+    let distance = distance.as_ivec2();
+    let chunks = (-distance.y..=distance.y)
+        .flat_map(|y| (-distance.x..=distance.x).map(move |x| center + IVec2::new(x, y)));
+    let (total, done) = chunks.fold((0, 0), |(total, done), chunk_pos| {
+        (total + 1, done + usize::from(loaded(chunk_pos)))
+    });
+    done as f32 / total as f32
+}
+
+fn loading_ui(
+    contexts: &mut EguiContexts,
+    localization: &Localization,
+    text_id: &str,
+    progress: f32,
+) -> Result {
+    egui::CentralPanel::default().show(contexts.ctx_mut()?, |ui| {
+        ui.vertical_centered(|ui| {
+            ui.add_space(ui.available_height() / 2.0 - 40.0);
+            ui.heading(localization.get("app-title"));
+            ui.label(localization.get(text_id));
+            ui.add(
+                egui::ProgressBar::new(progress)
+                    .desired_width(240.0)
+                    .show_percentage(),
+            );
+        });
+    });
+    Ok(())
+}
+
+fn asset_loading_ui(
+    mut contexts: EguiContexts,
+    localization: Res<Localization>,
+    assets: Res<LoadingAssets>,
+    asset_server: Res<AssetServer>,
+) -> Result {
+    let done = assets
+        .0
+        .iter()
+        .filter(|handle| {
+            matches!(
+                asset_server.get_recursive_dependency_load_state(handle.id()),
+                Some(
+                    RecursiveDependencyLoadState::Loaded | RecursiveDependencyLoadState::Failed(_)
+                )
+            )
+        })
+        .count();
+    let progress = done as f32 / assets.0.len().max(1) as f32;
+    loading_ui(&mut contexts, &localization, "loading-assets", progress)
+}
+
+// A chunk only counts once its biome's assets are in too, so the world never opens on chunks
+// still waiting for their textures.
+fn world_loading_ui(
+    mut contexts: EguiContexts,
+    localization: Res<Localization>,
+    mut loaded: ResMut<WorldLoaded>,
+    player: Single<&Transform, With<Player>>,
+    (chunk_manager, chunks): (Res<ChunkManager>, Query<(), With<ChunkMarker>>),
+    (biome_assets, asset_server): (Res<BiomeAssets>, Res<AssetServer>),
+    (world_seed, preset): (Res<WorldSeed>, Res<WorldgenPreset>),
+) -> Result {
+    let center = world_pos_to_chunk_pos(&player.translation.xy());
+    let progress = chunk_progress(center, chunk_manager.render_distance, |chunk_pos| {
+        let biome = biome_at_chunk(chunk_pos, world_seed.seed, &preset);
+        chunk_manager
+            .spawned_chunks
+            .get(&chunk_pos)
+            .is_some_and(|entity| chunks.contains(*entity))
+            && biome_assets.is_loaded(biome, &asset_server)
+    });
+    if progress >= 1.0 {
+        loaded.0 = true;
+        return Ok(());
+    }
+    loading_ui(&mut contexts, &localization, "loading-world", progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_progress_counts_the_loaded_chunks_around_the_center() {
+        let center = IVec2::new(4, -2);
+        assert_eq!(chunk_progress(center, UVec2::ONE, |_| true), 1.0);
+        // The three chunks in the row north of the center.
+        let progress = chunk_progress(center, UVec2::ONE, |chunk_pos| chunk_pos.y == -1);
+        assert!((progress - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(
+            chunk_progress(center, UVec2::ZERO, |pos| pos == center),
+            1.0
+        );
+    }
+}
@@ -0,0 +1,399 @@
+use std::fmt::Display;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::types::FluentNumber;
+use fluent_bundle::{FluentArgs, FluentResource};
+use fluent_syntax::ast;
+use unic_langid::LanguageIdentifier;
+
+use crate::inventory::{ItemId, ItemRegistry};
+use crate::settings::Settings;
+
+/// The language every other one falls back to for the messages it lacks.
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+/// The languages the settings offer, by code and by their name in that language. Each has a
+/// `locales/<code>.ftl` file in the assets.
+pub const LANGUAGES: [(&str, &str); 2] = [("en", "English"), ("de", "Deutsch")];
+
+/// Keeps the [`Localization`] in the language picked in the [`Settings`], loading its
+/// `locales/<code>.ftl` file when the language changes and reloading it when the file does, and
+/// titles the window in it. English is built into the game so there is always something to show,
+/// even on the loading screen.
+pub struct LocalizationPlugin;
+
+impl Plugin for LocalizationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Translations>()
+            .register_asset_loader(TranslationsLoader)
+            .init_resource::<Localization>()
+            .add_systems(
+                Update,
+                (
+                    load_language.run_if(resource_changed::<Settings>),
+                    update_translations.run_if(on_message::<AssetEvent<Translations>>),
+                    apply_window_title.run_if(resource_changed::<Localization>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// The source of one language's Fluent (`.ftl`) file, checked to parse when it loads.
+#[derive(Asset, TypePath, Clone, Debug, Default, PartialEq)]
+pub struct Translations {
+    pub source: String,
+}
+
+/// Parses Fluent source, describing the first syntax error by its line.
+fn parse_resource(source: &str) -> Result<FluentResource, String> {
+    FluentResource::try_new(source.to_string()).map_err(|(_, errors)| {
+        let error = &errors[0];
+        let line = source[..error.pos.start.min(source.len())]
+            .matches('\n')
+            .count()
+            + 1;
+        format!("Line {line}: {}", error.kind)
+    })
+}
+
+/// A language's messages, ready to be formatted.
+struct Messages {
+    bundle: FluentBundle<FluentResource>,
+    /// Every message and attribute formatted without arguments, by id and `id.attribute`.
+    formatted: HashMap<String, String>,
+}
+
+impl Messages {
+    fn parse(language: &str, source: &str) -> Result<Self, String> {
+        let resource = parse_resource(source)?;
+        let ids: Vec<String> = resource
+            .entries()
+            .filter_map(|entry| match entry {
+                ast::Entry::Message(message) => Some(message),
+                _ => None,
+            })
+            .flat_map(|message| {
+                let id = message.id.name;
+                let attributes = message
+                    .attributes
+                    .iter()
+                    .map(move |attribute| format!("{id}.{}", attribute.id.name));
+                std::iter::once(id.to_string()).chain(attributes)
+            })
+            .collect();
+
+        let language: LanguageIdentifier = language.parse().map_err(|err| format!("{err}"))?;
+        let mut bundle = FluentBundle::new_concurrent(vec![language]);
+        // Bidi isolation marks around placeables would show up as boxes in the UI font.
+        bundle.set_use_isolating(false);
+        bundle.add_resource(resource).map_err(|errors| {
+            errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        })?;
+
+        let mut messages = Self {
+            bundle,
+            formatted: HashMap::new(),
+        };
+        messages.formatted = ids
+            .into_iter()
+            .filter_map(|id| Some((id.clone(), messages.format(&id, None)?)))
+            .collect();
+        Ok(messages)
+    }
+
+    /// Formats the message `id`, or the attribute of a message if `id` is `message.attribute`.
+    /// Placeables without an argument are left as `{$name}`.
+    fn format(&self, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+        let (id, attribute) = match id.split_once('.') {
+            Some((id, attribute)) => (id, Some(attribute)),
+            None => (id, None),
+        };
+        let message = self.bundle.get_message(id)?;
+        let pattern = match attribute {
+            Some(attribute) => message.get_attribute(attribute)?.value(),
+            None => message.value()?,
+        };
+        let mut errors = Vec::new();
+        Some(
+            self.bundle
+                .format_pattern(pattern, args, &mut errors)
+                .into_owned(),
+        )
+    }
+}
+
+/// The messages shown to the player, in the language picked in the [`Settings`]. Messages it
+/// doesn't have come from English, and messages English doesn't have either show their id.
+///
+/// Item names and dialogue are written in English in their RON files, so their messages only
+/// need adding to the other languages: `item-<id>` for an item, `dialogue-<tree>-<node>`,
+/// `-speaker` and `-choice-<index>` for the lines of a dialogue tree, `quest-<name>` for a quest's
+/// title and `talker-<dialogue>` for who a quest sends the player to talk to.
+#[derive(Resource)]
+pub struct Localization {
+    language: String,
+    handle: Option<Handle<Translations>>,
+    translations: Option<Messages>,
+    fallback: Messages,
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self {
+            language: FALLBACK_LANGUAGE.to_string(),
+            handle: None,
+            translations: None,
+            fallback: Messages::parse(FALLBACK_LANGUAGE, include_str!("../assets/locales/en.ftl"))
+                .expect("the built-in English messages should parse"),
+        }
+    }
+}
+
+impl Localization {
+    /// The code of the language the messages are in.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// The message `id`, if this language or English has it. `message.attribute` is an
+    /// attribute of a message.
+    pub fn message(&self, id: &str) -> Option<&str> {
+        self.translations
+            .iter()
+            .chain([&self.fallback])
+            .find_map(|messages| messages.formatted.get(id))
+            .map(String::as_str)
+    }
+
+    /// The message `id`, or its id if there is no such message.
+    pub fn get<'a>(&'a self, id: &'a str) -> &'a str {
+        self.message(id).unwrap_or(id)
+    }
+
+    /// The message `id`, or `fallback` if there is no such message.
+    pub fn get_or<'a>(&'a self, id: &str, fallback: &'a str) -> &'a str {
+        self.message(id).unwrap_or(fallback)
+    }
+
+    /// The message `id` with its variables filled in from `args`, like
+    /// `format("notification-picked-up", &[("count", &2), ("item", &"Stone")])`. Arguments that
+    /// read as numbers go to Fluent as numbers, so selectors can pick their plural category.
+    pub fn format(&self, id: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            let value = value.to_string();
+            match value.parse::<FluentNumber>() {
+                Ok(number) => fluent_args.set(*name, number),
+                Err(_) => fluent_args.set(*name, value),
+            }
+        }
+        self.translations
+            .iter()
+            .chain([&self.fallback])
+            .find_map(|messages| messages.format(id, Some(&fluent_args)))
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// The name of an item, from its `item-<id>` message or else its definition.
+    pub fn item_name<'a>(&'a self, registry: &'a ItemRegistry, item: &'a ItemId) -> &'a str {
+        self.message(&format!("item-{}", item.0))
+            .or_else(|| {
+                registry
+                    .get(item)
+                    .map(|definition| definition.name.as_str())
+            })
+            .unwrap_or(&item.0)
+    }
+}
+
+pub struct TranslationsLoader;
+
+impl AssetLoader for TranslationsLoader {
+    type Asset = Translations;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Translations> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let source = String::from_utf8(bytes)?;
+        parse_resource(&source)?;
+        Ok(Translations { source })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ftl"]
+    }
+}
+
+fn load_language(
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut localization: ResMut<Localization>,
+) {
+    if localization.language == settings.language {
+        return;
+    }
+    info!("Switching language to {}", settings.language);
+    // Show English until the language's file is in.
+    localization.language.clone_from(&settings.language);
+    localization.translations = None;
+    localization.handle = (settings.language != FALLBACK_LANGUAGE)
+        .then(|| asset_server.load(format!("locales/{}.ftl", settings.language)));
+}
+
+fn update_translations(
+    mut events: MessageReader<AssetEvent<Translations>>,
+    translations: Res<Assets<Translations>>,
+    mut localization: ResMut<Localization>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event
+            && localization
+                .handle
+                .as_ref()
+                .is_some_and(|handle| handle.id() == *id)
+            && let Some(loaded) = translations.get(*id)
+        {
+            match Messages::parse(&localization.language, &loaded.source) {
+                Ok(messages) => {
+                    info!(
+                        "Loaded {} {} messages",
+                        messages.formatted.len(),
+                        localization.language
+                    );
+                    localization.translations = Some(messages);
+                }
+                Err(err) => error!("Bad {} messages: {err}", localization.language),
+            }
+        }
+    }
+}
+
+fn apply_window_title(
+    localization: Res<Localization>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+) {
+    let title = localization.get("app-title");
+    if window.title != title {
+        window.title = title.to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_fall_back_to_english_and_fill_in_their_variables() {
+        let source = "
+# Comments and blank lines are skipped.
+-brand = Mondschein
+notification-saved = Gespeichert
+    .hint = Die Welt ist sicher
+
+long-message =
+    Erste Zeile
+    zweite Zeile
+title = Willkommen in { -brand }
+apples = { $count ->
+    [one] Ein Apfel
+   *[other] { $count } Äpfel
+}
+mode = { $survival ->
+    [yes] Überleben
+   *[no] Kreativ
+}
+item-stone = Stein
+";
+        let localization = Localization {
+            translations: Some(Messages::parse("de", source).unwrap()),
+            ..default()
+        };
+        assert_eq!(localization.get("notification-saved"), "Gespeichert");
+        assert_eq!(
+            localization.get("notification-saved.hint"),
+            "Die Welt ist sicher"
+        );
+        assert_eq!(
+            localization.get("long-message"),
+            "Erste Zeile\nzweite Zeile"
+        );
+        assert_eq!(localization.get("title"), "Willkommen in Mondschein");
+        assert_eq!(localization.format("apples", &[("count", &1)]), "Ein Apfel");
+        assert_eq!(localization.format("apples", &[("count", &3)]), "3 Äpfel");
+        assert_eq!(
+            localization.format("mode", &[("survival", &"yes")]),
+            "Überleben"
+        );
+        assert_eq!(localization.format("mode", &[]), "Kreativ");
+        assert_eq!(
+            localization.format("worlds-playtime", &[("hours", &0), ("minutes", &1)]),
+            "1 minute"
+        );
+        assert_eq!(
+            localization.format("worlds-playtime", &[("hours", &2), ("minutes", &5)]),
+            "2h 5m"
+        );
+        assert_eq!(
+            localization.format(
+                "worlds-details",
+                &[
+                    ("seed", &7),
+                    ("difficulty", &"Hard"),
+                    ("survival", &"yes"),
+                    ("created", &"2026-10-14"),
+                    ("playtime", &"2h 5m"),
+                ]
+            ),
+            "Seed 7 · Hard · Survival · created 2026-10-14 · played 2h 5m"
+        );
+        assert_eq!(localization.get("menu-quit"), "Quit");
+        assert_eq!(localization.get("no-such-message"), "no-such-message");
+        assert_eq!(
+            localization.format(
+                "notification-picked-up",
+                &[("count", &3), ("item", &"Stein")]
+            ),
+            "+3 Stein"
+        );
+        assert_eq!(
+            localization.format("notification-picked-up", &[("count", &3)]),
+            "+3 {$item}"
+        );
+
+        let registry: ItemRegistry = ron::from_str(include_str!("../assets/items.ron")).unwrap();
+        assert_eq!(localization.item_name(&registry, &"stone".into()), "Stein");
+        assert_eq!(localization.item_name(&registry, &"wheat".into()), "Wheat");
+        assert!(
+            parse_resource("ok = fine\n{ broken")
+                .unwrap_err()
+                .starts_with("Line 2")
+        );
+        assert!(parse_resource("no value").is_err());
+    }
+
+    #[test]
+    fn every_language_parses() {
+        for (code, _) in LANGUAGES {
+            let path = format!("{}/assets/locales/{code}.ftl", env!("CARGO_MANIFEST_DIR"));
+            let source = std::fs::read_to_string(path).unwrap();
+            assert!(Messages::parse(code, &source).is_ok(), "{code}");
+        }
+    }
+}
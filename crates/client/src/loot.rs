@@ -0,0 +1,383 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_rand::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+use crate::chunk::ChunkManager;
+use crate::clock::GameClock;
+use crate::difficulty::Difficulty;
+use crate::health::Killed;
+use crate::inventory::{Inventory, ItemId, ItemRegistry, ItemStack};
+use crate::item_drops::spawn_item_drop;
+use crate::ron_asset::RonAssetLoader;
+use crate::tile_editing::TileBroken;
+use crate::tiles::{
+    TileKind, TileLocator, tile_to_world_pos, world_pos_to_tile, world_tile_to_chunk,
+};
+use crate::tools::ToolTier;
+use crate::worldgen::{Biome, WorldSeed, WorldgenPreset, biome_at_chunk};
+
+/// Loads the loot tables from `loot.ron` into [`LootTables`]. Entities with [`Loot`] drop a roll
+/// of their table when they are [`Killed`], and tiles with a table in [`LootTables::tiles`] drop
+/// a roll of it when broken, on top of their usual drop, and chests with [`Loot`] are filled
+/// with a roll of it once they have spawned. Rolls use the global [`WyRand`].
+pub struct LootPlugin;
+
+impl Plugin for LootPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LootTables>()
+            .register_asset_loader(RonAssetLoader::<LootTables>::new(&["loot.ron"]))
+            .init_resource::<LootTables>()
+            .add_observer(drop_kill_loot)
+            .add_systems(
+                Update,
+                (
+                    update_loot_tables.run_if(on_message::<AssetEvent<LootTables>>),
+                    drop_tile_loot
+                        .run_if(on_message::<TileBroken>)
+                        .run_if(in_state(GameState::Playing)),
+                    fill_loot_chests.run_if(in_state(GameState::Playing)),
+                ),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct LootAssets {
+    #[asset(path = "loot.ron")]
+    pub tables: Handle<LootTables>,
+}
+
+/// Names the loot table an entity drops from when it is killed, or that a chest is filled from.
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Loot(pub String);
+
+/// Every loot table, as defined in `loot.ron`.
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug, Default)]
+pub struct LootTables {
+    pub tables: HashMap<String, LootTable>,
+    /// The table each tile drops from when broken, if any.
+    #[serde(default)]
+    pub tiles: HashMap<TileKind, String>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct LootTable {
+    /// How many entries are picked.
+    #[serde(default = "one")]
+    pub rolls: u32,
+    pub entries: Vec<LootEntry>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// One possible pick from a [`LootTable`], picked with a chance proportional to its weight among
+/// the entries whose conditions are met.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct LootEntry {
+    /// The item dropped, or `None` for an entry that drops nothing.
+    #[serde(default)]
+    pub item: Option<ItemId>,
+    pub weight: u32,
+    /// The smallest and largest number of items dropped, both included.
+    #[serde(default = "one_each")]
+    pub count: (u32, u32),
+    #[serde(default)]
+    pub conditions: Vec<LootCondition>,
+}
+
+fn one_each() -> (u32, u32) {
+    (1, 1)
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum LootCondition {
+    /// The loot drops in this biome.
+    Biome(Biome),
+    /// The tile was broken with this tool or a better one. Kills count as bare hands.
+    Tool(ToolTier),
+    /// The loot drops on the night of a full moon.
+    FullMoon,
+}
+
+/// Where and how loot is dropped, for checking [`LootCondition`]s.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LootContext {
+    pub biome: Biome,
+    pub tool: ToolTier,
+    pub full_moon: bool,
+    /// Scales how many times the table is rolled.
+    pub difficulty: Difficulty,
+}
+
+impl LootCondition {
+    pub fn is_met(self, context: &LootContext) -> bool {
+        match self {
+            Self::Biome(biome) => context.biome == biome,
+            Self::Tool(tool) => context.tool >= tool,
+            Self::FullMoon => context.full_moon,
+        }
+    }
+}
+
+impl LootTable {
+    /// Rolls the table in `context`, returning the stacks to drop.
+    pub fn roll(&self, rng: &mut impl Rng, context: &LootContext) -> Vec<ItemStack> {
+        let entries: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.conditions.iter().all(|c| c.is_met(context)))
+            .collect();
+        let total: u32 = entries.iter().map(|entry| entry.weight).sum();
+        let mut stacks = Vec::new();
+        if total == 0 {
+            return stacks;
+        }
+        for _ in 0..context.difficulty.loot_rolls(self.rolls) {
+            let mut pick = rng.random_range(0..total);
+            let Some(entry) = entries.iter().find(|entry| {
+                let found = pick < entry.weight;
+                pick = pick.saturating_sub(entry.weight);
+                found
+            }) else {
+                continue;
+            };
+            let (min, max) = entry.count;
+            if let Some(item) = &entry.item {
+                stacks.push(ItemStack {
+                    item: item.clone(),
+                    count: rng.random_range(min..=max.max(min)),
+                });
+            }
+        }
+        stacks
+    }
+
+    /// Item ids the table drops that aren't in `registry`.
+    pub fn unknown_items<'a>(
+        &'a self,
+        registry: &'a ItemRegistry,
+    ) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.item.as_ref())
+            .filter(|item| registry.get(item).is_none())
+            .map(|item| item.0.as_str())
+    }
+}
+
+fn update_loot_tables(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<LootTables>>,
+    loot_tables: Res<Assets<LootTables>>,
+    registry: Res<ItemRegistry>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event
+            && let Some(loot_tables) = loot_tables.get(*id)
+        {
+            for (name, table) in &loot_tables.tables {
+                for item in table.unknown_items(&registry) {
+                    warn!("Loot table {name} drops unknown item {item}");
+                }
+            }
+            for (kind, name) in &loot_tables.tiles {
+                if !loot_tables.tables.contains_key(name) {
+                    warn!("{kind:?} tiles drop from unknown loot table {name}");
+                }
+            }
+            info!("Loaded {} loot tables", loot_tables.tables.len());
+            commands.insert_resource(loot_tables.clone());
+        }
+    }
+}
+
+/// Rolls the table named `name` and drops the loot at `pos`.
+fn drop_loot(
+    commands: &mut Commands,
+    (loot_tables, registry): (&LootTables, &ItemRegistry),
+    name: &str,
+    pos: Vec2,
+    rng: &mut impl Rng,
+    context: &LootContext,
+) {
+    let Some(table) = loot_tables.tables.get(name) else {
+        warn!("No loot table named {name}");
+        return;
+    };
+    for stack in table.roll(rng, context) {
+        spawn_item_drop(commands, registry, stack, pos);
+    }
+}
+
+fn drop_kill_loot(
+    killed: On<Killed>,
+    mut commands: Commands,
+    (loot_tables, registry): (Res<LootTables>, Res<ItemRegistry>),
+    (world_seed, preset, difficulty): (Res<WorldSeed>, Res<WorldgenPreset>, Res<Difficulty>),
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+    (looters, clock): (Query<(&Loot, &Transform)>, Res<GameClock>),
+) {
+    let Ok((loot, transform)) = looters.get(killed.entity) else {
+        return;
+    };
+    let pos = transform.translation.xy();
+    let chunk_pos = world_tile_to_chunk(world_pos_to_tile(pos)).0;
+    let context = LootContext {
+        biome: biome_at_chunk(chunk_pos, world_seed.seed, &preset),
+        tool: ToolTier::Hand,
+        full_moon: clock.is_full_moon(),
+        difficulty: *difficulty,
+    };
+    drop_loot(
+        &mut commands,
+        (&loot_tables, &registry),
+        &loot.0,
+        pos,
+        &mut **global_rng,
+        &context,
+    );
+}
+
+fn drop_tile_loot(
+    mut commands: Commands,
+    (mut broken_tiles, clock): (MessageReader<TileBroken>, Res<GameClock>),
+    (loot_tables, registry): (Res<LootTables>, Res<ItemRegistry>),
+    (world_seed, preset, difficulty): (Res<WorldSeed>, Res<WorldgenPreset>, Res<Difficulty>),
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+) {
+    for TileBroken {
+        world_tile,
+        kind,
+        tool,
+    } in broken_tiles.read().copied()
+    {
+        let Some(name) = loot_tables.tiles.get(&kind) else {
+            continue;
+        };
+        let chunk_pos = world_tile_to_chunk(world_tile).0;
+        let context = LootContext {
+            biome: biome_at_chunk(chunk_pos, world_seed.seed, &preset),
+            tool,
+            full_moon: clock.is_full_moon(),
+            difficulty: *difficulty,
+        };
+        drop_loot(
+            &mut commands,
+            (&loot_tables, &registry),
+            name,
+            tile_to_world_pos(world_tile),
+            &mut **global_rng,
+            &context,
+        );
+    }
+}
+
+fn fill_loot_chests(
+    mut commands: Commands,
+    (loot_tables, registry): (Res<LootTables>, Res<ItemRegistry>),
+    (world_seed, preset, difficulty): (Res<WorldSeed>, Res<WorldgenPreset>, Res<Difficulty>),
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+    mut chests: Query<(Entity, &Loot, &mut Inventory), With<TileKind>>,
+    (locator, clock): (TileLocator, Res<GameClock>),
+    mut chunk_manager: ResMut<ChunkManager>,
+) {
+    for (entity, loot, mut inventory) in &mut chests {
+        commands.entity(entity).remove::<Loot>();
+        let Some(world_tile) = locator.world_tile(entity) else {
+            continue;
+        };
+        let Some(table) = loot_tables.tables.get(&loot.0) else {
+            warn!("No loot table named {}", loot.0);
+            continue;
+        };
+        let chunk_pos = world_tile_to_chunk(world_tile).0;
+        let context = LootContext {
+            biome: biome_at_chunk(chunk_pos, world_seed.seed, &preset),
+            tool: ToolTier::Hand,
+            full_moon: clock.is_full_moon(),
+            difficulty: *difficulty,
+        };
+        for stack in table.roll(&mut **global_rng, &context) {
+            inventory.add(&stack.item, stack.count, &registry);
+        }
+        chunk_manager.dirty_chunks.insert(chunk_pos);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn loot_rolls_are_reproducible_and_follow_their_conditions() {
+        let registry: ItemRegistry = ron::from_str(include_str!("../assets/items.ron")).unwrap();
+        let loot_tables: LootTables = ron::from_str(include_str!("../assets/loot.ron")).unwrap();
+        for table in loot_tables.tables.values() {
+            assert_eq!(table.unknown_items(&registry).count(), 0);
+        }
+        assert!(
+            loot_tables
+                .tiles
+                .values()
+                .all(|name| loot_tables.tables.contains_key(name))
+        );
+
+        let roll = |name: &str, seed: u8, context: LootContext| {
+            let mut rng = WyRand::from_seed([seed; 8]);
+            let table = &loot_tables.tables[name];
+            (0..200)
+                .flat_map(|_| table.roll(&mut rng, &context))
+                .collect::<Vec<_>>()
+        };
+        let mountains = LootContext {
+            biome: Biome::Mountains,
+            tool: ToolTier::Stone,
+            full_moon: false,
+            difficulty: Difficulty::Normal,
+        };
+        let slime = roll("slime", 7, mountains);
+        assert!(!slime.is_empty());
+        assert_eq!(slime, roll("slime", 7, mountains));
+
+        // Iron only turns up in mountains, and only for a good enough pickaxe.
+        let iron = |stacks: Vec<ItemStack>| {
+            stacks
+                .iter()
+                .filter(|stack| stack.item == ItemId::from("iron_ingot"))
+                .count()
+        };
+        assert!(iron(roll("stone", 1, mountains)) > 0);
+        let plains = LootContext {
+            biome: Biome::Plains,
+            ..mountains
+        };
+        let wooden = LootContext {
+            tool: ToolTier::Wood,
+            ..mountains
+        };
+        assert_eq!(iron(roll("stone", 1, plains)), 0);
+        assert_eq!(iron(roll("stone", 1, wooden)), 0);
+
+        // Slimes only drop tonics under the full moon.
+        let tonics = |stacks: Vec<ItemStack>| {
+            stacks
+                .iter()
+                .filter(|stack| stack.item == ItemId::from("herbal_tonic"))
+                .count()
+        };
+        let full_moon = LootContext {
+            full_moon: true,
+            ..plains
+        };
+        assert!(tonics(roll("slime", 3, full_moon)) > 0);
+        assert_eq!(tonics(roll("slime", 3, plains)), 0);
+    }
+}
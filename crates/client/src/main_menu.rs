@@ -0,0 +1,181 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use bevy_rand::prelude::*;
+use rand::RngCore;
+
+use crate::GameState;
+use crate::localization::Localization;
+use crate::map_export::map_color;
+use crate::settings::SettingsWindow;
+use crate::world_select::WorldSelectPage;
+use crate::worldgen::{WorldgenPreset, get_tile_type};
+
+/// How many tiles across and down the preview behind the menu shows, one pixel each.
+const PREVIEW_SIZE: UVec2 = UVec2::new(320, 180);
+
+/// How fast the preview scrolls, in tiles per second.
+const PREVIEW_SCROLL_SPEED: f32 = 6.0;
+
+/// Darkens the preview so the menu stands out against it.
+const PREVIEW_TINT: Color = Color::srgb(0.6, 0.6, 0.6);
+
+/// The title screen the game opens on once its assets are loaded: starting a new world, opening
+/// a saved one, the settings and quitting. Behind it a map of a random world scrolls by.
+pub struct MainMenuPlugin;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::MainMenu), spawn_preview)
+            .add_systems(
+                Update,
+                scroll_menu_preview.run_if(in_state(GameState::MainMenu)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                main_menu_ui.run_if(in_state(GameState::MainMenu)),
+            );
+    }
+}
+
+/// The map of a world over `seed` scrolling behind the menu.
+#[derive(Component, Clone, Copy, Debug)]
+struct MenuPreview {
+    seed: u64,
+    /// How many tiles the preview has scrolled by.
+    scrolled: f32,
+    /// The world x of the leftmost column drawn.
+    left: i32,
+}
+
+/// Shifts every row of the `size` RGBA image in `data` one pixel to the left and fills the
+/// rightmost column with `pixel` for each row.
+pub fn scroll_preview(data: &mut [u8], size: UVec2, mut pixel: impl FnMut(u32) -> [u8; 4]) {
+    let row_bytes = size.x as usize * 4;
+    for (y, row) in data.chunks_exact_mut(row_bytes).enumerate() {
+        row.copy_within(4.., 0);
+        row[row_bytes - 4..].copy_from_slice(&pixel(y as u32));
+    }
+}
+
+/// The color of the preview at world tile `x` and image row `y`, with the world's middle row
+/// halfway down.
+fn preview_pixel(seed: u64, x: i32, y: u32) -> [u8; 4] {
+    let world_y = (PREVIEW_SIZE.y / 2) as i32 - y as i32;
+    map_color(get_tile_type(x, world_y, seed, &WorldgenPreset::default()))
+}
+
+fn spawn_preview(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+) {
+    let seed = global_rng.next_u64();
+    let mut data = Vec::with_capacity((PREVIEW_SIZE.x * PREVIEW_SIZE.y * 4) as usize);
+    for y in 0..PREVIEW_SIZE.y {
+        for x in 0..PREVIEW_SIZE.x {
+            data.extend(preview_pixel(seed, x as i32, y));
+        }
+    }
+    let mut image = Image::new(
+        Extent3d {
+            width: PREVIEW_SIZE.x,
+            height: PREVIEW_SIZE.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    commands.spawn((
+        Name::new("Menu preview"),
+        MenuPreview {
+            seed,
+            scrolled: 0.0,
+            left: 0,
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        ImageNode::new(images.add(image)).with_color(PREVIEW_TINT),
+        Pickable::IGNORE,
+        DespawnOnExit(GameState::MainMenu),
+    ));
+}
+
+fn scroll_menu_preview(
+    time: Res<Time>,
+    preview: Single<(&mut MenuPreview, &ImageNode)>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let (mut preview, image_node) = preview.into_inner();
+    preview.scrolled += PREVIEW_SCROLL_SPEED * time.delta_secs();
+    if preview.left >= preview.scrolled as i32 {
+        return;
+    }
+    let Some(data) = images
+        .get_mut(&image_node.image)
+        .and_then(|image| image.data.as_mut())
+    else {
+        return;
+    };
+    while preview.left < preview.scrolled as i32 {
+        preview.left += 1;
+        let (seed, x) = (preview.seed, preview.left + PREVIEW_SIZE.x as i32 - 1);
+        scroll_preview(data, PREVIEW_SIZE, |y| preview_pixel(seed, x, y));
+    }
+}
+
+fn main_menu_ui(
+    mut contexts: EguiContexts,
+    localization: Res<Localization>,
+    mut page: ResMut<WorldSelectPage>,
+    mut settings_window: ResMut<SettingsWindow>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit: MessageWriter<AppExit>,
+) -> Result {
+    egui::Window::new(localization.get("app-title"))
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.vertical_centered_justified(|ui| {
+                if ui.button(localization.get("menu-new-game")).clicked() {
+                    *page = WorldSelectPage::New;
+                    next_state.set(GameState::WorldSelect);
+                }
+                if ui.button(localization.get("menu-load-world")).clicked() {
+                    *page = WorldSelectPage::Load;
+                    next_state.set(GameState::WorldSelect);
+                }
+                if ui.button(localization.get("menu-settings")).clicked() {
+                    settings_window.open = !settings_window.open;
+                }
+                if ui.button(localization.get("menu-quit")).clicked() {
+                    exit.write(AppExit::Success);
+                }
+            });
+        });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_preview_scrolls_left_a_column_at_a_time() {
+        let size = UVec2::new(3, 2);
+        let mut data: Vec<u8> = (0..6).flat_map(|pixel| [pixel; 4]).collect();
+        scroll_preview(&mut data, size, |y| [10 + y as u8; 4]);
+        let pixels: Vec<u8> = data.chunks_exact(4).map(|pixel| pixel[0]).collect();
+        assert_eq!(pixels, vec![1, 2, 10, 4, 5, 11]);
+    }
+}
@@ -0,0 +1,239 @@
+use std::time::Duration;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::time::common_conditions::on_timer;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::GameState;
+use crate::chunk::{CHUNK_SIZE, ChunkManager, SavedTile, collect_chunk_data, generate_chunk};
+use crate::persistence::{ChunkData, WorldSave};
+use crate::player::Player;
+use crate::settings::Settings;
+use crate::tiles::{TileKind, world_pos_to_tile, world_tile_to_chunk};
+
+/// How many chunks around the player the exported map covers in each direction.
+pub const MAP_EXPORT_RADIUS: i32 = 32;
+
+/// Seconds of playtime after which an unvisited area is drawn fully aged.
+pub const MAP_AGING_SECS: f64 = 2.0 * 60.0 * 60.0;
+
+/// How much of the sepia tone the oldest visited areas get. Areas never visited get all of it.
+const MAX_VISITED_FADE: f32 = 0.6;
+
+/// Press F9 to write a PNG of the terrain around the player, one pixel per tile, to the world's
+/// save directory. Loaded chunks are stamped with the playtime they were last seen at, and areas
+/// not seen in a while are drawn faded towards sepia unless [`Settings::map_aging`] is off.
+pub struct MapExportPlugin;
+
+impl Plugin for MapExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                mark_explored_chunks.run_if(on_timer(Duration::from_secs(1))),
+                export_map,
+            )
+                .run_if(in_state(GameState::Playing))
+                .run_if(resource_exists::<WorldSave>),
+        );
+    }
+}
+
+/// Color of a tile on the exported map.
+pub fn map_color(kind: TileKind) -> [u8; 4] {
+    match kind {
+        TileKind::Grass => [106, 190, 48, 255],
+        TileKind::Water => [48, 96, 200, 255],
+        TileKind::Forest => [38, 120, 90, 255],
+        TileKind::Stone => [128, 128, 128, 255],
+        TileKind::Gravel => [140, 120, 100, 255],
+        TileKind::Snow => [240, 240, 250, 255],
+        TileKind::Chest => [150, 100, 50, 255],
+        TileKind::Farmland => [116, 78, 44, 255],
+        TileKind::Crop => [170, 170, 60, 255],
+        TileKind::DungeonWall | TileKind::SecretWall => [70, 66, 80, 255],
+        TileKind::DungeonFloor | TileKind::PressurePlate => [96, 92, 104, 255],
+        TileKind::Door => [124, 80, 40, 255],
+        TileKind::HouseWall => [104, 68, 36, 255],
+        TileKind::HouseFloor => [176, 132, 84, 255],
+        TileKind::HouseWindow => [150, 190, 220, 255],
+        TileKind::Torch => [255, 176, 48, 255],
+        TileKind::Campfire => [232, 104, 40, 255],
+    }
+}
+
+/// How far a chunk last seen `age` seconds of playtime ago fades towards sepia, from 0 to 1.
+/// `None` means it was never seen.
+pub fn map_fade(age: Option<f64>) -> f32 {
+    match age {
+        Some(age) => (age / MAP_AGING_SECS).clamp(0.0, 1.0) as f32 * MAX_VISITED_FADE,
+        None => 1.0,
+    }
+}
+
+/// Blends `color` towards its sepia tone by `fade`, from 0 to 1.
+pub fn aged_color(color: [u8; 4], fade: f32) -> [u8; 4] {
+    let [r, g, b, a] = color.map(f32::from);
+    let sepia = [
+        0.393 * r + 0.769 * g + 0.189 * b,
+        0.349 * r + 0.686 * g + 0.168 * b,
+        0.272 * r + 0.534 * g + 0.131 * b,
+    ];
+    let blend = |from: f32, to: f32| (from + (to.min(255.0) - from) * fade).round() as u8;
+    [
+        blend(r, sepia[0]),
+        blend(g, sepia[1]),
+        blend(b, sepia[2]),
+        a as u8,
+    ]
+}
+
+/// Draws the chunks from `min_chunk` to `max_chunk` (inclusive) with one pixel per tile and
+/// north up. Each chunk is faded by [`aged_color`] with the amount `fade` returns for it.
+pub fn render_map(
+    min_chunk: IVec2,
+    max_chunk: IVec2,
+    mut chunk: impl FnMut(IVec2) -> ChunkData,
+    fade: impl Fn(IVec2) -> f32,
+) -> Image {
+    let chunks = (max_chunk - min_chunk + IVec2::ONE).as_uvec2();
+    let size = chunks * CHUNK_SIZE;
+    let mut data = vec![0; (size.x * size.y * 4) as usize];
+
+    for chunk_y in min_chunk.y..=max_chunk.y {
+        for chunk_x in min_chunk.x..=max_chunk.x {
+            let chunk_pos = IVec2::new(chunk_x, chunk_y);
+            let origin = (chunk_pos - min_chunk).as_uvec2() * CHUNK_SIZE;
+            let fade = fade(chunk_pos);
+            for (index, kind) in chunk(chunk_pos).tiles.into_iter().enumerate() {
+                let index = index as u32;
+                let x = origin.x + index % CHUNK_SIZE.x;
+                let y = size.y - 1 - (origin.y + index / CHUNK_SIZE.x);
+                let pixel = ((y * size.x + x) * 4) as usize;
+                data[pixel..pixel + 4].copy_from_slice(&aged_color(map_color(kind), fade));
+            }
+        }
+    }
+
+    Image::new(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD,
+    )
+}
+
+fn mark_explored_chunks(chunk_manager: Res<ChunkManager>, mut world_save: ResMut<WorldSave>) {
+    for &chunk_pos in chunk_manager.spawned_chunks.keys() {
+        world_save.mark_explored(chunk_pos);
+    }
+}
+
+fn export_map(
+    keys: Res<ButtonInput<KeyCode>>,
+    player: Single<&Transform, With<Player>>,
+    chunk_manager: Res<ChunkManager>,
+    storages: Query<&TileStorage>,
+    tiles: Query<SavedTile>,
+    mut world_save: ResMut<WorldSave>,
+    settings: Res<Settings>,
+) -> Result {
+    if !keys.just_pressed(KeyCode::F9) {
+        return Ok(());
+    }
+
+    let player_chunk = world_tile_to_chunk(world_pos_to_tile(player.translation.xy())).0;
+    let radius = IVec2::splat(MAP_EXPORT_RADIUS);
+    let (seed, preset) = (world_save.metadata.seed, world_save.metadata.preset);
+    let ages: HashMap<IVec2, Option<f64>> = (-MAP_EXPORT_RADIUS..=MAP_EXPORT_RADIUS)
+        .flat_map(|y| (-MAP_EXPORT_RADIUS..=MAP_EXPORT_RADIUS).map(move |x| IVec2::new(x, y)))
+        .map(|offset| {
+            let chunk_pos = player_chunk + offset;
+            (chunk_pos, world_save.explored_age(chunk_pos))
+        })
+        .collect();
+    let image = render_map(
+        player_chunk - radius,
+        player_chunk + radius,
+        |chunk_pos| {
+            // Loaded chunks may have changes that are not saved yet.
+            chunk_manager
+                .spawned_chunks
+                .get(&chunk_pos)
+                .and_then(|entity| storages.get(*entity).ok())
+                .and_then(|storage| collect_chunk_data(storage, &tiles))
+                .or_else(|| world_save.load_chunk(chunk_pos))
+                .unwrap_or_else(|| generate_chunk(seed, &preset, chunk_pos))
+        },
+        |chunk_pos| {
+            if settings.map_aging {
+                map_fade(ages[&chunk_pos])
+            } else {
+                0.0
+            }
+        },
+    );
+
+    let path = world_save.dir.join(format!("map_{seed}.png"));
+    image.try_into_dynamic()?.save(&path)?;
+    info!("Exported map to {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_has_one_pixel_per_tile_with_north_up() {
+        let image = render_map(
+            IVec2::new(-1, -1),
+            IVec2::new(0, 0),
+            |chunk_pos| {
+                let kind = if chunk_pos == IVec2::new(-1, 0) {
+                    TileKind::Water
+                } else {
+                    TileKind::Grass
+                };
+                ChunkData {
+                    tiles: vec![kind; (CHUNK_SIZE.x * CHUNK_SIZE.y) as usize],
+                    ..default()
+                }
+            },
+            |_| 0.0,
+        );
+
+        let size = CHUNK_SIZE * 2;
+        assert_eq!(image.size(), size);
+        let pixel = |x: u32, y: u32| {
+            let index = ((y * size.x + x) * 4) as usize;
+            image.data.as_ref().unwrap()[index..index + 4].to_vec()
+        };
+        // Chunk (-1, 0) is the north-west quarter.
+        assert_eq!(pixel(0, 0), map_color(TileKind::Water));
+        assert_eq!(pixel(size.x - 1, 0), map_color(TileKind::Grass));
+        assert_eq!(pixel(0, size.y - 1), map_color(TileKind::Grass));
+    }
+
+    #[test]
+    fn areas_fade_with_age_and_unseen_ones_the_most() {
+        assert_eq!(map_fade(Some(0.0)), 0.0);
+        assert!(map_fade(Some(MAP_AGING_SECS / 2.0)) < map_fade(Some(MAP_AGING_SECS)));
+        assert_eq!(map_fade(Some(MAP_AGING_SECS * 10.0)), MAX_VISITED_FADE);
+        assert_eq!(map_fade(None), 1.0);
+
+        let grass = map_color(TileKind::Grass);
+        assert_eq!(aged_color(grass, 0.0), grass);
+        let [r, g, b, a] = aged_color(grass, 1.0);
+        assert!(r > g && g > b, "sepia is warm");
+        assert_eq!(a, 255);
+    }
+}
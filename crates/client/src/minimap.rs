@@ -0,0 +1,186 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::image::ImageSampler;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::GameState;
+use crate::chunk::{CHUNK_SIZE, ChunkPosition, ChunkUnloaded};
+use crate::map_export::map_color;
+use crate::player::Player;
+use crate::tiles::{TileKind, world_pos_to_tile, world_tile_to_chunk};
+
+/// How many tiles across and down the minimap shows, one pixel each.
+const MINIMAP_TILES: u32 = 64;
+
+/// How many screen pixels wide each minimap pixel is.
+const MINIMAP_SCALE: f32 = 2.0;
+
+const PLAYER_COLOR: [u8; 4] = [255, 255, 255, 255];
+
+/// What the minimap shows where no chunk is loaded.
+const UNKNOWN_COLOR: [u8; 4] = [0, 0, 0, 160];
+
+/// Draws the loaded chunks around the player in the bottom left corner, one pixel per tile in
+/// the colors of the exported map. Tiles are colored in as they come in or change and dropped
+/// when their chunk unloads, and the map is redrawn around the player when either happens or
+/// they step onto another tile.
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MinimapTiles>()
+            .add_systems(OnEnter(GameState::Playing), spawn_minimap)
+            .add_systems(
+                OnExit(GameState::Playing),
+                |mut tiles: ResMut<MinimapTiles>| {
+                    tiles.chunks.clear();
+                },
+            )
+            .add_systems(
+                Update,
+                (color_changed_tiles, forget_unloaded_chunks, draw_minimap)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// The map color of every tile of each loaded chunk, indexed like its [`TileStorage`].
+#[derive(Resource, Default)]
+pub struct MinimapTiles {
+    pub chunks: HashMap<IVec2, Vec<[u8; 4]>>,
+}
+
+impl MinimapTiles {
+    /// The color of the minimap at `world_tile`.
+    pub fn color(&self, world_tile: IVec2) -> [u8; 4] {
+        let (chunk_pos, tile_pos) = world_tile_to_chunk(world_tile);
+        self.chunks
+            .get(&chunk_pos)
+            .map_or(UNKNOWN_COLOR, |colors| colors[tile_index(tile_pos)])
+    }
+
+    fn set(&mut self, chunk_pos: IVec2, tile_pos: TilePos, kind: TileKind) {
+        let colors = self
+            .chunks
+            .entry(chunk_pos)
+            .or_insert_with(|| vec![UNKNOWN_COLOR; (CHUNK_SIZE.x * CHUNK_SIZE.y) as usize]);
+        colors[tile_index(tile_pos)] = map_color(kind);
+    }
+}
+
+fn tile_index(tile_pos: TilePos) -> usize {
+    (tile_pos.y * CHUNK_SIZE.x + tile_pos.x) as usize
+}
+
+#[derive(Component)]
+struct Minimap;
+
+fn spawn_minimap(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut image = Image::new(
+        Extent3d {
+            width: MINIMAP_TILES,
+            height: MINIMAP_TILES,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        UNKNOWN_COLOR.repeat((MINIMAP_TILES * MINIMAP_TILES) as usize),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    let size = Val::Px(MINIMAP_TILES as f32 * MINIMAP_SCALE);
+    commands.spawn((
+        Name::new("Minimap"),
+        Minimap,
+        DespawnOnExit(GameState::Playing),
+        ImageNode::new(images.add(image)),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(12.0),
+            bottom: Val::Px(12.0),
+            width: size,
+            height: size,
+            border: UiRect::all(Val::Px(MINIMAP_SCALE)),
+            ..default()
+        },
+        BorderColor::all(Color::BLACK),
+        Pickable::IGNORE,
+    ));
+}
+
+fn color_changed_tiles(
+    mut minimap: ResMut<MinimapTiles>,
+    tiles: Query<(&TileKind, &TilePos, &TilemapId), Changed<TileKind>>,
+    chunks: Query<&ChunkPosition>,
+) {
+    for (kind, tile_pos, tilemap_id) in &tiles {
+        if let Ok(ChunkPosition(chunk_pos)) = chunks.get(tilemap_id.0) {
+            minimap.set(*chunk_pos, *tile_pos, *kind);
+        }
+    }
+}
+
+fn forget_unloaded_chunks(
+    mut minimap: ResMut<MinimapTiles>,
+    mut unloaded: MessageReader<ChunkUnloaded>,
+) {
+    for ChunkUnloaded(chunk_pos) in unloaded.read() {
+        minimap.chunks.remove(chunk_pos);
+    }
+}
+
+fn draw_minimap(
+    minimap: Res<MinimapTiles>,
+    player: Single<&Transform, With<Player>>,
+    image_node: Single<&ImageNode, With<Minimap>>,
+    mut images: ResMut<Assets<Image>>,
+    mut drawn_around: Local<Option<IVec2>>,
+) {
+    let center = world_pos_to_tile(player.translation.xy());
+    if !minimap.is_changed() && *drawn_around == Some(center) {
+        return;
+    }
+    let Some(data) = images
+        .get_mut(&image_node.image)
+        .and_then(|image| image.data.as_mut())
+    else {
+        return;
+    };
+    *drawn_around = Some(center);
+    let half = (MINIMAP_TILES / 2) as i32;
+    for (index, pixel) in data.chunks_exact_mut(4).enumerate() {
+        let (x, y) = (
+            (index as u32 % MINIMAP_TILES) as i32,
+            (index as u32 / MINIMAP_TILES) as i32,
+        );
+        // The top row is the northernmost.
+        let world_tile = center + IVec2::new(x - half, half - y);
+        let color = if world_tile == center {
+            PLAYER_COLOR
+        } else {
+            minimap.color(world_tile)
+        };
+        pixel.copy_from_slice(&color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_are_colored_in_by_chunk() {
+        let mut minimap = MinimapTiles::default();
+        minimap.set(IVec2::new(-1, 0), TilePos::new(9, 3), TileKind::Water);
+        assert_eq!(minimap.color(IVec2::new(-1, 3)), map_color(TileKind::Water));
+        // The rest of the chunk hasn't come in yet, and neither has its neighbour.
+        assert_eq!(minimap.color(IVec2::new(-2, 3)), UNKNOWN_COLOR);
+        assert_eq!(minimap.color(IVec2::new(0, 3)), UNKNOWN_COLOR);
+
+        minimap.chunks.remove(&IVec2::new(-1, 0));
+        assert_eq!(minimap.color(IVec2::new(-1, 3)), UNKNOWN_COLOR);
+    }
+}
@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+
+use crate::GameState;
+use crate::chunk::TILE_SIZE;
+use crate::clock::{ClockEvent, GameClock, MoonPhase};
+use crate::hit_feedback::spawn_floating_text;
+use crate::player::Player;
+
+const HUD_SCALE: f32 = 3.0;
+
+const FULL_MOON_COLOR: Color = Color::srgb(0.95, 0.93, 0.8);
+
+const BLOOD_MOON_COLOR: Color = Color::srgb(0.9, 0.25, 0.2);
+
+/// Shows the [`MoonPhase`] at the top of the screen, in red under a blood moon, and announces the
+/// full moon and the blood moon as they rise at dusk. How the moon lights up the night and what
+/// slimes come out under it, and what they drop, is up to the ambient light, enemies and loot
+/// tables.
+pub struct MoonPlugin;
+
+impl Plugin for MoonPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), spawn_moon_hud)
+            .add_systems(
+                Update,
+                (
+                    update_moon_hud
+                        .run_if(resource_changed::<MoonPhase>.or(on_message::<ClockEvent>)),
+                    announce_full_moon.run_if(on_message::<ClockEvent>),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct MoonAssets {
+    /// Every [`MoonPhase`], in order.
+    #[asset(path = "ui/moon.png")]
+    pub phases: Handle<Image>,
+    #[asset(texture_atlas_layout(tile_size_x = 16, tile_size_y = 16, columns = 8, rows = 1))]
+    pub phases_layout: Handle<TextureAtlasLayout>,
+}
+
+#[derive(Component)]
+struct MoonIcon;
+
+#[derive(Component)]
+struct MoonLabel;
+
+/// What the moon is called on the HUD and what its icon is tinted.
+fn moon_display(clock: &GameClock, moon_phase: MoonPhase) -> (&'static str, Color) {
+    if clock.is_blood_moon() {
+        ("Blood moon", BLOOD_MOON_COLOR)
+    } else {
+        (moon_phase.name(), Color::WHITE)
+    }
+}
+
+fn spawn_moon_hud(
+    mut commands: Commands,
+    assets: Res<MoonAssets>,
+    clock: Res<GameClock>,
+    moon_phase: Res<MoonPhase>,
+) {
+    let (name, color) = moon_display(&clock, *moon_phase);
+    let icon_size = Val::Px(16.0 * HUD_SCALE / 2.0);
+    commands
+        .spawn((
+            Name::new("Moon"),
+            DespawnOnExit(GameState::Playing),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(2.0 * HUD_SCALE),
+                ..default()
+            },
+            Pickable::IGNORE,
+        ))
+        .with_children(|row| {
+            row.spawn((
+                MoonIcon,
+                ImageNode::from_atlas_image(
+                    assets.phases.clone(),
+                    TextureAtlas {
+                        layout: assets.phases_layout.clone(),
+                        index: *moon_phase as usize,
+                    },
+                )
+                .with_color(color),
+                Node {
+                    width: icon_size,
+                    height: icon_size,
+                    ..default()
+                },
+            ));
+            row.spawn((
+                MoonLabel,
+                Text::new(name),
+                TextFont::from_font_size(6.0 * HUD_SCALE),
+                TextShadow::default(),
+            ));
+        });
+}
+
+fn update_moon_hud(
+    clock: Res<GameClock>,
+    moon_phase: Res<MoonPhase>,
+    mut icon: Single<&mut ImageNode, With<MoonIcon>>,
+    mut label: Single<&mut Text, With<MoonLabel>>,
+) {
+    let (name, color) = moon_display(&clock, *moon_phase);
+    if let Some(atlas) = &mut icon.texture_atlas {
+        atlas.index = *moon_phase as usize;
+    }
+    icon.color = color;
+    if label.0 != name {
+        label.0 = name.into();
+    }
+}
+
+fn announce_full_moon(
+    mut commands: Commands,
+    mut clock_events: MessageReader<ClockEvent>,
+    clock: Res<GameClock>,
+    player: Single<&Transform, With<Player>>,
+) {
+    let dusk = clock_events.read().any(|event| *event == ClockEvent::Dusk);
+    if !dusk || !clock.is_full_moon() {
+        return;
+    }
+    let (text, color) = if clock.is_blood_moon() {
+        ("A blood moon rises", BLOOD_MOON_COLOR)
+    } else {
+        ("The full moon rises", FULL_MOON_COLOR)
+    };
+    spawn_floating_text(
+        &mut commands,
+        player.translation.xy() + Vec2::Y * TILE_SIZE.y,
+        text.into(),
+        color,
+    );
+}
@@ -0,0 +1,275 @@
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_seedling::prelude::*;
+
+use crate::GameState;
+use crate::ai::Behavior;
+use crate::biome_assets::BiomeAssets;
+use crate::boss::{BossEvent, BossEventKind};
+use crate::enemies::{EnemyState, PlayerSpotted};
+use crate::health::{Health, LifeState};
+use crate::player::Player;
+
+/// The player's heart starts pounding at or below this fraction of their health.
+pub const LOW_HEALTH_FRACTION: f32 = 0.3;
+
+/// Seconds without any enemy chasing the player before the danger is over.
+pub const CALM_DELAY_SECS: f32 = 4.0;
+
+/// Length of `heartbeat.wav`, which the vignette pulses with.
+const HEARTBEAT_SECS: f32 = 0.85;
+
+/// Layers audio cues over the game as danger comes and goes. The [`MusicDirector`] plays a
+/// stinger when an enemy first spots the player and the calm cue of the biome they are in once no
+/// enemy has chased them for [`CALM_DELAY_SECS`]. At low health a heartbeat loops and a red vignette pulses along with it.
+/// Boss fights loop their own theme, sting on every phase change and end on the calm cue once the
+/// boss is defeated.
+pub struct MusicDirectorPlugin;
+
+impl Plugin for MusicDirectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MusicDirector>()
+            .add_systems(OnEnter(GameState::Playing), spawn_vignette)
+            .add_systems(OnEnter(LifeState::Dead), calm_down)
+            .add_systems(OnExit(GameState::Playing), calm_down)
+            .add_systems(
+                Update,
+                (
+                    direct_music.run_if(in_state(LifeState::Alive)),
+                    follow_boss_fights.run_if(on_message::<BossEvent>),
+                    play_heartbeat,
+                    pulse_vignette,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct MusicAssets {
+    #[asset(path = "sfx/music/stinger.wav")]
+    pub stinger: Handle<AudioSample>,
+    /// Loops seamlessly, one beat every [`HEARTBEAT_SECS`].
+    #[asset(path = "sfx/music/heartbeat.wav")]
+    pub heartbeat: Handle<AudioSample>,
+    /// Loops seamlessly for as long as a boss fight lasts.
+    #[asset(path = "sfx/music/boss.wav")]
+    pub boss_theme: Handle<AudioSample>,
+    #[asset(path = "ui/vignette.png")]
+    pub vignette: Handle<Image>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mood {
+    #[default]
+    Calm,
+    Danger,
+}
+
+/// A one-off cue the [`MusicDirector`] wants played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cue {
+    Stinger,
+    Calm,
+}
+
+/// Decides the [`Mood`] from threat events and which cues go with its changes.
+#[derive(Resource, Default, Debug)]
+pub struct MusicDirector {
+    pub mood: Mood,
+    /// Seconds since an enemy last chased the player.
+    calm_secs: f32,
+}
+
+impl MusicDirector {
+    /// Advances by `secs` seconds, in which the player was `spotted` by an enemy that wasn't
+    /// chasing them before and was `threatened` if any enemy is chasing them now. Returns the cue
+    /// to play if the mood changed.
+    pub fn update(&mut self, spotted: bool, threatened: bool, secs: f32) -> Option<Cue> {
+        match self.mood {
+            Mood::Calm if spotted => {
+                self.mood = Mood::Danger;
+                self.calm_secs = 0.0;
+                Some(Cue::Stinger)
+            }
+            Mood::Calm => None,
+            Mood::Danger if threatened || spotted => {
+                self.calm_secs = 0.0;
+                None
+            }
+            Mood::Danger => {
+                self.calm_secs += secs;
+                (self.calm_secs >= CALM_DELAY_SECS).then(|| {
+                    self.mood = Mood::Calm;
+                    Cue::Calm
+                })
+            }
+        }
+    }
+}
+
+/// The looping heartbeat, while the player's health is low.
+#[derive(Component)]
+struct Heartbeat;
+
+/// The looping theme of an ongoing boss fight.
+#[derive(Component)]
+struct BossTheme;
+
+#[derive(Component)]
+struct Vignette;
+
+fn spawn_vignette(mut commands: Commands, music_assets: Res<MusicAssets>) {
+    commands.spawn((
+        Name::new("Vignette"),
+        Vignette,
+        DespawnOnExit(GameState::Playing),
+        ImageNode::new(music_assets.vignette.clone()),
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        Visibility::Hidden,
+    ));
+}
+
+/// Settles back to [`Mood::Calm`] without a cue, when the player dies or leaves the world.
+fn calm_down(mut director: ResMut<MusicDirector>) {
+    *director = MusicDirector::default();
+}
+
+fn direct_music(
+    mut commands: Commands,
+    time: Res<Time>,
+    (music_assets, biome_assets): (Res<MusicAssets>, Res<BiomeAssets>),
+    mut director: ResMut<MusicDirector>,
+    mut spotted: MessageReader<PlayerSpotted>,
+    enemies: Query<&Behavior<EnemyState>>,
+) {
+    let spotted = spotted.read().count() > 0;
+    let threatened = enemies
+        .iter()
+        .any(|behavior| *behavior.state() == EnemyState::Chasing);
+    let sample = match director.update(spotted, threatened, time.delta_secs()) {
+        Some(Cue::Stinger) => &music_assets.stinger,
+        Some(Cue::Calm) => match biome_assets.player_music() {
+            Some(music) => music,
+            None => return,
+        },
+        None => return,
+    };
+    commands.spawn(SamplePlayer::new(sample.clone()));
+}
+
+fn follow_boss_fights(
+    mut commands: Commands,
+    (music_assets, biome_assets): (Res<MusicAssets>, Res<BiomeAssets>),
+    mut events: MessageReader<BossEvent>,
+    themes: Query<Entity, With<BossTheme>>,
+) {
+    let mut playing = !themes.is_empty();
+    for event in events.read() {
+        match event.kind {
+            BossEventKind::Engaged if !playing => {
+                playing = true;
+                commands.spawn((
+                    Name::new("Boss theme"),
+                    BossTheme,
+                    DespawnOnExit(GameState::Playing),
+                    SamplePlayer::new(music_assets.boss_theme.clone()).looping(),
+                ));
+            }
+            BossEventKind::Engaged => {}
+            BossEventKind::PhaseChanged(_) => {
+                commands.spawn(SamplePlayer::new(music_assets.stinger.clone()));
+            }
+            BossEventKind::Defeated | BossEventKind::Disengaged => {
+                playing = false;
+                for entity in &themes {
+                    commands.entity(entity).try_despawn();
+                }
+                if event.kind == BossEventKind::Defeated
+                    && let Some(music) = biome_assets.player_music()
+                {
+                    commands.spawn(SamplePlayer::new(music.clone()));
+                }
+            }
+        }
+    }
+}
+
+fn is_low(health: &Health) -> bool {
+    !health.is_dead() && health.current <= health.max * LOW_HEALTH_FRACTION
+}
+
+fn play_heartbeat(
+    mut commands: Commands,
+    music_assets: Res<MusicAssets>,
+    player: Single<&Health, With<Player>>,
+    heartbeats: Query<Entity, With<Heartbeat>>,
+) {
+    match (is_low(&player), heartbeats.is_empty()) {
+        (true, true) => {
+            commands.spawn((
+                Name::new("Heartbeat"),
+                Heartbeat,
+                DespawnOnExit(GameState::Playing),
+                SamplePlayer::new(music_assets.heartbeat.clone()).looping(),
+            ));
+        }
+        (false, false) => {
+            for entity in &heartbeats {
+                commands.entity(entity).despawn();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn pulse_vignette(
+    time: Res<Time>,
+    player: Single<&Health, With<Player>>,
+    vignette: Single<(&mut ImageNode, &mut Visibility), With<Vignette>>,
+) {
+    let (mut image, mut visibility) = vignette.into_inner();
+    if !is_low(&player) {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+    // Strongest on the beat, fading until the next one.
+    let beat = (time.elapsed_secs() % HEARTBEAT_SECS) / HEARTBEAT_SECS;
+    image.color = Color::srgba(1.0, 1.0, 1.0, 1.0 - 0.5 * beat);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn danger_stings_once_and_resolves_after_a_calm_spell() {
+        let mut director = MusicDirector::default();
+        assert_eq!(director.update(false, false, 10.0), None);
+        assert_eq!(director.update(true, true, 0.1), Some(Cue::Stinger));
+        // More enemies joining the chase don't sting again.
+        assert_eq!(director.update(true, true, 0.1), None);
+        assert_eq!(director.update(false, false, CALM_DELAY_SECS - 1.0), None);
+        // Being chased again restarts the calm spell.
+        assert_eq!(director.update(false, true, 0.1), None);
+        assert_eq!(director.update(false, false, CALM_DELAY_SECS - 1.0), None);
+        assert_eq!(director.update(false, false, 1.0), Some(Cue::Calm));
+        assert_eq!(director.mood, Mood::Calm);
+
+        assert!(is_low(&Health {
+            current: 3.0,
+            ..Health::full(10.0)
+        }));
+        assert!(!is_low(&Health {
+            current: 0.0,
+            ..Health::full(10.0)
+        }));
+    }
+}
@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+use fastnoise_lite::FastNoiseLite;
+use noisy_bevy::simplex_noise_2d_seeded;
+use serde::{Deserialize, Serialize};
+
+/// A seeded 2D noise function returning values in roughly `-1.0..=1.0`.
+pub trait NoiseSource: Send + Sync {
+    fn sample(&self, pos: Vec2, seed: f32) -> f32;
+}
+
+/// Simplex noise from noisy_bevy.
+pub struct SimplexNoise;
+
+impl NoiseSource for SimplexNoise {
+    fn sample(&self, pos: Vec2, seed: f32) -> f32 {
+        simplex_noise_2d_seeded(pos, seed)
+    }
+}
+
+/// Smoothly interpolated random values on an integer lattice. Cheaper than simplex and gives a
+/// blockier look.
+pub struct ValueNoise;
+
+impl ValueNoise {
+    fn lattice(x: i32, y: i32, seed: u32) -> f32 {
+        let mut h = (x as u32).wrapping_mul(0x27d4_eb2d)
+            ^ (y as u32).wrapping_mul(0x1656_67b1)
+            ^ seed.wrapping_mul(0x9e37_79b9);
+        h ^= h >> 15;
+        h = h.wrapping_mul(0x85eb_ca6b);
+        h ^= h >> 13;
+        h = h.wrapping_mul(0xc2b2_ae35);
+        h ^= h >> 16;
+        h as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+impl NoiseSource for ValueNoise {
+    fn sample(&self, pos: Vec2, seed: f32) -> f32 {
+        let seed = seed.to_bits();
+        let cell = pos.floor();
+        let t = pos - cell;
+        let t = t * t * (3.0 - 2.0 * t);
+        let (x, y) = (cell.x as i32, cell.y as i32);
+
+        let bottom = Self::lattice(x, y, seed).lerp(Self::lattice(x + 1, y, seed), t.x);
+        let top = Self::lattice(x, y + 1, seed).lerp(Self::lattice(x + 1, y + 1, seed), t.x);
+        bottom.lerp(top, t.y)
+    }
+}
+
+/// OpenSimplex2 noise from FastNoise Lite. Smoother than [`SimplexNoise`], without its
+/// directional artifacts.
+pub struct OpenSimplex2Noise;
+
+impl NoiseSource for OpenSimplex2Noise {
+    fn sample(&self, pos: Vec2, seed: f32) -> f32 {
+        // FastNoise Lite only mixes the low bits of its seed into the gradients, while float seeds
+        // mostly differ in their high bits.
+        let bits = seed.to_bits();
+        let mut noise = FastNoiseLite::with_seed((bits ^ bits >> 16) as i32);
+        // Positions come in already scaled by the preset's frequencies.
+        noise.set_frequency(Some(1.0));
+        noise.get_noise_2d(pos.x, pos.y)
+    }
+}
+
+/// Which [`NoiseSource`] a world is generated with.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NoiseBackend {
+    #[default]
+    Simplex,
+    Value,
+    FastNoiseLite,
+}
+
+impl NoiseBackend {
+    pub const ALL: [NoiseBackend; 3] = [
+        NoiseBackend::Simplex,
+        NoiseBackend::Value,
+        NoiseBackend::FastNoiseLite,
+    ];
+
+    pub fn source(self) -> &'static dyn NoiseSource {
+        match self {
+            NoiseBackend::Simplex => &SimplexNoise,
+            NoiseBackend::Value => &ValueNoise,
+            NoiseBackend::FastNoiseLite => &OpenSimplex2Noise,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::generate_chunk;
+    use crate::worldgen::WorldgenPreset;
+
+    #[test]
+    fn value_noise_is_continuous_and_in_range() {
+        let mut previous = ValueNoise.sample(Vec2::ZERO, 0.5);
+        for i in 1..1000 {
+            let pos = Vec2::new(i as f32 * 0.01, i as f32 * -0.007);
+            let value = ValueNoise.sample(pos, 0.5);
+            assert!((-1.0..=1.0).contains(&value));
+            assert!((value - previous).abs() < 0.1);
+            previous = value;
+        }
+        assert_eq!(
+            ValueNoise.sample(Vec2::new(3.0, 4.0), 0.5),
+            ValueNoise::lattice(3, 4, 0.5f32.to_bits()),
+        );
+    }
+
+    #[test]
+    fn fastnoise_lite_is_deterministic_for_a_seed() {
+        let samples = |seed: f32| -> Vec<f32> {
+            (0..200)
+                .map(|i| Vec2::new(i as f32 * 0.13, i as f32 * -0.07))
+                .map(|pos| OpenSimplex2Noise.sample(pos, seed))
+                .collect()
+        };
+        assert_eq!(samples(0.5), samples(0.5));
+        assert_ne!(samples(0.5), samples(0.25));
+        assert!(
+            samples(0.5)
+                .iter()
+                .all(|value| (-1.0..=1.0).contains(value))
+        );
+
+        let preset = WorldgenPreset {
+            noise: NoiseBackend::FastNoiseLite,
+        };
+        let chunk_pos = IVec2::new(3, -2);
+        assert_eq!(
+            generate_chunk(1234, &preset, chunk_pos).tiles,
+            generate_chunk(1234, &preset, chunk_pos).tiles,
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+/// How long a notification stays on screen.
+pub const NOTIFICATION_DURATION: Duration = Duration::from_secs(3);
+
+/// How many notifications show at once. The rest wait their turn.
+const MAX_SHOWN: usize = 4;
+
+/// How long a notification takes to fade out at the end of its time.
+const FADE_SECS: f32 = 0.5;
+
+/// Shows the [`Notifications`] in the bottom right corner, each for [`NOTIFICATION_DURATION`]
+/// of real time so they still go away while the game is paused.
+pub struct NotificationsPlugin;
+
+impl Plugin for NotificationsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Notifications>()
+            .add_systems(Update, tick_notifications)
+            .add_systems(EguiPrimaryContextPass, notifications_ui);
+    }
+}
+
+/// A short message for the player.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notification {
+    pub text: String,
+    /// How many times it was pushed in a row.
+    pub count: u32,
+    timer: Timer,
+}
+
+impl Notification {
+    /// How opaque it is, fading from 1 to 0 at the end of its time.
+    pub fn opacity(&self) -> f32 {
+        (self.timer.remaining_secs() / FADE_SECS).min(1.0)
+    }
+}
+
+/// The notifications on screen and waiting to be, oldest first. Any system can
+/// [`push`](Notifications::push) one.
+#[derive(Resource, Default)]
+pub struct Notifications {
+    queue: VecDeque<Notification>,
+}
+
+impl Notifications {
+    /// Queues `text`. Pushing the same text as the newest notification stacks onto it instead,
+    /// counting it again and starting its time over.
+    pub fn push(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        if let Some(newest) = self.queue.back_mut()
+            && newest.text == text
+        {
+            newest.count += 1;
+            newest.timer.reset();
+            return;
+        }
+        self.queue.push_back(Notification {
+            text,
+            count: 1,
+            timer: Timer::new(NOTIFICATION_DURATION, TimerMode::Once),
+        });
+    }
+
+    /// The notifications on screen, oldest first.
+    pub fn shown(&self) -> impl Iterator<Item = &Notification> {
+        self.queue.iter().take(MAX_SHOWN)
+    }
+
+    /// Runs down the time of the notifications on screen, dropping those whose time is up.
+    pub fn tick(&mut self, delta: Duration) {
+        for notification in self.queue.iter_mut().take(MAX_SHOWN) {
+            notification.timer.tick(delta);
+        }
+        self.queue
+            .retain(|notification| !notification.timer.is_finished());
+    }
+}
+
+fn tick_notifications(time: Res<Time<Real>>, mut notifications: ResMut<Notifications>) {
+    if !notifications.queue.is_empty() {
+        notifications.tick(time.delta());
+    }
+}
+
+fn notifications_ui(mut contexts: EguiContexts, notifications: Res<Notifications>) -> Result {
+    if notifications.queue.is_empty() {
+        return Ok(());
+    }
+    egui::Area::new(egui::Id::new("notifications"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -8.0))
+        .interactable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.with_layout(egui::Layout::top_down(egui::Align::Max), |ui| {
+                for notification in notifications.shown() {
+                    let text = match notification.count {
+                        1 => notification.text.clone(),
+                        count => format!("{} ×{count}", notification.text),
+                    };
+                    ui.scope(|ui| {
+                        ui.set_opacity(notification.opacity());
+                        egui::Frame::popup(ui.style()).show(ui, |ui| ui.label(text));
+                    });
+                }
+            });
+        });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notifications_stack_wait_their_turn_and_time_out() {
+        let mut notifications = Notifications::default();
+        notifications.push("Saved");
+        notifications.push("Saved");
+        for item in ["+1 Stone", "+1 Wood", "+1 Wheat", "+1 Torch"] {
+            notifications.push(item);
+        }
+        let shown: Vec<_> = notifications
+            .shown()
+            .map(|n| (n.text.as_str(), n.count))
+            .collect();
+        assert_eq!(
+            shown,
+            [
+                ("Saved", 2),
+                ("+1 Stone", 1),
+                ("+1 Wood", 1),
+                ("+1 Wheat", 1)
+            ]
+        );
+
+        notifications.tick(NOTIFICATION_DURATION - Duration::from_millis(250));
+        assert!(notifications.shown().all(|n| n.opacity() < 1.0));
+        notifications.tick(Duration::from_millis(250));
+        // The one left waiting only starts counting down once it shows.
+        let shown: Vec<_> = notifications.shown().map(|n| n.text.as_str()).collect();
+        assert_eq!(shown, ["+1 Torch"]);
+        assert_eq!(notifications.shown().next().unwrap().opacity(), 1.0);
+    }
+}
@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystems;
+use bevy_asset_loader::prelude::*;
+use bevy_modern_pixel_camera::prelude::*;
+
+use crate::GameState;
+use crate::pixel_snap::pixel_grid;
+use crate::settings::Settings;
+use crate::wind::Wind;
+
+/// How many world units per second the clouds drift in the strongest wind.
+const CLOUD_DRIFT: f32 = 12.0;
+
+/// Draws a starry sky and clouds far behind the chunks, where the world isn't loaded yet, that
+/// scroll slower than the camera the further away they are. Their offsets are snapped to the same
+/// pixel grid as the camera, so they step along with it instead of shimmering against it.
+pub struct ParallaxPlugin;
+
+impl Plugin for ParallaxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), spawn_parallax_layers)
+            .add_systems(
+                PostUpdate,
+                scroll_parallax_layers
+                    .before(TransformSystems::Propagate)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct ParallaxAssets {
+    #[asset(path = "sky.png")]
+    pub sky: Handle<Image>,
+    #[asset(path = "clouds.png")]
+    pub clouds: Handle<Image>,
+}
+
+/// A background that repeats its image across the view, scrolling by a fraction of the camera's
+/// movement.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ParallaxLayer {
+    /// How far it scrolls for every world unit the camera moves, from 0 for a background that
+    /// stays put on screen to 1 for one as close as the world.
+    scroll: f32,
+    /// The size of the repeating image, in world units.
+    tile: Vec2,
+    /// How far the wind has blown it, in world units.
+    drift: Vec2,
+    /// How much of the [`CLOUD_DRIFT`] the layer drifts with.
+    wind: f32,
+}
+
+/// Where a [`ParallaxLayer`] goes relative to a camera at `camera`: the scrolled and drifted
+/// offset of its image, wrapped around within one `tile` so the layer always covers the view, and
+/// snapped to the pixel `grid`.
+pub fn parallax_offset(camera: Vec2, scroll: f32, drift: Vec2, tile: Vec2, grid: f32) -> Vec2 {
+    let offset = (drift - camera * scroll).rem_euclid(tile) - tile / 2.0;
+    (offset / grid).round() * grid
+}
+
+fn spawn_parallax_layers(mut commands: Commands, assets: Res<ParallaxAssets>) {
+    let mut spawn_layer = |name: &'static str, image: &Handle<Image>, layer: ParallaxLayer, z| {
+        commands.spawn((
+            Name::new(name),
+            layer,
+            DespawnOnExit(GameState::Playing),
+            Sprite {
+                image: image.clone(),
+                image_mode: SpriteImageMode::Tiled {
+                    tile_x: true,
+                    tile_y: true,
+                    stretch_value: 1.0,
+                },
+                ..default()
+            },
+            Transform::from_xyz(0.0, 0.0, z),
+        ));
+    };
+    // Far enough behind the chunks and everything on them.
+    let sky = ParallaxLayer {
+        scroll: 0.1,
+        tile: Vec2::splat(64.0),
+        drift: Vec2::ZERO,
+        wind: 0.0,
+    };
+    spawn_layer("Sky", &assets.sky, sky, -20.0);
+    let clouds = ParallaxLayer {
+        scroll: 0.3,
+        tile: Vec2::new(128.0, 64.0),
+        drift: Vec2::ZERO,
+        wind: 1.0,
+    };
+    spawn_layer("Clouds", &assets.clouds, clouds, -10.0);
+}
+
+fn scroll_parallax_layers(
+    time: Res<Time>,
+    (settings, wind): (Res<Settings>, Res<Wind>),
+    camera: Single<(&Transform, &Projection), With<PixelZoom>>,
+    mut layers: Query<(&mut ParallaxLayer, &mut Sprite, &mut Transform), Without<PixelZoom>>,
+) {
+    let (camera_transform, projection) = camera.into_inner();
+    let Projection::Orthographic(projection) = projection else {
+        return;
+    };
+    let grid = pixel_grid(settings.pixel_snapping, projection);
+    let camera_pos = (camera_transform.translation.xy() / grid).round() * grid;
+    for (mut layer, mut sprite, mut transform) in &mut layers {
+        let blown = wind.velocity() * CLOUD_DRIFT * layer.wind * time.delta_secs();
+        layer.drift = (layer.drift + blown).rem_euclid(layer.tile);
+        // A tile wider than the view on each side, to wrap around in.
+        sprite.custom_size = Some(projection.area.size() + layer.tile * 2.0);
+        let offset = parallax_offset(camera_pos, layer.scroll, layer.drift, layer.tile, grid);
+        transform.translation = (camera_pos + offset).extend(transform.translation.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers_scroll_slower_than_the_camera_on_the_pixel_grid() {
+        let tile = Vec2::new(128.0, 64.0);
+        let at = |camera: Vec2| parallax_offset(camera, 0.25, Vec2::ZERO, tile, 1.0);
+        // Moving the camera 8 units moves the layer 2 units back on screen.
+        assert_eq!(
+            at(Vec2::new(48.0, 0.0)) - at(Vec2::new(40.0, 0.0)),
+            Vec2::new(-2.0, 0.0)
+        );
+        // Offsets wrap around within a tile and land on whole pixels.
+        for camera in [Vec2::new(1003.0, -77.0), Vec2::new(-5.5, 12345.0)] {
+            let offset = at(camera);
+            assert!(offset.abs().cmple(tile / 2.0).all());
+            assert_eq!(offset, offset.round());
+        }
+        let smooth = parallax_offset(Vec2::new(3.0, 0.0), 0.1, Vec2::ZERO, tile, 0.25);
+        assert_eq!(smooth.x % 0.25, 0.0);
+    }
+}
@@ -0,0 +1,384 @@
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+use bevy_asset_loader::prelude::*;
+use bevy_modern_pixel_camera::prelude::*;
+use bevy_rand::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::GameState;
+use crate::chunk::TILE_SIZE;
+use crate::clock::{GameClock, Season};
+use crate::lighting::LightSource;
+use crate::map_export::map_color;
+use crate::player::Player;
+use crate::ron_asset::RonAssetLoader;
+use crate::tile_editing::TileBroken;
+use crate::tiles::{TileKind, WorldTiles, tile_to_world_pos, world_pos_to_tile};
+use crate::weather::Weather;
+use crate::wind::Wind;
+
+/// The most particles alive at once. Emissions past it are dropped.
+pub const MAX_PARTICLES: usize = 400;
+
+/// The most particles spawned in one frame. Emissions past it are dropped, so a burst of effects
+/// is spread over the frame budget instead of stalling it.
+pub const MAX_PARTICLES_PER_FRAME: usize = 64;
+
+/// How far above the ground particles are drawn.
+const PARTICLE_Z: f32 = 0.5;
+
+/// Raindrops splashing on the ground around the camera each second in the rain, twice as many
+/// in a storm.
+const SPLASHES_PER_SEC: f32 = 30.0;
+
+/// Seconds between fireflies coming out on a clear night.
+const FIREFLY_SECS: u64 = 400;
+
+/// How many tiles from the player fireflies come out.
+const FIREFLY_RADIUS: f32 = 8.0;
+
+/// Loads the particle emitters from `particles.ron` into [`Emitters`] and runs the particles
+/// they emit through [`EmitParticles`]: debris off broken tiles, splashes in the rain and
+/// fireflies over grass and undergrowth on clear nights in spring and summer. No more than
+/// [`MAX_PARTICLES_PER_FRAME`] are spawned a frame or [`MAX_PARTICLES`] are alive at once.
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Emitters>()
+            .register_asset_loader(RonAssetLoader::<Emitters>::new(&["particles.ron"]))
+            .init_resource::<Emitters>()
+            .add_message::<EmitParticles>()
+            .add_systems(
+                Update,
+                (
+                    update_emitters.run_if(on_message::<AssetEvent<Emitters>>),
+                    (
+                        emit_debris.run_if(on_message::<TileBroken>),
+                        emit_splashes,
+                        emit_fireflies.run_if(on_timer(Duration::from_millis(FIREFLY_SECS))),
+                        spawn_particles,
+                        move_particles,
+                    )
+                        .chain()
+                        .run_if(in_state(GameState::Playing)),
+                ),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct ParticleAssets {
+    #[asset(path = "particles.ron")]
+    pub emitters: Handle<Emitters>,
+}
+
+/// Every particle emitter, as defined in `particles.ron`.
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug, Default)]
+pub struct Emitters {
+    pub emitters: HashMap<String, Emitter>,
+}
+
+/// How the particles of one kind of effect are launched and move.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Emitter {
+    /// How many particles are spawned each time it emits.
+    pub count: u32,
+    /// The shortest and longest a particle lasts, in seconds.
+    pub lifetime: (f32, f32),
+    /// The slowest and fastest a particle is launched, in world units per second.
+    pub speed: (f32, f32),
+    /// Which way particles are launched, turned up to `spread` radians either way.
+    pub direction: Vec2,
+    pub spread: f32,
+    /// How fast particles fall, in world units per second squared.
+    #[serde(default)]
+    pub gravity: f32,
+    /// How fast particles drift in the strongest [`Wind`], in world units per second.
+    #[serde(default)]
+    pub wind: f32,
+    /// The width and height of a particle, in world units.
+    pub size: f32,
+    /// The sRGB color of the particles, unless the emission picks its own.
+    pub color: (f32, f32, f32),
+    /// How many times a second particles glow on and off, or 0 if they fade out as they age.
+    #[serde(default)]
+    pub flicker: f32,
+    /// The level of the light each particle gives off, see [`LightSource`].
+    #[serde(default)]
+    pub light: u8,
+}
+
+impl Emitter {
+    /// The velocity and lifetime of a newly launched particle.
+    pub fn launch(&self, rng: &mut impl Rng) -> (Vec2, f32) {
+        let angle = rng.random_range(-1.0..=1.0) * self.spread;
+        let speed = rng.random_range(self.speed.0..=self.speed.1);
+        let lifetime = rng.random_range(self.lifetime.0..=self.lifetime.1);
+        let direction = Vec2::from_angle(angle).rotate(self.direction.normalize_or_zero());
+        (direction * speed, lifetime)
+    }
+}
+
+/// Asks for the emitter named `emitter` in [`Emitters`] to emit at `pos`.
+#[derive(Message, Clone, Debug, Default, PartialEq)]
+pub struct EmitParticles {
+    pub emitter: String,
+    pub pos: Vec2,
+    /// Replaces the color of the emitter, like debris taking the color of its tile.
+    pub color: Option<Color>,
+    /// Added to the velocity of every particle, like dust trailing behind whoever kicked it up.
+    pub velocity: Vec2,
+}
+
+#[derive(Component)]
+struct Particle {
+    age: f32,
+    lifetime: f32,
+    velocity: Vec2,
+    gravity: f32,
+    wind: f32,
+    flicker: f32,
+}
+
+impl Particle {
+    /// How opaque the particle is at its age: fading out, or glowing on and off if it flickers.
+    fn alpha(&self) -> f32 {
+        let fade = 1.0 - self.age / self.lifetime;
+        if self.flicker > 0.0 {
+            let glow = (self.age * self.flicker * TAU).sin() * 0.5 + 0.5;
+            // Fading in and out at either end of its life.
+            glow * (fade.min(1.0 - fade) * 4.0).min(1.0)
+        } else {
+            fade
+        }
+    }
+}
+
+fn update_emitters(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<Emitters>>,
+    emitters: Res<Assets<Emitters>>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event
+            && let Some(emitters) = emitters.get(*id)
+        {
+            info!("Loaded {} particle emitters", emitters.emitters.len());
+            commands.insert_resource(emitters.clone());
+        }
+    }
+}
+
+fn emit_debris(mut broken: MessageReader<TileBroken>, mut emit: MessageWriter<EmitParticles>) {
+    for TileBroken {
+        world_tile, kind, ..
+    } in broken.read()
+    {
+        let [red, green, blue, _] = map_color(*kind);
+        emit.write(EmitParticles {
+            emitter: "debris".into(),
+            pos: tile_to_world_pos(*world_tile),
+            color: Some(Color::srgb_u8(red, green, blue)),
+            velocity: Vec2::ZERO,
+        });
+    }
+}
+
+fn emit_splashes(
+    time: Res<Time>,
+    weather: Res<Weather>,
+    camera: Single<(&Transform, &Projection), With<PixelZoom>>,
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+    mut emit: MessageWriter<EmitParticles>,
+    mut due: Local<f32>,
+) {
+    let rate = match *weather {
+        Weather::Rain => SPLASHES_PER_SEC,
+        Weather::Storm => SPLASHES_PER_SEC * 2.0,
+        Weather::Clear | Weather::Snow => 0.0,
+    };
+    let (transform, projection) = camera.into_inner();
+    let Projection::Orthographic(projection) = projection else {
+        return;
+    };
+    *due += rate * time.delta_secs();
+    while *due >= 1.0 {
+        *due -= 1.0;
+        let area = projection.area;
+        let offset = Vec2::new(
+            global_rng.random_range(area.min.x..=area.max.x),
+            global_rng.random_range(area.min.y..=area.max.y),
+        );
+        emit.write(EmitParticles {
+            emitter: "splash".into(),
+            pos: transform.translation.xy() + offset,
+            ..default()
+        });
+    }
+}
+
+fn emit_fireflies(
+    (clock, season, weather): (Res<GameClock>, Res<Season>, Res<Weather>),
+    player: Single<&Transform, With<Player>>,
+    tiles: WorldTiles,
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+    mut emit: MessageWriter<EmitParticles>,
+) {
+    let warm = matches!(*season, Season::Spring | Season::Summer);
+    if !clock.is_night() || !warm || *weather != Weather::Clear {
+        return;
+    }
+    let offset = Vec2::new(
+        global_rng.random_range(-1.0..=1.0),
+        global_rng.random_range(-1.0..=1.0),
+    ) * FIREFLY_RADIUS
+        * TILE_SIZE.x;
+    let pos = player.translation.xy() + offset;
+    let over_plants = tiles
+        .get_tile(world_pos_to_tile(pos))
+        .is_some_and(|kind| matches!(kind, TileKind::Grass | TileKind::Forest));
+    if over_plants {
+        emit.write(EmitParticles {
+            emitter: "fireflies".into(),
+            pos,
+            ..default()
+        });
+    }
+}
+
+fn spawn_particles(
+    mut commands: Commands,
+    emitters: Res<Emitters>,
+    mut emissions: MessageReader<EmitParticles>,
+    particles: Query<(), With<Particle>>,
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+) {
+    let alive = particles.iter().count();
+    let mut budget = MAX_PARTICLES_PER_FRAME.min(MAX_PARTICLES.saturating_sub(alive));
+    for emission in emissions.read() {
+        let Some(emitter) = emitters.emitters.get(&emission.emitter) else {
+            warn_once!("No particle emitter named {}", emission.emitter);
+            continue;
+        };
+        let (red, green, blue) = emitter.color;
+        let color = emission.color.unwrap_or(Color::srgb(red, green, blue));
+        for _ in 0..emitter.count {
+            let Some(left) = budget.checked_sub(1) else {
+                return;
+            };
+            budget = left;
+            let (velocity, lifetime) = emitter.launch(&mut *global_rng);
+            let mut particle = commands.spawn((
+                Particle {
+                    age: 0.0,
+                    lifetime,
+                    velocity: velocity + emission.velocity,
+                    gravity: emitter.gravity,
+                    wind: emitter.wind,
+                    flicker: emitter.flicker,
+                },
+                DespawnOnExit(GameState::Playing),
+                Sprite::from_color(color, Vec2::splat(emitter.size)),
+                Transform::from_translation(emission.pos.extend(PARTICLE_Z)),
+            ));
+            if emitter.light > 0 {
+                particle.insert(LightSource {
+                    level: emitter.light,
+                });
+            }
+        }
+    }
+}
+
+fn move_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    wind: Res<Wind>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    let secs = time.delta_secs();
+    for (entity, mut particle, mut transform, mut sprite) in &mut particles {
+        particle.age += secs;
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        particle.velocity.y -= particle.gravity * secs;
+        let drift = wind.velocity() * particle.wind;
+        transform.translation += ((particle.velocity + drift) * secs).extend(0.0);
+        sprite.color.set_alpha(particle.alpha());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn emitters_launch_particles_within_their_ranges() {
+        let emitters: Emitters = ron::from_str(include_str!("../assets/particles.ron")).unwrap();
+        for name in ["debris", "dust", "splash", "fireflies"] {
+            assert!(emitters.emitters.contains_key(name), "{name}");
+        }
+        let debris = &emitters.emitters["debris"];
+        let mut rng = WyRand::from_seed([3; 8]);
+        for _ in 0..100 {
+            let (velocity, lifetime) = debris.launch(&mut rng);
+            let speed = velocity.length();
+            assert!((debris.speed.0 - 1e-3..=debris.speed.1 + 1e-3).contains(&speed));
+            assert!((debris.lifetime.0..=debris.lifetime.1).contains(&lifetime));
+            assert!(velocity.angle_to(debris.direction).abs() <= debris.spread + 1e-4);
+        }
+    }
+
+    #[test]
+    fn emissions_stay_within_the_particle_budget() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, EntropyPlugin::<WyRand>::with_seed([1; 8])))
+            .insert_resource(Emitters {
+                emitters: HashMap::from_iter([(
+                    "burst".to_string(),
+                    Emitter {
+                        count: 40,
+                        lifetime: (1.0, 1.0),
+                        speed: (1.0, 1.0),
+                        direction: Vec2::Y,
+                        spread: 0.0,
+                        gravity: 0.0,
+                        wind: 0.0,
+                        size: 1.0,
+                        color: (1.0, 1.0, 1.0),
+                        flicker: 0.0,
+                        light: 0,
+                    },
+                )]),
+            })
+            .add_message::<EmitParticles>()
+            .add_systems(Update, spawn_particles);
+        let emit = |app: &mut App, times: u32| {
+            for _ in 0..times {
+                app.world_mut().write_message(EmitParticles {
+                    emitter: "burst".into(),
+                    ..default()
+                });
+            }
+            app.update();
+            let mut particles = app.world_mut().query::<&Particle>();
+            particles.iter(app.world()).count()
+        };
+        assert_eq!(emit(&mut app, 1), 40);
+        assert_eq!(emit(&mut app, 3), 40 + MAX_PARTICLES_PER_FRAME);
+        for _ in 0..10 {
+            emit(&mut app, 3);
+        }
+        assert_eq!(emit(&mut app, 3), MAX_PARTICLES);
+    }
+}
@@ -0,0 +1,592 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use std::f32::consts::SQRT_2;
+
+use bevy::ecs::query::QueryData;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::GameState;
+use crate::chunk::CHUNK_SIZE;
+use crate::tiles::{
+    TileProperties, WorldTiles, tile_to_world_pos, world_pos_to_tile, world_tile_to_chunk,
+};
+use crate::worldgen::{WorldSeed, WorldgenPreset, get_tile_type};
+
+/// Tiles all path searches together may expand each frame.
+pub const NODES_PER_FRAME: usize = 2048;
+
+/// Tiles a single search may expand before it gives up, so an unreachable goal in a large open
+/// area doesn't keep searching for ever.
+pub const MAX_SEARCH_NODES: usize = 8192;
+
+/// Chunks all [`LongPath`] route searches together may expand each frame.
+pub const ROUTE_NODES_PER_FRAME: usize = 256;
+
+/// How many chunks along its route a [`LongPath`] finds a tile path through at a time.
+pub const LEG_CHUNKS: usize = 2;
+
+/// Finds paths for any entity with a [`FindPath`] or a [`LongPath`]. Searches are A* over the
+/// walkable tiles, preferring tiles that are quick to cross, and are spread over as many frames
+/// as they need to stay within [`NODES_PER_FRAME`]. Chunks that aren't loaded are searched over
+/// their generated terrain. Found paths are smoothed so agents walk straight wherever nothing is
+/// in the way.
+pub struct PathfindingPlugin;
+
+impl Plugin for PathfindingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (plan_routes, follow_routes, advance_path_searches)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Insert on an entity with a [`Transform`] to find a path from where it is to `goal`. Once the
+/// search is done this is replaced by a [`Path`], or by [`Unreachable`] if there is none. Any
+/// previous path stays until then, so agents can keep following it while they path again.
+#[derive(Component, Debug)]
+pub struct FindPath {
+    pub goal: Vec2,
+    search: Option<PathSearch>,
+}
+
+impl FindPath {
+    pub fn to(goal: Vec2) -> Self {
+        Self { goal, search: None }
+    }
+}
+
+/// The waypoints left to walk to, in world space. The last one is the goal.
+#[derive(Component, Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    pub waypoints: VecDeque<Vec2>,
+}
+
+impl Path {
+    /// Drops the waypoints within `reached` of `pos` and returns the one to head for next, or
+    /// `None` once the goal has been reached.
+    pub fn next_waypoint(&mut self, pos: Vec2, reached: f32) -> Option<Vec2> {
+        while let Some(waypoint) = self.waypoints.front() {
+            if waypoint.distance(pos) > reached {
+                return Some(*waypoint);
+            }
+            self.waypoints.pop_front();
+        }
+        None
+    }
+}
+
+/// Insert on an entity with a [`Transform`] to walk to a `goal` however far away it is. A route
+/// over whole chunks is planned first, judging each by the generated tile in its middle, and is
+/// then walked a leg of [`LEG_CHUNKS`] at a time: each leg is a [`FindPath`] that is only searched
+/// once the last one has been walked, over the tiles of the chunks that have loaded by then. This
+/// is replaced by the [`FindPath`] of the last leg, or by [`Unreachable`] if there is no route.
+#[derive(Component, Debug)]
+pub struct LongPath {
+    pub goal: Vec2,
+    search: Option<PathSearch>,
+    /// The chunks left to walk through, once planned.
+    route: Option<VecDeque<IVec2>>,
+    /// Whether a leg has been sent off, so the agent's [`Path`] is one of the route's.
+    walking: bool,
+}
+
+impl LongPath {
+    pub fn to(goal: Vec2) -> Self {
+        Self {
+            goal,
+            search: None,
+            route: None,
+            walking: false,
+        }
+    }
+
+    /// Drops the chunks of the route up to `chunk_pos`, where the agent is, and returns where
+    /// the next leg goes: the goal once it is within [`LEG_CHUNKS`], which is the last leg, or the
+    /// middle of the chunk that far along the route otherwise. `None` until the route is planned.
+    fn next_leg(&mut self, chunk_pos: IVec2) -> Option<(Vec2, bool)> {
+        let route = self.route.as_mut()?;
+        if let Some(index) = route.iter().position(|chunk| *chunk == chunk_pos) {
+            route.drain(..=index);
+        }
+        if route.len() <= LEG_CHUNKS {
+            return Some((self.goal, true));
+        }
+        Some((
+            tile_to_world_pos(chunk_middle(route[LEG_CHUNKS - 1])),
+            false,
+        ))
+    }
+}
+
+/// Marks an entity whose last [`FindPath`] didn't find a way to its goal.
+#[derive(Component, Debug)]
+pub struct Unreachable;
+
+/// Where a [`PathSearch`] is at.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchStatus {
+    Searching,
+    /// The tiles to walk through, from the start to the goal.
+    Found(Vec<IVec2>),
+    Unreachable,
+}
+
+/// An A* search between two world tiles that can be run a few tiles at a time.
+#[derive(Clone, Debug)]
+pub struct PathSearch {
+    goal: IVec2,
+    open: BinaryHeap<OpenTile>,
+    came_from: HashMap<IVec2, IVec2>,
+    costs: HashMap<IVec2, f32>,
+    expanded: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct OpenTile {
+    world_tile: IVec2,
+    cost: f32,
+    estimate: f32,
+}
+
+impl PartialEq for OpenTile {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OpenTile {}
+
+impl PartialOrd for OpenTile {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenTile {
+    /// Reversed, so that [`BinaryHeap`] pops the lowest estimate first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PathSearch {
+    pub fn new(start: IVec2, goal: IVec2) -> Self {
+        Self {
+            goal,
+            open: BinaryHeap::from([OpenTile {
+                world_tile: start,
+                cost: 0.0,
+                estimate: octile_distance(start, goal),
+            }]),
+            came_from: HashMap::default(),
+            costs: HashMap::from_iter([(start, 0.0)]),
+            expanded: 0,
+        }
+    }
+
+    /// Expands up to `budget` tiles, taking the ones it expands off it. `cost` gives the cost of
+    /// walking onto a tile, at least 1, or `None` if it can't be walked on.
+    pub fn step(
+        &mut self,
+        budget: &mut usize,
+        cost: impl Fn(IVec2) -> Option<f32>,
+    ) -> SearchStatus {
+        if cost(self.goal).is_none() {
+            return SearchStatus::Unreachable;
+        }
+        while *budget > 0 {
+            let Some(current) = self.open.pop() else {
+                return SearchStatus::Unreachable;
+            };
+            if current.world_tile == self.goal {
+                return SearchStatus::Found(self.reconstruct());
+            }
+            // Tiles are pushed again when a cheaper way to them is found, which leaves the old
+            // entry behind.
+            if current.cost > self.costs[&current.world_tile] {
+                continue;
+            }
+            *budget -= 1;
+            self.expanded += 1;
+            if self.expanded > MAX_SEARCH_NODES {
+                return SearchStatus::Unreachable;
+            }
+
+            for (x, y) in [
+                (1, 0),
+                (-1, 0),
+                (0, 1),
+                (0, -1),
+                (1, 1),
+                (1, -1),
+                (-1, 1),
+                (-1, -1),
+            ] {
+                let neighbor = current.world_tile + IVec2::new(x, y);
+                let Some(tile_cost) = cost(neighbor) else {
+                    continue;
+                };
+                let diagonal = x != 0 && y != 0;
+                // Diagonal steps can't cut the corners of blocked tiles.
+                if diagonal
+                    && (cost(current.world_tile + IVec2::new(x, 0)).is_none()
+                        || cost(current.world_tile + IVec2::new(0, y)).is_none())
+                {
+                    continue;
+                }
+                let step = if diagonal { SQRT_2 } else { 1.0 };
+                let neighbor_cost = current.cost + step * tile_cost;
+                if self
+                    .costs
+                    .get(&neighbor)
+                    .is_some_and(|known| *known <= neighbor_cost)
+                {
+                    continue;
+                }
+                self.costs.insert(neighbor, neighbor_cost);
+                self.came_from.insert(neighbor, current.world_tile);
+                self.open.push(OpenTile {
+                    world_tile: neighbor,
+                    cost: neighbor_cost,
+                    estimate: neighbor_cost + octile_distance(neighbor, self.goal),
+                });
+            }
+        }
+        SearchStatus::Searching
+    }
+
+    fn reconstruct(&self) -> Vec<IVec2> {
+        let mut path = vec![self.goal];
+        while let Some(previous) = self.came_from.get(path.last().unwrap()) {
+            path.push(*previous);
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// The shortest distance between two tiles moving in eight directions.
+fn octile_distance(a: IVec2, b: IVec2) -> f32 {
+    let delta = (a - b).abs();
+    let (long, short) = (delta.max_element(), delta.min_element());
+    (long - short) as f32 + short as f32 * SQRT_2
+}
+
+/// Drops the tiles of `path` that can be skipped by walking in a straight line, keeping the
+/// first and last.
+pub fn smooth_path(path: &[IVec2], walkable: impl Fn(IVec2) -> bool) -> Vec<IVec2> {
+    let Some(&start) = path.first() else {
+        return Vec::new();
+    };
+    let mut smoothed = vec![start];
+    let mut anchor = 0;
+    while anchor < path.len() - 1 {
+        anchor = (anchor + 1..path.len())
+            .rev()
+            .find(|&index| line_of_sight(path[anchor], path[index], &walkable))
+            .unwrap_or(anchor + 1);
+        smoothed.push(path[anchor]);
+    }
+    smoothed
+}
+
+/// Whether every tile the line between the centers of `from` and `to` passes through is
+/// walkable. A line through a corner needs both tiles beside the corner to be walkable.
+fn line_of_sight(from: IVec2, to: IVec2, walkable: impl Fn(IVec2) -> bool) -> bool {
+    let delta = to - from;
+    let (steps, sign) = (delta.abs(), delta.signum());
+    let (mut x, mut y) = (0, 0);
+    let mut world_tile = from;
+    while x < steps.x || y < steps.y {
+        // Which tile edge the line crosses next.
+        match ((1 + 2 * x) * steps.y).cmp(&((1 + 2 * y) * steps.x)) {
+            Ordering::Equal => {
+                if !walkable(world_tile + IVec2::new(sign.x, 0))
+                    || !walkable(world_tile + IVec2::new(0, sign.y))
+                {
+                    return false;
+                }
+                world_tile += sign;
+                x += 1;
+                y += 1;
+            }
+            Ordering::Less => {
+                world_tile.x += sign.x;
+                x += 1;
+            }
+            Ordering::Greater => {
+                world_tile.y += sign.y;
+                y += 1;
+            }
+        }
+        if !walkable(world_tile) {
+            return false;
+        }
+    }
+    true
+}
+
+/// The cost of walking onto a tile with `properties`, at least 1, or `None` if it can't be walked
+/// on.
+fn walk_cost(properties: TileProperties) -> Option<f32> {
+    (!properties.solid).then(|| 1.0 / properties.speed)
+}
+
+fn chunk_middle(chunk_pos: IVec2) -> IVec2 {
+    chunk_pos * CHUNK_SIZE.as_ivec2() + (CHUNK_SIZE / 2).as_ivec2()
+}
+
+fn chunk_at(pos: Vec2) -> IVec2 {
+    world_tile_to_chunk(world_pos_to_tile(pos)).0
+}
+
+fn plan_routes(
+    mut commands: Commands,
+    (world_seed, preset): (Res<WorldSeed>, Res<WorldgenPreset>),
+    mut agents: Query<(Entity, &mut LongPath, &Transform)>,
+) {
+    let mut budget = ROUTE_NODES_PER_FRAME;
+    for (entity, mut long_path, transform) in &mut agents {
+        if budget == 0 {
+            break;
+        }
+        if long_path.route.is_some() {
+            continue;
+        }
+        let goal = chunk_at(long_path.goal);
+        if long_path.search.is_none() {
+            // A new route, so any failure is its own.
+            commands.entity(entity).remove::<Unreachable>();
+        }
+        let search = long_path
+            .search
+            .get_or_insert_with(|| PathSearch::new(chunk_at(transform.translation.xy()), goal));
+        // The goal's chunk doesn't have to be walkable in the middle to get to the goal.
+        let cost = |chunk_pos: IVec2| {
+            if chunk_pos == goal {
+                return Some(1.0);
+            }
+            let middle = chunk_middle(chunk_pos);
+            walk_cost(TileProperties::of(get_tile_type(
+                middle.x,
+                middle.y,
+                world_seed.seed,
+                &preset,
+            )))
+        };
+        match search.step(&mut budget, cost) {
+            SearchStatus::Searching => {}
+            SearchStatus::Found(chunks) => long_path.route = Some(chunks.into()),
+            SearchStatus::Unreachable => {
+                commands
+                    .entity(entity)
+                    .remove::<LongPath>()
+                    .insert(Unreachable);
+            }
+        }
+    }
+}
+
+/// What [`follow_routes`] needs of an agent.
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct RouteAgent {
+    entity: Entity,
+    long_path: &'static mut LongPath,
+    transform: &'static Transform,
+    path: Option<&'static Path>,
+    unreachable: Has<Unreachable>,
+}
+
+/// Sends off the next leg of every planned [`LongPath`] whose last leg has been walked.
+fn follow_routes(mut commands: Commands, mut agents: Query<RouteAgent, Without<FindPath>>) {
+    for mut agent in &mut agents {
+        if agent.long_path.walking {
+            if agent.unreachable {
+                commands.entity(agent.entity).remove::<LongPath>();
+                continue;
+            }
+            if agent.path.is_some_and(|path| !path.waypoints.is_empty()) {
+                continue;
+            }
+        }
+        let chunk_pos = chunk_at(agent.transform.translation.xy());
+        let Some((leg, last)) = agent.long_path.next_leg(chunk_pos) else {
+            continue;
+        };
+        agent.long_path.walking = true;
+        let mut entity = commands.entity(agent.entity);
+        entity.insert(FindPath::to(leg));
+        if last {
+            entity.remove::<LongPath>();
+        }
+    }
+}
+
+pub fn advance_path_searches(
+    mut commands: Commands,
+    mut agents: Query<(Entity, &mut FindPath, &Transform)>,
+    tiles: WorldTiles,
+    (world_seed, preset): (Res<WorldSeed>, Res<WorldgenPreset>),
+) {
+    // Unloaded chunks are taken as generated, as if all their water were deep.
+    let cost = |world_tile: IVec2| {
+        walk_cost(tiles.properties(world_tile).unwrap_or_else(|| {
+            TileProperties::of(get_tile_type(
+                world_tile.x,
+                world_tile.y,
+                world_seed.seed,
+                &preset,
+            ))
+        }))
+    };
+    let mut budget = NODES_PER_FRAME;
+    for (entity, mut find_path, transform) in &mut agents {
+        if budget == 0 {
+            break;
+        }
+        let goal = find_path.goal;
+        let search = find_path.search.get_or_insert_with(|| {
+            PathSearch::new(
+                world_pos_to_tile(transform.translation.xy()),
+                world_pos_to_tile(goal),
+            )
+        });
+        match search.step(&mut budget, cost) {
+            SearchStatus::Searching => {}
+            SearchStatus::Found(world_tiles) => {
+                let smoothed = smooth_path(&world_tiles, |world_tile| cost(world_tile).is_some());
+                // The agent is already on the first tile, and the goal is more precise than the
+                // center of the last.
+                let mut waypoints: VecDeque<_> = smoothed[1..]
+                    .iter()
+                    .map(|world_tile| tile_to_world_pos(*world_tile))
+                    .collect();
+                waypoints.pop_back();
+                waypoints.push_back(goal);
+                commands
+                    .entity(entity)
+                    .remove::<(FindPath, Unreachable)>()
+                    .insert(Path { waypoints });
+            }
+            SearchStatus::Unreachable => {
+                commands
+                    .entity(entity)
+                    .remove::<(FindPath, Path)>()
+                    .insert(Unreachable);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::platform::collections::HashSet;
+
+    use super::*;
+
+    /// Runs a search to the end, `budget` tiles at a time, returning the result and how many
+    /// steps it took.
+    fn search(
+        start: IVec2,
+        goal: IVec2,
+        budget: usize,
+        blocked: &HashSet<IVec2>,
+    ) -> (SearchStatus, usize) {
+        let mut search = PathSearch::new(start, goal);
+        let cost = |world_tile| (!blocked.contains(&world_tile)).then_some(1.0);
+        for steps in 1.. {
+            let status = search.step(&mut budget.clone(), cost);
+            if status != SearchStatus::Searching {
+                return (status, steps);
+            }
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn paths_go_around_walls_and_are_smoothed() {
+        // A wall along x = 0 with a gap at y = 4, across the chunk boundaries at the origin.
+        let mut blocked: HashSet<_> = (-10..=10)
+            .filter(|y| *y != 4)
+            .map(|y| IVec2::new(0, y))
+            .collect();
+        let (start, goal) = (IVec2::new(-5, -3), IVec2::new(5, -3));
+
+        let (status, steps) = search(start, goal, 4, &blocked);
+        let SearchStatus::Found(path) = status else {
+            panic!("{status:?}");
+        };
+        assert!(steps > 1, "the search should be spread over several steps");
+        assert_eq!((path[0], *path.last().unwrap()), (start, goal));
+        assert!(path.contains(&IVec2::new(0, 4)));
+        assert!(
+            path.windows(2)
+                .all(|pair| (pair[1] - pair[0]).abs().max_element() == 1)
+        );
+        // The same search with a big enough budget finishes in one go.
+        assert_eq!(search(start, goal, MAX_SEARCH_NODES, &blocked).1, 1);
+
+        let walkable = |world_tile| !blocked.contains(&world_tile);
+        let smoothed = smooth_path(&path, walkable);
+        assert_eq!((smoothed[0], *smoothed.last().unwrap()), (start, goal));
+        assert!(smoothed.len() < path.len());
+        assert!(
+            smoothed
+                .windows(2)
+                .all(|pair| line_of_sight(pair[0], pair[1], walkable))
+        );
+        assert_eq!(
+            smooth_path(&[start, start + IVec2::X, start + IVec2::ONE], walkable),
+            vec![start, start + IVec2::ONE]
+        );
+
+        // A goal boxed in on all four sides can't be reached, even diagonally. The search
+        // gives up after expanding `MAX_SEARCH_NODES` of the open field around it.
+        let enclosed = IVec2::new(20, 20);
+        blocked
+            .extend([IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y].map(|side| enclosed + side));
+        assert_eq!(
+            search(enclosed - IVec2::X * 5, enclosed, 64, &blocked).0,
+            SearchStatus::Unreachable
+        );
+    }
+
+    #[test]
+    fn agents_follow_their_path_waypoint_by_waypoint() {
+        let mut path = Path {
+            waypoints: VecDeque::from([Vec2::new(8.0, 0.0), Vec2::new(8.0, 16.0)]),
+        };
+        assert_eq!(
+            path.next_waypoint(Vec2::ZERO, 2.0),
+            Some(Vec2::new(8.0, 0.0))
+        );
+        assert_eq!(
+            path.next_waypoint(Vec2::new(7.0, 0.0), 2.0),
+            Some(Vec2::new(8.0, 16.0))
+        );
+        assert_eq!(path.next_waypoint(Vec2::new(8.0, 15.0), 2.0), None);
+        assert!(path.waypoints.is_empty());
+    }
+
+    #[test]
+    fn long_paths_are_walked_a_few_chunks_at_a_time() {
+        let goal = tile_to_world_pos(chunk_middle(IVec2::new(4, 1)));
+        let mut long_path = LongPath::to(goal);
+        assert_eq!(long_path.next_leg(IVec2::ZERO), None);
+
+        long_path.route = Some((0..=4).map(|x| IVec2::new(x, x.min(1))).collect());
+        let middle = |x, y| tile_to_world_pos(chunk_middle(IVec2::new(x, y)));
+        assert_eq!(long_path.next_leg(IVec2::ZERO), Some((middle(2, 1), false)));
+        // An agent that strays off its route carries on from where it was.
+        assert_eq!(
+            long_path.next_leg(IVec2::new(0, -1)),
+            Some((middle(2, 1), false))
+        );
+        assert_eq!(long_path.next_leg(IVec2::new(2, 1)), Some((goal, true)));
+    }
+}
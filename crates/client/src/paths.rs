@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use directories::{ProjectDirs, UserDirs};
+
+/// Where the game keeps its files. Defaults to the platform's data and config directories for the
+/// project, or a single directory passed with `--data-dir` for portable installs and tests.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct AppPaths {
+    /// Holds the saved worlds.
+    pub data_dir: PathBuf,
+    /// Holds the settings file.
+    pub config_dir: PathBuf,
+    /// Where screenshots are written.
+    pub screenshots_dir: PathBuf,
+}
+
+impl Default for AppPaths {
+    fn default() -> Self {
+        let Some(project_dirs) = ProjectDirs::from("", "", "moonlit") else {
+            warn!("No home directory found, keeping files in the working directory");
+            return Self::portable(PathBuf::from("."));
+        };
+        let data_dir = project_dirs.data_dir().to_path_buf();
+        Self {
+            screenshots_dir: UserDirs::new()
+                .and_then(|user_dirs| user_dirs.picture_dir().map(|dir| dir.join("moonlit")))
+                .unwrap_or_else(|| data_dir.join("screenshots")),
+            config_dir: project_dirs.config_dir().to_path_buf(),
+            data_dir,
+        }
+    }
+}
+
+impl AppPaths {
+    /// Reads `--data-dir <path>` or `--data-dir=<path>` from the command line arguments, not
+    /// including the program name. Other arguments are ignored.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let data_dir = if arg == "--data-dir" {
+                args.next()
+            } else if let Some(data_dir) = arg.strip_prefix("--data-dir=") {
+                Some(data_dir.to_string())
+            } else {
+                continue;
+            };
+            match data_dir {
+                Some(data_dir) => return Self::portable(data_dir.into()),
+                None => warn!("--data-dir needs a path, using the default directories"),
+            }
+        }
+        Self::default()
+    }
+
+    /// Keeps everything in `dir`.
+    pub fn portable(dir: PathBuf) -> Self {
+        Self {
+            data_dir: dir.clone(),
+            config_dir: dir.clone(),
+            screenshots_dir: dir.join("screenshots"),
+        }
+    }
+
+    pub fn saves_dir(&self) -> PathBuf {
+        self.data_dir.join("saves")
+    }
+
+    pub fn settings_file(&self) -> PathBuf {
+        self.config_dir.join("settings.ron")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn data_dir_flag_overrides_both_directories() {
+        let expected = AppPaths::portable(PathBuf::from("portable"));
+        assert_eq!(
+            AppPaths::from_args(args(&["--data-dir", "portable"])),
+            expected
+        );
+        assert_eq!(
+            AppPaths::from_args(args(&["--verbose", "--data-dir=portable"])),
+            expected
+        );
+        assert_eq!(
+            expected.saves_dir(),
+            PathBuf::from("portable").join("saves")
+        );
+        assert_eq!(
+            AppPaths::from_args(args(&["--data-dir"])),
+            AppPaths::default()
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use bevy_enhanced_input::prelude::*;
+
+use crate::GameState;
+use crate::autosave::SaveAndQuit;
+use crate::localization::Localization;
+use crate::player::Player;
+use crate::settings::SettingsWindow;
+
+/// How dark the screen gets behind the pause menu.
+const PAUSE_DIM: Color = Color::srgba(0.0, 0.0, 0.0, 0.5);
+
+/// Pauses the game with Escape or Start: the clock, chunk streaming, AI and everything else on
+/// [`Time<Virtual>`] stop, the player's controls go quiet and the screen dims behind a menu to
+/// resume, change the settings or save and go back to the main menu.
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<PauseState>()
+            .add_input_context::<PauseInput>()
+            .add_systems(OnEnter(GameState::Playing), spawn_pause_input)
+            .add_systems(OnEnter(PauseState::Paused), pause)
+            .add_systems(OnExit(PauseState::Paused), resume)
+            .add_observer(toggle_pause)
+            .add_systems(
+                EguiPrimaryContextPass,
+                pause_menu_ui.run_if(in_state(PauseState::Paused)),
+            );
+    }
+}
+
+/// Whether the game is paused, while [`GameState::Playing`].
+#[derive(SubStates, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[source(GameState = GameState::Playing)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
+/// Whether the game is paused.
+pub fn paused(state: Option<Res<State<PauseState>>>) -> bool {
+    state.is_some_and(|state| *state.get() == PauseState::Paused)
+}
+
+/// Kept apart from the player's controls so pausing still works while they are switched off.
+#[derive(Component)]
+struct PauseInput;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct TogglePause;
+
+fn spawn_pause_input(mut commands: Commands) {
+    commands.spawn((
+        PauseInput,
+        DespawnOnExit(GameState::Playing),
+        actions!(
+            PauseInput[(
+                Action::<TogglePause>::new(),
+                bindings![KeyCode::Escape, GamepadButton::Start],
+            )]
+        ),
+    ));
+}
+
+fn toggle_pause(
+    _: On<Start<TogglePause>>,
+    state: Option<Res<State<PauseState>>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    match state.as_deref().map(State::get) {
+        Some(PauseState::Running) => next_state.set(PauseState::Paused),
+        Some(PauseState::Paused) => next_state.set(PauseState::Running),
+        None => {}
+    }
+}
+
+fn pause(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    players: Query<Entity, With<Player>>,
+) {
+    time.pause();
+    for player in &players {
+        commands
+            .entity(player)
+            .insert(ContextActivity::<Player>::INACTIVE);
+    }
+    commands.spawn((
+        Name::new("Pause dim"),
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        BackgroundColor(PAUSE_DIM),
+        // Over the HUD, under screen transitions.
+        GlobalZIndex(i32::MAX - 1),
+        DespawnOnExit(PauseState::Paused),
+    ));
+}
+
+fn resume(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    players: Query<Entity, With<Player>>,
+) {
+    time.unpause();
+    for player in &players {
+        commands
+            .entity(player)
+            .insert(ContextActivity::<Player>::ACTIVE);
+    }
+}
+
+fn pause_menu_ui(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    localization: Res<Localization>,
+    mut settings_window: ResMut<SettingsWindow>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) -> Result {
+    egui::Window::new(localization.get("pause-title"))
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.vertical_centered_justified(|ui| {
+                if ui.button(localization.get("pause-resume")).clicked() {
+                    next_state.set(PauseState::Running);
+                }
+                if ui.button(localization.get("menu-settings")).clicked() {
+                    settings_window.open = !settings_window.open;
+                }
+                if ui.button(localization.get("pause-save-quit")).clicked() {
+                    commands.trigger(SaveAndQuit);
+                }
+            });
+        });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use super::*;
+
+    #[test]
+    fn pausing_stops_virtual_time_until_resumed() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .insert_state(GameState::Playing)
+            .add_sub_state::<PauseState>()
+            .add_systems(OnEnter(PauseState::Paused), pause)
+            .add_systems(OnExit(PauseState::Paused), resume);
+        let player = app.world_mut().spawn(Player).id();
+        app.update();
+        assert!(!app.world().resource::<Time<Virtual>>().is_paused());
+
+        app.world_mut()
+            .resource_mut::<NextState<PauseState>>()
+            .set(PauseState::Paused);
+        app.update();
+        assert!(app.world().resource::<Time<Virtual>>().is_paused());
+        assert!(!**app.world().get::<ContextActivity<Player>>(player).unwrap());
+        assert!(app.world_mut().run_system_cached(paused).unwrap());
+
+        app.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::MainMenu);
+        app.update();
+        assert!(!app.world().resource::<Time<Virtual>>().is_paused());
+        assert!(!app.world_mut().run_system_cached(paused).unwrap());
+    }
+}
@@ -0,0 +1,732 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy::tasks::{IoTaskPool, Task, block_on};
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+use crate::chunk::{CHUNK_SIZE, generate_chunk};
+use crate::difficulty::Difficulty;
+use crate::dungeons::Locked;
+use crate::farming::Crop;
+use crate::inventory::Inventory;
+use crate::loot::Loot;
+use crate::quests::QuestLog;
+use crate::temperature::SurvivalMode;
+use crate::tiles::TileKind;
+use crate::waypoints::Waypoints;
+use crate::world_map::ExploredMap;
+use crate::worldgen::WorldgenPreset;
+
+mod migrations;
+
+pub use migrations::SAVE_FORMAT_VERSION;
+
+/// Width and height of a region file, in chunks.
+pub const REGION_SIZE: i32 = 16;
+
+const METADATA_FILE: &str = "world.ron";
+const EXPLORED_FILE: &str = "explored.ron";
+const MAP_FILE: &str = "map.ron";
+
+pub struct PersistencePlugin;
+
+impl Plugin for PersistencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            track_playtime
+                .run_if(in_state(GameState::Playing))
+                .run_if(resource_exists::<WorldSave>),
+        )
+        .add_systems(Last, flush_world_save.run_if(resource_exists::<WorldSave>));
+    }
+}
+
+/// The tiles of a single chunk, indexed by `TilePos::to_index`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ChunkData {
+    pub tiles: Vec<TileKind>,
+    /// The contents of the chunk's chests, by tile index.
+    #[serde(default)]
+    pub containers: Vec<(u16, Inventory)>,
+    /// The chunk's crops, by tile index.
+    #[serde(default)]
+    pub crops: Vec<(u16, Crop)>,
+    /// The chunk's locked doors and chests, by tile index.
+    #[serde(default)]
+    pub locks: Vec<(u16, Locked)>,
+    /// The loot tables of the chunk's chests that haven't been filled yet, by tile index.
+    #[serde(default)]
+    pub loot: Vec<(u16, Loot)>,
+}
+
+/// The tiles of a chunk that differ from what the generator produces for it, as
+/// `(index, kind)` pairs, the state of its chests, crops and locks, and the entities persisted in
+/// it.
+/// Unmodified chunks have no delta and are not saved at all.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+struct ChunkDelta {
+    tiles: Vec<(u16, TileKind)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    containers: Vec<(u16, Inventory)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    crops: Vec<(u16, Crop)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    locks: Vec<(u16, Locked)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    loot: Vec<(u16, Loot)>,
+    /// The chunk's [`Persist`](crate::save::Persist) entities, as a serialized scene.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entities: Option<String>,
+}
+
+impl ChunkDelta {
+    fn from_generated(metadata: &WorldMetadata, chunk_pos: IVec2, data: &ChunkData) -> Self {
+        debug_assert_eq!(data.tiles.len(), (CHUNK_SIZE.x * CHUNK_SIZE.y) as usize);
+        let generated = generate_chunk(metadata.seed, &metadata.preset, chunk_pos);
+        Self {
+            tiles: data
+                .tiles
+                .iter()
+                .zip(&generated.tiles)
+                .enumerate()
+                .filter(|(_, (tile, generated))| tile != generated)
+                .map(|(index, (tile, _))| (index as u16, *tile))
+                .collect(),
+            containers: data.containers.clone(),
+            crops: data.crops.clone(),
+            locks: data.locks.clone(),
+            loot: data.loot.clone(),
+            entities: None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+            && self.containers.is_empty()
+            && self.crops.is_empty()
+            && self.locks.is_empty()
+            && self.loot.is_empty()
+            && self.entities.is_none()
+    }
+}
+
+/// Information about a world shown on the world selection screen.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WorldMetadata {
+    pub name: String,
+    pub seed: u64,
+    #[serde(default)]
+    pub preset: WorldgenPreset,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// Whether the world is played in [`SurvivalMode`].
+    #[serde(default)]
+    pub survival: SurvivalMode,
+    /// Creation time, in seconds since the Unix epoch.
+    pub created: u64,
+    pub playtime_secs: f64,
+    /// Where the player was when the world was last saved.
+    #[serde(default, alias = "camera_pos")]
+    pub player_pos: Vec2,
+    /// The player's health when the world was last saved, or `None` for full health.
+    #[serde(default)]
+    pub player_health: Option<f32>,
+    /// The player's inventory when the world was last saved, or `None` for an empty one.
+    #[serde(default)]
+    pub player_inventory: Option<Inventory>,
+    /// The player's quests when the world was last saved, or `None` if they had none.
+    #[serde(default)]
+    pub quest_log: Option<QuestLog>,
+    /// The [`GameClock`](crate::clock::GameClock) when the world was last saved, or `None` for a
+    /// new world.
+    #[serde(default)]
+    pub clock_days: Option<f64>,
+    /// The world's waypoints when it was last saved, or `None` if it had none.
+    #[serde(default)]
+    pub waypoints: Option<Waypoints>,
+}
+
+/// The zstd level region files are compressed at unless the settings say otherwise.
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// How region files are compressed. Files are read back whichever way they were written.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SaveCompression {
+    /// Plain RON, handy for inspecting saves.
+    None,
+    /// zstd, at [`WorldSave::compression_level`].
+    #[default]
+    #[serde(alias = "Fast")]
+    Zstd,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+struct Region {
+    chunks: HashMap<IVec2, ChunkDelta>,
+}
+
+/// The world being played: its metadata and saved chunks, stored on disk as one file per
+/// region of [`REGION_SIZE`]² chunks under `dir`. Regions are read lazily and cached.
+///
+/// Only player-modified tiles are saved; the rest of a chunk is regenerated from the seed.
+#[derive(Resource)]
+pub struct WorldSave {
+    pub dir: PathBuf,
+    pub metadata: WorldMetadata,
+    /// Applied to region files written from now on.
+    pub compression: SaveCompression,
+    /// The zstd level of [`SaveCompression::Zstd`], from 1 (fastest) to 22 (smallest).
+    pub compression_level: i32,
+    regions: HashMap<IVec2, Region>,
+    unsaved_regions: HashSet<IVec2>,
+    /// Playtime, in seconds, at which each chunk was last seen.
+    explored: HashMap<IVec2, f64>,
+    /// The tiles the player has seen, for the world map. Saved on every flush.
+    pub explored_map: ExploredMap,
+    /// Background writes started by [`WorldSave::flush_async`], by destination.
+    writes: HashMap<PathBuf, Task<Result>>,
+}
+
+enum SaveFileData {
+    Metadata(WorldMetadata),
+    Region(Region),
+    Explored(HashMap<IVec2, f64>),
+    Map(ExploredMap),
+}
+
+/// A file to write: its contents and any copy of it in another format to remove.
+struct SaveFile {
+    path: PathBuf,
+    stale_path: Option<PathBuf>,
+    data: SaveFileData,
+    /// The zstd level to compress it at, if at all.
+    zstd_level: Option<i32>,
+}
+
+impl SaveFile {
+    fn write(self) -> Result {
+        let bytes = match &self.data {
+            SaveFileData::Metadata(metadata) => migrations::write(metadata, true)?.into_bytes(),
+            SaveFileData::Explored(explored) => migrations::write(explored, false)?.into_bytes(),
+            SaveFileData::Map(explored_map) => migrations::write(explored_map, false)?.into_bytes(),
+            SaveFileData::Region(region) => {
+                let text = migrations::write(region, false)?;
+                match self.zstd_level {
+                    Some(level) => zstd::encode_all(text.as_bytes(), level)?,
+                    None => text.into_bytes(),
+                }
+            }
+        };
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, bytes)?;
+        if let Some(stale_path) = self.stale_path.filter(|path| path.exists()) {
+            fs::remove_file(stale_path)?;
+        }
+        Ok(())
+    }
+}
+
+impl WorldSave {
+    pub fn new(dir: PathBuf, metadata: WorldMetadata) -> Self {
+        Self {
+            dir,
+            metadata,
+            compression: SaveCompression::default(),
+            compression_level: DEFAULT_ZSTD_LEVEL,
+            regions: HashMap::default(),
+            unsaved_regions: HashSet::default(),
+            explored: HashMap::default(),
+            explored_map: ExploredMap::default(),
+            writes: HashMap::default(),
+        }
+    }
+
+    /// Opens the existing world stored in `dir`.
+    pub fn open(dir: PathBuf) -> Result<Self> {
+        let metadata = migrations::read_metadata(&fs::read_to_string(dir.join(METADATA_FILE))?)?;
+        let explored_path = dir.join(EXPLORED_FILE);
+        let explored = if explored_path.exists() {
+            migrations::read_explored(&fs::read_to_string(explored_path)?)?
+        } else {
+            HashMap::default()
+        };
+        let map_path = dir.join(MAP_FILE);
+        let explored_map = if map_path.exists() {
+            migrations::read_map(&fs::read_to_string(map_path)?)?
+        } else {
+            ExploredMap::default()
+        };
+        let mut world_save = Self::new(dir, metadata);
+        world_save.explored = explored;
+        world_save.explored_map = explored_map;
+        Ok(world_save)
+    }
+
+    /// Creates a new world in its own directory under `saves_dir`.
+    pub fn create(
+        saves_dir: &Path,
+        name: &str,
+        seed: u64,
+        preset: WorldgenPreset,
+        difficulty: Difficulty,
+        survival: SurvivalMode,
+    ) -> Result<Self> {
+        let dir_name: String = name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        let mut dir = saves_dir.join(&dir_name);
+        let mut suffix = 1;
+        while dir.exists() {
+            suffix += 1;
+            dir = saves_dir.join(format!("{dir_name}_{suffix}"));
+        }
+
+        let created = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let mut world_save = Self::new(
+            dir,
+            WorldMetadata {
+                name: name.to_string(),
+                seed,
+                preset,
+                difficulty,
+                survival,
+                created,
+                playtime_secs: 0.0,
+                player_pos: Vec2::ZERO,
+                player_health: None,
+                player_inventory: None,
+                quest_log: None,
+                clock_days: None,
+                waypoints: None,
+            },
+        );
+        world_save.flush()?;
+        Ok(world_save)
+    }
+
+    /// Returns the tiles of `chunk_pos` with the saved modifications applied, or `None` if the
+    /// chunk has none.
+    pub fn load_chunk(&mut self, chunk_pos: IVec2) -> Option<ChunkData> {
+        let region_pos = chunk_pos.div_euclid(IVec2::splat(REGION_SIZE));
+        let delta = self.region(region_pos).chunks.get(&chunk_pos)?.clone();
+        let mut data = generate_chunk(self.metadata.seed, &self.metadata.preset, chunk_pos);
+        for (index, kind) in delta.tiles {
+            data.tiles[index as usize] = kind;
+        }
+        data.containers = delta.containers;
+        data.crops = delta.crops;
+        data.locks = delta.locks;
+        data.loot = delta.loot;
+        Some(data)
+    }
+
+    /// Stores the tiles of `chunk_pos` that differ from the generated terrain, along with its
+    /// chests, crops and locks. They are written to disk at the end of the frame.
+    pub fn store_chunk(&mut self, chunk_pos: IVec2, data: ChunkData) {
+        let mut delta = ChunkDelta::from_generated(&self.metadata, chunk_pos, &data);
+        let region_pos = chunk_pos.div_euclid(IVec2::splat(REGION_SIZE));
+        let chunks = &mut self.region(region_pos).chunks;
+        // The entities are stored separately, see `store_chunk_entities`.
+        delta.entities = chunks
+            .get(&chunk_pos)
+            .and_then(|delta| delta.entities.clone());
+        self.store_delta(region_pos, chunk_pos, delta);
+    }
+
+    /// The serialized [`Persist`](crate::save::Persist) entities of `chunk_pos`, or `None` if it
+    /// has none.
+    pub fn chunk_entities(&mut self, chunk_pos: IVec2) -> Option<&str> {
+        let region_pos = chunk_pos.div_euclid(IVec2::splat(REGION_SIZE));
+        let delta = self.region(region_pos).chunks.get(&chunk_pos)?;
+        delta.entities.as_deref()
+    }
+
+    /// Replaces the serialized entities of `chunk_pos`, keeping its tiles. They are written to
+    /// disk at the end of the frame.
+    pub fn store_chunk_entities(&mut self, chunk_pos: IVec2, entities: Option<String>) {
+        let region_pos = chunk_pos.div_euclid(IVec2::splat(REGION_SIZE));
+        let chunks = &mut self.region(region_pos).chunks;
+        let mut delta = chunks.get(&chunk_pos).cloned().unwrap_or_default();
+        delta.entities = entities;
+        self.store_delta(region_pos, chunk_pos, delta);
+    }
+
+    fn store_delta(&mut self, region_pos: IVec2, chunk_pos: IVec2, delta: ChunkDelta) {
+        let chunks = &mut self.region(region_pos).chunks;
+        if delta.is_empty() {
+            if chunks.remove(&chunk_pos).is_none() {
+                return;
+            }
+        } else {
+            chunks.insert(chunk_pos, delta);
+        }
+        self.unsaved_regions.insert(region_pos);
+    }
+
+    /// Records that the player has just seen `chunk_pos`. Saved with the metadata on the next
+    /// flush.
+    pub fn mark_explored(&mut self, chunk_pos: IVec2) {
+        self.explored.insert(chunk_pos, self.metadata.playtime_secs);
+    }
+
+    /// How many seconds of playtime ago the player last saw `chunk_pos`, or `None` if they never
+    /// have.
+    pub fn explored_age(&self, chunk_pos: IVec2) -> Option<f64> {
+        let explored = self.explored.get(&chunk_pos)?;
+        Some(self.metadata.playtime_secs - explored)
+    }
+
+    /// Writes the metadata and every region touched since the last flush to disk, waiting for
+    /// any background writes to finish first.
+    pub fn flush(&mut self) -> Result {
+        for (_, write) in self.writes.drain() {
+            block_on(write)?;
+        }
+        for file in self.take_unsaved_files() {
+            file.write()?;
+        }
+        Ok(())
+    }
+
+    /// Like [`WorldSave::flush`], but serializes, compresses and writes the files on the IO task
+    /// pool so large saves don't stall the frame. Files still being written by an earlier flush
+    /// are left for the next one.
+    pub fn flush_async(&mut self) {
+        self.finish_writes();
+        let task_pool = IoTaskPool::get();
+        for file in self.take_unsaved_files() {
+            let path = file.path.clone();
+            let write = task_pool.spawn(async move { file.write() });
+            self.writes.insert(path, write);
+        }
+    }
+
+    /// Collects the background writes that have finished, logging any that failed.
+    fn finish_writes(&mut self) {
+        let finished: Vec<PathBuf> = self
+            .writes
+            .iter()
+            .filter(|(_, write)| write.is_finished())
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in finished {
+            let write = self.writes.remove(&path).unwrap();
+            if let Err(err) = block_on(write) {
+                error!("Failed to write {}: {err}", path.display());
+            }
+        }
+    }
+
+    fn take_unsaved_files(&mut self) -> Vec<SaveFile> {
+        let mut files = Vec::new();
+        let metadata_path = self.dir.join(METADATA_FILE);
+        if !self.writes.contains_key(&metadata_path) {
+            files.push(SaveFile {
+                path: metadata_path,
+                stale_path: None,
+                data: SaveFileData::Metadata(self.metadata.clone()),
+                zstd_level: None,
+            });
+        }
+        let explored_path = self.dir.join(EXPLORED_FILE);
+        if !self.explored.is_empty() && !self.writes.contains_key(&explored_path) {
+            files.push(SaveFile {
+                path: explored_path,
+                stale_path: None,
+                data: SaveFileData::Explored(self.explored.clone()),
+                zstd_level: None,
+            });
+        }
+        let map_path = self.dir.join(MAP_FILE);
+        if !self.explored_map.chunks.is_empty() && !self.writes.contains_key(&map_path) {
+            files.push(SaveFile {
+                path: map_path,
+                stale_path: None,
+                data: SaveFileData::Map(self.explored_map.clone()),
+                zstd_level: None,
+            });
+        }
+
+        let unsaved: Vec<IVec2> = self.unsaved_regions.iter().copied().collect();
+        for region_pos in unsaved {
+            let [plain, compressed] = self.region_paths(region_pos);
+            let (path, stale_path) = match self.compression {
+                SaveCompression::None => (plain, compressed),
+                SaveCompression::Zstd => (compressed, plain),
+            };
+            // Writing either format while the other is in flight could leave both behind.
+            if self.writes.contains_key(&path) || self.writes.contains_key(&stale_path) {
+                continue;
+            }
+            self.unsaved_regions.remove(&region_pos);
+            files.push(SaveFile {
+                path,
+                stale_path: Some(stale_path),
+                data: SaveFileData::Region(self.regions[&region_pos].clone()),
+                zstd_level: (self.compression == SaveCompression::Zstd)
+                    .then_some(self.compression_level),
+            });
+        }
+        files
+    }
+
+    fn region(&mut self, region_pos: IVec2) -> &mut Region {
+        if !self.regions.contains_key(&region_pos) {
+            let (region, upgraded) = self.read_region(region_pos).unwrap_or_else(|err| {
+                error!("Failed to read region {region_pos}: {err}");
+                (Region::default(), false)
+            });
+            // Rewrite upgraded regions in the current format on the next flush.
+            if upgraded {
+                self.unsaved_regions.insert(region_pos);
+            }
+            self.regions.insert(region_pos, region);
+        }
+        self.regions.get_mut(&region_pos).unwrap()
+    }
+
+    fn read_region(&self, region_pos: IVec2) -> Result<(Region, bool)> {
+        let [plain, compressed] = self.region_paths(region_pos);
+        let text = if compressed.exists() {
+            let file = fs::File::open(compressed)?;
+            let mut text = String::new();
+            zstd::Decoder::new(file)?.read_to_string(&mut text)?;
+            text
+        } else if plain.exists() {
+            fs::read_to_string(plain)?
+        } else {
+            return Ok((Region::default(), false));
+        };
+        migrations::read_region(&text, &self.metadata)
+    }
+
+    /// The uncompressed and compressed file paths of a region.
+    fn region_paths(&self, region_pos: IVec2) -> [PathBuf; 2] {
+        let name = format!("r.{}.{}.ron", region_pos.x, region_pos.y);
+        let dir = self.dir.join("regions");
+        [dir.join(&name), dir.join(name + ".zst")]
+    }
+}
+
+impl Drop for WorldSave {
+    fn drop(&mut self) {
+        // Dropping a task cancels it, which could leave a half-written file behind.
+        for (path, write) in self.writes.drain() {
+            if let Err(err) = block_on(write) {
+                error!("Failed to write {}: {err}", path.display());
+            }
+        }
+    }
+}
+
+/// Lists the worlds under `saves_dir`, most recently created first. Directories without
+/// readable metadata are skipped.
+pub fn list_worlds(saves_dir: &Path) -> Vec<WorldSave> {
+    let Ok(entries) = fs::read_dir(saves_dir) else {
+        return Vec::new();
+    };
+    let mut worlds: Vec<WorldSave> = entries
+        .filter_map(|entry| WorldSave::open(entry.ok()?.path()).ok())
+        .collect();
+    worlds.sort_by_key(|world| std::cmp::Reverse(world.metadata.created));
+    worlds
+}
+
+fn track_playtime(time: Res<Time<Real>>, mut world_save: ResMut<WorldSave>) {
+    world_save.metadata.playtime_secs += time.delta_secs_f64();
+}
+
+fn flush_world_save(mut world_save: ResMut<WorldSave>) {
+    if !world_save.unsaved_regions.is_empty() {
+        world_save.flush_async();
+    } else if !world_save.writes.is_empty() {
+        world_save.finish_writes();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::tasks::TaskPool;
+    use bevy_ecs_tilemap::prelude::TilePos;
+
+    use super::*;
+    use crate::inventory::{ItemId, ItemStack};
+
+    #[test]
+    fn stored_chunks_survive_a_reload() {
+        let mut tiles = vec![TileKind::Stone; (CHUNK_SIZE.x * CHUNK_SIZE.y) as usize];
+        tiles[4] = TileKind::Chest;
+        tiles[12] = TileKind::Crop;
+        let chest = Inventory {
+            slots: vec![
+                None,
+                Some(ItemStack {
+                    item: ItemId::from("stick"),
+                    count: 5,
+                }),
+            ],
+        };
+        let chunk = ChunkData {
+            tiles,
+            containers: vec![(4, chest)],
+            crops: vec![(12, Crop { planted_at: 5.5 })],
+            locks: vec![(
+                4,
+                Locked {
+                    key: ItemId::from("dungeon_key"),
+                },
+            )],
+            loot: vec![(4, Loot("dungeon".into()))],
+        };
+
+        let saves_dir = std::env::temp_dir().join(format!("moonlit-saves-{}", std::process::id()));
+        let mut world_save = WorldSave::create(
+            &saves_dir,
+            "Test world",
+            42,
+            WorldgenPreset::default(),
+            Difficulty::default(),
+            SurvivalMode::default(),
+        )
+        .unwrap();
+        let dir = world_save.dir.clone();
+        world_save.store_chunk(IVec2::new(-1, 20), chunk.clone());
+        world_save.mark_explored(IVec2::new(-1, 20));
+        world_save
+            .explored_map
+            .record(IVec2::new(-1, 20), TilePos::new(3, 7), TileKind::Snow);
+        world_save.metadata.playtime_secs = 30.0;
+        world_save.flush().unwrap();
+
+        let mut reloaded = WorldSave::open(dir.clone()).unwrap();
+        assert_eq!(reloaded.metadata.seed, 42);
+        assert_eq!(dir.file_name().unwrap(), "Test_world");
+        assert_eq!(reloaded.load_chunk(IVec2::new(-1, 20)), Some(chunk));
+        assert_eq!(reloaded.load_chunk(IVec2::new(0, 20)), None);
+        assert!(dir.join("regions").join("r.-1.1.ron.zst").exists());
+        assert_eq!(reloaded.explored_age(IVec2::new(-1, 20)), Some(30.0));
+        assert_eq!(reloaded.explored_age(IVec2::new(0, 20)), None);
+        assert_eq!(reloaded.explored_map, world_save.explored_map);
+
+        assert_eq!(list_worlds(&saves_dir).len(), 1);
+        fs::remove_dir_all(saves_dir).unwrap();
+    }
+
+    #[test]
+    fn only_modified_tiles_are_saved() {
+        let chunk_pos = IVec2::new(3, -2);
+        let mut world_save = WorldSave::new(
+            PathBuf::new(),
+            WorldMetadata {
+                name: "Delta".to_string(),
+                seed: 7,
+                preset: WorldgenPreset::default(),
+                difficulty: Difficulty::default(),
+                survival: SurvivalMode::default(),
+                created: 0,
+                playtime_secs: 0.0,
+                player_pos: Vec2::ZERO,
+                player_health: None,
+                player_inventory: None,
+                quest_log: None,
+                clock_days: None,
+                waypoints: None,
+            },
+        );
+        let generated = generate_chunk(7, &WorldgenPreset::default(), chunk_pos);
+
+        world_save.store_chunk(chunk_pos, generated.clone());
+        assert_eq!(world_save.load_chunk(chunk_pos), None);
+        assert!(world_save.unsaved_regions.is_empty());
+
+        let mut modified = generated;
+        modified.tiles[12] = if modified.tiles[12] == TileKind::Water {
+            TileKind::Stone
+        } else {
+            TileKind::Water
+        };
+        world_save.store_chunk(chunk_pos, modified.clone());
+        let region = &world_save.regions[&chunk_pos.div_euclid(IVec2::splat(REGION_SIZE))];
+        assert_eq!(
+            region.chunks[&chunk_pos].tiles,
+            vec![(12, modified.tiles[12])]
+        );
+        assert_eq!(world_save.load_chunk(chunk_pos), Some(modified));
+    }
+
+    #[test]
+    fn async_flushes_switch_region_compression() {
+        IoTaskPool::get_or_init(TaskPool::new);
+        // The generator never makes a checkerboard, so the chunk always differs from the terrain.
+        let chunk = ChunkData {
+            tiles: (0..CHUNK_SIZE.x * CHUNK_SIZE.y)
+                .map(|i| [TileKind::Water, TileKind::Snow][i as usize % 2])
+                .collect(),
+            ..default()
+        };
+
+        let saves_dir =
+            std::env::temp_dir().join(format!("moonlit-async-saves-{}", std::process::id()));
+        let mut world_save = WorldSave::create(
+            &saves_dir,
+            "Async",
+            3,
+            WorldgenPreset::default(),
+            Difficulty::default(),
+            SurvivalMode::default(),
+        )
+        .unwrap();
+        let [plain, compressed] = world_save.region_paths(IVec2::ZERO);
+        world_save.store_chunk(IVec2::ZERO, chunk.clone());
+        world_save.flush_async();
+        // Waits for the write started above.
+        world_save.flush().unwrap();
+        assert!(compressed.exists() && !plain.exists());
+
+        world_save.compression = SaveCompression::None;
+        world_save.store_chunk(IVec2::ONE, chunk.clone());
+        world_save.flush().unwrap();
+        assert!(plain.exists() && !compressed.exists());
+
+        let mut reloaded = WorldSave::open(world_save.dir.clone()).unwrap();
+        assert_eq!(reloaded.load_chunk(IVec2::ZERO), Some(chunk));
+        fs::remove_dir_all(saves_dir).unwrap();
+    }
+    #[test]
+    fn regions_round_trip_at_every_compression_level() {
+        let chunk = ChunkData {
+            tiles: (0..CHUNK_SIZE.x * CHUNK_SIZE.y)
+                .map(|i| [TileKind::Water, TileKind::Snow][i as usize % 2])
+                .collect(),
+            ..default()
+        };
+        let saves_dir =
+            std::env::temp_dir().join(format!("moonlit-zstd-levels-{}", std::process::id()));
+        for level in [1, DEFAULT_ZSTD_LEVEL, 19] {
+            let mut world_save = WorldSave::create(
+                &saves_dir,
+                &format!("Level {level}"),
+                3,
+                WorldgenPreset::default(),
+                Difficulty::default(),
+                SurvivalMode::default(),
+            )
+            .unwrap();
+            world_save.compression_level = level;
+            world_save.store_chunk(IVec2::ZERO, chunk.clone());
+            world_save.flush().unwrap();
+            let mut reloaded = WorldSave::open(world_save.dir.clone()).unwrap();
+            assert_eq!(reloaded.load_chunk(IVec2::ZERO), Some(chunk.clone()));
+        }
+        fs::remove_dir_all(saves_dir).unwrap();
+    }
+}
@@ -0,0 +1,185 @@
+//! Save files are written as `(version: N, data: ...)`. Files from older versions are upgraded
+//! when read: to change a saved layout, bump [`SAVE_FORMAT_VERSION`], keep the previous layout
+//! here as a versioned struct and add a match arm that converts it.
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::{ChunkData, ChunkDelta, ExploredMap, Region, WorldMetadata};
+
+/// Version of the save files written by this build.
+///
+/// 1. Unversioned files holding every tile of a saved chunk.
+/// 2. Versioned files holding only the tiles that differ from the generated terrain.
+pub const SAVE_FORMAT_VERSION: u32 = 2;
+
+#[derive(Serialize)]
+struct VersionedRef<'a, T> {
+    version: u32,
+    data: &'a T,
+}
+
+#[derive(Deserialize)]
+struct Versioned<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct VersionHeader {
+    /// Version 1 files had no header.
+    #[serde(default = "unversioned")]
+    version: u32,
+}
+
+fn unversioned() -> u32 {
+    1
+}
+
+/// Serializes `data` with the current format version.
+pub(super) fn write<T: Serialize>(data: &T, pretty: bool) -> Result<String> {
+    let file = VersionedRef {
+        version: SAVE_FORMAT_VERSION,
+        data,
+    };
+    Ok(if pretty {
+        ron::ser::to_string_pretty(&file, default())?
+    } else {
+        ron::to_string(&file)?
+    })
+}
+
+fn version_of(text: &str) -> Result<u32> {
+    Ok(ron::from_str::<VersionHeader>(text)?.version)
+}
+
+fn read_current<T: DeserializeOwned>(text: &str) -> Result<T> {
+    Ok(ron::from_str::<Versioned<T>>(text)?.data)
+}
+
+fn newer_version(version: u32) -> BevyError {
+    format!("saved by a newer version of the game (format {version})").into()
+}
+
+pub(super) fn read_metadata(text: &str) -> Result<WorldMetadata> {
+    match version_of(text)? {
+        // Fields added since version 1 have serde defaults.
+        1 => Ok(ron::from_str(text)?),
+        SAVE_FORMAT_VERSION => read_current(text),
+        version => Err(newer_version(version)),
+    }
+}
+
+/// Reads the exploration timestamps. The file was added in version 2, so there is nothing to
+/// upgrade yet.
+pub(super) fn read_explored(text: &str) -> Result<HashMap<IVec2, f64>> {
+    match version_of(text)? {
+        SAVE_FORMAT_VERSION => read_current(text),
+        version => Err(newer_version(version)),
+    }
+}
+
+/// Reads the tiles shown on the world map. The file was added in version 2, so there is nothing
+/// to upgrade yet.
+pub(super) fn read_map(text: &str) -> Result<ExploredMap> {
+    match version_of(text)? {
+        SAVE_FORMAT_VERSION => read_current(text),
+        version => Err(newer_version(version)),
+    }
+}
+
+#[derive(Deserialize)]
+struct RegionV1 {
+    chunks: HashMap<IVec2, ChunkData>,
+}
+
+/// Reads a region file, returning it in the current layout and whether it had to be upgraded.
+pub(super) fn read_region(text: &str, metadata: &WorldMetadata) -> Result<(Region, bool)> {
+    match version_of(text)? {
+        1 => {
+            let region: RegionV1 = ron::from_str(text)?;
+            let chunks = region
+                .chunks
+                .into_iter()
+                .map(|(chunk_pos, data)| {
+                    let delta = ChunkDelta::from_generated(metadata, chunk_pos, &data);
+                    (chunk_pos, delta)
+                })
+                .filter(|(_, delta)| !delta.is_empty())
+                .collect();
+            Ok((Region { chunks }, true))
+        }
+        SAVE_FORMAT_VERSION => Ok((read_current(text)?, false)),
+        version => Err(newer_version(version)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::chunk::CHUNK_SIZE;
+    use crate::noise::NoiseBackend;
+    use crate::persistence::WorldSave;
+    use crate::tiles::TileKind;
+
+    use super::*;
+
+    fn fixture(version: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/saves")
+            .join(version)
+    }
+
+    #[test]
+    fn loads_unversioned_v1_saves() {
+        let mut world_save = WorldSave::open(fixture("v1")).unwrap();
+        assert_eq!(world_save.metadata.name, "Old world");
+        assert_eq!(world_save.metadata.seed, 42);
+        assert_eq!(world_save.metadata.player_pos, Vec2::ZERO);
+
+        let chunk = world_save.load_chunk(IVec2::ZERO).unwrap();
+        assert_eq!(
+            chunk.tiles,
+            vec![TileKind::Stone; (CHUNK_SIZE.x * CHUNK_SIZE.y) as usize]
+        );
+        assert!(world_save.unsaved_regions.contains(&IVec2::ZERO));
+    }
+
+    #[test]
+    fn loads_v2_saves() {
+        let mut world_save = WorldSave::open(fixture("v2")).unwrap();
+        assert_eq!(world_save.metadata.preset.noise, NoiseBackend::Value);
+        assert_eq!(world_save.metadata.player_pos, Vec2::new(16.0, -8.0));
+
+        let chunk = world_save.load_chunk(IVec2::new(-1, 0)).unwrap();
+        assert_eq!(chunk.tiles[5], TileKind::Snow);
+        assert!(world_save.unsaved_regions.is_empty());
+    }
+
+    #[test]
+    fn current_files_round_trip() {
+        let metadata = WorldSave::open(fixture("v2")).unwrap().metadata.clone();
+        assert_eq!(
+            read_metadata(&write(&metadata, true).unwrap()).unwrap(),
+            metadata
+        );
+    }
+
+    #[test]
+    fn reads_the_version_from_the_header() {
+        for pretty in [false, true] {
+            let text = write(&IVec2::new(3, -4), pretty).unwrap();
+            assert_eq!(version_of(&text).unwrap(), SAVE_FORMAT_VERSION);
+            assert_eq!(read_current::<IVec2>(&text).unwrap(), IVec2::new(3, -4));
+        }
+        let v1 = std::fs::read_to_string(fixture("v1").join("world.ron")).unwrap();
+        assert_eq!(version_of(&v1).unwrap(), 1);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        assert!(read_metadata("(version: 99, data: ())").is_err());
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::chunk::{ChunkManager, ChunkMarker};
+use crate::tiles::{world_pos_to_tile, world_tile_to_chunk};
+
+/// The world-space position under the mouse cursor.
+///
+/// The pixel camera letterboxes the world into an integer-scaled viewport, so positions over the
+/// black bars around it count as outside the world.
+#[derive(SystemParam)]
+pub struct CursorWorldPos<'w, 's> {
+    window: Option<Single<'w, 's, &'static Window, With<PrimaryWindow>>>,
+    camera: Option<Single<'w, 's, (&'static Camera, &'static GlobalTransform), With<Camera2d>>>,
+}
+
+impl CursorWorldPos<'_, '_> {
+    pub fn get(&self) -> Option<Vec2> {
+        let (camera, camera_transform) = **self.camera.as_ref()?;
+        let cursor = self.window.as_ref()?.cursor_position()?;
+        if !camera.logical_viewport_rect()?.contains(cursor) {
+            return None;
+        }
+        camera.viewport_to_world_2d(camera_transform, cursor).ok()
+    }
+}
+
+/// A loaded tile found by [`TilePicking`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PickedTile {
+    pub world_tile: IVec2,
+    pub chunk_pos: IVec2,
+    pub tile_pos: TilePos,
+    /// The tile's entity, with a [`TileKind`](crate::tiles::TileKind).
+    pub entity: Entity,
+}
+
+/// Finds the tile under the cursor, or at any world position. It reads the [`ChunkManager`], so
+/// systems that also edit tiles through [`WorldTiles`](crate::tiles::WorldTiles) should use
+/// [`CursorWorldPos`] instead.
+#[derive(SystemParam)]
+pub struct TilePicking<'w, 's> {
+    pub cursor: CursorWorldPos<'w, 's>,
+    chunk_manager: Res<'w, ChunkManager>,
+    storages: Query<'w, 's, &'static TileStorage, With<ChunkMarker>>,
+}
+
+impl TilePicking<'_, '_> {
+    /// The tile under the cursor, or `None` if the cursor is outside the world view or over a
+    /// chunk that isn't loaded.
+    pub fn under_cursor(&self) -> Option<PickedTile> {
+        self.at(self.cursor.get()?)
+    }
+
+    /// The loaded tile containing `world_pos`.
+    pub fn at(&self, world_pos: Vec2) -> Option<PickedTile> {
+        let world_tile = world_pos_to_tile(world_pos);
+        let (chunk_pos, tile_pos) = world_tile_to_chunk(world_tile);
+        let chunk_entity = self.chunk_manager.spawned_chunks.get(&chunk_pos)?;
+        let entity = self.storages.get(*chunk_entity).ok()?.get(&tile_pos)?;
+        Some(PickedTile {
+            world_tile,
+            chunk_pos,
+            tile_pos,
+            entity,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::chunk::CHUNK_SIZE;
+
+    #[test]
+    fn picks_loaded_tiles_by_world_position() {
+        let mut world = World::new();
+        world.init_resource::<ChunkManager>();
+        let chunk = world.spawn(ChunkMarker).id();
+        let tile = world.spawn_empty().id();
+        let mut storage = TileStorage::empty(CHUNK_SIZE.into());
+        storage.set(&TilePos::new(9, 0), tile);
+        world.entity_mut(chunk).insert(storage);
+        world
+            .resource_mut::<ChunkManager>()
+            .spawned_chunks
+            .insert(IVec2::new(-1, 2), chunk);
+
+        world
+            .run_system_once(move |picking: TilePicking| {
+                // No window or camera, so nothing is under the cursor.
+                assert_eq!(picking.under_cursor(), None);
+
+                // Tile (-1, 20) is the bottom-right tile of chunk (-1, 2).
+                let picked = picking.at(Vec2::new(-20.0, 322.0)).unwrap();
+                assert_eq!(picked.world_tile, IVec2::new(-1, 20));
+                assert_eq!(picked.chunk_pos, IVec2::new(-1, 2));
+                assert_eq!(picked.tile_pos, TilePos::new(9, 0));
+                assert_eq!(picked.entity, tile);
+
+                assert_eq!(picking.at(Vec2::new(0.0, 322.0)), None);
+            })
+            .unwrap();
+    }
+}
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystems;
+use bevy_modern_pixel_camera::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+/// Rounds the rendered positions of the camera and sprites so textures don't shimmer as they
+/// move. Only the [`GlobalTransform`] is rounded, so movement itself keeps full precision.
+pub struct PixelSnapPlugin;
+
+impl Plugin for PixelSnapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            snap_to_pixel_grid.after(TransformSystems::Propagate),
+        );
+    }
+}
+
+/// How moving sprites and the camera are aligned to the pixel grid.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PixelSnapping {
+    /// Everything lands on whole virtual pixels. Crisp, but slow movement steps visibly.
+    #[default]
+    Strict,
+    /// Positions are rounded to whole screen pixels instead, so the camera and sprites can sit
+    /// between virtual pixels and scroll smoothly at any zoom.
+    Smooth,
+}
+
+/// How far apart, in world units, rendered positions are snapped to with `snapping` through the
+/// camera's `projection`.
+pub fn pixel_grid(snapping: PixelSnapping, projection: &OrthographicProjection) -> f32 {
+    // One world unit is one virtual pixel, drawn `1 / scale` screen pixels wide.
+    match snapping {
+        PixelSnapping::Strict => 1.0,
+        PixelSnapping::Smooth => projection.scale,
+    }
+}
+
+/// Entities whose rendered position is snapped: sprites and the pixel camera.
+type Snapped = Or<(With<Sprite>, With<PixelZoom>)>;
+
+fn snap_to_pixel_grid(
+    settings: Res<Settings>,
+    camera: Single<&Projection, With<PixelZoom>>,
+    mut transforms: Query<&mut GlobalTransform, Snapped>,
+) {
+    let Projection::Orthographic(projection) = *camera else {
+        return;
+    };
+    let grid = pixel_grid(settings.pixel_snapping, projection);
+
+    for mut transform in &mut transforms {
+        let mut affine = transform.affine();
+        let snapped = (affine.translation.xy() / grid).round() * grid;
+        affine.translation.x = snapped.x;
+        affine.translation.y = snapped.y;
+        *transform = GlobalTransform::from(affine);
+    }
+}
@@ -0,0 +1,379 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy::transform::TransformSystems;
+use bevy_enhanced_input::prelude::*;
+
+use crate::collision::{TileCollider, resolve_movement};
+use crate::dialogue::talking;
+use crate::health::{Health, LifeState, PLAYER_MAX_HEALTH, WORLD_SPAWN};
+use crate::hotbar::{CycleHotbar, SelectHotbarSlot, slot_key_bindings};
+use crate::inventory::{Inventory, PLAYER_INVENTORY_SLOTS};
+use crate::lighting::{LightSource, PLAYER_LIGHT};
+use crate::persistence::WorldSave;
+use crate::player_animation::PlayerAnimation;
+use crate::settings::{Keybinds, Settings};
+use crate::spatial::Spatial;
+use crate::stamina::{SPRINT_MULTIPLIER, Stamina};
+use crate::status_effects::StatusEffects;
+use crate::tiles::{Surface, SurfaceProfile, WorldTiles, world_pos_to_tile};
+use crate::y_sort::YSort;
+use crate::{GameAssets, GameState};
+
+/// Walking speed on grass, in world units per second. Other tiles scale it by their
+/// [`TileProperties::speed`](crate::tiles::TileProperties::speed) and sprinting by
+/// [`SPRINT_MULTIPLIER`].
+pub const PLAYER_SPEED: f32 = 120.0;
+
+/// Spawns the player where the world was last saved, moves it with the movement keys, blocked by
+/// solid tiles, sprints while the sprint key is held and there is [`Stamina`] left, and has the
+/// camera follow it. How quickly the player speeds up and turns depends on the
+/// [`SurfaceProfile`] of the tile underfoot. Chunks stream in around the player.
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_input_context::<Player>()
+            .add_systems(OnEnter(GameState::Playing), spawn_player)
+            .add_systems(
+                Update,
+                (
+                    move_player
+                        .run_if(in_state(LifeState::Alive))
+                        .run_if(not(talking)),
+                    rebind_player
+                        .run_if(in_state(GameState::Playing))
+                        .run_if(resource_changed::<Settings>),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                follow_player
+                    .before(TransformSystems::Propagate)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Component)]
+#[require(YSort)]
+pub struct Player;
+
+/// Current movement, in world units per second.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Velocity(pub Vec2);
+
+/// The surface of the tile under the player.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Footing(pub Surface);
+
+/// Makes a camera trail the player. The player can move within the deadzone without the camera
+/// scrolling, which keeps the pixel art from shimmering during small movements.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct CameraFollow {
+    /// Half the size of the deadzone rectangle around the camera center, in world units.
+    pub deadzone: Vec2,
+    /// How quickly the camera catches up, as an exponential decay rate per second.
+    pub speed: f32,
+    /// Progress through the bob cycle, in radians.
+    bob_phase: f32,
+    /// The bob offset applied to the camera last frame.
+    bob_offset: Vec2,
+}
+
+impl Default for CameraFollow {
+    fn default() -> Self {
+        Self {
+            deadzone: Vec2::new(24.0, 16.0),
+            speed: 8.0,
+            bob_phase: 0.0,
+            bob_offset: Vec2::ZERO,
+        }
+    }
+}
+
+/// Distance walked per camera bob, in world units.
+const BOB_STRIDE: f32 = 24.0;
+
+/// Where the camera has to move to so `target` is back inside the deadzone around it.
+pub fn follow_target(camera: Vec2, target: Vec2, deadzone: Vec2) -> Vec2 {
+    target - (target - camera).clamp(-deadzone, deadzone)
+}
+
+/// Moves `velocity` towards `target` for `secs` seconds on a surface with `profile`. The speed
+/// changes by at most `profile.acceleration * max_speed` per second and the direction turns by
+/// at most `profile.turn_rate`, so slippery surfaces keep sliding the old way for a while.
+pub fn steer(
+    velocity: Vec2,
+    target: Vec2,
+    max_speed: f32,
+    profile: &SurfaceProfile,
+    secs: f32,
+) -> Vec2 {
+    let direction = match (velocity.try_normalize(), target.try_normalize()) {
+        (Some(current), Some(wanted)) => {
+            let max_turn = profile.turn_rate * secs;
+            Vec2::from_angle(current.angle_to(wanted).clamp(-max_turn, max_turn)).rotate(current)
+        }
+        (None, Some(wanted)) => wanted,
+        (current, None) => current.unwrap_or(Vec2::ZERO),
+    };
+    let speed = velocity.length();
+    let max_change = profile.acceleration * max_speed * secs;
+    direction * (speed + (target.length() - speed).clamp(-max_change, max_change))
+}
+
+#[derive(InputAction)]
+#[action_output(Vec2)]
+pub struct PlayerMovement;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct PlayerSprint;
+
+/// Talks to whatever is next to the player.
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct Interact;
+
+/// Swings at whatever is in front of the player.
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct Attack;
+
+fn spawn_player(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    world_save: Option<Res<WorldSave>>,
+    settings: Res<Settings>,
+) {
+    let (pos, health) = match world_save.as_deref().map(|world_save| &world_save.metadata) {
+        // A player who quit from the death screen comes back as if they had respawned.
+        Some(metadata) if metadata.player_health.is_none_or(|health| health > 0.0) => (
+            metadata.player_pos,
+            metadata.player_health.unwrap_or(PLAYER_MAX_HEALTH),
+        ),
+        _ => (WORLD_SPAWN, PLAYER_MAX_HEALTH),
+    };
+    let inventory = world_save
+        .as_deref()
+        .and_then(|world_save| world_save.metadata.player_inventory.clone())
+        .unwrap_or_else(|| Inventory::new(PLAYER_INVENTORY_SLOTS));
+    let keybinds = &settings.keybinds;
+    commands.spawn((
+        Name::new("Player"),
+        Player,
+        DespawnOnExit(GameState::Playing),
+        Sprite::from_atlas_image(
+            game_assets.player.clone(),
+            TextureAtlas::from(game_assets.player_layout.clone()),
+        ),
+        PlayerAnimation::default(),
+        Transform::from_translation(pos.extend(1.0)),
+        TileCollider {
+            half_size: Vec2::new(5.0, 6.0),
+        },
+        Stamina::default(),
+        Health {
+            current: health,
+            ..Health::full(PLAYER_MAX_HEALTH)
+        },
+        inventory,
+        Velocity::default(),
+        Footing::default(),
+        Spatial,
+        LightSource {
+            level: PLAYER_LIGHT,
+        },
+        player_actions(keybinds),
+    ));
+}
+
+/// The player's controls, with the keys from `keybinds`.
+fn player_actions(keybinds: &Keybinds) -> impl Bundle {
+    actions!(Player[
+        (
+            Action::<PlayerMovement>::new(),
+            DeadZone::default(),
+            SmoothNudge::default(),
+            Bindings::spawn((
+                Cardinal::new(
+                    keybinds.move_up,
+                    keybinds.move_left,
+                    keybinds.move_down,
+                    keybinds.move_right,
+                ),
+                Cardinal::arrows(),
+                Axial::left_stick(),
+            )),
+        ),
+        (
+            Action::<PlayerSprint>::new(),
+            bindings![keybinds.sprint, keybinds.sprint_button],
+        ),
+        (
+            Action::<Interact>::new(),
+            bindings![keybinds.interact, keybinds.interact_button],
+        ),
+        (
+            Action::<Attack>::new(),
+            bindings![keybinds.attack, keybinds.attack_button],
+        ),
+        (
+            Action::<SelectHotbarSlot>::new(),
+            Bindings::spawn(slot_key_bindings()),
+        ),
+        (
+            Action::<CycleHotbar>::new(),
+            Bindings::spawn(Bidirectional::new(
+                keybinds.next_slot_button,
+                keybinds.previous_slot_button,
+            )),
+        ),
+    ])
+}
+
+/// Swaps the player's bindings for the ones in [`Settings::keybinds`] when they are changed.
+fn rebind_player(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    player: Single<Entity, With<Player>>,
+    mut bound: Local<Option<Keybinds>>,
+) {
+    if bound.as_ref() == Some(&settings.keybinds) {
+        return;
+    }
+    if bound.is_some() {
+        commands
+            .entity(*player)
+            .despawn_related::<Actions<Player>>()
+            .insert(player_actions(&settings.keybinds));
+    }
+    *bound = Some(settings.keybinds.clone());
+}
+
+fn move_player(
+    time: Res<Time>,
+    movement: Single<&Action<PlayerMovement>>,
+    sprint: Single<&Action<PlayerSprint>>,
+    player: Single<
+        (
+            &mut Transform,
+            &TileCollider,
+            &mut Stamina,
+            &mut Velocity,
+            &mut Footing,
+        ),
+        With<Player>,
+    >,
+    effects: Single<&StatusEffects, With<Player>>,
+    tiles: WorldTiles,
+) {
+    let (mut transform, collider, mut stamina, mut velocity, mut footing) = player.into_inner();
+    let secs = time.delta_secs();
+    if secs == 0.0 {
+        return;
+    }
+    let pos = transform.translation.xy();
+    let properties = tiles.properties(world_pos_to_tile(pos));
+    let surface = properties.map_or(Surface::Ground, |properties| properties.surface);
+    footing.set_if_neq(Footing(surface));
+
+    let input = ***movement;
+    let mut max_speed = PLAYER_SPEED
+        * properties.map_or(1.0, |properties| properties.speed)
+        * effects.speed_multiplier();
+    if input != Vec2::ZERO && ***sprint && stamina.drain(secs) {
+        max_speed *= SPRINT_MULTIPLIER;
+    }
+    velocity.0 = steer(
+        velocity.0,
+        input.clamp_length_max(1.0) * max_speed,
+        max_speed,
+        &surface.profile(),
+        secs,
+    );
+    if velocity.0 == Vec2::ZERO {
+        return;
+    }
+
+    // Tiles in chunks that haven't loaded yet block movement too.
+    let moved = resolve_movement(pos, velocity.0 * secs, collider.half_size, |world_tile| {
+        tiles
+            .properties(world_tile)
+            .is_none_or(|properties| properties.solid)
+    });
+    // Walls stop movement along the axis they block.
+    velocity.0 = (moved - pos) / secs;
+    transform.translation = moved.extend(transform.translation.z);
+}
+
+pub fn follow_player(
+    time: Res<Time>,
+    player: Single<(Ref<Player>, &Transform, &Velocity, &Footing)>,
+    mut cameras: Query<(&mut Transform, &mut CameraFollow), Without<Player>>,
+) {
+    let (player, player_transform, velocity, footing) = player.into_inner();
+    let player_pos = player_transform.translation.xy();
+    let speed = velocity.0.length();
+    for (mut transform, mut follow) in &mut cameras {
+        // Jump straight to a newly spawned player instead of panning across the world.
+        let mut camera_pos = if player.is_added() {
+            player_pos
+        } else {
+            transform.translation.xy() - follow.bob_offset
+        };
+        let target = follow_target(camera_pos, player_pos, follow.deadzone);
+        camera_pos.smooth_nudge(&target, follow.speed, time.delta_secs());
+
+        // One bob per stride, fading out as the player slows down.
+        follow.bob_phase = (follow.bob_phase + speed * time.delta_secs() / BOB_STRIDE * TAU) % TAU;
+        let strength = (speed / PLAYER_SPEED).min(1.0);
+        follow.bob_offset = Vec2::Y * footing.0.profile().bob * strength * follow.bob_phase.sin();
+        transform.translation = (camera_pos + follow.bob_offset).extend(transform.translation.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camera_only_moves_once_the_player_leaves_the_deadzone() {
+        let deadzone = Vec2::new(24.0, 16.0);
+        let camera = Vec2::new(100.0, 0.0);
+        assert_eq!(
+            follow_target(camera, Vec2::new(110.0, -16.0), deadzone),
+            camera
+        );
+        assert_eq!(
+            follow_target(camera, Vec2::new(130.0, -20.0), deadzone),
+            Vec2::new(106.0, -4.0)
+        );
+    }
+
+    #[test]
+    fn slippery_surfaces_turn_and_stop_slowly() {
+        let max_speed = PLAYER_SPEED;
+        let right = Vec2::X * max_speed;
+        let up = Vec2::Y * max_speed;
+        let ground = Surface::Ground.profile();
+        let snow = Surface::Snow.profile();
+
+        // Starting from rest heads straight for the input.
+        let start = steer(Vec2::ZERO, right, max_speed, &ground, 0.05);
+        assert_eq!(start, Vec2::X * max_speed * ground.acceleration * 0.05);
+
+        let on_ground = steer(right, up, max_speed, &ground, 0.05);
+        let on_snow = steer(right, up, max_speed, &snow, 0.05);
+        assert!(on_ground.angle_to(up).abs() < on_snow.angle_to(up).abs());
+        assert!((on_snow.length() - max_speed).abs() < 1e-3);
+
+        let stopping = steer(right, Vec2::ZERO, max_speed, &snow, 0.1);
+        assert!(stopping.x > 0.0 && stopping.x < max_speed);
+        assert_eq!(
+            steer(right, Vec2::ZERO, max_speed, &ground, 1.0),
+            Vec2::ZERO
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use bevy_enhanced_input::prelude::*;
+
+use crate::GameState;
+use crate::player::{Footing, PlayerMovement};
+use crate::sprite_animation::AnimatedSprite;
+use crate::tiles::Surface;
+
+/// Frames per row of the player sheet: the idle frames, then the walk frames, then the swim
+/// frames.
+pub const PLAYER_SHEET_COLUMNS: u32 = 8;
+
+/// Switches the player's [`AnimatedSprite`] between idle, walk and swim animations facing the
+/// direction it last moved in. The sheet in [`GameAssets`](crate::GameAssets) has one row per
+/// [`Facing`].
+pub struct PlayerAnimationPlugin;
+
+impl Plugin for PlayerAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, animate_player.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// The direction the player sprite faces, in the order of the sheet's rows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Facing {
+    #[default]
+    Down,
+    Up,
+    Left,
+    Right,
+}
+
+impl Facing {
+    /// The facing for a movement input, or `None` when standing still. Diagonals face along
+    /// the stronger axis, and horizontally when both are equal.
+    pub fn from_input(input: Vec2) -> Option<Self> {
+        if input == Vec2::ZERO {
+            None
+        } else if input.x.abs() >= input.y.abs() {
+            Some(if input.x < 0.0 {
+                Self::Left
+            } else {
+                Self::Right
+            })
+        } else {
+            Some(if input.y < 0.0 { Self::Down } else { Self::Up })
+        }
+    }
+
+    /// A unit vector pointing the way the sprite faces.
+    pub fn direction(self) -> Vec2 {
+        match self {
+            Self::Down => Vec2::NEG_Y,
+            Self::Up => Vec2::Y,
+            Self::Left => Vec2::NEG_X,
+            Self::Right => Vec2::X,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AnimationState {
+    #[default]
+    Idle,
+    Walk,
+    /// In water, whether moving or treading water.
+    Swim,
+}
+
+impl AnimationState {
+    fn first_column(self) -> usize {
+        match self {
+            Self::Idle => 0,
+            Self::Walk => 2,
+            Self::Swim => 6,
+        }
+    }
+
+    fn frame_count(self) -> usize {
+        match self {
+            Self::Idle | Self::Swim => 2,
+            Self::Walk => 4,
+        }
+    }
+
+    fn frames_per_second(self) -> f32 {
+        match self {
+            Self::Idle => 2.0,
+            Self::Walk => 8.0,
+            Self::Swim => 3.0,
+        }
+    }
+
+    /// The index in the player sheet of the first frame facing `facing`.
+    fn first_index(self, facing: Facing) -> usize {
+        facing as usize * PLAYER_SHEET_COLUMNS as usize + self.first_column()
+    }
+
+    fn animation(self, facing: Facing) -> AnimatedSprite {
+        AnimatedSprite::looping(
+            self.first_index(facing),
+            self.frame_count(),
+            self.frames_per_second(),
+        )
+    }
+}
+
+#[derive(Component, Clone, Debug, Default)]
+#[require(AnimatedSprite = AnimationState::Idle.animation(Facing::Down))]
+pub struct PlayerAnimation {
+    pub state: AnimationState,
+    pub facing: Facing,
+}
+
+impl PlayerAnimation {
+    /// Picks the animation for the current movement input and the surface underfoot. Changing
+    /// state restarts `sprite`, while turning keeps the cycle going.
+    pub fn update(&mut self, input: Vec2, surface: Surface, sprite: &mut AnimatedSprite) {
+        let facing = Facing::from_input(input);
+        let state = match (surface, facing) {
+            (Surface::Water, _) => AnimationState::Swim,
+            (_, Some(_)) => AnimationState::Walk,
+            (_, None) => AnimationState::Idle,
+        };
+        self.facing = facing.unwrap_or(self.facing);
+        if state != self.state {
+            self.state = state;
+            *sprite = state.animation(self.facing);
+        } else {
+            sprite.first = state.first_index(self.facing);
+        }
+    }
+}
+
+fn animate_player(
+    movement: Single<&Action<PlayerMovement>>,
+    mut players: Query<(&mut PlayerAnimation, &Footing, &mut AnimatedSprite)>,
+) {
+    for (mut animation, footing, mut sprite) in &mut players {
+        animation.update(***movement, footing.0, &mut sprite);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walking_faces_the_input_and_idling_keeps_the_facing() {
+        let mut animation = PlayerAnimation::default();
+        let mut sprite = AnimationState::Idle.animation(Facing::Down);
+        animation.update(Vec2::new(-0.7, 0.3), Surface::Ground, &mut sprite);
+        assert_eq!(animation.state, AnimationState::Walk);
+        assert_eq!(animation.facing, Facing::Left);
+        assert_eq!(sprite.index(), 2 * 8 + 2);
+
+        // Three frames in at 8 fps, and turning keeps the cycle going.
+        sprite.advance(0.4);
+        animation.update(Vec2::NEG_Y, Surface::Ground, &mut sprite);
+        assert_eq!(sprite.index(), 2 + 3);
+
+        animation.update(Vec2::ZERO, Surface::Ground, &mut sprite);
+        assert_eq!(animation.state, AnimationState::Idle);
+        assert_eq!(animation.facing, Facing::Down);
+        assert_eq!(sprite.index(), 0);
+        assert_eq!(Facing::from_input(Vec2::new(0.2, -0.9)), Some(Facing::Down));
+
+        animation.update(Vec2::Y, Surface::Water, &mut sprite);
+        assert_eq!(animation.state, AnimationState::Swim);
+        assert_eq!(sprite.index(), 8 + 6);
+    }
+}
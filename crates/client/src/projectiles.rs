@@ -0,0 +1,271 @@
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_enhanced_input::prelude::*;
+use serde::Deserialize;
+
+use crate::GameState;
+use crate::combat::{AttackReady, Hit, Hitbox, HitboxSystems, Lifetime};
+use crate::hotbar::Hotbar;
+use crate::inventory::{Inventory, ItemId, ItemRegistry};
+use crate::picking::CursorWorldPos;
+use crate::player::{Attack, Player, Velocity};
+use crate::player_animation::PlayerAnimation;
+use crate::tiles::{Surface, WorldTiles, world_pos_to_tile};
+
+/// Seconds a projectile lasts, whether it is flying or lying where it landed.
+pub const PROJECTILE_SECS: f32 = 2.0;
+
+/// Seconds the player has to wait between shots.
+pub const SHOT_COOLDOWN_SECS: f32 = 0.5;
+
+/// How hard projectiles knock back what they hit, in world units per second.
+const PROJECTILE_KNOCKBACK: f32 = 80.0;
+
+const PROJECTILE_HALF_SIZE: Vec2 = Vec2::splat(3.0);
+
+/// Shoots the [`RangedWeapon`] in the selected hotbar slot with the attack key, towards the
+/// cursor or, without one, the way the player faces. Each shot uses up one of the weapon's ammo
+/// items. Projectiles fly until they hit something with [`Health`](crate::health::Health) or
+/// land on a solid tile, over water and chunks that haven't loaded.
+pub struct ProjectilesPlugin;
+
+impl Plugin for ProjectilesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(shoot)
+            .add_observer(add_projectile_sprite)
+            .add_systems(
+                Update,
+                (
+                    move_projectiles.before(HitboxSystems),
+                    despawn_on_hit.after(HitboxSystems),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct ProjectileAssets {
+    /// Projectiles flying to the right, indexed by [`RangedWeapon::sprite`].
+    #[asset(path = "projectiles.png")]
+    pub sheet: Handle<Image>,
+    #[asset(texture_atlas_layout(tile_size_x = 8, tile_size_y = 8, columns = 2, rows = 1))]
+    pub layout: Handle<TextureAtlasLayout>,
+}
+
+/// What a ranged weapon shoots, see
+/// [`ItemDefinition::ranged`](crate::inventory::ItemDefinition::ranged).
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct RangedWeapon {
+    /// The item each shot uses up.
+    pub ammo: ItemId,
+    /// Index of the projectile's sprite in `projectiles.png`.
+    pub sprite: usize,
+    /// In world units per second.
+    pub speed: f32,
+    pub damage: f32,
+}
+
+/// The ranged weapon in the selected hotbar slot, if any.
+pub fn held_weapon<'a>(
+    hotbar: &Hotbar,
+    inventory: &Inventory,
+    registry: &'a ItemRegistry,
+) -> Option<&'a RangedWeapon> {
+    let stack = hotbar.selected_stack(inventory)?;
+    registry.get(&stack.item)?.ranged.as_ref()
+}
+
+/// An arrow, thrown rock or the like, flying with its [`Velocity`] and hurting what it hits with
+/// its [`Hitbox`].
+#[derive(Component, Clone, Copy, Debug)]
+#[require(Velocity, Lifetime = Lifetime::from_secs(PROJECTILE_SECS))]
+pub struct Projectile {
+    /// Index of the projectile's sprite in `projectiles.png`.
+    pub sprite: usize,
+}
+
+/// Spawns a projectile shot by `owner` from `pos`, flying with `velocity` and dealing `damage`.
+pub fn spawn_projectile(
+    commands: &mut Commands,
+    owner: Entity,
+    pos: Vec2,
+    velocity: Vec2,
+    sprite: usize,
+    damage: f32,
+) {
+    commands.spawn((
+        Name::new("Projectile"),
+        Projectile { sprite },
+        Hitbox::new(owner, damage, PROJECTILE_KNOCKBACK, PROJECTILE_HALF_SIZE),
+        Velocity(velocity),
+        Transform::from_translation(pos.extend(1.5))
+            .with_rotation(Quat::from_rotation_z(velocity.to_angle())),
+        DespawnOnExit(GameState::Playing),
+    ));
+}
+
+fn add_projectile_sprite(
+    add: On<Add, Projectile>,
+    mut commands: Commands,
+    assets: Res<ProjectileAssets>,
+    projectiles: Query<&Projectile>,
+) {
+    let Ok(projectile) = projectiles.get(add.entity) else {
+        return;
+    };
+    commands.entity(add.entity).insert(Sprite::from_atlas_image(
+        assets.sheet.clone(),
+        TextureAtlas {
+            layout: assets.layout.clone(),
+            index: projectile.sprite,
+        },
+    ));
+}
+
+fn shoot(
+    _: On<Start<Attack>>,
+    mut commands: Commands,
+    (time, mut ready_at): (Res<Time>, Local<f32>),
+    (hotbar, registry, ready): (Res<Hotbar>, Res<ItemRegistry>, AttackReady),
+    player: Single<(Entity, &Transform, &PlayerAnimation, &mut Inventory), With<Player>>,
+    cursor: CursorWorldPos,
+) {
+    let (player, transform, animation, mut inventory) = player.into_inner();
+    if !ready.get() || time.elapsed_secs() < *ready_at {
+        return;
+    }
+    let Some(weapon) = held_weapon(&hotbar, &inventory, &registry) else {
+        return;
+    };
+    if !inventory.consume(&weapon.ammo, 1) {
+        return;
+    }
+    *ready_at = time.elapsed_secs() + SHOT_COOLDOWN_SECS;
+    let pos = transform.translation.xy();
+    let direction = cursor
+        .get()
+        .and_then(|target| (target - pos).try_normalize())
+        .unwrap_or_else(|| animation.facing.direction());
+    spawn_projectile(
+        &mut commands,
+        player,
+        pos,
+        direction * weapon.speed,
+        weapon.sprite,
+        weapon.damage,
+    );
+}
+
+fn move_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut projectiles: Query<(Entity, &mut Velocity, &mut Transform), With<Projectile>>,
+    tiles: WorldTiles,
+) {
+    for (entity, mut velocity, mut transform) in &mut projectiles {
+        if velocity.0 == Vec2::ZERO {
+            continue;
+        }
+        let pos = transform.translation.xy() + velocity.0 * time.delta_secs();
+        // Water is only solid to walkers.
+        let landed = tiles
+            .properties(world_pos_to_tile(pos))
+            .is_some_and(|properties| properties.solid && properties.surface != Surface::Water);
+        if landed {
+            velocity.0 = Vec2::ZERO;
+            commands.entity(entity).remove::<Hitbox>();
+        } else {
+            transform.translation = pos.extend(transform.translation.z);
+        }
+    }
+}
+
+fn despawn_on_hit(
+    mut commands: Commands,
+    mut hits: MessageReader<Hit>,
+    projectiles: Query<(), With<Projectile>>,
+) {
+    for hit in hits.read() {
+        if projectiles.contains(hit.hitbox) {
+            commands.entity(hit.hitbox).try_despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::state::app::StatesPlugin;
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::chunk::ChunkManager;
+    use crate::collision::TileCollider;
+    use crate::combat::CombatPlugin;
+    use crate::health::{Damage, Health};
+    use crate::spatial::{Spatial, SpatialPlugin};
+
+    #[test]
+    fn projectiles_fly_until_they_hit_something() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin, SpatialPlugin, CombatPlugin))
+            .add_message::<Damage>()
+            .init_resource::<ChunkManager>()
+            .insert_state(GameState::Playing)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                50,
+            )))
+            .add_systems(
+                Update,
+                (
+                    move_projectiles.before(HitboxSystems),
+                    despawn_on_hit.after(HitboxSystems),
+                ),
+            );
+        let registry: ItemRegistry = ron::from_str(include_str!("../assets/items.ron")).unwrap();
+        let bow = registry.get(&ItemId::from("bow")).unwrap();
+        let bow = bow.ranged.clone().unwrap();
+
+        let owner = app.world_mut().spawn(Transform::default()).id();
+        let target = app
+            .world_mut()
+            .spawn((
+                Spatial,
+                Health::full(3.0),
+                TileCollider {
+                    half_size: Vec2::splat(4.0),
+                },
+                Transform::from_xyz(60.0, 0.0, 0.0),
+            ))
+            .id();
+        let mut commands = app.world_mut().commands();
+        spawn_projectile(
+            &mut commands,
+            owner,
+            Vec2::ZERO,
+            Vec2::X * bow.speed,
+            bow.sprite,
+            bow.damage,
+        );
+        app.world_mut().flush();
+
+        let mut hits = Vec::new();
+        for _ in 0..20 {
+            app.update();
+            let messages = app.world().resource::<Messages<Damage>>();
+            hits.extend(messages.iter_current_update_messages().copied());
+        }
+        assert_eq!(
+            hits,
+            vec![Damage {
+                target,
+                amount: bow.damage,
+                knockback: Vec2::X * PROJECTILE_KNOCKBACK,
+            }]
+        );
+        let mut projectiles = app.world_mut().query::<&Projectile>();
+        assert_eq!(projectiles.iter(app.world()).count(), 0);
+    }
+}
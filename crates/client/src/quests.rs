@@ -0,0 +1,477 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+use crate::chunk::TILE_SIZE;
+use crate::dialogue::{Conversation, DialogueEvent, Talker};
+use crate::inventory::{Inventory, ItemId, ItemRegistry, ItemStack};
+use crate::item_drops::spawn_item_drop;
+use crate::localization::Localization;
+use crate::notifications::Notifications;
+use crate::persistence::WorldSave;
+use crate::player::Player;
+use crate::ron_asset::RonAssetLoader;
+use crate::tiles::tile_to_world_pos;
+use crate::waypoints::Waypoints;
+
+/// How high quest markers float above the talkers they point at, in world units.
+const MARKER_HEIGHT: f32 = 14.0;
+
+/// Loads the quests from `quests.ron` into the [`QuestBook`]. Quests start when a
+/// [`DialogueEvent`] named by their [`Quest::starts_on`] is written, and their objectives are
+/// worked through in order. The [`QuestLog`] tracks progress and is saved with the world. Active
+/// objectives are listed on screen, and markers float over the places and talkers they lead to.
+/// The places are marked on the [`Waypoints`] too.
+pub struct QuestsPlugin;
+
+impl Plugin for QuestsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<QuestBook>()
+            .register_asset_loader(RonAssetLoader::<QuestBook>::new(&["quests.ron"]))
+            .init_resource::<QuestBook>()
+            .init_resource::<QuestLog>()
+            .add_systems(OnEnter(GameState::Playing), (load_quest_log, spawn_tracker))
+            .add_systems(
+                Update,
+                (
+                    update_quest_book.run_if(on_message::<AssetEvent<QuestBook>>),
+                    start_quests.run_if(on_message::<DialogueEvent>),
+                    track_quests,
+                    update_tracker,
+                    place_markers,
+                    mark_quest_waypoints,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct QuestAssets {
+    #[asset(path = "quests.ron")]
+    pub quests: Handle<QuestBook>,
+    #[asset(path = "ui/quest_marker.png")]
+    pub marker: Handle<Image>,
+}
+
+/// Something a quest asks of the player.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub enum Objective {
+    /// Carry `count` of `item`. The items aren't taken away.
+    Collect { item: ItemId, count: u32 },
+    /// Come within `radius` tiles of the world tile `tile`.
+    Reach { tile: IVec2, radius: f32 },
+    /// Start talking to a [`Talker`] with the dialogue `dialogue`, called `name` on screen.
+    TalkTo { dialogue: String, name: String },
+}
+
+/// What the player is up to, for checking [`Objective`]s against.
+#[derive(Clone, Copy, Debug)]
+pub struct Progress<'a> {
+    pub pos: Vec2,
+    pub inventory: &'a Inventory,
+    /// The dialogue of the talker the player just started talking to.
+    pub talking_to: Option<&'a str>,
+}
+
+impl Objective {
+    pub fn is_met(&self, progress: Progress) -> bool {
+        match self {
+            Objective::Collect { item, count } => progress.inventory.count(item) >= *count,
+            Objective::Reach { tile, radius } => {
+                progress.pos.distance(tile_to_world_pos(*tile)) <= radius * TILE_SIZE.x
+            }
+            Objective::TalkTo { dialogue, .. } => progress.talking_to == Some(dialogue.as_str()),
+        }
+    }
+
+    /// What the tracker shows for the objective.
+    pub fn describe(
+        &self,
+        inventory: &Inventory,
+        registry: &ItemRegistry,
+        localization: &Localization,
+    ) -> String {
+        match self {
+            Objective::Collect { item, count } => {
+                let name = localization.item_name(registry, item);
+                let carried = inventory.count(item).min(*count);
+                localization.format(
+                    "objective-collect",
+                    &[("item", &name), ("carried", &carried), ("count", count)],
+                )
+            }
+            Objective::Reach { .. } => localization.get("objective-reach").to_string(),
+            Objective::TalkTo { dialogue, name } => {
+                let name = localization.get_or(&format!("talker-{dialogue}"), name);
+                localization.format("objective-talk-to", &[("name", &name)])
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Quest {
+    pub title: String,
+    /// The [`DialogueEvent`] that starts the quest.
+    #[serde(default)]
+    pub starts_on: Option<String>,
+    pub objectives: Vec<Objective>,
+    /// Given to the player when the quest is completed.
+    #[serde(default)]
+    pub rewards: Vec<ItemStack>,
+}
+
+impl Quest {
+    /// Item ids the quest uses that aren't in `registry`.
+    pub fn unknown_items<'a>(
+        &'a self,
+        registry: &'a ItemRegistry,
+    ) -> impl Iterator<Item = &'a str> + 'a {
+        self.objectives
+            .iter()
+            .filter_map(|objective| match objective {
+                Objective::Collect { item, .. } => Some(item),
+                _ => None,
+            })
+            .chain(self.rewards.iter().map(|stack| &stack.item))
+            .filter(|item| registry.get(item).is_none())
+            .map(|item| item.0.as_str())
+    }
+}
+
+/// Every quest by name, as defined in `quests.ron`.
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug, Default)]
+pub struct QuestBook {
+    pub quests: HashMap<String, Quest>,
+}
+
+/// A started quest and the objective it is at.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ActiveQuest {
+    pub quest: String,
+    pub objective: usize,
+}
+
+/// The player's quests, saved with the world.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct QuestLog {
+    pub active: Vec<ActiveQuest>,
+    pub completed: Vec<String>,
+}
+
+impl QuestLog {
+    /// Starts `quest`, unless it was already started.
+    pub fn start(&mut self, quest: &str) -> bool {
+        let started = self.completed.iter().any(|completed| completed == quest)
+            || self.active.iter().any(|active| active.quest == quest);
+        if !started {
+            self.active.push(ActiveQuest {
+                quest: quest.to_string(),
+                objective: 0,
+            });
+        }
+        !started
+    }
+
+    /// The objectives the active quests are at, with the name of their quest.
+    pub fn objectives<'a>(
+        &'a self,
+        quest_book: &'a QuestBook,
+    ) -> impl Iterator<Item = (&'a str, &'a Objective)> + 'a {
+        self.active.iter().filter_map(|active| {
+            let quest = quest_book.quests.get(&active.quest)?;
+            Some((
+                active.quest.as_str(),
+                quest.objectives.get(active.objective)?,
+            ))
+        })
+    }
+
+    /// Moves the active quests past every objective met by `progress` and returns the quests
+    /// that got completed.
+    pub fn advance(&mut self, quest_book: &QuestBook, progress: Progress) -> Vec<String> {
+        let mut completed = Vec::new();
+        self.active.retain_mut(|active| {
+            let Some(quest) = quest_book.quests.get(&active.quest) else {
+                return true;
+            };
+            while quest
+                .objectives
+                .get(active.objective)
+                .is_some_and(|objective| objective.is_met(progress))
+            {
+                active.objective += 1;
+            }
+            let done = active.objective >= quest.objectives.len();
+            if done {
+                completed.push(active.quest.clone());
+            }
+            !done
+        });
+        self.completed.extend(completed.iter().cloned());
+        completed
+    }
+}
+
+fn update_quest_book(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<QuestBook>>,
+    quest_books: Res<Assets<QuestBook>>,
+    registry: Res<ItemRegistry>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event
+            && let Some(quest_book) = quest_books.get(*id)
+        {
+            for (name, quest) in &quest_book.quests {
+                for item in quest.unknown_items(&registry) {
+                    warn!("Quest {name} uses unknown item {item}");
+                }
+            }
+            info!("Loaded {} quests", quest_book.quests.len());
+            commands.insert_resource(quest_book.clone());
+        }
+    }
+}
+
+fn load_quest_log(mut commands: Commands, world_save: Option<Res<WorldSave>>) {
+    let quest_log = world_save
+        .and_then(|world_save| world_save.metadata.quest_log.clone())
+        .unwrap_or_default();
+    commands.insert_resource(quest_log);
+}
+
+fn start_quests(
+    mut events: MessageReader<DialogueEvent>,
+    quest_book: Res<QuestBook>,
+    mut quest_log: ResMut<QuestLog>,
+) {
+    for DialogueEvent { event, .. } in events.read() {
+        for (name, quest) in &quest_book.quests {
+            if quest.starts_on.as_ref() == Some(event) && quest_log.start(name) {
+                info!("Started quest {name}");
+            }
+        }
+    }
+}
+
+fn track_quests(
+    mut commands: Commands,
+    (quest_book, registry, localization): (Res<QuestBook>, Res<ItemRegistry>, Res<Localization>),
+    mut quest_log: ResMut<QuestLog>,
+    conversation: Option<Res<Conversation>>,
+    player: Single<(&Transform, &mut Inventory), With<Player>>,
+    talkers: Query<&Talker>,
+    mut notifications: ResMut<Notifications>,
+) {
+    let (transform, mut inventory) = player.into_inner();
+    let pos = transform.translation.xy();
+    let talking_to = conversation
+        .filter(|conversation| conversation.is_added())
+        .and_then(|conversation| talkers.get(conversation.speaker).ok())
+        .map(|talker| talker.dialogue.as_str());
+    let progress = Progress {
+        pos,
+        inventory: &inventory,
+        talking_to,
+    };
+    // Only look at the log mutably once something changes, so the tracker keeps still.
+    if !quest_log
+        .objectives(&quest_book)
+        .any(|(_, objective)| objective.is_met(progress))
+    {
+        return;
+    }
+    for name in quest_log.advance(&quest_book, progress) {
+        info!("Completed quest {name}");
+        let title = localization.get_or(&format!("quest-{name}"), &quest_book.quests[&name].title);
+        notifications
+            .push(localization.format("notification-quest-complete", &[("quest", &title)]));
+        for reward in &quest_book.quests[&name].rewards {
+            let left = inventory.add(&reward.item, reward.count, &registry);
+            if left > 0 {
+                let stack = ItemStack {
+                    item: reward.item.clone(),
+                    count: left,
+                };
+                spawn_item_drop(&mut commands, &registry, stack, pos);
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct QuestTracker;
+
+#[derive(Component)]
+struct QuestMarker;
+
+fn spawn_tracker(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Quest tracker"),
+        QuestTracker,
+        DespawnOnExit(GameState::Playing),
+        Text::default(),
+        TextFont::from_font_size(14.0),
+        TextShadow::default(),
+        TextLayout::new_with_justify(Justify::Right),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            right: Val::Px(12.0),
+            ..default()
+        },
+        Pickable::IGNORE,
+    ));
+}
+
+fn update_tracker(
+    (quest_book, registry): (Res<QuestBook>, Res<ItemRegistry>),
+    localization: Res<Localization>,
+    quest_log: Res<QuestLog>,
+    inventory: Single<Ref<Inventory>, With<Player>>,
+    mut tracker: Single<&mut Text, With<QuestTracker>>,
+) {
+    if !quest_log.is_changed()
+        && !inventory.is_changed()
+        && !quest_book.is_changed()
+        && !localization.is_changed()
+    {
+        return;
+    }
+    let lines: Vec<String> = quest_log
+        .objectives(&quest_book)
+        .map(|(name, objective)| {
+            let title =
+                localization.get_or(&format!("quest-{name}"), &quest_book.quests[name].title);
+            let objective = objective.describe(&inventory, &registry, &localization);
+            format!("{title}\n{objective}")
+        })
+        .collect();
+    tracker.0 = lines.join("\n\n");
+}
+
+/// Floats a [`QuestMarker`] over every place and talker an active objective leads to.
+fn place_markers(
+    mut commands: Commands,
+    time: Res<Time>,
+    (quest_book, quest_log, quest_assets): (Res<QuestBook>, Res<QuestLog>, Res<QuestAssets>),
+    talkers: Query<(&Talker, &Transform), Without<QuestMarker>>,
+    mut markers: Query<(Entity, &mut Transform), With<QuestMarker>>,
+) {
+    let mut targets = Vec::new();
+    for (_, objective) in quest_log.objectives(&quest_book) {
+        match objective {
+            Objective::Reach { tile, .. } => targets.push(tile_to_world_pos(*tile)),
+            Objective::TalkTo { dialogue, .. } => targets.extend(
+                talkers
+                    .iter()
+                    .filter(|(talker, _)| talker.dialogue == *dialogue)
+                    .map(|(_, transform)| transform.translation.xy() + Vec2::Y * MARKER_HEIGHT),
+            ),
+            Objective::Collect { .. } => {}
+        }
+    }
+
+    let bob = (time.elapsed_secs() * 4.0).sin().round();
+    let mut markers = markers.iter_mut();
+    for target in targets {
+        let translation = (target + Vec2::Y * bob).extend(5.0);
+        match markers.next() {
+            Some((_, mut transform)) => transform.translation = translation,
+            None => {
+                commands.spawn((
+                    Name::new("Quest marker"),
+                    QuestMarker,
+                    DespawnOnExit(GameState::Playing),
+                    Sprite::from_image(quest_assets.marker.clone()),
+                    Transform::from_translation(translation),
+                ));
+            }
+        }
+    }
+    for (entity, _) in markers {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Keeps a waypoint on the place each quest's [`Objective::Reach`] leads to while the quest is at
+/// it.
+fn mark_quest_waypoints(
+    quest_book: Res<QuestBook>,
+    localization: Res<Localization>,
+    quest_log: Res<QuestLog>,
+    mut waypoints: ResMut<Waypoints>,
+) {
+    if !quest_log.is_changed() && !quest_book.is_changed() && !localization.is_changed() {
+        return;
+    }
+    for name in quest_book.quests.keys() {
+        waypoints.unmark(&format!("quest {name}"));
+    }
+    for (name, objective) in quest_log.objectives(&quest_book) {
+        if let Objective::Reach { tile, .. } = objective {
+            let title =
+                localization.get_or(&format!("quest-{name}"), &quest_book.quests[name].title);
+            waypoints.mark(&format!("quest {name}"), title, *tile);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress<'a>(inventory: &'a Inventory, talking_to: Option<&'a str>) -> Progress<'a> {
+        Progress {
+            pos: Vec2::ZERO,
+            inventory,
+            talking_to,
+        }
+    }
+
+    #[test]
+    fn quests_work_through_their_objectives_in_order() {
+        let registry: ItemRegistry = ron::from_str(include_str!("../assets/items.ron")).unwrap();
+        let quest_book: QuestBook = ron::from_str(include_str!("../assets/quests.ron")).unwrap();
+        for (name, quest) in &quest_book.quests {
+            assert_eq!(quest.unknown_items(&registry).count(), 0, "{name}");
+        }
+
+        let mut quest_log = QuestLog::default();
+        assert!(quest_log.start("first_harvest"));
+        assert!(!quest_log.start("first_harvest"));
+        let wheat = ItemId::from("wheat");
+        let mut inventory = Inventory::new(4);
+        inventory.add(&wheat, 2, &registry);
+        // The signpost doesn't count before the wheat is collected.
+        let completed = quest_log.advance(&quest_book, progress(&inventory, Some("signpost")));
+        assert!(completed.is_empty());
+        assert_eq!(quest_log.active[0].objective, 0);
+        assert_eq!(
+            quest_book.quests["first_harvest"].objectives[0].describe(
+                &inventory,
+                &registry,
+                &Localization::default()
+            ),
+            "Collect Wheat: 2/3"
+        );
+
+        inventory.add(&wheat, 1, &registry);
+        assert!(
+            quest_log
+                .advance(&quest_book, progress(&inventory, None))
+                .is_empty()
+        );
+        assert_eq!(quest_log.active[0].objective, 1);
+        let completed = quest_log.advance(&quest_book, progress(&inventory, Some("signpost")));
+        assert_eq!(completed, vec!["first_harvest".to_string()]);
+        assert!(quest_log.active.is_empty());
+        // Completed quests can't be started again.
+        assert!(!quest_log.start("first_harvest"));
+    }
+}
@@ -0,0 +1,258 @@
+use bevy::ecs::entity::EntityHashMap;
+use bevy::prelude::*;
+use bevy::reflect::FromType;
+use bevy::scene::serde::SceneDeserializer;
+use bevy::scene::{DynamicScene, DynamicSceneBuilder, SceneFilter};
+use serde::de::DeserializeSeed;
+
+use crate::GameState;
+use crate::chunk::{ChunkManager, ChunkMarker};
+use crate::persistence::WorldSave;
+use crate::tiles::{world_pos_to_tile, world_tile_to_chunk};
+
+/// Saves entities marked with [`Persist`] with the chunk they are in, see
+/// [`unload_chunk_entities`] and [`restore_chunk_entities`].
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        // Saved entities need their position to be restored where they were.
+        app.register_type_data::<Transform, ReflectSaveableComponent>()
+            .register_type_data::<Name, ReflectSaveableComponent>();
+    }
+}
+
+/// Marks an entity to be saved with the chunk it is in, together with its saveable components.
+/// Anything else about it, like its sprite, has to be added back when it is restored, for
+/// example by an observer for one of its saved components.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component, SaveableComponent)]
+#[require(DespawnOnExit<GameState> = DespawnOnExit(GameState::Playing))]
+pub struct Persist;
+
+/// Type data marking a component as part of an entity's save data. Components opt in with
+/// `#[reflect(Component, SaveableComponent)]`; foreign types can be registered with
+/// `App::register_type_data`.
+#[derive(Clone)]
+pub struct ReflectSaveableComponent;
+
+impl<T: Component> FromType<T> for ReflectSaveableComponent {
+    fn from_type() -> Self {
+        ReflectSaveableComponent
+    }
+}
+
+fn saveable_filter(registry: &AppTypeRegistry) -> SceneFilter {
+    registry
+        .read()
+        .iter_with_data::<ReflectSaveableComponent>()
+        .fold(SceneFilter::deny_all(), |filter, (registration, _)| {
+            filter.allow_by_id(registration.type_id())
+        })
+}
+
+/// The [`Persist`] entities positioned inside the chunk at `chunk_pos`.
+fn chunk_entities(world: &mut World, chunk_pos: IVec2) -> Vec<Entity> {
+    let mut query =
+        world.query_filtered::<(Entity, &Transform), (With<Persist>, Without<ChunkMarker>)>();
+    query
+        .iter(world)
+        .filter(|(_, transform)| {
+            world_tile_to_chunk(world_pos_to_tile(transform.translation.xy())).0 == chunk_pos
+        })
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+fn serialize_entities(world: &World, entities: Vec<Entity>) -> Result<String> {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let scene = DynamicSceneBuilder::from_world(world)
+        .with_component_filter(saveable_filter(&registry))
+        .extract_entities(entities.into_iter())
+        .build();
+    Ok(scene.serialize(&registry.read())?)
+}
+
+/// Stores the [`Persist`] entities of the unloading chunk at `chunk_pos` in the [`WorldSave`]
+/// and despawns them.
+pub fn unload_chunk_entities(world: &mut World, chunk_pos: IVec2) {
+    let entities = chunk_entities(world, chunk_pos);
+    let data = match entities.is_empty() {
+        true => None,
+        false => match serialize_entities(world, entities.clone()) {
+            Ok(data) => Some(data),
+            Err(err) => {
+                error!("Failed to save the entities of chunk {chunk_pos}: {err}");
+                return;
+            }
+        },
+    };
+    world
+        .resource_mut::<WorldSave>()
+        .store_chunk_entities(chunk_pos, data);
+    for entity in entities {
+        world.despawn(entity);
+    }
+}
+
+/// Stores the [`Persist`] entities of every loaded chunk in the [`WorldSave`], leaving them in
+/// the world.
+pub fn store_loaded_chunk_entities(world: &mut World) {
+    let chunks: Vec<IVec2> = world
+        .resource::<ChunkManager>()
+        .spawned_chunks
+        .keys()
+        .copied()
+        .collect();
+    let mut stored = Vec::new();
+    for chunk_pos in chunks {
+        let entities = chunk_entities(world, chunk_pos);
+        if entities.is_empty() {
+            stored.push((chunk_pos, None));
+            continue;
+        }
+        match serialize_entities(world, entities) {
+            Ok(data) => stored.push((chunk_pos, Some(data))),
+            Err(err) => error!("Failed to save the entities of chunk {chunk_pos}: {err}"),
+        }
+    }
+    let mut world_save = world.resource_mut::<WorldSave>();
+    for (chunk_pos, data) in stored {
+        world_save.store_chunk_entities(chunk_pos, data);
+    }
+}
+
+/// Spawns the entities stored with the chunk at `chunk_pos`, if it has any.
+pub fn restore_chunk_entities(world: &mut World, chunk_pos: IVec2) {
+    let Some(data) = world
+        .resource_mut::<WorldSave>()
+        .chunk_entities(chunk_pos)
+        .map(str::to_string)
+    else {
+        return;
+    };
+    if let Err(err) = deserialize_chunk_entities(world, &data) {
+        error!("Failed to load the entities of chunk {chunk_pos}: {err}");
+    }
+}
+
+/// Spawns the entities described by data stored by [`unload_chunk_entities`].
+pub fn deserialize_chunk_entities(world: &mut World, data: &str) -> Result<()> {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+
+    let scene: DynamicScene = {
+        let registry = registry.read();
+        let mut deserializer = ron::Deserializer::from_str(data)?;
+        SceneDeserializer {
+            type_registry: &registry,
+        }
+        .deserialize(&mut deserializer)?
+    };
+
+    scene.write_to_world_with(world, &mut EntityHashMap::default(), &registry)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use bevy::platform::collections::HashSet;
+
+    use super::*;
+    use crate::difficulty::Difficulty;
+    use crate::persistence::WorldMetadata;
+    use crate::temperature::SurvivalMode;
+    use crate::worldgen::WorldgenPreset;
+
+    #[derive(Component, Reflect, Debug, PartialEq)]
+    #[reflect(Component, SaveableComponent)]
+    struct Health(u32);
+
+    #[derive(Component, Reflect)]
+    #[reflect(Component)]
+    struct Unsaved;
+
+    fn test_world() -> World {
+        let mut world = World::new();
+        let registry = AppTypeRegistry::default();
+        {
+            let mut registry = registry.write();
+            registry.register::<Persist>();
+            registry.register::<Health>();
+            registry.register::<Unsaved>();
+            registry.register::<Transform>();
+            registry.register_type_data::<Transform, ReflectSaveableComponent>();
+        }
+        world.insert_resource(registry);
+        world.insert_resource(WorldSave::new(
+            PathBuf::new(),
+            WorldMetadata {
+                name: "Entities".to_string(),
+                seed: 3,
+                preset: WorldgenPreset::default(),
+                difficulty: Difficulty::default(),
+                survival: SurvivalMode::default(),
+                created: 0,
+                playtime_secs: 0.0,
+                player_pos: Vec2::ZERO,
+                player_health: None,
+                player_inventory: None,
+                quest_log: None,
+                clock_days: None,
+                waypoints: None,
+            },
+        ));
+        world
+    }
+
+    #[test]
+    fn persisted_entities_unload_with_their_chunk_and_come_back() {
+        let mut world = test_world();
+        world.spawn((
+            Persist,
+            Transform::from_xyz(20.0, 30.0, 0.0),
+            Health(7),
+            Unsaved,
+        ));
+        let neighbor = world
+            .spawn((Persist, Transform::from_xyz(-20.0, 30.0, 0.0), Health(3)))
+            .id();
+        let unpersisted = world
+            .spawn((Transform::from_xyz(24.0, 24.0, 0.0), Health(1)))
+            .id();
+
+        unload_chunk_entities(&mut world, IVec2::ZERO);
+        let mut remaining = world.query_filtered::<Entity, With<Health>>();
+        let remaining: HashSet<_> = remaining.iter(&world).collect();
+        assert_eq!(remaining, HashSet::from([neighbor, unpersisted]));
+
+        restore_chunk_entities(&mut world, IVec2::ZERO);
+        let mut query = world.query::<(&Transform, &Health, Has<Unsaved>, Has<Persist>)>();
+        let restored: Vec<_> = query
+            .iter(&world)
+            .filter(|(transform, ..)| transform.translation.xy() == Vec2::new(20.0, 30.0))
+            .collect();
+        assert_eq!(restored.len(), 1);
+        let (transform, health, has_unsaved, has_persist) = restored[0];
+        assert_eq!(transform.translation, Vec3::new(20.0, 30.0, 0.0));
+        assert_eq!(*health, Health(7));
+        assert!(!has_unsaved && has_persist);
+
+        // A chunk unloaded without any persisted entities forgets the ones it had.
+        unload_chunk_entities(&mut world, IVec2::ZERO);
+        assert!(
+            world
+                .resource_mut::<WorldSave>()
+                .chunk_entities(IVec2::ZERO)
+                .is_some()
+        );
+        unload_chunk_entities(&mut world, IVec2::ZERO);
+        assert_eq!(
+            world
+                .resource_mut::<WorldSave>()
+                .chunk_entities(IVec2::ZERO),
+            None
+        );
+    }
+}
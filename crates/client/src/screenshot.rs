@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use bevy_enhanced_input::prelude::*;
+
+use crate::localization::Localization;
+use crate::paths::AppPaths;
+use crate::settings::Settings;
+use crate::world_select::format_date;
+
+/// How long the confirmation stays on screen after a screenshot.
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+
+/// Captures the window to a timestamped PNG in [`AppPaths::screenshots_dir`] when the screenshot
+/// key is pressed, in menus as well as in game.
+pub struct ScreenshotPlugin;
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenshotToast>()
+            .add_input_context::<ScreenshotInput>()
+            .add_systems(Startup, spawn_screenshot_input)
+            .add_systems(
+                Update,
+                rebind_screenshot.run_if(resource_changed::<Settings>),
+            )
+            .add_observer(take_screenshot)
+            .add_systems(EguiPrimaryContextPass, screenshot_toast);
+    }
+}
+
+/// The input context for screenshots, bound to the key it holds.
+#[derive(Component)]
+struct ScreenshotInput(KeyCode);
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct TakeScreenshot;
+
+#[derive(Resource)]
+struct ScreenshotToast {
+    message: String,
+    timer: Timer,
+}
+
+impl Default for ScreenshotToast {
+    fn default() -> Self {
+        let mut timer = Timer::new(TOAST_DURATION, TimerMode::Once);
+        timer.finish();
+        Self {
+            message: String::new(),
+            timer,
+        }
+    }
+}
+
+impl ScreenshotToast {
+    fn show(&mut self, message: String) {
+        self.message = message;
+        self.timer.reset();
+    }
+}
+
+/// File name for a screenshot taken at `time`, e.g. `2026-10-14_09-30-00.png`.
+pub fn screenshot_file_name(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let secs_of_day = secs % 86_400;
+    format!(
+        "{}_{:02}-{:02}-{:02}.png",
+        format_date(secs),
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+fn spawn_screenshot_input(mut commands: Commands, settings: Res<Settings>) {
+    let key = settings.keybinds.screenshot;
+    commands.spawn((
+        ScreenshotInput(key),
+        actions!(ScreenshotInput[(Action::<TakeScreenshot>::new(), bindings![key])]),
+    ));
+}
+
+fn rebind_screenshot(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    input: Single<(Entity, &ScreenshotInput)>,
+) {
+    let (entity, ScreenshotInput(key)) = *input;
+    if *key != settings.keybinds.screenshot {
+        commands.entity(entity).despawn();
+        spawn_screenshot_input(commands, settings);
+    }
+}
+
+fn take_screenshot(_: On<Start<TakeScreenshot>>, mut commands: Commands, paths: Res<AppPaths>) {
+    let path = paths
+        .screenshots_dir
+        .join(screenshot_file_name(SystemTime::now()));
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(save_screenshot(path));
+}
+
+fn save_screenshot(
+    path: PathBuf,
+) -> impl FnMut(On<ScreenshotCaptured>, Res<Localization>, ResMut<ScreenshotToast>) {
+    move |captured, localization, mut toast| match write_png(&captured.image, &path) {
+        Ok(()) => {
+            info!("Saved screenshot to {}", path.display());
+            toast.show(localization.format("screenshot-saved", &[("path", &path.display())]));
+        }
+        Err(err) => {
+            error!("Failed to save screenshot to {}: {err}", path.display());
+            toast.show(localization.get("screenshot-failed").to_string());
+        }
+    }
+}
+
+fn write_png(image: &Image, path: &Path) -> Result {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // The alpha channel holds brightness when HDR is on rather than transparency.
+    image.clone().try_into_dynamic()?.to_rgb8().save(path)?;
+    Ok(())
+}
+
+fn screenshot_toast(
+    mut contexts: EguiContexts,
+    time: Res<Time<Real>>,
+    mut toast: ResMut<ScreenshotToast>,
+) -> Result {
+    if toast.timer.tick(time.delta()).is_finished() {
+        return Ok(());
+    }
+    egui::Area::new(egui::Id::new("screenshot_toast"))
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 8.0))
+        .show(contexts.ctx_mut()?, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label(&toast.message);
+            });
+        });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_screenshots_by_utc_time() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_791_936_000 + 9 * 3600 + 30 * 60 + 5);
+        assert_eq!(screenshot_file_name(time), "2026-10-14_09-30-05.png");
+    }
+}
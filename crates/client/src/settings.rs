@@ -0,0 +1,615 @@
+use std::fs;
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy::window::{PresentMode, PrimaryWindow, WindowMode};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use bevy_modern_pixel_camera::prelude::*;
+use bevy_seedling::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+use crate::chunk::{CHUNK_RENDER_DISTANCE, ChunkManager};
+use crate::clock::{DEFAULT_DAY_SECS, GameClock};
+use crate::crt::CrtFilter;
+use crate::localization::{FALLBACK_LANGUAGE, LANGUAGES, Localization};
+use crate::paths::AppPaths;
+use crate::pause::PauseState;
+use crate::persistence::{DEFAULT_ZSTD_LEVEL, SaveCompression, WorldSave};
+use crate::pixel_snap::PixelSnapping;
+
+/// Keeps the window and audio in sync with [`Settings`] and writes them to the config file
+/// whenever they change. The main and pause menus open [`SettingsWindow`] to change the graphics,
+/// audio and controls while they apply.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Settings>()
+            .init_resource::<SettingsWindow>()
+            .add_systems(
+                Update,
+                (
+                    (
+                        apply_window_settings,
+                        apply_volumes,
+                        apply_render_distance,
+                        apply_pixel_resolution,
+                        apply_clock_settings,
+                        save_settings,
+                    )
+                        .run_if(resource_changed::<Settings>),
+                    apply_save_compression.run_if(
+                        resource_exists::<WorldSave>
+                            .and(resource_changed::<Settings>.or(resource_added::<WorldSave>)),
+                    ),
+                ),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                settings_ui.run_if(in_state(GameState::MainMenu).or(in_state(PauseState::Paused))),
+            );
+    }
+}
+
+/// Player preferences, stored in [`AppPaths::settings_file`]. Loaded by
+/// `main` before the app is built so the window opens with the saved mode.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub window_mode: WindowModeSetting,
+    pub vsync: bool,
+    pub pixel_snapping: PixelSnapping,
+    /// Linear volumes, from 0 to 1.
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub effects_volume: f32,
+    /// Controller rumble strength, from 0 (off) to 1.
+    pub haptics_intensity: f32,
+    /// How many chunks are kept loaded around the player in each direction.
+    pub render_distance: UVec2,
+    /// How many game pixels the view fits across and down, one of [`PIXEL_RESOLUTIONS`].
+    pub pixel_resolution: UVec2,
+    pub save_compression: SaveCompression,
+    /// The zstd level of [`SaveCompression::Zstd`], from 1 (fastest) to 22 (smallest).
+    pub save_compression_level: i32,
+    /// Real-time seconds a day lasts.
+    pub day_length_secs: f32,
+    /// How many times faster than `day_length_secs` says time passes in the world.
+    pub time_scale: f32,
+    /// Fade areas of the exported map the player hasn't visited in a while.
+    pub map_aging: bool,
+    /// Draw the world like an old CRT, as set up by `crt`.
+    pub crt_filter: bool,
+    pub crt: CrtFilter,
+    pub keybinds: Keybinds,
+    /// The code of the language the game is in, one of [`LANGUAGES`].
+    pub language: String,
+    /// The newest version whose changelog the player has opened.
+    pub last_seen_version: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window_mode: WindowModeSetting::BorderlessFullscreen,
+            vsync: true,
+            pixel_snapping: PixelSnapping::default(),
+            master_volume: 1.0,
+            music_volume: 1.0,
+            effects_volume: 1.0,
+            haptics_intensity: 1.0,
+            render_distance: CHUNK_RENDER_DISTANCE,
+            pixel_resolution: PIXEL_RESOLUTIONS[1],
+            save_compression: SaveCompression::default(),
+            save_compression_level: DEFAULT_ZSTD_LEVEL,
+            day_length_secs: DEFAULT_DAY_SECS,
+            time_scale: 1.0,
+            map_aging: true,
+            crt_filter: false,
+            crt: CrtFilter::default(),
+            keybinds: Keybinds::default(),
+            language: FALLBACK_LANGUAGE.to_string(),
+            last_seen_version: None,
+        }
+    }
+}
+
+/// The sizes of the view the settings offer, in game pixels. Bigger ones show more of the world
+/// with smaller pixels.
+pub const PIXEL_RESOLUTIONS: [UVec2; 4] = [
+    UVec2::new(256, 144),
+    UVec2::new(320, 180),
+    UVec2::new(384, 216),
+    UVec2::new(480, 270),
+];
+
+/// The furthest the settings let chunks load around the player, in each direction.
+const MAX_RENDER_DISTANCE: u32 = 6;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowModeSetting {
+    Windowed,
+    BorderlessFullscreen,
+    Fullscreen,
+}
+
+impl WindowModeSetting {
+    pub const ALL: [Self; 3] = [Self::Windowed, Self::BorderlessFullscreen, Self::Fullscreen];
+}
+
+impl From<WindowModeSetting> for WindowMode {
+    fn from(mode: WindowModeSetting) -> Self {
+        match mode {
+            WindowModeSetting::Windowed => WindowMode::Windowed,
+            WindowModeSetting::BorderlessFullscreen => {
+                WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+            }
+            WindowModeSetting::Fullscreen => {
+                WindowMode::Fullscreen(MonitorSelection::Current, VideoModeSelection::Current)
+            }
+        }
+    }
+}
+
+/// Keyboard and controller bindings. Arrow keys and the left stick always move the player as
+/// well, and Escape and Start always pause.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Keybinds {
+    pub move_up: KeyCode,
+    pub move_left: KeyCode,
+    pub move_down: KeyCode,
+    pub move_right: KeyCode,
+    pub sprint: KeyCode,
+    pub interact: KeyCode,
+    pub attack: KeyCode,
+    pub screenshot: KeyCode,
+    pub sprint_button: GamepadButton,
+    pub interact_button: GamepadButton,
+    pub attack_button: GamepadButton,
+    pub next_slot_button: GamepadButton,
+    pub previous_slot_button: GamepadButton,
+}
+
+impl Default for Keybinds {
+    fn default() -> Self {
+        Self {
+            move_up: KeyCode::KeyW,
+            move_left: KeyCode::KeyA,
+            move_down: KeyCode::KeyS,
+            move_right: KeyCode::KeyD,
+            sprint: KeyCode::ShiftLeft,
+            interact: KeyCode::KeyE,
+            attack: KeyCode::Space,
+            screenshot: KeyCode::F12,
+            sprint_button: GamepadButton::LeftThumb,
+            interact_button: GamepadButton::South,
+            attack_button: GamepadButton::West,
+            next_slot_button: GamepadButton::RightTrigger,
+            previous_slot_button: GamepadButton::LeftTrigger,
+        }
+    }
+}
+
+/// An action in the controls list, with its key and controller button if it has them.
+pub struct Control<'a> {
+    /// The id of the action's name in the [`Localization`].
+    pub id: &'static str,
+    pub key: Option<&'a mut KeyCode>,
+    pub button: Option<&'a mut GamepadButton>,
+}
+
+impl Keybinds {
+    /// Every action with its bindings, in the order the settings list them.
+    pub fn controls_mut(&mut self) -> [Control<'_>; 10] {
+        let control = |id, key, button| Control { id, key, button };
+        [
+            control("control-move-up", Some(&mut self.move_up), None),
+            control("control-move-left", Some(&mut self.move_left), None),
+            control("control-move-down", Some(&mut self.move_down), None),
+            control("control-move-right", Some(&mut self.move_right), None),
+            control(
+                "control-sprint",
+                Some(&mut self.sprint),
+                Some(&mut self.sprint_button),
+            ),
+            control(
+                "control-interact",
+                Some(&mut self.interact),
+                Some(&mut self.interact_button),
+            ),
+            control(
+                "control-attack",
+                Some(&mut self.attack),
+                Some(&mut self.attack_button),
+            ),
+            control("control-next-slot", None, Some(&mut self.next_slot_button)),
+            control(
+                "control-previous-slot",
+                None,
+                Some(&mut self.previous_slot_button),
+            ),
+            control("control-screenshot", Some(&mut self.screenshot), None),
+        ]
+    }
+
+    /// The ids of the actions that share a key or button with another one.
+    pub fn conflicts(&self) -> Vec<&'static str> {
+        // The controls only come as mutable borrows, so look through a copy.
+        let mut keybinds = self.clone();
+        let controls = keybinds.controls_mut();
+        let shares = |index: usize| {
+            let control = &controls[index];
+            controls.iter().enumerate().any(|(other_index, other)| {
+                other_index != index
+                    && (matches!((&control.key, &other.key), (Some(a), Some(b)) if a == b)
+                        || matches!((&control.button, &other.button), (Some(a), Some(b)) if a == b))
+            })
+        };
+        (0..controls.len())
+            .filter(|index| shares(*index))
+            .map(|index| controls[index].id)
+            .collect()
+    }
+}
+
+impl Settings {
+    /// Reads the settings from the config file, falling back to the defaults if it is missing
+    /// or invalid.
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(data) => ron::from_str(&data).unwrap_or_else(|err| {
+                warn!("Ignoring invalid settings in {}: {err}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, ron::ser::to_string_pretty(self, default())?)?;
+        Ok(())
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+}
+
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+) {
+    let mode = settings.window_mode.into();
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    let present_mode = settings.present_mode();
+    if window.present_mode != present_mode {
+        window.present_mode = present_mode;
+    }
+}
+
+/// A volume node and which of the configurable buses it belongs to.
+type AudioVolume = (
+    &'static mut VolumeNode,
+    Has<MainBus>,
+    Has<SfxBus>,
+    Has<SamplerPool<MusicPool>>,
+);
+
+fn apply_volumes(settings: Res<Settings>, mut nodes: Query<AudioVolume>) {
+    for (mut node, main_bus, sfx_bus, music_pool) in &mut nodes {
+        let volume = if main_bus {
+            settings.master_volume
+        } else if sfx_bus {
+            settings.effects_volume
+        } else if music_pool {
+            settings.music_volume
+        } else {
+            continue;
+        };
+        node.volume = Volume::Linear(volume);
+    }
+}
+
+fn apply_render_distance(settings: Res<Settings>, mut chunk_manager: ResMut<ChunkManager>) {
+    chunk_manager.render_distance = settings.render_distance;
+}
+
+fn apply_pixel_resolution(settings: Res<Settings>, mut cameras: Query<&mut PixelZoom>) {
+    for mut zoom in &mut cameras {
+        zoom.set_if_neq(PixelZoom::FitSize {
+            width: settings.pixel_resolution.x as i32,
+            height: settings.pixel_resolution.y as i32,
+        });
+    }
+}
+
+fn apply_clock_settings(settings: Res<Settings>, mut clock: ResMut<GameClock>) {
+    clock.day_secs = settings.day_length_secs;
+    clock.scale = settings.time_scale;
+}
+
+fn apply_save_compression(settings: Res<Settings>, mut world_save: ResMut<WorldSave>) {
+    world_save.compression = settings.save_compression;
+    world_save.compression_level = settings.save_compression_level.clamp(1, 22);
+}
+
+fn save_settings(settings: Res<Settings>, paths: Res<AppPaths>) {
+    if let Err(err) = settings.save(&paths.settings_file()) {
+        error!("Failed to save settings: {err}");
+    }
+}
+
+/// The tabs of the settings window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SettingsTab {
+    #[default]
+    Graphics,
+    Audio,
+    Controls,
+}
+
+/// Whether the settings window is open on the main or pause menu, and what it shows.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct SettingsWindow {
+    pub open: bool,
+    pub tab: SettingsTab,
+    rebinding: Option<Rebinding>,
+}
+
+fn settings_ui(
+    mut contexts: EguiContexts,
+    localization: Res<Localization>,
+    mut settings: ResMut<Settings>,
+    mut window: ResMut<SettingsWindow>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+) -> Result {
+    // Edit a copy so the settings are only saved when something actually changed.
+    let mut edited = settings.clone();
+    let mut open = window.open;
+    egui::Window::new(localization.get("settings-title"))
+        .id(egui::Id::new("settings"))
+        .open(&mut open)
+        .anchor(egui::Align2::RIGHT_TOP, egui::Vec2::new(-8.0, 8.0))
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.horizontal(|ui| {
+                for (tab, id) in [
+                    (SettingsTab::Graphics, "settings-graphics"),
+                    (SettingsTab::Audio, "settings-audio"),
+                    (SettingsTab::Controls, "settings-controls"),
+                ] {
+                    ui.selectable_value(&mut window.tab, tab, localization.get(id));
+                }
+            });
+            ui.separator();
+            match window.tab {
+                SettingsTab::Graphics => graphics_ui(ui, &localization, &mut edited),
+                SettingsTab::Audio => audio_ui(ui, &localization, &mut edited),
+                SettingsTab::Controls => {
+                    controls_ui(
+                        ui,
+                        &localization,
+                        &mut edited.keybinds,
+                        &mut window.rebinding,
+                        (&keys, &gamepads),
+                    );
+                }
+            }
+        });
+    window.open = open;
+    if !open {
+        window.rebinding = None;
+    }
+    settings.set_if_neq(edited);
+    Ok(())
+}
+
+fn graphics_ui(ui: &mut egui::Ui, localization: &Localization, settings: &mut Settings) {
+    let language_name = |code: &str| {
+        LANGUAGES
+            .iter()
+            .find(|(language, _)| *language == code)
+            .map_or(code.to_string(), |(_, name)| name.to_string())
+    };
+    egui::ComboBox::from_label(localization.get("settings-language"))
+        .selected_text(language_name(&settings.language))
+        .show_ui(ui, |ui| {
+            for (code, name) in LANGUAGES {
+                ui.selectable_value(&mut settings.language, code.to_string(), name);
+            }
+        });
+    egui::ComboBox::from_label(localization.get("settings-window"))
+        .selected_text(format!("{:?}", settings.window_mode))
+        .show_ui(ui, |ui| {
+            for mode in WindowModeSetting::ALL {
+                ui.selectable_value(&mut settings.window_mode, mode, format!("{mode:?}"));
+            }
+        });
+    ui.checkbox(&mut settings.vsync, localization.get("settings-vsync"));
+    let resolution = |size: UVec2| format!("{} × {}", size.x, size.y);
+    egui::ComboBox::from_label(localization.get("settings-pixel-resolution"))
+        .selected_text(resolution(settings.pixel_resolution))
+        .show_ui(ui, |ui| {
+            for size in PIXEL_RESOLUTIONS {
+                ui.selectable_value(&mut settings.pixel_resolution, size, resolution(size));
+            }
+        });
+    ui.add(
+        egui::Slider::new(&mut settings.render_distance.x, 1..=MAX_RENDER_DISTANCE)
+            .text(localization.get("settings-render-distance-across")),
+    );
+    ui.add(
+        egui::Slider::new(&mut settings.render_distance.y, 1..=MAX_RENDER_DISTANCE)
+            .text(localization.get("settings-render-distance-down")),
+    );
+    ui.separator();
+    ui.checkbox(
+        &mut settings.crt_filter,
+        localization.get("settings-crt-filter"),
+    );
+    ui.add_enabled_ui(settings.crt_filter, |ui| {
+        let crt = &mut settings.crt;
+        for (value, range, id) in [
+            (&mut crt.curvature, 0.0..=0.3, "settings-crt-curvature"),
+            (&mut crt.scanlines, 0.0..=1.0, "settings-crt-scanlines"),
+            (&mut crt.aberration, 0.0..=4.0, "settings-crt-aberration"),
+        ] {
+            ui.add(egui::Slider::new(value, range).text(localization.get(id)));
+        }
+    });
+}
+
+fn audio_ui(ui: &mut egui::Ui, localization: &Localization, settings: &mut Settings) {
+    for (volume, id) in [
+        (&mut settings.master_volume, "settings-master-volume"),
+        (&mut settings.music_volume, "settings-music-volume"),
+        (&mut settings.effects_volume, "settings-effects-volume"),
+        (&mut settings.haptics_intensity, "settings-rumble"),
+    ] {
+        ui.add(egui::Slider::new(volume, 0.0..=1.0).text(localization.get(id)));
+    }
+}
+
+/// The binding in [`Keybinds::controls_mut`] waiting for the next key or button pressed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Rebinding {
+    Key(usize),
+    Button(usize),
+}
+
+/// Every action's bindings, each with a button that waits for the next key or controller button
+/// pressed to bind to it. Escape and Start are kept for pausing.
+fn controls_ui(
+    ui: &mut egui::Ui,
+    localization: &Localization,
+    keybinds: &mut Keybinds,
+    rebinding: &mut Option<Rebinding>,
+    (keys, gamepads): (&ButtonInput<KeyCode>, &Query<&Gamepad>),
+) {
+    let pressed_key = keys
+        .get_just_pressed()
+        .find(|key| **key != KeyCode::Escape)
+        .copied();
+    let pressed_button = gamepads
+        .iter()
+        .flat_map(Gamepad::get_just_pressed)
+        .find(|button| **button != GamepadButton::Start)
+        .copied();
+    let conflicts = keybinds.conflicts();
+
+    egui::Grid::new("keybinds").show(ui, |ui| {
+        ui.strong(localization.get("controls-action"));
+        ui.strong(localization.get("controls-keyboard"));
+        ui.strong(localization.get("controls-controller"));
+        ui.end_row();
+        for (index, control) in keybinds.controls_mut().into_iter().enumerate() {
+            let name = localization.get(control.id);
+            if conflicts.contains(&control.id) {
+                ui.colored_label(egui::Color32::RED, name)
+                    .on_hover_text(localization.get("controls-conflict"));
+            } else {
+                ui.label(name);
+            }
+            match control.key {
+                Some(key) => {
+                    if *rebinding == Some(Rebinding::Key(index))
+                        && let Some(pressed) = pressed_key
+                    {
+                        *key = pressed;
+                        *rebinding = None;
+                    }
+                    binding_button(
+                        ui,
+                        localization,
+                        rebinding,
+                        Rebinding::Key(index),
+                        format!("{key:?}"),
+                    );
+                }
+                None => {
+                    ui.label("-");
+                }
+            }
+            match control.button {
+                Some(button) => {
+                    if *rebinding == Some(Rebinding::Button(index))
+                        && let Some(pressed) = pressed_button
+                    {
+                        *button = pressed;
+                        *rebinding = None;
+                    }
+                    binding_button(
+                        ui,
+                        localization,
+                        rebinding,
+                        Rebinding::Button(index),
+                        format!("{button:?}"),
+                    );
+                }
+                None => {
+                    ui.label("-");
+                }
+            }
+            ui.end_row();
+        }
+    });
+    if ui.button(localization.get("controls-reset")).clicked() {
+        *keybinds = Keybinds::default();
+        *rebinding = None;
+    }
+}
+
+/// A button showing `label`, or that it is waiting for a press while `this` is being rebound.
+/// Clicking it starts or stops rebinding `this`.
+fn binding_button(
+    ui: &mut egui::Ui,
+    localization: &Localization,
+    rebinding: &mut Option<Rebinding>,
+    this: Rebinding,
+    label: String,
+) {
+    let waiting = *rebinding == Some(this);
+    let text = if waiting {
+        localization.get("controls-waiting").to_string()
+    } else {
+        label
+    };
+    if ui.button(text).clicked() {
+        *rebinding = if waiting { None } else { Some(this) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sharing_a_key_or_button_is_a_conflict() {
+        let mut keybinds = Keybinds::default();
+        assert!(keybinds.conflicts().is_empty());
+
+        keybinds.attack = keybinds.interact;
+        keybinds.next_slot_button = keybinds.sprint_button;
+        assert_eq!(
+            keybinds.conflicts(),
+            vec![
+                "control-sprint",
+                "control-interact",
+                "control-attack",
+                "control-next-slot"
+            ]
+        );
+    }
+}
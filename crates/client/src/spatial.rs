@@ -0,0 +1,157 @@
+use bevy::ecs::entity::EntityHashMap;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::tiles::{world_pos_to_tile, world_tile_to_chunk};
+
+/// Keeps the [`SpatialIndex`] up to date with the positions of [`Spatial`] entities, before
+/// `Update` so that systems there see every entity spawned or moved in the previous frame.
+pub struct SpatialPlugin;
+
+impl Plugin for SpatialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialIndex>()
+            .add_observer(remove_from_index)
+            .add_systems(PreUpdate, update_index);
+    }
+}
+
+/// Marks an entity to be tracked by the [`SpatialIndex`].
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Spatial;
+
+/// [`Spatial`] entities bucketed by the chunk they are in, for finding the ones near a point
+/// without going through all of them. Frozen entities stay where they froze.
+#[derive(Resource, Debug, Default)]
+pub struct SpatialIndex {
+    cells: HashMap<IVec2, Vec<(Entity, Vec2)>>,
+    entity_cells: EntityHashMap<IVec2>,
+}
+
+fn cell_of(pos: Vec2) -> IVec2 {
+    world_tile_to_chunk(world_pos_to_tile(pos)).0
+}
+
+impl SpatialIndex {
+    /// Records `entity` at `pos`, moving it to another cell if it has left its old one.
+    pub fn insert(&mut self, entity: Entity, pos: Vec2) {
+        let cell = cell_of(pos);
+        match self.entity_cells.insert(entity, cell) {
+            Some(old_cell) if old_cell == cell => {
+                if let Some(entry) = self
+                    .cells
+                    .get_mut(&cell)
+                    .and_then(|entries| entries.iter_mut().find(|(e, _)| *e == entity))
+                {
+                    entry.1 = pos;
+                }
+                return;
+            }
+            Some(old_cell) => self.remove_from_cell(entity, old_cell),
+            None => {}
+        }
+        self.cells.entry(cell).or_default().push((entity, pos));
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some(cell) = self.entity_cells.remove(&entity) {
+            self.remove_from_cell(entity, cell);
+        }
+    }
+
+    fn remove_from_cell(&mut self, entity: Entity, cell: IVec2) {
+        if let Some(entries) = self.cells.get_mut(&cell) {
+            entries.retain(|(e, _)| *e != entity);
+            if entries.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    /// The entities in the chunk at `chunk_pos`.
+    pub fn in_chunk(&self, chunk_pos: IVec2) -> impl Iterator<Item = Entity> + '_ {
+        self.cells
+            .get(&chunk_pos)
+            .into_iter()
+            .flatten()
+            .map(|(entity, _)| *entity)
+    }
+
+    /// The entities within `radius` of `center`, with their distance to it.
+    pub fn within(&self, center: Vec2, radius: f32) -> impl Iterator<Item = (Entity, f32)> + '_ {
+        let min = cell_of(center - Vec2::splat(radius));
+        let max = cell_of(center + Vec2::splat(radius));
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .map(move |(entity, pos)| (*entity, pos.distance(center)))
+            .filter(move |(_, distance)| *distance <= radius)
+    }
+}
+
+/// [`Spatial`] entities that were spawned or moved.
+type Moved = (With<Spatial>, Changed<Transform>);
+
+fn update_index(mut index: ResMut<SpatialIndex>, moved: Query<(Entity, &Transform), Moved>) {
+    for (entity, transform) in &moved {
+        index.insert(entity, transform.translation.xy());
+    }
+}
+
+fn remove_from_index(remove: On<Remove, Spatial>, mut index: ResMut<SpatialIndex>) {
+    index.remove(remove.entity);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{CHUNK_SIZE, TILE_SIZE};
+
+    #[test]
+    fn entities_are_found_near_where_they_last_moved() {
+        let mut app = App::new();
+        app.add_plugins(SpatialPlugin);
+        let near = app
+            .world_mut()
+            .spawn((Spatial, Transform::from_xyz(10.0, 0.0, 0.0)))
+            .id();
+        let far = app
+            .world_mut()
+            .spawn((Spatial, Transform::from_xyz(200.0, 400.0, 0.0)))
+            .id();
+        app.world_mut().spawn(Transform::from_xyz(0.0, 0.0, 0.0));
+        app.update();
+
+        let within = |app: &App, center: Vec2, radius: f32| {
+            let mut entities: Vec<_> = app
+                .world()
+                .resource::<SpatialIndex>()
+                .within(center, radius)
+                .collect();
+            entities.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+            entities
+                .into_iter()
+                .map(|(entity, _)| entity)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(within(&app, Vec2::ZERO, 20.0), vec![near]);
+        assert_eq!(within(&app, Vec2::ZERO, 500.0), vec![near, far]);
+
+        // Moving into the next chunk moves the entity to its cell.
+        let next_chunk = CHUNK_SIZE.x as f32 * TILE_SIZE.x;
+        app.world_mut()
+            .get_mut::<Transform>(near)
+            .unwrap()
+            .translation
+            .x = next_chunk;
+        app.update();
+        assert_eq!(within(&app, Vec2::ZERO, 20.0), vec![]);
+        let index = app.world().resource::<SpatialIndex>();
+        assert_eq!(index.in_chunk(IVec2::X).collect::<Vec<_>>(), vec![near]);
+        assert_eq!(index.in_chunk(IVec2::ZERO).count(), 0);
+
+        app.world_mut().despawn(far);
+        assert_eq!(within(&app, Vec2::ZERO, 500.0), vec![near]);
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+
+use crate::GameState;
+
+/// Steps the [`TextureAtlas`] of every [`AnimatedSprite`] through its frames, and triggers
+/// [`AnimationFinished`] on sprites whose animation plays once when it reaches its last frame.
+pub struct SpriteAnimationPlugin;
+
+impl Plugin for SpriteAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            animate_sprites.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Plays `frames` atlas frames starting at `first` at `fps` frames per second, over and over if
+/// `looping`.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[require(Sprite)]
+pub struct AnimatedSprite {
+    pub first: usize,
+    pub frames: usize,
+    pub fps: f32,
+    pub looping: bool,
+    frame: usize,
+    /// Seconds spent on the current frame.
+    elapsed: f32,
+    finished: bool,
+}
+
+impl AnimatedSprite {
+    /// An animation that starts over after its last frame.
+    pub fn looping(first: usize, frames: usize, fps: f32) -> Self {
+        Self {
+            first,
+            frames,
+            fps,
+            looping: true,
+            frame: 0,
+            elapsed: 0.0,
+            finished: false,
+        }
+    }
+
+    /// An animation that stops on its last frame.
+    pub fn once(first: usize, frames: usize, fps: f32) -> Self {
+        Self {
+            looping: false,
+            ..Self::looping(first, frames, fps)
+        }
+    }
+
+    /// Switches to `frames` frames starting at `first`, from the start.
+    pub fn play(&mut self, first: usize, frames: usize, fps: f32) {
+        *self = Self {
+            looping: self.looping,
+            ..Self::looping(first, frames, fps)
+        };
+    }
+
+    /// The index of the current frame in the atlas.
+    pub fn index(&self) -> usize {
+        self.first + self.frame
+    }
+
+    /// Advances the animation by `secs` seconds, returning whether it just finished.
+    pub fn advance(&mut self, secs: f32) -> bool {
+        if self.finished || self.frames == 0 || self.fps <= 0.0 {
+            return false;
+        }
+        self.elapsed += secs;
+        let frame_secs = self.fps.recip();
+        while self.elapsed >= frame_secs {
+            self.elapsed -= frame_secs;
+            if self.frame + 1 < self.frames {
+                self.frame += 1;
+            } else if self.looping {
+                self.frame = 0;
+            } else {
+                self.finished = true;
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Triggered on an entity whose [`AnimatedSprite`] that plays once showed its last frame for as
+/// long as the others.
+#[derive(EntityEvent, Clone, Copy, Debug)]
+pub struct AnimationFinished {
+    pub entity: Entity,
+}
+
+fn animate_sprites(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut sprites: Query<(Entity, &mut AnimatedSprite, &mut Sprite)>,
+) {
+    for (entity, mut animation, mut sprite) in &mut sprites {
+        if animation.advance(time.delta_secs()) {
+            commands.trigger(AnimationFinished { entity });
+        }
+        if let Some(atlas) = &mut sprite.texture_atlas
+            && atlas.index != animation.index()
+        {
+            atlas.index = animation.index();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animations_loop_or_finish_once() {
+        let mut looping = AnimatedSprite::looping(4, 3, 10.0);
+        assert!(!looping.advance(0.25));
+        assert_eq!(looping.index(), 6);
+        assert!(!looping.advance(0.1));
+        assert_eq!(looping.index(), 4);
+
+        let mut once = AnimatedSprite::once(0, 2, 10.0);
+        assert!(!once.advance(0.15));
+        assert_eq!(once.index(), 1);
+        assert!(once.advance(0.1));
+        // It stays on its last frame without finishing again.
+        assert!(!once.advance(1.0));
+        assert_eq!(once.index(), 1);
+
+        once.play(8, 2, 5.0);
+        assert_eq!(once.index(), 8);
+        assert!(!once.looping);
+    }
+}
@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::GameState;
+
+/// Stamina a full bar holds, in seconds of sprinting.
+pub const MAX_STAMINA: f32 = 4.0;
+
+/// How much faster sprinting is than walking.
+pub const SPRINT_MULTIPLIER: f32 = 1.8;
+
+/// Stamina regained per second once the player has rested for [`REGEN_DELAY`].
+const REGEN_RATE: f32 = 1.0;
+
+/// Seconds without sprinting before stamina starts coming back.
+const REGEN_DELAY: f32 = 0.75;
+
+/// Regenerates [`Stamina`] between sprints and shows it as a bar while it isn't full.
+pub struct StaminaPlugin;
+
+impl Plugin for StaminaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            regenerate_stamina.run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            EguiPrimaryContextPass,
+            stamina_bar.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Spent by sprinting, in seconds of sprint.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Stamina {
+    pub current: f32,
+    pub max: f32,
+    /// Seconds since stamina was last spent.
+    pub rested: f32,
+}
+
+impl Default for Stamina {
+    fn default() -> Self {
+        Self {
+            current: MAX_STAMINA,
+            max: MAX_STAMINA,
+            rested: REGEN_DELAY,
+        }
+    }
+}
+
+impl Stamina {
+    /// Spends `secs` seconds of sprint, returning `false` if the bar is already empty.
+    pub fn drain(&mut self, secs: f32) -> bool {
+        if self.current <= 0.0 {
+            return false;
+        }
+        self.current = (self.current - secs).max(0.0);
+        self.rested = 0.0;
+        true
+    }
+
+    /// Advances the rest timer by `secs` seconds, refilling the bar once it passes
+    /// [`REGEN_DELAY`].
+    pub fn regenerate(&mut self, secs: f32) {
+        self.rested += secs;
+        if self.rested >= REGEN_DELAY {
+            self.current = (self.current + secs * REGEN_RATE).min(self.max);
+        }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.current / self.max
+    }
+}
+
+fn regenerate_stamina(time: Res<Time>, mut stamina: Query<&mut Stamina>) {
+    for mut stamina in &mut stamina {
+        stamina.regenerate(time.delta_secs());
+    }
+}
+
+fn stamina_bar(mut contexts: EguiContexts, stamina: Single<&Stamina>) -> Result {
+    if stamina.current >= stamina.max {
+        return Ok(());
+    }
+    egui::Area::new(egui::Id::new("stamina_bar"))
+        .anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(8.0, -8.0))
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.add(
+                egui::ProgressBar::new(stamina.fraction())
+                    .desired_width(96.0)
+                    .fill(egui::Color32::from_rgb(220, 190, 60)),
+            );
+        });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stamina_drains_and_refills_after_resting() {
+        let mut stamina = Stamina::default();
+        assert!(stamina.drain(MAX_STAMINA));
+        assert!(!stamina.drain(0.1), "an empty bar can't be spent");
+
+        stamina.regenerate(REGEN_DELAY / 2.0);
+        assert_eq!(stamina.current, 0.0);
+        stamina.regenerate(REGEN_DELAY);
+        assert_eq!(stamina.current, REGEN_DELAY * REGEN_RATE);
+
+        for _ in 0..100 {
+            stamina.regenerate(1.0);
+        }
+        assert_eq!(stamina.fraction(), 1.0);
+    }
+}
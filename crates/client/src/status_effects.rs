@@ -0,0 +1,399 @@
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_enhanced_input::prelude::*;
+use serde::Deserialize;
+
+use crate::GameState;
+use crate::combat::AttackReady;
+use crate::health::{Damage, Heal, LifeState, Respawn};
+use crate::hotbar::Hotbar;
+use crate::inventory::{Inventory, ItemRegistry};
+use crate::player::{Attack, Player};
+use crate::tiles::{WorldTiles, world_pos_to_tile};
+
+/// Seconds between the ticks of every effect, and between the stacks tiles apply.
+pub const EFFECT_TICK_SECS: f32 = 1.0;
+
+/// How many times an effect stacks at most.
+pub const MAX_STACKS: u32 = 3;
+
+/// Health poison takes every tick, per stack.
+pub const POISON_DAMAGE: f32 = 0.5;
+
+/// How much slower each stack of [`StatusEffectKind::Slow`] makes walking.
+pub const SLOW_PER_STACK: f32 = 0.2;
+
+/// Health regeneration gives back every tick, per stack.
+pub const REGENERATION_HEAL: f32 = 0.5;
+
+const HUD_SCALE: f32 = 3.0;
+
+/// Applies timed [`StatusEffects`] to everything with [`Health`](crate::health::Health), from [`ApplyEffect`] messages,
+/// the [`TileProperties::effect`](crate::tiles::TileProperties::effect) of the tile it stands on
+/// and the [`ItemDefinition::effect`](crate::inventory::ItemDefinition::effect) of items the
+/// player drinks or eats with the attack key. Effects stack up to [`MAX_STACKS`] and tick every
+/// [`EFFECT_TICK_SECS`]. The player's effects are shown as icons in the top left corner.
+pub struct StatusEffectsPlugin;
+
+impl Plugin for StatusEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<ApplyEffect>()
+            .add_observer(use_consumable)
+            .add_observer(clear_effects)
+            .add_systems(OnEnter(GameState::Playing), spawn_status_icons)
+            .add_systems(
+                Update,
+                (
+                    apply_tile_effects,
+                    apply_effects.run_if(on_message::<ApplyEffect>),
+                    tick_effects.run_if(in_state(LifeState::Alive)),
+                    update_status_icons,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct StatusEffectAssets {
+    /// One icon per [`StatusEffectKind`], in order.
+    #[asset(path = "ui/status_effects.png")]
+    pub icons: Handle<Image>,
+    #[asset(texture_atlas_layout(tile_size_x = 8, tile_size_y = 8, columns = 3, rows = 1))]
+    pub icons_layout: Handle<TextureAtlasLayout>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatusEffectKind {
+    /// Takes [`POISON_DAMAGE`] every tick.
+    Poison,
+    /// Walks [`SLOW_PER_STACK`] slower.
+    Slow,
+    /// Gives back [`REGENERATION_HEAL`] every tick.
+    Regeneration,
+}
+
+impl StatusEffectKind {
+    pub const ALL: [Self; 3] = [Self::Poison, Self::Slow, Self::Regeneration];
+}
+
+/// One stack of an effect, lasting `secs`.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct StatusEffect {
+    pub kind: StatusEffectKind,
+    pub secs: f32,
+}
+
+/// An effect on an entity, with how many times it has stacked.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ActiveEffect {
+    pub kind: StatusEffectKind,
+    pub stacks: u32,
+    /// Seconds until the effect wears off.
+    pub secs: f32,
+    /// Seconds until the effect next ticks.
+    tick_secs: f32,
+}
+
+/// The effects an entity is under.
+#[derive(Component, Clone, Debug, Default)]
+pub struct StatusEffects {
+    active: Vec<ActiveEffect>,
+}
+
+impl StatusEffects {
+    /// Adds a stack of `effect`, up to [`MAX_STACKS`], lasting at least `effect.secs` from now.
+    pub fn apply(&mut self, effect: StatusEffect) {
+        match self
+            .active
+            .iter_mut()
+            .find(|active| active.kind == effect.kind)
+        {
+            Some(active) => {
+                active.stacks = (active.stacks + 1).min(MAX_STACKS);
+                active.secs = active.secs.max(effect.secs);
+            }
+            None => self.active.push(ActiveEffect {
+                kind: effect.kind,
+                stacks: 1,
+                secs: effect.secs,
+                tick_secs: EFFECT_TICK_SECS,
+            }),
+        }
+    }
+
+    pub fn get(&self, kind: StatusEffectKind) -> Option<&ActiveEffect> {
+        self.active.iter().find(|active| active.kind == kind)
+    }
+
+    pub fn clear(&mut self) {
+        self.active.clear();
+    }
+
+    /// What walking speed is multiplied by.
+    pub fn speed_multiplier(&self) -> f32 {
+        let stacks = self
+            .get(StatusEffectKind::Slow)
+            .map_or(0, |active| active.stacks);
+        1.0 - SLOW_PER_STACK * stacks as f32
+    }
+
+    /// Advances by `secs` seconds, returning the effects that ticked with their stacks. Effects
+    /// that wear off are removed after their last tick.
+    pub fn tick(&mut self, secs: f32) -> Vec<(StatusEffectKind, u32)> {
+        let mut ticked = Vec::new();
+        for active in &mut self.active {
+            active.secs -= secs;
+            active.tick_secs -= secs;
+            if active.tick_secs <= 0.0 {
+                active.tick_secs += EFFECT_TICK_SECS;
+                ticked.push((active.kind, active.stacks));
+            }
+        }
+        self.active.retain(|active| active.secs > 0.0);
+        ticked
+    }
+}
+
+/// Adds a stack of `effect` to the [`StatusEffects`] of `target`.
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct ApplyEffect {
+    pub target: Entity,
+    pub effect: StatusEffect,
+}
+
+/// The effect of drinking or eating the item in the selected hotbar slot, if it does anything.
+pub fn held_effect(
+    hotbar: &Hotbar,
+    inventory: &Inventory,
+    registry: &ItemRegistry,
+) -> Option<StatusEffect> {
+    let stack = hotbar.selected_stack(inventory)?;
+    registry.get(&stack.item)?.effect
+}
+
+fn use_consumable(
+    _: On<Start<Attack>>,
+    (hotbar, registry, ready): (Res<Hotbar>, Res<ItemRegistry>, AttackReady),
+    player: Single<(Entity, &mut Inventory), With<Player>>,
+    mut effects: MessageWriter<ApplyEffect>,
+) {
+    let (target, mut inventory) = player.into_inner();
+    if !ready.get() {
+        return;
+    }
+    let Some(stack) = hotbar.selected_stack(&inventory) else {
+        return;
+    };
+    let Some(effect) = registry
+        .get(&stack.item)
+        .and_then(|definition| definition.effect)
+    else {
+        return;
+    };
+    let item = stack.item.clone();
+    if inventory.consume(&item, 1) {
+        effects.write(ApplyEffect { target, effect });
+    }
+}
+
+/// Respawning cures the player.
+fn clear_effects(_: On<Respawn>, mut player: Single<&mut StatusEffects, With<Player>>) {
+    player.clear();
+}
+
+fn apply_tile_effects(
+    time: Res<Time>,
+    mut ready_in: Local<f32>,
+    standing: Query<(Entity, &Transform), With<StatusEffects>>,
+    tiles: WorldTiles,
+    mut effects: MessageWriter<ApplyEffect>,
+) {
+    *ready_in -= time.delta_secs();
+    if *ready_in > 0.0 {
+        return;
+    }
+    *ready_in = EFFECT_TICK_SECS;
+    for (target, transform) in &standing {
+        let world_tile = world_pos_to_tile(transform.translation.xy());
+        if let Some(effect) = tiles
+            .properties(world_tile)
+            .and_then(|properties| properties.effect)
+        {
+            effects.write(ApplyEffect { target, effect });
+        }
+    }
+}
+
+fn apply_effects(mut applied: MessageReader<ApplyEffect>, mut targets: Query<&mut StatusEffects>) {
+    for ApplyEffect { target, effect } in applied.read().copied() {
+        if let Ok(mut effects) = targets.get_mut(target) {
+            effects.apply(effect);
+        }
+    }
+}
+
+fn tick_effects(
+    time: Res<Time>,
+    mut targets: Query<(Entity, &mut StatusEffects)>,
+    mut damage: MessageWriter<Damage>,
+    mut heal: MessageWriter<Heal>,
+) {
+    for (target, mut effects) in &mut targets {
+        for (kind, stacks) in effects.tick(time.delta_secs()) {
+            let amount = stacks as f32;
+            match kind {
+                StatusEffectKind::Poison => {
+                    damage.write(Damage {
+                        target,
+                        amount: POISON_DAMAGE * amount,
+                        knockback: Vec2::ZERO,
+                    });
+                }
+                StatusEffectKind::Slow => {}
+                StatusEffectKind::Regeneration => {
+                    heal.write(Heal {
+                        target,
+                        amount: REGENERATION_HEAL * amount,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct StatusIcon(StatusEffectKind);
+
+#[derive(Component)]
+struct StatusStacks(StatusEffectKind);
+
+fn spawn_status_icons(mut commands: Commands, assets: Res<StatusEffectAssets>) {
+    let icon_size = Val::Px(8.0 * HUD_SCALE);
+    commands
+        .spawn((
+            Name::new("Status effects"),
+            DespawnOnExit(GameState::Playing),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.0),
+                left: Val::Px(12.0),
+                column_gap: Val::Px(2.0 * HUD_SCALE),
+                ..default()
+            },
+            Pickable::IGNORE,
+        ))
+        .with_children(|row| {
+            for (index, kind) in StatusEffectKind::ALL.into_iter().enumerate() {
+                row.spawn((
+                    StatusIcon(kind),
+                    ImageNode::from_atlas_image(
+                        assets.icons.clone(),
+                        TextureAtlas {
+                            layout: assets.icons_layout.clone(),
+                            index,
+                        },
+                    ),
+                    Node {
+                        width: icon_size,
+                        height: icon_size,
+                        display: Display::None,
+                        ..default()
+                    },
+                ))
+                .with_child((
+                    StatusStacks(kind),
+                    Text::default(),
+                    TextFont::from_font_size(6.0 * HUD_SCALE),
+                    TextShadow::default(),
+                    Node {
+                        position_type: PositionType::Absolute,
+                        right: Val::Px(-HUD_SCALE),
+                        bottom: Val::Px(-2.0 * HUD_SCALE),
+                        ..default()
+                    },
+                ));
+            }
+        });
+}
+
+fn update_status_icons(
+    player: Single<&StatusEffects, With<Player>>,
+    mut icons: Query<(&StatusIcon, &mut Node, &mut ImageNode)>,
+    mut stacks: Query<(&StatusStacks, &mut Text)>,
+) {
+    for (StatusIcon(kind), mut node, mut image) in &mut icons {
+        let Some(active) = player.get(*kind) else {
+            node.display = Display::None;
+            continue;
+        };
+        node.display = Display::Flex;
+        // Blink while the effect is about to wear off.
+        let fading = active.secs < EFFECT_TICK_SECS * 2.0 && active.secs % 0.5 < 0.25;
+        image.color = Color::WHITE.with_alpha(if fading { 0.4 } else { 1.0 });
+    }
+    for (StatusStacks(kind), mut text) in &mut stacks {
+        let stacks = player.get(*kind).map_or(0, |active| active.stacks);
+        let count = if stacks > 1 {
+            stacks.to_string()
+        } else {
+            String::new()
+        };
+        if text.0 != count {
+            text.0 = count;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effects_stack_tick_and_wear_off() {
+        let poison = StatusEffect {
+            kind: StatusEffectKind::Poison,
+            secs: 3.0,
+        };
+        let slow = StatusEffect {
+            kind: StatusEffectKind::Slow,
+            secs: 1.5,
+        };
+        let mut effects = StatusEffects::default();
+        for _ in 0..MAX_STACKS + 2 {
+            effects.apply(poison);
+        }
+        effects.apply(slow);
+        effects.apply(slow);
+        assert_eq!(
+            effects.get(StatusEffectKind::Poison).unwrap().stacks,
+            MAX_STACKS
+        );
+        assert!((effects.speed_multiplier() - (1.0 - 2.0 * SLOW_PER_STACK)).abs() < 1e-6);
+
+        let mut ticks = Vec::new();
+        for _ in 0..8 {
+            ticks.extend(effects.tick(0.5));
+        }
+        // Poison ticks once a second for as long as it lasts, slowness wears off first.
+        let poison_ticks: Vec<_> = ticks
+            .iter()
+            .filter(|(kind, _)| *kind == StatusEffectKind::Poison)
+            .collect();
+        assert_eq!(
+            poison_ticks,
+            vec![&(StatusEffectKind::Poison, MAX_STACKS); 3]
+        );
+        assert!(effects.get(StatusEffectKind::Poison).is_none());
+        assert_eq!(effects.speed_multiplier(), 1.0);
+
+        // Stacking again refreshes how long the effect lasts, but never shortens it.
+        effects.apply(poison);
+        effects.apply(StatusEffect {
+            secs: 1.0,
+            ..poison
+        });
+        assert_eq!(effects.get(StatusEffectKind::Poison).unwrap().secs, 3.0);
+    }
+}
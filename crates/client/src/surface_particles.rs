@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+
+use crate::GameState;
+use crate::collision::TileCollider;
+use crate::particles::EmitParticles;
+use crate::player::{Footing, Player, Velocity};
+
+/// Distance the player moves between two particles, in world units.
+const PARTICLE_SPACING: f32 = 10.0;
+
+/// Kicks up small puffs of the `dust` emitter at the player's feet while it moves, colored by
+/// the [`SurfaceProfile`](crate::tiles::SurfaceProfile) of the tile underfoot: dust on ground,
+/// leaves in undergrowth, snow flurries and water splashes, that drift off in the
+/// [`Wind`](crate::wind::Wind).
+pub struct SurfaceParticlesPlugin;
+
+impl Plugin for SurfaceParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            kick_up_particles.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+fn kick_up_particles(
+    time: Res<Time>,
+    player: Single<(&Transform, &Velocity, &Footing, &TileCollider), With<Player>>,
+    mut emit: MessageWriter<EmitParticles>,
+    mut travelled: Local<f32>,
+) {
+    let (transform, velocity, footing, collider) = player.into_inner();
+    *travelled += velocity.0.length() * time.delta_secs();
+    if *travelled < PARTICLE_SPACING {
+        return;
+    }
+    *travelled %= PARTICLE_SPACING;
+
+    let feet = transform.translation.xy() - Vec2::Y * collider.half_size.y;
+    emit.write(EmitParticles {
+        emitter: "dust".into(),
+        pos: feet,
+        color: Some(footing.0.profile().particle),
+        // Trailing behind the player.
+        velocity: -velocity.0.normalize_or_zero() * 8.0,
+    });
+}
@@ -0,0 +1,296 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+use crate::ambient_light::AmbientCurve;
+use crate::clock::{GameClock, Season};
+use crate::health::{Damage, LifeState, Respawn};
+use crate::inventory::{Inventory, ItemRegistry};
+use crate::player::Player;
+use crate::stamina::Stamina;
+use crate::status_effects::{ApplyEffect, EFFECT_TICK_SECS, StatusEffect, StatusEffectKind};
+use crate::tiles::{TileKind, TileProperties, WorldTiles, world_pos_to_tile, world_tile_to_chunk};
+use crate::weather::Weather;
+use crate::wind::Wind;
+use crate::worldgen::{Biome, WorldSeed, WorldgenPreset, biome_at_chunk};
+
+/// How many degrees warmer a [`TileKind::Torch`] keeps whoever stands right next to it.
+pub const TORCH_WARMTH: f32 = 6.0;
+
+/// How many degrees warmer a [`TileKind::Campfire`] keeps whoever stands right next to it.
+pub const CAMPFIRE_WARMTH: f32 = 20.0;
+
+/// How many tiles away the warmth of a fire can be felt.
+const HEAT_RADIUS: i32 = 4;
+
+/// What the body temperature starts at, and comes back to after respawning.
+const COMFORTABLE: f32 = 18.0;
+
+/// Below this, the player is freezing.
+pub const FREEZING: f32 = 0.0;
+
+/// Above this, the player is overheating.
+pub const OVERHEATING: f32 = 35.0;
+
+/// Seconds it takes the body temperature to get most of the way to the temperature around it.
+const ADAPT_SECS: f32 = 20.0;
+
+/// Health exposure to the cold or heat takes every [`EFFECT_TICK_SECS`].
+const EXPOSURE_DAMAGE: f32 = 0.25;
+
+/// Seconds of sprint overheating takes out of the [`Stamina`] bar every [`EFFECT_TICK_SECS`].
+const HEAT_EXHAUSTION: f32 = 1.0;
+
+/// How many degrees colder the darkest night is than the middle of the day.
+const NIGHT_CHILL: f32 = 10.0;
+
+/// How many degrees colder the strongest wind makes it feel.
+const WIND_CHILL: f32 = 6.0;
+
+/// In survival mode, gives the player a [`BodyTemperature`] that follows the temperature around
+/// them: the biome and season set it, nights, bad weather and the wind cool it, and torches and
+/// campfires close by warm it up. Clothing in the inventory, like a straw cloak or a straw hat,
+/// adds its [`warmth`](crate::inventory::ItemDefinition::warmth). A freezing player is slowed and
+/// an overheating one gets exhausted, and both take damage until they warm up or cool down. The
+/// body temperature is shown as a bar in the bottom left corner.
+pub struct TemperaturePlugin;
+
+impl Plugin for TemperaturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SurvivalMode>()
+            .add_observer(add_body_temperature)
+            .add_observer(reset_body_temperature)
+            .add_systems(
+                Update,
+                (
+                    feel_temperature,
+                    suffer_exposure
+                        .run_if(on_timer(Duration::from_secs_f32(EFFECT_TICK_SECS)))
+                        .run_if(in_state(LifeState::Alive)),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing).and(resource_equals(SurvivalMode(true)))),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                temperature_bar
+                    .run_if(in_state(GameState::Playing).and(resource_equals(SurvivalMode(true)))),
+            );
+    }
+}
+
+/// Whether the world is played in survival mode, picked when it is created and stored with it.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SurvivalMode(pub bool);
+
+/// How warm the player is, in degrees.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct BodyTemperature(pub f32);
+
+impl Default for BodyTemperature {
+    fn default() -> Self {
+        Self(COMFORTABLE)
+    }
+}
+
+/// What the cold or heat is doing to the player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Exposure {
+    Freezing,
+    Overheating,
+}
+
+impl BodyTemperature {
+    /// Warms up or cools down towards the `felt` temperature for `secs` seconds.
+    pub fn adapt(&mut self, felt: f32, secs: f32) {
+        self.0 += (felt - self.0) * (1.0 - (-secs / ADAPT_SECS).exp());
+    }
+
+    pub fn exposure(self) -> Option<Exposure> {
+        if self.0 < FREEZING {
+            Some(Exposure::Freezing)
+        } else if self.0 > OVERHEATING {
+            Some(Exposure::Overheating)
+        } else {
+            None
+        }
+    }
+}
+
+/// The temperature of the air in `biome` in `season`, in `daylight` from 0 at night to 1 at
+/// noon, cooled by the `weather` and a wind blowing at `wind` strength.
+pub fn air_temperature(
+    biome: Biome,
+    season: Season,
+    daylight: f32,
+    weather: Weather,
+    wind: f32,
+) -> f32 {
+    let base = match biome {
+        Biome::Ocean => 14.0,
+        Biome::Plains => 18.0,
+        Biome::Forest => 16.0,
+        Biome::Mountains => 6.0,
+        Biome::Tundra => -6.0,
+    };
+    let seasonal = match season {
+        Season::Spring => 0.0,
+        Season::Summer => 10.0,
+        Season::Autumn => -2.0,
+        Season::Winter => -14.0,
+    };
+    let weather_chill = match weather {
+        Weather::Clear => 0.0,
+        Weather::Rain => 4.0,
+        Weather::Snow => 6.0,
+        Weather::Storm => 8.0,
+    };
+    base + seasonal - NIGHT_CHILL * (1.0 - daylight) - weather_chill - WIND_CHILL * wind
+}
+
+/// How much warmer the warmest tile within [`HEAT_RADIUS`] of `world_tile` keeps it, fading
+/// with distance. `tile_at` looks the loaded tiles up.
+fn heat_near(world_tile: IVec2, tile_at: impl Fn(IVec2) -> Option<TileKind>) -> f32 {
+    let mut heat: f32 = 0.0;
+    for y in -HEAT_RADIUS..=HEAT_RADIUS {
+        for x in -HEAT_RADIUS..=HEAT_RADIUS {
+            let offset = IVec2::new(x, y);
+            let Some(kind) = tile_at(world_tile + offset) else {
+                continue;
+            };
+            let falloff = 1.0 - offset.as_vec2().length() / (HEAT_RADIUS + 1) as f32;
+            heat = heat.max(TileProperties::of(kind).warmth * falloff);
+        }
+    }
+    heat
+}
+
+/// The [`warmth`](crate::inventory::ItemDefinition::warmth) of the clothing in `inventory`,
+/// counting each kind of item once however many are carried.
+pub fn clothing_warmth(inventory: &Inventory, registry: &ItemRegistry) -> f32 {
+    let mut worn = Vec::new();
+    for stack in inventory.slots.iter().flatten() {
+        if !worn.contains(&&stack.item) {
+            worn.push(&stack.item);
+        }
+    }
+    worn.into_iter()
+        .filter_map(|item| registry.get(item))
+        .map(|definition| definition.warmth)
+        .sum()
+}
+
+fn add_body_temperature(add: On<Add, Player>, mut commands: Commands) {
+    commands
+        .entity(add.entity)
+        .insert(BodyTemperature::default());
+}
+
+fn reset_body_temperature(_: On<Respawn>, mut player: Single<&mut BodyTemperature, With<Player>>) {
+    **player = BodyTemperature::default();
+}
+
+fn feel_temperature(
+    time: Res<Time>,
+    (clock, season, curve): (Res<GameClock>, Res<Season>, Res<AmbientCurve>),
+    (weather, wind, registry): (Res<Weather>, Res<Wind>, Res<ItemRegistry>),
+    (world_seed, preset): (Res<WorldSeed>, Res<WorldgenPreset>),
+    tiles: WorldTiles,
+    player: Single<(&Transform, &Inventory, &mut BodyTemperature), With<Player>>,
+) {
+    let (transform, inventory, mut temperature) = player.into_inner();
+    let world_tile = world_pos_to_tile(transform.translation.xy());
+    let biome = biome_at_chunk(world_tile_to_chunk(world_tile).0, world_seed.seed, &preset);
+    let air = air_temperature(
+        biome,
+        *season,
+        curve.daylight(&clock),
+        *weather,
+        wind.strength,
+    );
+    let felt = air
+        + heat_near(world_tile, |tile| tiles.get_tile(tile))
+        + clothing_warmth(inventory, &registry);
+    temperature.adapt(felt, time.delta_secs());
+}
+
+fn suffer_exposure(
+    player: Single<(Entity, &BodyTemperature, &mut Stamina), With<Player>>,
+    mut damage: MessageWriter<Damage>,
+    mut effects: MessageWriter<ApplyEffect>,
+) {
+    let (target, temperature, mut stamina) = player.into_inner();
+    match temperature.exposure() {
+        None => return,
+        Some(Exposure::Freezing) => {
+            effects.write(ApplyEffect {
+                target,
+                effect: StatusEffect {
+                    kind: StatusEffectKind::Slow,
+                    secs: EFFECT_TICK_SECS * 2.0,
+                },
+            });
+        }
+        Some(Exposure::Overheating) => {
+            stamina.drain(HEAT_EXHAUSTION);
+        }
+    }
+    damage.write(Damage {
+        target,
+        amount: EXPOSURE_DAMAGE,
+        knockback: Vec2::ZERO,
+    });
+}
+
+fn temperature_bar(mut contexts: EguiContexts, temperature: Single<&BodyTemperature>) -> Result {
+    let fraction = (temperature.0 - FREEZING) / (OVERHEATING - FREEZING);
+    let color = match temperature.exposure() {
+        Some(Exposure::Freezing) => egui::Color32::from_rgb(90, 150, 230),
+        Some(Exposure::Overheating) => egui::Color32::from_rgb(230, 110, 60),
+        None => egui::Color32::from_rgb(120, 190, 90),
+    };
+    egui::Area::new(egui::Id::new("temperature_bar"))
+        .anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(8.0, -32.0))
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.add(
+                egui::ProgressBar::new(fraction.clamp(0.0, 1.0))
+                    .desired_width(96.0)
+                    .fill(color)
+                    .text(format!("{:.0}°", temperature.0)),
+            );
+        });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_cold_is_felt_and_warmed_up_by_fires() {
+        let noon = air_temperature(Biome::Plains, Season::Summer, 1.0, Weather::Clear, 0.0);
+        let night = air_temperature(Biome::Tundra, Season::Winter, 0.0, Weather::Snow, 1.0);
+        assert_eq!(noon, 28.0);
+        assert!(night < FREEZING);
+
+        // A campfire right next to the player warms more than a torch further away.
+        let campfire = heat_near(IVec2::ZERO, |tile| {
+            (tile == IVec2::new(1, 0)).then_some(TileKind::Campfire)
+        });
+        let torch = heat_near(IVec2::ZERO, |tile| {
+            (tile == IVec2::new(3, 0)).then_some(TileKind::Torch)
+        });
+        assert!(campfire > torch && torch > 0.0);
+        assert_eq!(heat_near(IVec2::ZERO, |_| Some(TileKind::Grass)), 0.0);
+
+        let mut temperature = BodyTemperature::default();
+        temperature.adapt(night, ADAPT_SECS * 5.0);
+        assert_eq!(temperature.exposure(), Some(Exposure::Freezing));
+        temperature.adapt(night + campfire, ADAPT_SECS * 5.0);
+        assert!((temperature.0 - (night + campfire)).abs() < 0.5);
+    }
+}
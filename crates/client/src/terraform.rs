@@ -0,0 +1,355 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_egui::input::egui_wants_any_pointer_input;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::GameState;
+use crate::console::RegisterConsoleCommand;
+use crate::picking::CursorWorldPos;
+use crate::tiles::{TileKind, WorldTiles, world_pos_to_tile};
+use crate::worldgen::Biome;
+
+/// How many brush strokes can be undone.
+pub const UNDO_LIMIT: usize = 64;
+
+/// Creative-mode brushes for sculpting the loaded terrain. Once the `creative` console command
+/// turns on [`CreativeMode`], pick a brush in the "Terraform" window, click to apply it around the
+/// cursor and press Ctrl+Z to undo the last stroke. Edited chunks are marked dirty, so the changes
+/// are saved like any other tile edit.
+pub struct TerraformPlugin;
+
+impl Plugin for TerraformPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Terraform>()
+            .init_resource::<CreativeMode>()
+            .register_console_command("creative", "creative", toggle_creative_mode)
+            .add_systems(
+                OnExit(GameState::Playing),
+                (clear_history, leave_creative_mode),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                terraform_window
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(resource_equals(CreativeMode(true))),
+            )
+            .add_systems(
+                Update,
+                (
+                    apply_brush.run_if(not(egui_wants_any_pointer_input)),
+                    undo_stroke,
+                )
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(resource_equals(CreativeMode(true))),
+            );
+    }
+}
+
+/// Whether the terraform brushes are available. Off in every world until the `creative` console
+/// command turns it on, and off again once the world is left.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CreativeMode(pub bool);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Brush {
+    /// Moves tiles one elevation band up, e.g. water to grass or stone to snow.
+    Raise,
+    /// Moves tiles one elevation band down.
+    Lower,
+    /// Repaints every tile with the biome's main tile.
+    Paint(Biome),
+    /// Replaces each tile with the most common tile around it.
+    Smooth,
+}
+
+/// The terrain bands from [`get_tile_type`](crate::worldgen::get_tile_type), lowest first.
+pub fn elevation_band(kind: TileKind) -> u8 {
+    match kind {
+        TileKind::Water => 0,
+        TileKind::Grass
+        | TileKind::Forest
+        | TileKind::Chest
+        | TileKind::Farmland
+        | TileKind::Crop
+        | TileKind::HouseWall
+        | TileKind::HouseFloor
+        | TileKind::HouseWindow
+        | TileKind::Torch
+        | TileKind::Campfire => 1,
+        TileKind::Stone
+        | TileKind::Gravel
+        | TileKind::DungeonWall
+        | TileKind::DungeonFloor
+        | TileKind::Door
+        | TileKind::SecretWall
+        | TileKind::PressurePlate => 2,
+        TileKind::Snow => 3,
+    }
+}
+
+fn raised(kind: TileKind) -> TileKind {
+    match elevation_band(kind) {
+        0 => TileKind::Grass,
+        1 => TileKind::Stone,
+        _ => TileKind::Snow,
+    }
+}
+
+fn lowered(kind: TileKind) -> TileKind {
+    match elevation_band(kind) {
+        3 => TileKind::Stone,
+        2 => TileKind::Grass,
+        _ => TileKind::Water,
+    }
+}
+
+fn biome_tile(biome: Biome) -> TileKind {
+    match biome {
+        Biome::Ocean => TileKind::Water,
+        Biome::Plains => TileKind::Grass,
+        Biome::Forest => TileKind::Forest,
+        Biome::Mountains => TileKind::Stone,
+        Biome::Tundra => TileKind::Snow,
+    }
+}
+
+/// The tiles within `radius` tiles of `center`.
+pub fn brush_area(center: IVec2, radius: i32) -> impl Iterator<Item = IVec2> {
+    (-radius..=radius)
+        .flat_map(move |y| (-radius..=radius).map(move |x| IVec2::new(x, y)))
+        .filter(move |offset| offset.length_squared() <= radius * radius)
+        .map(move |offset| center + offset)
+}
+
+/// The new kinds of the tiles a brush changes. Tiles `get_tile` returns `None` for, such as those
+/// in unloaded chunks, are left alone.
+pub fn brush_edits(
+    brush: Brush,
+    center: IVec2,
+    radius: i32,
+    get_tile: impl Fn(IVec2) -> Option<TileKind>,
+) -> Vec<(IVec2, TileKind)> {
+    brush_area(center, radius)
+        .filter_map(|world_tile| {
+            let kind = get_tile(world_tile)?;
+            let new_kind = match brush {
+                Brush::Raise => raised(kind),
+                Brush::Lower => lowered(kind),
+                Brush::Paint(biome) => biome_tile(biome),
+                Brush::Smooth => most_common_neighbor(world_tile, &get_tile).unwrap_or(kind),
+            };
+            (new_kind != kind).then_some((world_tile, new_kind))
+        })
+        .collect()
+}
+
+fn most_common_neighbor(
+    world_tile: IVec2,
+    get_tile: impl Fn(IVec2) -> Option<TileKind>,
+) -> Option<TileKind> {
+    let mut counts = HashMap::<TileKind, u32>::default();
+    for y in -1..=1 {
+        for x in -1..=1 {
+            if let Some(kind) = get_tile(world_tile + IVec2::new(x, y)) {
+                *counts.entry(kind).or_default() += 1;
+            }
+        }
+    }
+    // Break ties by elevation so the result doesn't depend on hash order.
+    counts
+        .into_iter()
+        .max_by_key(|&(kind, count)| (count, elevation_band(kind), kind.texture_index()))
+        .map(|(kind, _)| kind)
+}
+
+#[derive(Resource)]
+pub struct Terraform {
+    brush: Option<Brush>,
+    radius: i32,
+    /// The previous kinds of the tiles each stroke changed, oldest first.
+    history: Vec<Vec<(IVec2, TileKind)>>,
+}
+
+impl Default for Terraform {
+    fn default() -> Self {
+        Self {
+            brush: None,
+            radius: 3,
+            history: Vec::new(),
+        }
+    }
+}
+
+/// Run condition for whether a brush is selected, so clicks go to the brush.
+pub fn brush_selected(terraform: Res<Terraform>) -> bool {
+    terraform.brush.is_some()
+}
+
+fn clear_history(mut terraform: ResMut<Terraform>) {
+    terraform.history.clear();
+}
+
+fn leave_creative_mode(mut creative: ResMut<CreativeMode>, mut terraform: ResMut<Terraform>) {
+    creative.0 = false;
+    terraform.brush = None;
+}
+
+fn toggle_creative_mode(world: &mut World, _: &[&str]) -> Result<String, String> {
+    let mut creative = world.resource_mut::<CreativeMode>();
+    creative.0 = !creative.0;
+    if creative.0 {
+        return Ok("Creative mode on".to_string());
+    }
+    // Clicks go back to breaking and placing tiles.
+    world.resource_mut::<Terraform>().brush = None;
+    Ok("Creative mode off".to_string())
+}
+
+fn terraform_window(mut contexts: EguiContexts, mut terraform: ResMut<Terraform>) -> Result {
+    const BIOMES: [Biome; 5] = [
+        Biome::Ocean,
+        Biome::Plains,
+        Biome::Forest,
+        Biome::Mountains,
+        Biome::Tundra,
+    ];
+
+    egui::Window::new("Terraform")
+        .default_open(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.add(egui::Slider::new(&mut terraform.radius, 0..=16).text("Radius"));
+            ui.horizontal_wrapped(|ui| {
+                ui.selectable_value(&mut terraform.brush, None, "Off");
+                ui.selectable_value(&mut terraform.brush, Some(Brush::Raise), "Raise");
+                ui.selectable_value(&mut terraform.brush, Some(Brush::Lower), "Lower");
+                ui.selectable_value(&mut terraform.brush, Some(Brush::Smooth), "Smooth");
+            });
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Paint");
+                for biome in BIOMES {
+                    let brush = Some(Brush::Paint(biome));
+                    ui.selectable_value(&mut terraform.brush, brush, format!("{biome:?}"));
+                }
+            });
+            ui.label(format!(
+                "Click: apply, Ctrl+Z: undo ({} strokes)",
+                terraform.history.len()
+            ));
+        });
+    Ok(())
+}
+
+fn apply_brush(
+    mouse: Res<ButtonInput<MouseButton>>,
+    cursor: CursorWorldPos,
+    mut terraform: ResMut<Terraform>,
+    mut tiles: WorldTiles,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let (Some(brush), Some(cursor_pos)) = (terraform.brush, cursor.get()) else {
+        return;
+    };
+
+    let edits = brush_edits(
+        brush,
+        world_pos_to_tile(cursor_pos),
+        terraform.radius,
+        |world_tile| tiles.get_tile(world_tile),
+    );
+    if edits.is_empty() {
+        return;
+    }
+    let mut stroke = Vec::with_capacity(edits.len());
+    for (world_tile, kind) in edits {
+        if let Some(previous) = tiles.get_tile(world_tile) {
+            tiles.set_tile(world_tile, kind);
+            stroke.push((world_tile, previous));
+        }
+    }
+    if terraform.history.len() == UNDO_LIMIT {
+        terraform.history.remove(0);
+    }
+    terraform.history.push(stroke);
+}
+
+fn undo_stroke(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut terraform: ResMut<Terraform>,
+    mut tiles: WorldTiles,
+) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !(ctrl && keys.just_pressed(KeyCode::KeyZ)) {
+        return;
+    }
+    let Some(stroke) = terraform.history.pop() else {
+        return;
+    };
+    // Tiles whose chunk unloaded since the stroke keep the edit, which is already saved.
+    for (world_tile, previous) in stroke {
+        tiles.set_tile(world_tile, previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::run_console_line;
+
+    fn terrain(world_tile: IVec2) -> Option<TileKind> {
+        match world_tile.x {
+            ..-5 => None,
+            -5..0 => Some(TileKind::Water),
+            _ => Some(TileKind::Grass),
+        }
+    }
+
+    #[test]
+    fn raising_moves_tiles_up_one_band() {
+        let edits: HashMap<_, _> = brush_edits(Brush::Raise, IVec2::new(-5, 0), 1, terrain)
+            .into_iter()
+            .collect();
+        // The tile left of the center is in an unloaded chunk.
+        assert_eq!(edits.len(), 4);
+        assert_eq!(edits[&IVec2::new(-5, 0)], TileKind::Grass);
+        assert_eq!(edits[&IVec2::new(-4, 0)], TileKind::Grass);
+        assert_eq!(raised(TileKind::Forest), TileKind::Stone);
+        assert_eq!(lowered(TileKind::Water), TileKind::Water);
+    }
+
+    #[test]
+    fn smoothing_removes_isolated_tiles() {
+        let get_tile = |world_tile: IVec2| {
+            Some(if world_tile == IVec2::ZERO {
+                TileKind::Snow
+            } else {
+                TileKind::Grass
+            })
+        };
+        assert_eq!(
+            brush_edits(Brush::Smooth, IVec2::ZERO, 2, get_tile),
+            vec![(IVec2::ZERO, TileKind::Grass)]
+        );
+    }
+
+    #[test]
+    fn the_creative_command_toggles_the_brushes() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, TerraformPlugin));
+        let world = app.world_mut();
+        world.resource_mut::<Terraform>().brush = Some(Brush::Raise);
+
+        assert_eq!(
+            run_console_line(world, "creative").unwrap(),
+            "Creative mode on"
+        );
+        assert_eq!(*world.resource::<CreativeMode>(), CreativeMode(true));
+        assert_eq!(
+            run_console_line(world, "creative").unwrap(),
+            "Creative mode off"
+        );
+        assert_eq!(*world.resource::<CreativeMode>(), CreativeMode(false));
+        assert_eq!(world.resource::<Terraform>().brush, None);
+    }
+}
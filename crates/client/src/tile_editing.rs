@@ -0,0 +1,254 @@
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_egui::input::egui_wants_any_pointer_input;
+
+use crate::GameState;
+
+use crate::collision::{TileCollider, overlaps_solid};
+use crate::debug_placer::placing;
+use crate::dungeons::Locked;
+use crate::health::LifeState;
+use crate::hotbar::Hotbar;
+use crate::inventory::{Inventory, ItemRegistry};
+use crate::picking::CursorWorldPos;
+use crate::player::Player;
+use crate::terraform::brush_selected;
+use crate::tile_highlight::{can_interact, in_reach};
+use crate::tiles::{TileKind, TileProperties, WorldTiles, tile_to_world_pos, world_pos_to_tile};
+use crate::tools::{ToolTier, break_time};
+
+/// Holding left-click breaks the tile under the cursor into what lies beneath it (see
+/// [`TileKind::broken`]), taking longer for harder tiles and less time with a better tool in the
+/// selected [`Hotbar`] slot, while cracks spread over the tile. Right-click places the tile of the
+/// selected item, using it up from the player's [`Inventory`]. Both only work within
+/// [`PLAYER_REACH`](crate::tile_highlight::PLAYER_REACH). Edits go through [`WorldTiles`], so
+/// they are saved and redrawn like any other tile change.
+///
+/// Clicks are left to the terraform brush and the debug placer while either is in use.
+pub struct TileEditingPlugin;
+
+impl Plugin for TileEditingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Breaking>()
+            .add_message::<TileBroken>()
+            .add_systems(OnEnter(GameState::Playing), spawn_crack_overlay)
+            .add_systems(
+                Update,
+                (
+                    (break_tiles, place_tiles)
+                        .run_if(in_state(LifeState::Alive))
+                        .run_if(not(egui_wants_any_pointer_input))
+                        .run_if(not(brush_selected))
+                        .run_if(not(placing)),
+                    update_crack_overlay,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct CrackAssets {
+    /// Cracks spreading over a tile, one frame per stage of breaking.
+    #[asset(path = "cracks.png")]
+    pub cracks: Handle<Image>,
+    #[asset(texture_atlas_layout(tile_size_x = 16, tile_size_y = 16, columns = 4, rows = 1))]
+    pub cracks_layout: Handle<TextureAtlasLayout>,
+}
+
+/// Frames in `cracks.png`.
+const CRACK_STAGES: usize = 4;
+
+/// Written when the player breaks a tile, with the kind it had before breaking and the tool it
+/// was broken with.
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct TileBroken {
+    pub world_tile: IVec2,
+    pub kind: TileKind,
+    pub tool: ToolTier,
+}
+
+/// The tile the player is breaking.
+#[derive(Resource, Default)]
+struct Breaking {
+    world_tile: IVec2,
+    /// How far along breaking is, from 0 to 1, or `None` when the player isn't breaking
+    /// anything.
+    progress: Option<f32>,
+}
+
+#[derive(Component)]
+struct CrackOverlay;
+
+/// Whether a `placed` tile can replace a `target` tile. Solid tiles other than water, which
+/// fills holes, have to be broken first, crops are only planted in farmland and torches and
+/// campfires don't stand in water.
+pub fn can_place(target: TileKind, placed: TileKind) -> bool {
+    if placed == TileKind::Crop {
+        return target == TileKind::Farmland;
+    }
+    if matches!(placed, TileKind::Torch | TileKind::Campfire) {
+        return target != placed && !TileProperties::of(target).solid;
+    }
+    target != placed && (target == TileKind::Water || !TileProperties::of(target).solid)
+}
+
+fn break_tiles(
+    (time, mouse): (Res<Time>, Res<ButtonInput<MouseButton>>),
+    cursor: CursorWorldPos,
+    (hotbar, registry): (Res<Hotbar>, Res<ItemRegistry>),
+    mut breaking: ResMut<Breaking>,
+    mut broken_tiles: MessageWriter<TileBroken>,
+    player: Single<(&Transform, &Inventory), With<Player>>,
+    (mut tiles, locks): (WorldTiles, Query<(), With<Locked>>),
+) {
+    let (transform, inventory) = player.into_inner();
+    let tool = hotbar
+        .selected_stack(inventory)
+        .and_then(|stack| registry.get(&stack.item)?.tool)
+        .unwrap_or_default();
+    let target = cursor
+        .get()
+        .filter(|_| mouse.pressed(MouseButton::Left))
+        .map(world_pos_to_tile)
+        .and_then(|world_tile| Some((world_tile, tiles.get_tile(world_tile)?)))
+        .filter(|&(world_tile, kind)| can_interact(transform.translation.xy(), world_tile, kind))
+        // Locked chests have to be unlocked first.
+        .filter(|&(world_tile, _)| {
+            tiles
+                .tile_entity(world_tile)
+                .is_none_or(|entity| !locks.contains(entity))
+        })
+        .and_then(|(world_tile, kind)| Some((world_tile, kind, break_time(kind, tool)?)));
+    let Some((world_tile, kind, secs)) = target else {
+        breaking.progress = None;
+        return;
+    };
+
+    // Moving to another tile starts over.
+    let progress = match breaking.progress {
+        Some(progress) if breaking.world_tile == world_tile => progress,
+        _ => 0.0,
+    } + time.delta_secs() / secs.max(f32::EPSILON);
+    if progress < 1.0 {
+        *breaking = Breaking {
+            world_tile,
+            progress: Some(progress),
+        };
+        return;
+    }
+    breaking.progress = None;
+    if let Some(broken) = kind.broken() {
+        tiles.set_tile(world_tile, broken);
+        broken_tiles.write(TileBroken {
+            world_tile,
+            kind,
+            tool,
+        });
+    }
+}
+
+fn place_tiles(
+    mouse: Res<ButtonInput<MouseButton>>,
+    cursor: CursorWorldPos,
+    (hotbar, registry): (Res<Hotbar>, Res<ItemRegistry>),
+    player: Single<(&Transform, &TileCollider, &mut Inventory), With<Player>>,
+    mut tiles: WorldTiles,
+) {
+    if !mouse.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let Some(world_tile) = cursor.get().map(world_pos_to_tile) else {
+        return;
+    };
+    let Some(kind) = tiles.get_tile(world_tile) else {
+        return;
+    };
+    let (transform, collider, mut inventory) = player.into_inner();
+    let player_pos = transform.translation.xy();
+    if !in_reach(player_pos, world_tile) {
+        return;
+    }
+    let Some((item, placed)) = hotbar.selected_stack(&inventory).and_then(|stack| {
+        let placed = registry.get(&stack.item)?.tile?;
+        Some((stack.item.clone(), placed))
+    }) else {
+        return;
+    };
+    // Don't wall the player in.
+    let traps_player = TileProperties::of(placed).solid
+        && overlaps_solid(player_pos, collider.half_size, &|tile| tile == world_tile);
+    if !can_place(kind, placed) || traps_player {
+        return;
+    }
+    if inventory.consume(&item, 1) {
+        tiles.set_tile(world_tile, placed);
+    }
+}
+
+fn spawn_crack_overlay(mut commands: Commands, crack_assets: Res<CrackAssets>) {
+    commands.spawn((
+        CrackOverlay,
+        DespawnOnExit(GameState::Playing),
+        Sprite::from_atlas_image(
+            crack_assets.cracks.clone(),
+            TextureAtlas::from(crack_assets.cracks_layout.clone()),
+        ),
+        // Just above the tile highlight.
+        Transform::from_xyz(0.0, 0.0, 0.45),
+        Visibility::Hidden,
+    ));
+}
+
+fn update_crack_overlay(
+    breaking: Res<Breaking>,
+    overlay: Single<(&mut Transform, &mut Visibility, &mut Sprite), With<CrackOverlay>>,
+) {
+    let (mut transform, mut visibility, mut sprite) = overlay.into_inner();
+    let Some(progress) = breaking.progress else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    let center = tile_to_world_pos(breaking.world_tile);
+    transform.translation = center.extend(transform.translation.z);
+    *visibility = Visibility::Inherited;
+    if let Some(atlas) = &mut sprite.texture_atlas {
+        atlas.index = ((progress * CRACK_STAGES as f32) as usize).min(CRACK_STAGES - 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_are_placed_on_open_ground_and_in_holes() {
+        assert!(can_place(TileKind::Grass, TileKind::Stone));
+        assert!(can_place(TileKind::Water, TileKind::Gravel));
+        assert!(!can_place(TileKind::Stone, TileKind::Grass));
+        assert!(!can_place(TileKind::Snow, TileKind::Snow));
+        assert!(can_place(TileKind::Farmland, TileKind::Crop));
+        assert!(!can_place(TileKind::Grass, TileKind::Crop));
+        assert!(can_place(TileKind::Gravel, TileKind::Torch));
+        assert!(!can_place(TileKind::Water, TileKind::Torch));
+        assert!(!can_place(TileKind::Water, TileKind::Campfire));
+
+        // Breaking keeps digging down until it reaches water.
+        let mut kind = TileKind::Forest;
+        let mut layers = vec![kind];
+        while let Some(broken) = kind.broken() {
+            kind = broken;
+            layers.push(kind);
+        }
+        assert_eq!(
+            layers,
+            [
+                TileKind::Forest,
+                TileKind::Grass,
+                TileKind::Gravel,
+                TileKind::Water
+            ]
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+use crate::GameState;
+use crate::chunk::TILE_SIZE;
+use crate::health::LifeState;
+use crate::picking::TilePicking;
+use crate::player::Player;
+use crate::tiles::{TileKind, TileProperties, tile_to_world_pos};
+
+/// How far from the player, in world units, tiles can be interacted with.
+pub const PLAYER_REACH: f32 = 4.5 * TILE_SIZE.x;
+
+const INTERACTABLE_TINT: Color = Color::srgba(1.0, 1.0, 1.0, 0.35);
+const OUT_OF_REACH_TINT: Color = Color::srgba(0.9, 0.2, 0.2, 0.25);
+
+/// Draws a highlight over the tile under the cursor, white when the player can interact with it
+/// and red when it is out of reach or can't be broken.
+pub struct TileHighlightPlugin;
+
+impl Plugin for TileHighlightPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), spawn_highlight)
+            .add_systems(Update, update_highlight.run_if(in_state(LifeState::Alive)))
+            .add_systems(OnEnter(LifeState::Dead), hide_highlight);
+    }
+}
+
+#[derive(Component)]
+struct TileHighlight;
+
+/// Whether `world_tile` is within [`PLAYER_REACH`] of a player at `player_pos`.
+pub fn in_reach(player_pos: Vec2, world_tile: IVec2) -> bool {
+    player_pos.distance(tile_to_world_pos(world_tile)) <= PLAYER_REACH
+}
+
+/// Whether a player at `player_pos` can interact with a `kind` tile at `world_tile`.
+pub fn can_interact(player_pos: Vec2, world_tile: IVec2, kind: TileKind) -> bool {
+    TileProperties::of(kind).breakable && in_reach(player_pos, world_tile)
+}
+
+fn spawn_highlight(mut commands: Commands) {
+    commands.spawn((
+        TileHighlight,
+        DespawnOnExit(GameState::Playing),
+        Sprite::from_color(INTERACTABLE_TINT, Vec2::new(TILE_SIZE.x, TILE_SIZE.y)),
+        // Above the tiles, below particles and the player.
+        Transform::from_xyz(0.0, 0.0, 0.4),
+        Visibility::Hidden,
+    ));
+}
+
+fn update_highlight(
+    picking: TilePicking,
+    kinds: Query<&TileKind>,
+    player: Single<&Transform, (With<Player>, Without<TileHighlight>)>,
+    highlight: Single<(&mut Transform, &mut Visibility, &mut Sprite), With<TileHighlight>>,
+) {
+    let (mut transform, mut visibility, mut sprite) = highlight.into_inner();
+    let Some((picked, &kind)) = picking
+        .under_cursor()
+        .and_then(|picked| Some((picked, kinds.get(picked.entity).ok()?)))
+    else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let center = tile_to_world_pos(picked.world_tile);
+    transform.translation = center.extend(transform.translation.z);
+    *visibility = Visibility::Inherited;
+    sprite.color = if can_interact(player.translation.xy(), picked.world_tile, kind) {
+        INTERACTABLE_TINT
+    } else {
+        OUT_OF_REACH_TINT
+    };
+}
+
+fn hide_highlight(mut highlight: Single<&mut Visibility, With<TileHighlight>>) {
+    **highlight = Visibility::Hidden;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_breakable_tiles_in_reach_are_interactable() {
+        let player = Vec2::new(8.0, 0.0);
+        assert!(can_interact(player, IVec2::new(4, 0), TileKind::Stone));
+        assert!(can_interact(player, IVec2::new(-3, 0), TileKind::Grass));
+        assert!(!can_interact(player, IVec2::new(6, 0), TileKind::Stone));
+        assert!(!can_interact(player, IVec2::new(1, 0), TileKind::Water));
+    }
+}
@@ -0,0 +1,913 @@
+use bevy::ecs::system::SystemParam;
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::chunk::{CHUNK_SIZE, ChunkLayer, ChunkManager, ChunkMarker, ChunkPosition, TILE_SIZE};
+use crate::clock::Season;
+use crate::lighting::{CAMPFIRE_LIGHT, TORCH_LIGHT};
+use crate::status_effects::{StatusEffect, StatusEffectKind};
+use crate::temperature::{CAMPFIRE_WARMTH, TORCH_WARMTH};
+use crate::tools::ToolTier;
+
+/// Index in `tiles.png` of the patches of snow settling over tiles in winter.
+const SNOW_OVERLAY: u32 = 34;
+
+/// The terrain type of a single tile, independent of how it is drawn.
+#[derive(
+    Component, Reflect, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash,
+)]
+#[reflect(Component)]
+pub enum TileKind {
+    #[default]
+    Grass,
+    Water,
+    Forest,
+    Stone,
+    Gravel,
+    Snow,
+    /// Stores items, see [`ChestsPlugin`](crate::chests::ChestsPlugin).
+    Chest,
+    /// Tilled ground that seeds can be planted in.
+    Farmland,
+    /// A growing crop, see [`FarmingPlugin`](crate::farming::FarmingPlugin).
+    Crop,
+    /// The walls and floor of a dungeon, see [`DungeonsPlugin`](crate::dungeons::DungeonsPlugin).
+    DungeonWall,
+    DungeonFloor,
+    /// Opens into floor when the player interacts with it, unless it is
+    /// [`Locked`](crate::dungeons::Locked).
+    Door,
+    /// A dungeon wall with a passage behind it, revealed by interacting with it or stepping on a
+    /// [`TileKind::PressurePlate`] nearby.
+    SecretWall,
+    PressurePlate,
+    /// The walls and floor of a village house, see
+    /// [`VillagersPlugin`](crate::villagers::VillagersPlugin).
+    HouseWall,
+    HouseFloor,
+    /// A house wall that lets light through, lit up at night while the villagers are home.
+    HouseWindow,
+    /// Lights up its surroundings, see [`LightingPlugin`](crate::lighting::LightingPlugin).
+    Torch,
+    /// Lights and warms up its surroundings, see
+    /// [`TemperaturePlugin`](crate::temperature::TemperaturePlugin).
+    Campfire,
+}
+
+impl TileKind {
+    /// Index of this tile in `tiles.png`.
+    pub fn texture_index(self) -> u32 {
+        match self {
+            TileKind::Grass => 0,
+            TileKind::Water => 1,
+            TileKind::Forest => 2,
+            TileKind::Stone => 3,
+            TileKind::Gravel => 4,
+            TileKind::Snow => 5,
+            TileKind::Chest => 6,
+            TileKind::Farmland => 7,
+            // Followed by the later growth stages.
+            TileKind::Crop => 8,
+            TileKind::DungeonWall => 12,
+            TileKind::DungeonFloor => 13,
+            TileKind::Door => 14,
+            TileKind::SecretWall => 15,
+            TileKind::PressurePlate => 16,
+            TileKind::HouseWall => 17,
+            TileKind::HouseFloor => 18,
+            TileKind::HouseWindow => 23,
+            TileKind::Torch => 24,
+            TileKind::Campfire => 25,
+        }
+    }
+
+    /// Index of this tile in `tiles.png` during `season`. Plains grass and forest undergrowth
+    /// turn brown in autumn and are snowed over in winter.
+    pub fn seasonal_texture_index(self, season: Season) -> u32 {
+        match (self, season) {
+            (TileKind::Grass, Season::Autumn) => 19,
+            (TileKind::Grass, Season::Winter) => 20,
+            (TileKind::Forest, Season::Autumn) => 21,
+            (TileKind::Forest, Season::Winter) => 22,
+            _ => self.texture_index(),
+        }
+    }
+
+    /// Index in `tiles.png` of what the tile draws in `layer` during `season`, or `None` if
+    /// nothing of it is drawn there. Chests, torches and campfires stand on grass and crops grow
+    /// out of farmland, which the ground shows beneath them. Flowers bloom in the undergrowth in
+    /// spring and summer, and snow settles over farmland, crops, stone and gravel in winter.
+    pub fn layer_texture_index(self, layer: ChunkLayer, season: Season) -> Option<u32> {
+        match (layer, self) {
+            (ChunkLayer::Ground, TileKind::Chest | TileKind::Torch | TileKind::Campfire) => {
+                Some(TileKind::Grass.seasonal_texture_index(season))
+            }
+            (ChunkLayer::Ground, TileKind::Crop) => Some(TileKind::Farmland.texture_index()),
+            (ChunkLayer::Ground, _) => Some(self.seasonal_texture_index(season)),
+            (ChunkLayer::Decoration, TileKind::Crop) => Some(self.texture_index()),
+            (ChunkLayer::Decoration, TileKind::Forest) => self
+                .animation(layer, season)
+                .map(|animation| animation.start),
+            (ChunkLayer::Object, TileKind::Chest | TileKind::Torch | TileKind::Campfire) => {
+                Some(self.texture_index())
+            }
+            (
+                ChunkLayer::Overlay,
+                TileKind::Farmland | TileKind::Crop | TileKind::Stone | TileKind::Gravel,
+            ) if season == Season::Winter => Some(SNOW_OVERLAY),
+            _ => None,
+        }
+    }
+
+    /// The frames in `tiles.png` the tile cycles through in `layer` during `season`, or `None` if
+    /// it stays on its [`layer_texture_index`](Self::layer_texture_index). Flowers sway in the
+    /// undergrowth in spring and summer. Water ripples in the tile shader instead, see
+    /// [`update_shorelines`].
+    pub fn animation(self, layer: ChunkLayer, season: Season) -> Option<AnimatedTile> {
+        match (layer, self, season) {
+            (ChunkLayer::Decoration, TileKind::Forest, Season::Spring | Season::Summer) => {
+                Some(AnimatedTile {
+                    start: 30,
+                    end: 34,
+                    speed: 0.6,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// What the tile turns into when broken, or `None` if it can't be: undergrowth, snow, chests,
+    /// torches and campfires are cleared down to grass, crops are harvested off their farmland,
+    /// grass and farmland are dug up to gravel, rock breaks into rubble and digging through gravel
+    /// leaves a hole that fills with water. Dungeons and houses can't be dug through.
+    pub fn broken(self) -> Option<Self> {
+        match self {
+            TileKind::Forest
+            | TileKind::Snow
+            | TileKind::Chest
+            | TileKind::Torch
+            | TileKind::Campfire => Some(TileKind::Grass),
+            TileKind::Crop => Some(TileKind::Farmland),
+            TileKind::Grass | TileKind::Farmland | TileKind::Stone => Some(TileKind::Gravel),
+            TileKind::Gravel => Some(TileKind::Water),
+            TileKind::Water
+            | TileKind::DungeonWall
+            | TileKind::DungeonFloor
+            | TileKind::Door
+            | TileKind::SecretWall
+            | TileKind::PressurePlate
+            | TileKind::HouseWall
+            | TileKind::HouseFloor
+            | TileKind::HouseWindow => None,
+        }
+    }
+
+    /// Inverse of [`TileKind::texture_index`], [`TileKind::seasonal_texture_index`] and the frames
+    /// of [`TileKind::animation`].
+    pub fn from_texture_index(index: u32) -> Option<Self> {
+        match index {
+            0 => Some(TileKind::Grass),
+            1 => Some(TileKind::Water),
+            2 => Some(TileKind::Forest),
+            3 => Some(TileKind::Stone),
+            4 => Some(TileKind::Gravel),
+            5 => Some(TileKind::Snow),
+            6 => Some(TileKind::Chest),
+            7 => Some(TileKind::Farmland),
+            8..=11 => Some(TileKind::Crop),
+            12 => Some(TileKind::DungeonWall),
+            13 => Some(TileKind::DungeonFloor),
+            14 => Some(TileKind::Door),
+            15 => Some(TileKind::SecretWall),
+            16 => Some(TileKind::PressurePlate),
+            17 => Some(TileKind::HouseWall),
+            18 => Some(TileKind::HouseFloor),
+            19 | 20 => Some(TileKind::Grass),
+            21 | 22 => Some(TileKind::Forest),
+            23 => Some(TileKind::HouseWindow),
+            24 => Some(TileKind::Torch),
+            25 => Some(TileKind::Campfire),
+            26..=29 => Some(TileKind::Water),
+            30..=33 => Some(TileKind::Forest),
+            _ => None,
+        }
+    }
+}
+
+/// How a tile affects things moving over it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileProperties {
+    /// Blocks [`TileCollider`](crate::collision::TileCollider)s.
+    pub solid: bool,
+    /// Blocks light, see [`LightingPlugin`](crate::lighting::LightingPlugin).
+    pub opaque: bool,
+    /// The level of the light the tile gives off, or 0 if it doesn't.
+    pub light: u8,
+    /// How many degrees warmer the tile keeps whoever stands right next to it, see
+    /// [`TemperaturePlugin`](crate::temperature::TemperaturePlugin).
+    pub warmth: f32,
+    /// Multiplier for the speed of anything walking or swimming across the tile.
+    pub speed: f32,
+    pub surface: Surface,
+    /// Can be broken by the player, see [`TileKind::broken`].
+    pub breakable: bool,
+    /// Seconds it takes to break the tile by hand. Tools divide it by their
+    /// [`speed`](ToolTier::speed).
+    pub hardness: f32,
+    /// The worst tool that can break the tile.
+    pub min_tool: ToolTier,
+    /// Stacked onto whatever stands on the tile, once every
+    /// [`EFFECT_TICK_SECS`](crate::status_effects::EFFECT_TICK_SECS).
+    pub effect: Option<StatusEffect>,
+}
+
+impl TileProperties {
+    /// Water next to land, which can be waded through and leaves whoever wades slowed for a
+    /// little while.
+    pub const SHALLOW_WATER: Self = Self {
+        solid: false,
+        opaque: false,
+        light: 0,
+        warmth: 0.0,
+        speed: 0.45,
+        surface: Surface::Water,
+        breakable: false,
+        hardness: 0.0,
+        min_tool: ToolTier::Hand,
+        effect: Some(StatusEffect {
+            kind: StatusEffectKind::Slow,
+            secs: 3.0,
+        }),
+    };
+
+    pub fn of(kind: TileKind) -> Self {
+        let (solid, speed, surface) = match kind {
+            TileKind::Grass => (false, 1.0, Surface::Ground),
+            // Undergrowth and loose ground.
+            TileKind::Forest => (false, 0.75, Surface::Undergrowth),
+            TileKind::Gravel => (false, 0.85, Surface::Ground),
+            TileKind::Snow => (false, 0.6, Surface::Snow),
+            TileKind::Farmland => (false, 0.9, Surface::Ground),
+            TileKind::Crop => (false, 0.8, Surface::Undergrowth),
+            TileKind::DungeonFloor
+            | TileKind::PressurePlate
+            | TileKind::HouseFloor
+            | TileKind::Torch
+            | TileKind::Campfire => (false, 1.0, Surface::Ground),
+            // Deep water and mountain rock.
+            TileKind::Water => (true, 0.0, Surface::Water),
+            TileKind::Stone => (true, 0.0, Surface::Ground),
+            TileKind::Chest
+            | TileKind::DungeonWall
+            | TileKind::Door
+            | TileKind::SecretWall
+            | TileKind::HouseWall
+            | TileKind::HouseWindow => (true, 0.0, Surface::Ground),
+        };
+        let (hardness, min_tool) = match kind {
+            TileKind::Crop | TileKind::Torch | TileKind::Campfire => (0.2, ToolTier::Hand),
+            TileKind::Snow => (0.3, ToolTier::Hand),
+            TileKind::Forest => (0.4, ToolTier::Hand),
+            TileKind::Grass | TileKind::Farmland => (0.5, ToolTier::Hand),
+            TileKind::Gravel => (0.6, ToolTier::Hand),
+            TileKind::Chest => (1.0, ToolTier::Hand),
+            TileKind::Stone => (3.0, ToolTier::Wood),
+            TileKind::Water
+            | TileKind::DungeonWall
+            | TileKind::DungeonFloor
+            | TileKind::Door
+            | TileKind::SecretWall
+            | TileKind::PressurePlate
+            | TileKind::HouseWall
+            | TileKind::HouseFloor
+            | TileKind::HouseWindow => (0.0, ToolTier::Hand),
+        };
+        let (light, warmth) = match kind {
+            TileKind::Torch => (TORCH_LIGHT, TORCH_WARMTH),
+            TileKind::Campfire => (CAMPFIRE_LIGHT, CAMPFIRE_WARMTH),
+            _ => (0, 0.0),
+        };
+        Self {
+            solid,
+            // Light shines through water and windows.
+            opaque: solid && !matches!(kind, TileKind::Water | TileKind::HouseWindow),
+            light,
+            warmth,
+            speed,
+            surface,
+            breakable: kind.broken().is_some(),
+            hardness,
+            min_tool,
+            effect: None,
+        }
+    }
+}
+
+/// What moving across a tile feels like. See [`Surface::profile`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Surface {
+    #[default]
+    Ground,
+    Undergrowth,
+    /// Packed snow and ice, which are slippery.
+    Snow,
+    Water,
+}
+
+/// How quickly things get up to speed and change direction on a [`Surface`], and the effects
+/// moving across it makes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SurfaceProfile {
+    /// How many times per second the full speed is gained or lost.
+    pub acceleration: f32,
+    /// How fast the direction of movement can turn, in radians per second.
+    pub turn_rate: f32,
+    /// Color of the particles kicked up while moving.
+    pub particle: Color,
+    /// How far the camera bobs while moving, in world units.
+    pub bob: f32,
+}
+
+impl Surface {
+    pub fn profile(self) -> SurfaceProfile {
+        let (acceleration, turn_rate, particle, bob) = match self {
+            Surface::Ground => (12.0, 20.0, Color::srgb(0.55, 0.45, 0.3), 1.0),
+            Surface::Undergrowth => (10.0, 16.0, Color::srgb(0.3, 0.55, 0.3), 1.0),
+            Surface::Snow => (2.5, 4.0, Color::srgb(0.95, 0.95, 1.0), 0.5),
+            Surface::Water => (4.0, 6.0, Color::srgb(0.7, 0.85, 1.0), 2.0),
+        };
+        SurfaceProfile {
+            acceleration,
+            turn_rate,
+            particle,
+            bob,
+        }
+    }
+}
+
+/// Converts a world-space position to the world tile coordinate containing it.
+pub fn world_pos_to_tile(world_pos: Vec2) -> IVec2 {
+    let tile_size = Vec2::new(TILE_SIZE.x, TILE_SIZE.y);
+    // Tile centers sit on multiples of the tile size, so shift by half a tile.
+    ((world_pos + tile_size / 2.0) / tile_size)
+        .floor()
+        .as_ivec2()
+}
+
+/// Converts a world tile coordinate to the world-space position of the tile's center.
+pub fn tile_to_world_pos(world_tile: IVec2) -> Vec2 {
+    world_tile.as_vec2() * Vec2::new(TILE_SIZE.x, TILE_SIZE.y)
+}
+
+/// Splits a world tile coordinate into the chunk containing it and the position within that
+/// chunk.
+pub fn world_tile_to_chunk(world_tile: IVec2) -> (IVec2, TilePos) {
+    let chunk_size = CHUNK_SIZE.as_ivec2();
+    let chunk_pos = world_tile.div_euclid(chunk_size);
+    let local = world_tile.rem_euclid(chunk_size).as_uvec2();
+    (chunk_pos, local.into())
+}
+
+/// Inverse of [`world_tile_to_chunk`].
+pub fn chunk_tile_to_world(chunk_pos: IVec2, tile_pos: TilePos) -> IVec2 {
+    chunk_pos * CHUNK_SIZE.as_ivec2() + UVec2::from(tile_pos).as_ivec2()
+}
+
+/// Finds where loaded tile entities are in the world.
+#[derive(SystemParam)]
+pub struct TileLocator<'w, 's> {
+    tiles: Query<'w, 's, (&'static TilePos, &'static TilemapId)>,
+    chunks: Query<'w, 's, &'static ChunkPosition>,
+}
+
+impl TileLocator<'_, '_> {
+    /// The world tile coordinate of the tile `entity`, or `None` if it isn't a tile of a chunk.
+    pub fn world_tile(&self, entity: Entity) -> Option<IVec2> {
+        let (tile_pos, tilemap_id) = self.tiles.get(entity).ok()?;
+        let ChunkPosition(chunk_pos) = self.chunks.get(tilemap_id.0).ok()?;
+        Some(chunk_tile_to_world(*chunk_pos, *tile_pos))
+    }
+}
+
+/// Read/write access to tiles by world tile coordinate, across all loaded chunks.
+///
+/// Use [`world_pos_to_tile`] to go from a world-space position to a world tile coordinate.
+/// Writes mark the owning chunk dirty in [`ChunkManager`] and the tile's visuals are refreshed
+/// by [`update_tile_textures`].
+#[derive(SystemParam)]
+pub struct WorldTiles<'w, 's> {
+    chunk_manager: ResMut<'w, ChunkManager>,
+    storages: Query<'w, 's, &'static TileStorage, With<ChunkMarker>>,
+    tiles: Query<'w, 's, &'static mut TileKind>,
+}
+
+impl WorldTiles<'_, '_> {
+    /// The chunk positions of every loaded chunk.
+    pub fn loaded_chunks(&self) -> impl Iterator<Item = IVec2> + '_ {
+        self.chunk_manager.spawned_chunks.keys().copied()
+    }
+
+    /// Returns the kind of the tile at `world_tile`, or `None` if its chunk is not loaded.
+    pub fn get_tile(&self, world_tile: IVec2) -> Option<TileKind> {
+        let entity = self.tile_entity(world_tile)?;
+        self.tiles.get(entity).ok().copied()
+    }
+
+    /// Changes the tile at `world_tile`, returning `false` if its chunk is not loaded.
+    pub fn set_tile(&mut self, world_tile: IVec2, kind: TileKind) -> bool {
+        let Some(entity) = self.tile_entity(world_tile) else {
+            return false;
+        };
+        let Ok(mut tile_kind) = self.tiles.get_mut(entity) else {
+            return false;
+        };
+
+        if *tile_kind != kind {
+            *tile_kind = kind;
+            let (chunk_pos, _) = world_tile_to_chunk(world_tile);
+            self.chunk_manager.dirty_chunks.insert(chunk_pos);
+        }
+        true
+    }
+
+    /// Marks the chunk of `world_tile` dirty after a change to the tile's saved state other
+    /// than its kind, such as a lock being opened.
+    pub fn mark_changed(&mut self, world_tile: IVec2) {
+        let (chunk_pos, _) = world_tile_to_chunk(world_tile);
+        self.chunk_manager.dirty_chunks.insert(chunk_pos);
+    }
+
+    /// Returns the movement properties of the tile at `world_tile`, or `None` if its chunk is not
+    /// loaded. Water bordering land counts as [`TileProperties::SHALLOW_WATER`].
+    pub fn properties(&self, world_tile: IVec2) -> Option<TileProperties> {
+        let kind = self.get_tile(world_tile)?;
+        let shallow = kind == TileKind::Water
+            && (-1..=1).any(|y| {
+                (-1..=1).any(|x| {
+                    self.get_tile(world_tile + IVec2::new(x, y))
+                        .is_some_and(|neighbor| neighbor != TileKind::Water)
+                })
+            });
+        Some(if shallow {
+            TileProperties::SHALLOW_WATER
+        } else {
+            TileProperties::of(kind)
+        })
+    }
+
+    /// Returns the entity of the tile at `world_tile`, or `None` if its chunk is not loaded.
+    pub fn tile_entity(&self, world_tile: IVec2) -> Option<Entity> {
+        let (chunk_pos, tile_pos) = world_tile_to_chunk(world_tile);
+        let chunk_entity = self.chunk_manager.spawned_chunks.get(&chunk_pos)?;
+        self.storages.get(*chunk_entity).ok()?.get(&tile_pos)
+    }
+}
+
+/// The tiles drawn over loaded tiles in the [`ChunkLayer::STACKED`] layers of their chunk.
+#[derive(SystemParam)]
+pub struct LayerTiles<'w, 's> {
+    chunk_manager: Res<'w, ChunkManager>,
+    ground: Query<'w, 's, (&'static TilePos, &'static TilemapId), With<TileKind>>,
+    chunks: Query<'w, 's, &'static ChunkPosition>,
+    storages: Query<'w, 's, &'static mut TileStorage, Without<ChunkMarker>>,
+    textures: Query<'w, 's, &'static mut TileTextureIndex, Without<TileKind>>,
+}
+
+impl LayerTiles<'_, '_> {
+    /// The tilemap of `layer` in the chunk of the ground tile `entity` and the tile's position
+    /// in it, or `None` if `entity` isn't a tile of a loaded chunk.
+    fn locate(&self, entity: Entity, layer: ChunkLayer) -> Option<(Entity, TilePos)> {
+        let (tile_pos, tilemap_id) = self.ground.get(entity).ok()?;
+        let ChunkPosition(chunk_pos) = self.chunks.get(tilemap_id.0).ok()?;
+        Some((self.chunk_manager.layer(*chunk_pos, layer)?, *tile_pos))
+    }
+
+    /// The texture of the tile drawn in `layer` over the ground tile `entity`, or `None` if
+    /// nothing is drawn there.
+    pub fn texture_mut(
+        &mut self,
+        entity: Entity,
+        layer: ChunkLayer,
+    ) -> Option<Mut<'_, TileTextureIndex>> {
+        let (tilemap, tile_pos) = self.locate(entity, layer)?;
+        let tile = self.storages.get(tilemap).ok()?.get(&tile_pos)?;
+        self.textures.get_mut(tile).ok()
+    }
+
+    /// Draws the texture at `index` in `layer` over the ground tile `entity`, animated if it has
+    /// an `animation`, spawning the tile in that layer if there isn't one yet. With no `index`,
+    /// the tile in that layer is despawned instead.
+    fn draw(
+        &mut self,
+        commands: &mut Commands,
+        entity: Entity,
+        layer: ChunkLayer,
+        index: Option<u32>,
+        animation: Option<AnimatedTile>,
+    ) {
+        let Some((tilemap, tile_pos)) = self.locate(entity, layer) else {
+            return;
+        };
+        let Ok(mut storage) = self.storages.get_mut(tilemap) else {
+            return;
+        };
+        match (storage.get(&tile_pos), index) {
+            (Some(tile), Some(index)) => {
+                if let Ok(mut texture_index) = self.textures.get_mut(tile) {
+                    texture_index.0 = index;
+                }
+                animate(commands, tile, animation);
+            }
+            (Some(tile), None) => {
+                storage.remove(&tile_pos);
+                commands.entity(tile).despawn();
+            }
+            (None, Some(index)) => {
+                let tile = commands
+                    .spawn((
+                        TileBundle {
+                            position: tile_pos,
+                            tilemap_id: TilemapId(tilemap),
+                            texture_index: TileTextureIndex(index),
+                            ..default()
+                        },
+                        ChildOf(tilemap),
+                    ))
+                    .id();
+                animate(commands, tile, animation);
+                storage.set(&tile_pos, tile);
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+fn animate(commands: &mut Commands, tile: Entity, animation: Option<AnimatedTile>) {
+    match animation {
+        Some(animation) => commands.entity(tile).insert(animation),
+        None => commands.entity(tile).remove::<AnimatedTile>(),
+    };
+}
+
+/// Keeps the textures and [`animation`](TileKind::animation)s of tiles in step with their kind
+/// and the [`Season`] in every [`ChunkLayer`], retexturing every loaded tile when the season
+/// changes. Tiles in the layers over the ground come and go as what is drawn there does.
+pub fn update_tile_textures(
+    mut commands: Commands,
+    season: Res<Season>,
+    mut tiles: Query<(Entity, Ref<TileKind>, &mut TileTextureIndex)>,
+    mut layer_tiles: LayerTiles,
+) {
+    for (entity, kind, mut texture_index) in tiles.iter_mut() {
+        if season.is_changed() || kind.is_changed() {
+            if let Some(index) = kind.layer_texture_index(ChunkLayer::Ground, *season) {
+                texture_index.0 = index;
+            }
+            animate(
+                &mut commands,
+                entity,
+                kind.animation(ChunkLayer::Ground, *season),
+            );
+            for layer in ChunkLayer::STACKED {
+                let index = kind.layer_texture_index(layer, *season);
+                let animation = kind.animation(layer, *season);
+                layer_tiles.draw(&mut commands, entity, layer, index, animation);
+            }
+        }
+    }
+}
+
+/// The color of a water tile with land to its north, east, south and west where `edges` is
+/// true, which the tile shader reads as where to draw foam instead of tinting the tile.
+pub fn shoreline_color(edges: [bool; 4]) -> TileColor {
+    let [north, east, south, west] = edges.map(f32::from);
+    TileColor(Color::linear_rgba(north, east, south, west))
+}
+
+/// Keeps the [`shoreline_color`] of water tiles in step with the tiles around them as tiles
+/// change and chunks load in. Land in chunks that aren't loaded doesn't count until they are.
+pub fn update_shorelines(
+    chunk_manager: Res<ChunkManager>,
+    storages: Query<&TileStorage, With<ChunkMarker>>,
+    changed: Query<(&TilePos, &TilemapId), Changed<TileKind>>,
+    chunks: Query<&ChunkPosition>,
+    mut tiles: Query<(&TileKind, &mut TileColor)>,
+) {
+    let mut around = HashSet::new();
+    for (tile_pos, tilemap_id) in &changed {
+        let Ok(ChunkPosition(chunk_pos)) = chunks.get(tilemap_id.0) else {
+            continue;
+        };
+        let world_tile = chunk_tile_to_world(*chunk_pos, *tile_pos);
+        around.insert(world_tile);
+        around.extend(SHORELINE_EDGES.map(|edge| world_tile + edge));
+    }
+    let tile_entity = |world_tile: IVec2| {
+        let (chunk_pos, tile_pos) = world_tile_to_chunk(world_tile);
+        let chunk_entity = chunk_manager.spawned_chunks.get(&chunk_pos)?;
+        storages.get(*chunk_entity).ok()?.get(&tile_pos)
+    };
+    for world_tile in around {
+        let Some(entity) = tile_entity(world_tile) else {
+            continue;
+        };
+        let color = match tiles.get(entity) {
+            Ok((&TileKind::Water, _)) => shoreline_color(SHORELINE_EDGES.map(|edge| {
+                tile_entity(world_tile + edge)
+                    .and_then(|neighbor| tiles.get(neighbor).ok())
+                    .is_some_and(|(kind, _)| *kind != TileKind::Water)
+            })),
+            _ => TileColor::default(),
+        };
+        if let Ok((_, mut tile_color)) = tiles.get_mut(entity)
+            && tile_color.0 != color.0
+        {
+            *tile_color = color;
+        }
+    }
+}
+
+/// The directions to the north, east, south and west neighbors of a tile.
+const SHORELINE_EDGES: [IVec2; 4] = [IVec2::Y, IVec2::X, IVec2::NEG_Y, IVec2::NEG_X];
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn negative_tiles_map_to_negative_chunks() {
+        assert_eq!(
+            world_tile_to_chunk(IVec2::new(-1, -1)),
+            (IVec2::new(-1, -1), TilePos::new(9, 9))
+        );
+        assert_eq!(
+            world_tile_to_chunk(IVec2::new(-10, 10)),
+            (IVec2::new(-1, 1), TilePos::new(0, 0))
+        );
+        assert_eq!(
+            world_tile_to_chunk(IVec2::new(-11, 9)),
+            (IVec2::new(-2, 0), TilePos::new(9, 9))
+        );
+        assert_eq!(
+            chunk_tile_to_world(IVec2::new(-2, 0), TilePos::new(9, 9)),
+            IVec2::new(-11, 9)
+        );
+    }
+
+    #[test]
+    fn world_pos_round_trips_through_tiles() {
+        for world_tile in [IVec2::ZERO, IVec2::new(-1, -1), IVec2::new(37, -42)] {
+            assert_eq!(world_pos_to_tile(tile_to_world_pos(world_tile)), world_tile);
+        }
+        // Positions up to half a tile from the center belong to that tile.
+        assert_eq!(world_pos_to_tile(Vec2::new(7.9, -7.9)), IVec2::ZERO);
+        assert_eq!(world_pos_to_tile(Vec2::new(-8.1, 8.0)), IVec2::new(-1, 1));
+    }
+
+    fn spawn_test_chunk(world: &mut World, chunk_pos: IVec2) {
+        let chunk_entity = world.spawn(ChunkMarker).id();
+        let mut storage = TileStorage::empty(CHUNK_SIZE.into());
+        for x in 0..CHUNK_SIZE.x {
+            for y in 0..CHUNK_SIZE.y {
+                let tile_pos = TilePos::new(x, y);
+                storage.set(&tile_pos, world.spawn(TileKind::Grass).id());
+            }
+        }
+        world.entity_mut(chunk_entity).insert(storage);
+        world
+            .resource_mut::<ChunkManager>()
+            .spawned_chunks
+            .insert(chunk_pos, chunk_entity);
+    }
+
+    #[test]
+    fn set_tile_marks_only_changed_chunks_dirty() {
+        let mut world = World::new();
+        world.init_resource::<ChunkManager>();
+        spawn_test_chunk(&mut world, IVec2::new(-1, 0));
+
+        world
+            .run_system_once(|mut tiles: WorldTiles| {
+                assert_eq!(tiles.get_tile(IVec2::new(-3, 4)), Some(TileKind::Grass));
+                assert!(tiles.set_tile(IVec2::new(-3, 4), TileKind::Grass));
+                assert!(tiles.set_tile(IVec2::new(-3, 5), TileKind::Stone));
+                assert_eq!(tiles.get_tile(IVec2::new(-3, 5)), Some(TileKind::Stone));
+
+                assert_eq!(tiles.get_tile(IVec2::new(3, 4)), None);
+                assert!(!tiles.set_tile(IVec2::new(3, 4), TileKind::Stone));
+            })
+            .unwrap();
+
+        let dirty = &world.resource::<ChunkManager>().dirty_chunks;
+        assert_eq!(dirty.len(), 1);
+        assert!(dirty.contains(&IVec2::new(-1, 0)));
+    }
+
+    #[test]
+    fn water_next_to_land_is_shallow() {
+        let mut world = World::new();
+        world.init_resource::<ChunkManager>();
+        spawn_test_chunk(&mut world, IVec2::new(-1, 0));
+
+        world
+            .run_system_once(|mut tiles: WorldTiles| {
+                for y in 3..=7 {
+                    for x in -7..=-3 {
+                        tiles.set_tile(IVec2::new(x, y), TileKind::Water);
+                    }
+                }
+                assert_eq!(
+                    tiles.properties(IVec2::new(-7, 5)),
+                    Some(TileProperties::SHALLOW_WATER)
+                );
+                assert!(tiles.properties(IVec2::new(-5, 5)).unwrap().solid);
+                assert_eq!(tiles.properties(IVec2::new(-8, 5)).unwrap().speed, 1.0);
+                assert_eq!(tiles.properties(IVec2::new(0, 5)), None);
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn animations_cycle_through_frames_of_their_own_kind() {
+        for season in Season::ALL {
+            for layer in ChunkLayer::ALL {
+                for kind in [TileKind::Water, TileKind::Forest, TileKind::Grass] {
+                    let Some(animation) = kind.animation(layer, season) else {
+                        continue;
+                    };
+                    assert!(animation.end > animation.start + 1);
+                    for frame in animation.start..animation.end {
+                        assert_eq!(TileKind::from_texture_index(frame), Some(kind));
+                    }
+                }
+            }
+        }
+        let flowers = |season| TileKind::Forest.animation(ChunkLayer::Decoration, season);
+        assert!(flowers(Season::Summer).is_some() && flowers(Season::Winter).is_none());
+        assert!(
+            TileKind::Stone
+                .animation(ChunkLayer::Ground, Season::Summer)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn layers_draw_what_stands_on_the_ground_over_it() {
+        let drawn = |kind: TileKind, season| {
+            ChunkLayer::ALL.map(|layer| kind.layer_texture_index(layer, season))
+        };
+        let grass = TileKind::Grass.texture_index();
+        assert_eq!(
+            drawn(TileKind::Grass, Season::Summer),
+            [Some(grass), None, None, None]
+        );
+        assert_eq!(
+            drawn(TileKind::Chest, Season::Summer),
+            [
+                Some(grass),
+                None,
+                Some(TileKind::Chest.texture_index()),
+                None
+            ]
+        );
+        assert_eq!(
+            drawn(TileKind::Crop, Season::Winter),
+            [
+                Some(TileKind::Farmland.texture_index()),
+                Some(TileKind::Crop.texture_index()),
+                None,
+                Some(SNOW_OVERLAY)
+            ]
+        );
+        // Snowed-over grass is drawn as such on the ground.
+        assert_eq!(
+            drawn(TileKind::Torch, Season::Winter)[0],
+            Some(TileKind::Grass.seasonal_texture_index(Season::Winter))
+        );
+        assert_eq!(drawn(TileKind::Stone, Season::Summer)[3], None);
+        assert_eq!(drawn(TileKind::Water, Season::Winter)[3], None);
+    }
+
+    #[test]
+    fn layer_tiles_come_and_go_with_what_is_drawn_in_them() {
+        let mut world = World::new();
+        world.init_resource::<ChunkManager>();
+        world.init_resource::<Season>();
+        let chunk = world.spawn(ChunkPosition(IVec2::ZERO)).id();
+        let layers =
+            ChunkLayer::STACKED.map(|_| world.spawn(TileStorage::empty(CHUNK_SIZE.into())).id());
+        let mut chunk_manager = world.resource_mut::<ChunkManager>();
+        chunk_manager.spawned_chunks.insert(IVec2::ZERO, chunk);
+        chunk_manager.layers.insert(IVec2::ZERO, layers);
+        let tile_pos = TilePos::new(2, 3);
+        let tile = world
+            .spawn((
+                TileKind::Chest,
+                tile_pos,
+                TilemapId(chunk),
+                TileTextureIndex(0),
+            ))
+            .id();
+
+        let layer_tile = |world: &World, layer: ChunkLayer| {
+            let storage = world
+                .get::<TileStorage>(layers[layer as usize - 1])
+                .unwrap();
+            let tile = storage.get(&tile_pos)?;
+            Some(world.get::<TileTextureIndex>(tile).unwrap().0)
+        };
+        world.run_system_once(update_tile_textures).unwrap();
+        assert_eq!(
+            layer_tile(&world, ChunkLayer::Object),
+            Some(TileKind::Chest.texture_index())
+        );
+        assert_eq!(layer_tile(&world, ChunkLayer::Decoration), None);
+
+        world.insert_resource(Season::Winter);
+        *world.get_mut::<TileKind>(tile).unwrap() = TileKind::Crop;
+        world.run_system_once(update_tile_textures).unwrap();
+        assert_eq!(layer_tile(&world, ChunkLayer::Object), None);
+        assert_eq!(
+            layer_tile(&world, ChunkLayer::Decoration),
+            Some(TileKind::Crop.texture_index())
+        );
+        assert_eq!(layer_tile(&world, ChunkLayer::Overlay), Some(SNOW_OVERLAY));
+        assert_eq!(
+            world.get::<TileTextureIndex>(tile).unwrap().0,
+            TileKind::Farmland.texture_index()
+        );
+    }
+
+    #[test]
+    fn shorelines_follow_the_land_around_water() {
+        let mut world = World::new();
+        world.init_resource::<ChunkManager>();
+        let chunk = world.spawn((ChunkMarker, ChunkPosition(IVec2::ZERO))).id();
+        let mut storage = TileStorage::empty(CHUNK_SIZE.into());
+        for x in 0..CHUNK_SIZE.x {
+            for y in 0..CHUNK_SIZE.y {
+                let kind = if y == 3 && (3..5).contains(&x) {
+                    TileKind::Water
+                } else {
+                    TileKind::Grass
+                };
+                let tile_pos = TilePos::new(x, y);
+                let tile = world
+                    .spawn((kind, tile_pos, TilemapId(chunk), TileColor::default()))
+                    .id();
+                storage.set(&tile_pos, tile);
+            }
+        }
+        let pond = storage.get(&TilePos::new(3, 3)).unwrap();
+        let east = storage.get(&TilePos::new(4, 3)).unwrap();
+        world.entity_mut(chunk).insert(storage);
+        world
+            .resource_mut::<ChunkManager>()
+            .spawned_chunks
+            .insert(IVec2::ZERO, chunk);
+
+        let color = |world: &World| world.get::<TileColor>(pond).unwrap().0;
+        world.run_system_once(update_shorelines).unwrap();
+        assert_eq!(color(&world), shoreline_color([true, false, true, true]).0);
+        // Filling in the water next to it moves the shore.
+        *world.get_mut::<TileKind>(east).unwrap() = TileKind::Grass;
+        world.run_system_once(update_shorelines).unwrap();
+        assert_eq!(color(&world), shoreline_color([true; 4]).0);
+        assert_eq!(
+            world.get::<TileColor>(east).unwrap().0,
+            TileColor::default().0
+        );
+    }
+
+    #[test]
+    fn seasons_retexture_grass_and_forests_only() {
+        let kinds = [
+            TileKind::Grass,
+            TileKind::Forest,
+            TileKind::Snow,
+            TileKind::Farmland,
+        ];
+        for season in Season::ALL {
+            for kind in kinds {
+                let index = kind.seasonal_texture_index(season);
+                assert_eq!(TileKind::from_texture_index(index), Some(kind));
+            }
+        }
+        assert_eq!(
+            TileKind::Grass.seasonal_texture_index(Season::Summer),
+            TileKind::Grass.texture_index()
+        );
+        assert_ne!(
+            TileKind::Grass.seasonal_texture_index(Season::Winter),
+            TileKind::Grass.texture_index()
+        );
+        assert_eq!(
+            TileKind::Snow.seasonal_texture_index(Season::Autumn),
+            TileKind::Snow.texture_index()
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+use crate::tiles::{TileKind, TileProperties};
+
+/// How good a tool is at breaking tiles, from bare hands up. Tiles can require a minimum tier,
+/// see [`TileProperties::min_tool`].
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub enum ToolTier {
+    #[default]
+    Hand,
+    Wood,
+    Stone,
+    Iron,
+}
+
+impl ToolTier {
+    /// How many times faster than bare hands the tool breaks tiles.
+    pub fn speed(self) -> f32 {
+        match self {
+            ToolTier::Hand => 1.0,
+            ToolTier::Wood => 2.0,
+            ToolTier::Stone => 3.0,
+            ToolTier::Iron => 5.0,
+        }
+    }
+}
+
+/// Seconds it takes to break a `kind` tile with `tool`, or `None` if the tile can't be broken
+/// or needs a better tool.
+pub fn break_time(kind: TileKind, tool: ToolTier) -> Option<f32> {
+    let properties = TileProperties::of(kind);
+    (properties.breakable && tool >= properties.min_tool)
+        .then(|| properties.hardness / tool.speed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn better_tools_break_faster_and_stone_needs_one() {
+        assert_eq!(break_time(TileKind::Stone, ToolTier::Hand), None);
+        assert_eq!(break_time(TileKind::Water, ToolTier::Iron), None);
+        let wood = break_time(TileKind::Stone, ToolTier::Wood).unwrap();
+        let iron = break_time(TileKind::Stone, ToolTier::Iron).unwrap();
+        assert!(iron < wood);
+        assert!(break_time(TileKind::Snow, ToolTier::Hand).unwrap() < wood);
+    }
+}
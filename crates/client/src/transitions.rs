@@ -0,0 +1,263 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::GameState;
+use crate::health::{LifeState, Respawn};
+
+/// Seconds it takes to cover or reveal the whole screen.
+const TRANSITION_SECS: f32 = 0.4;
+
+/// How many cells the screen dissolves in, across and down. At the default 320 by 180 view they
+/// are 10 pixels wide.
+const DISSOLVE_CELLS: UVec2 = UVec2::new(32, 18);
+
+/// Covers the screen with black over the game and its menus to hide jumps: it fades in on
+/// entering the main menu and the world, fades out when the player dies and dissolves back on
+/// respawn. Entering a dungeon dissolves to black and back. Other plugins drive it through the
+/// [`ScreenTransition`] resource.
+pub struct TransitionsPlugin;
+
+impl Plugin for TransitionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenTransition>()
+            .add_systems(Startup, spawn_overlay)
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                |mut transition: ResMut<ScreenTransition>| transition.reveal(TransitionStyle::Fade),
+            )
+            .add_systems(
+                OnEnter(GameState::Playing),
+                |mut transition: ResMut<ScreenTransition>| transition.reveal(TransitionStyle::Fade),
+            )
+            .add_systems(
+                OnEnter(LifeState::Dead),
+                |mut transition: ResMut<ScreenTransition>| transition.cover(TransitionStyle::Fade),
+            )
+            .add_observer(|_: On<Respawn>, mut transition: ResMut<ScreenTransition>| {
+                transition.reveal(TransitionStyle::Dissolve);
+            })
+            .add_systems(Update, (advance_transition, draw_overlay).chain());
+    }
+}
+
+/// How the screen goes black.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TransitionStyle {
+    /// The whole screen darkens evenly.
+    #[default]
+    Fade,
+    /// Blocky cells of the screen go black one by one, in a scattered order.
+    Dissolve,
+}
+
+/// Where a [`ScreenTransition`] is at.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum TransitionPhase {
+    #[default]
+    Clear,
+    /// Going black over `secs` of the [`TRANSITION_SECS`], then revealing again if `then_reveal`.
+    Covering { secs: f32, then_reveal: bool },
+    /// Black until it is told to reveal.
+    Covered,
+    /// Coming back from black over `secs` of the [`TRANSITION_SECS`].
+    Revealing { secs: f32 },
+}
+
+/// The state of the black overlay.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScreenTransition {
+    style: TransitionStyle,
+    phase: TransitionPhase,
+}
+
+impl ScreenTransition {
+    /// Goes black in `style` from however covered the screen is now, and stays that way.
+    pub fn cover(&mut self, style: TransitionStyle) {
+        self.start_covering(style, false);
+    }
+
+    /// Goes black in `style` and comes straight back.
+    pub fn cover_and_reveal(&mut self, style: TransitionStyle) {
+        self.start_covering(style, true);
+    }
+
+    /// Comes back from black in `style`, starting fully covered.
+    pub fn reveal(&mut self, style: TransitionStyle) {
+        self.style = style;
+        self.phase = TransitionPhase::Revealing { secs: 0.0 };
+    }
+
+    fn start_covering(&mut self, style: TransitionStyle, then_reveal: bool) {
+        let secs = self.coverage() * TRANSITION_SECS;
+        self.style = style;
+        self.phase = TransitionPhase::Covering { secs, then_reveal };
+    }
+
+    /// How much of the screen is covered, from 0 to 1.
+    pub fn coverage(&self) -> f32 {
+        match self.phase {
+            TransitionPhase::Clear => 0.0,
+            TransitionPhase::Covering { secs, .. } => secs / TRANSITION_SECS,
+            TransitionPhase::Covered => 1.0,
+            TransitionPhase::Revealing { secs } => 1.0 - secs / TRANSITION_SECS,
+        }
+    }
+
+    fn advance(&mut self, delta_secs: f32) {
+        self.phase = match self.phase {
+            TransitionPhase::Covering { secs, then_reveal } => {
+                let secs = secs + delta_secs;
+                if secs < TRANSITION_SECS {
+                    TransitionPhase::Covering { secs, then_reveal }
+                } else if then_reveal {
+                    TransitionPhase::Revealing { secs: 0.0 }
+                } else {
+                    TransitionPhase::Covered
+                }
+            }
+            TransitionPhase::Revealing { secs } if secs + delta_secs < TRANSITION_SECS => {
+                TransitionPhase::Revealing {
+                    secs: secs + delta_secs,
+                }
+            }
+            TransitionPhase::Revealing { .. } => TransitionPhase::Clear,
+            phase => phase,
+        };
+    }
+}
+
+/// The opacity of a cell of the overlay whose turn to go black comes at `threshold`, between 0
+/// and 1, when `coverage` of the screen is covered.
+pub fn cell_alpha(style: TransitionStyle, coverage: f32, threshold: f32) -> f32 {
+    match style {
+        TransitionStyle::Fade => coverage,
+        TransitionStyle::Dissolve if coverage > threshold => 1.0,
+        TransitionStyle::Dissolve => 0.0,
+    }
+}
+
+/// When the cell at `cell` goes black in a dissolve. Hashed so neighbours dissolve at unrelated
+/// times.
+fn cell_threshold(cell: UVec2) -> f32 {
+    let hash = (cell.x.wrapping_mul(0x9e37_79b9) ^ cell.y.wrapping_mul(0x85eb_ca6b))
+        .wrapping_mul(0xc2b2_ae35);
+    (hash >> 24) as f32 / 256.0
+}
+
+#[derive(Component)]
+struct TransitionOverlay;
+
+fn overlay_image(data: Vec<u8>) -> Image {
+    let mut image = Image::new(
+        Extent3d {
+            width: DISSOLVE_CELLS.x,
+            height: DISSOLVE_CELLS.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+// Like the camera, the overlay lasts the whole session so it can cover state changes.
+fn spawn_overlay(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let data = vec![0; (DISSOLVE_CELLS.x * DISSOLVE_CELLS.y * 4) as usize];
+    commands.spawn((
+        Name::new("Transition overlay"),
+        TransitionOverlay,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        ImageNode::new(images.add(overlay_image(data))),
+        // Over every other UI node.
+        GlobalZIndex(i32::MAX),
+        Pickable::IGNORE,
+        Visibility::Hidden,
+    ));
+}
+
+fn advance_transition(time: Res<Time<Real>>, mut transition: ResMut<ScreenTransition>) {
+    if transition.phase != TransitionPhase::Clear && transition.phase != TransitionPhase::Covered {
+        transition.advance(time.delta_secs());
+    }
+}
+
+fn draw_overlay(
+    transition: Res<ScreenTransition>,
+    overlay: Single<(&ImageNode, &mut Visibility), With<TransitionOverlay>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if !transition.is_changed() {
+        return;
+    }
+    let (image_node, mut visibility) = overlay.into_inner();
+    let coverage = transition.coverage();
+    *visibility = if coverage > 0.0 {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    let Some(data) = images
+        .get_mut(&image_node.image)
+        .and_then(|image| image.data.as_mut())
+    else {
+        return;
+    };
+    for y in 0..DISSOLVE_CELLS.y {
+        for x in 0..DISSOLVE_CELLS.x {
+            let alpha = cell_alpha(transition.style, coverage, cell_threshold(UVec2::new(x, y)));
+            let index = ((y * DISSOLVE_CELLS.x + x) * 4) as usize;
+            data[index + 3] = (alpha * 255.0).round() as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transitions_cover_and_reveal_the_screen() {
+        let mut transition = ScreenTransition::default();
+        transition.cover_and_reveal(TransitionStyle::Dissolve);
+        transition.advance(TRANSITION_SECS / 2.0);
+        assert_eq!(transition.coverage(), 0.5);
+        transition.advance(TRANSITION_SECS / 2.0);
+        assert_eq!(transition.coverage(), 1.0);
+        transition.advance(TRANSITION_SECS);
+        assert_eq!(transition.phase, TransitionPhase::Clear);
+
+        // Covering halfway through a reveal picks up from where it was.
+        transition.reveal(TransitionStyle::Fade);
+        transition.advance(TRANSITION_SECS / 4.0);
+        transition.cover(TransitionStyle::Fade);
+        assert!((transition.coverage() - 0.75).abs() < 1e-5);
+        transition.advance(TRANSITION_SECS);
+        assert_eq!(transition.phase, TransitionPhase::Covered);
+
+        // A dissolve covers more and more cells until every one is black.
+        let cells = (0..DISSOLVE_CELLS.x)
+            .flat_map(|x| (0..DISSOLVE_CELLS.y).map(move |y| cell_threshold(UVec2::new(x, y))));
+        let covered = |coverage| {
+            cells
+                .clone()
+                .filter(|threshold| {
+                    cell_alpha(TransitionStyle::Dissolve, coverage, *threshold) == 1.0
+                })
+                .count()
+        };
+        assert_eq!(covered(0.0), 0);
+        assert!(covered(0.3) < covered(0.6));
+        assert_eq!(covered(1.0), (DISSOLVE_CELLS.x * DISSOLVE_CELLS.y) as usize);
+        assert_eq!(cell_alpha(TransitionStyle::Fade, 0.3, 0.9), 0.3);
+    }
+}
@@ -0,0 +1,591 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use bevy::ecs::entity_disabling::Disabled;
+use bevy::ecs::query::QueryData;
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+use bevy_asset_loader::prelude::*;
+use bevy_ecs_tilemap::prelude::TilePos;
+use bevy_rand::prelude::*;
+use rand::Rng;
+
+use crate::GameState;
+use crate::ai::{AddBehavior, AiSystems, Behavior};
+use crate::chunk::{CHUNK_SIZE, ChunkLoaded, ChunkManager, Freeze, TILE_SIZE};
+use crate::clock::GameClock;
+use crate::collision::{TileCollider, walk};
+use crate::debug_placer::{RegisterSpawnable, SpawnCategory};
+use crate::dialogue::{DialogueEvent, Talker};
+use crate::hit_feedback::spawn_floating_text;
+use crate::inventory::{Inventory, ItemId, ItemRegistry, ItemStack};
+use crate::item_drops::spawn_item_drop;
+use crate::lighting::LightSource;
+use crate::localization::Localization;
+use crate::pathfinding::{FindPath, LongPath, Path};
+use crate::persistence::ChunkData;
+use crate::player::{Player, Velocity};
+use crate::sprite_animation::AnimatedSprite;
+use crate::tiles::{
+    TileKind, WorldTiles, chunk_tile_to_world, tile_to_world_pos, world_pos_to_tile,
+    world_tile_to_chunk,
+};
+use crate::waypoints::Waypoints;
+use crate::worldgen::{Biome, WorldSeed, WorldgenPreset, biome_at_chunk, chunk_hash};
+use crate::y_sort::YSort;
+
+/// One in this many plains chunks has a village built onto it.
+const VILLAGE_RARITY: u32 = 10;
+
+/// Tells villages apart from the other structures picked by [`chunk_hash`].
+const VILLAGE_SALT: u32 = 0x5f41_7a2d;
+
+/// A village filling its chunk, top row first: `.` is grass, `#` a house wall, `O` a window,
+/// `,` a house floor, `F` farmland and `B` the bed of a villager. Villagers work either the field
+/// tile `W` or the shop counter `K`, making the lower house the village shop. Beds and work tiles
+/// are handed out to villagers in the order they appear.
+const VILLAGE: [&str; CHUNK_SIZE.y as usize] = [
+    ".#####.FF.",
+    ".#B,,#.WF.",
+    ".#,,,O.FF.",
+    ".##,##.FF.",
+    "..........",
+    ".##,##.FF.",
+    ".#,,,O.FF.",
+    ".#B,K#.FF.",
+    ".#####.FF.",
+    "..........",
+];
+
+/// What the village shop trades, each asked for by the [`DialogueEvent`] of its name.
+const TRADES: [Trade; 2] = [
+    Trade {
+        event: "buy_seeds",
+        pays: ("wheat", 1),
+        gets: ("seeds", 3),
+    },
+    Trade {
+        event: "buy_tonic",
+        pays: ("wheat", 5),
+        gets: ("herbal_tonic", 1),
+    },
+];
+
+/// The dialogue of shopkeepers during and outside of their shop's opening hours.
+const SHOP_OPEN_DIALOGUE: &str = "shop_open";
+const SHOP_CLOSED_DIALOGUE: &str = "shop_closed";
+
+/// How brightly lit windows shine.
+const WINDOW_LIGHT: u8 = 6;
+
+const TRADE_COLOR: Color = Color::srgb(0.95, 0.8, 0.45);
+
+/// Walking speed of villagers on grass, in world units per second.
+pub const VILLAGER_SPEED: f32 = 30.0;
+
+/// How fast villagers bob up and down, in frames per second.
+const VILLAGER_IDLE_FPS: f32 = 3.0;
+
+/// The times of day villagers get up, start and stop working, head home at dusk and go to bed,
+/// see [`GameClock::time_of_day`]. Shops are open while their shopkeeper works.
+pub const WAKE_TIME: f32 = 0.25;
+pub const WORK_START: f32 = 0.35;
+pub const WORK_END: f32 = 0.7;
+pub const HOME_TIME: f32 = 0.75;
+pub const BEDTIME: f32 = 0.875;
+
+/// How far from the middle of their village villagers wander, in world units.
+const WANDER_RADIUS: f32 = 4.0 * TILE_SIZE.x;
+
+/// How long villagers head somewhere before wandering off somewhere else, in seconds.
+const WANDER_SECS: Range<f32> = 4.0..10.0;
+
+/// Seconds between moves of the villagers frozen in unloaded chunks.
+pub const OFFSCREEN_TICK_SECS: u64 = 5;
+
+/// How close a villager has to come to a waypoint before heading for the next one.
+const WAYPOINT_REACHED: f32 = 2.0;
+
+/// Builds small villages of a house, a shop and a field onto some of the plains chunks as they
+/// are generated, with a villager for each building. Villagers keep to a schedule on the
+/// [`GameClock`]: they work the field or the shop counter through the day, wander about the
+/// village before and after, head home at dusk and sleep in their beds at night, taking a
+/// [`LongPath`] to wherever they are headed so they find their way back from however far they
+/// strayed. The shopkeeper trades wheat for [`TRADES`] while at work and turns customers away
+/// otherwise, and the windows light up from dusk until the villagers go to bed. While the chunk
+/// they are in is unloaded villagers are frozen and only simulated coarsely, jumping straight to
+/// where their schedule has them every [`OFFSCREEN_TICK_SECS`]. Their village doesn't get new
+/// villagers while they are around. Villages are marked on the [`Waypoints`] once found.
+pub struct VillagersPlugin;
+
+impl Plugin for VillagersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_behavior::<VillagerState>()
+            .add_observer(add_villager_sprite)
+            .register_spawnable("Villager", SpawnCategory::Mob, |commands, pos| {
+                let village = world_tile_to_chunk(world_pos_to_tile(pos)).0;
+                spawn_villager(
+                    commands,
+                    Villager {
+                        village,
+                        bed: pos,
+                        work: pos,
+                        keeps_shop: false,
+                    },
+                );
+            })
+            .add_systems(
+                Update,
+                (
+                    spawn_villagers.run_if(on_message::<ChunkLoaded>),
+                    follow_schedule,
+                    move_villagers,
+                    open_shops,
+                    light_windows,
+                    trade.run_if(on_message::<DialogueEvent>),
+                )
+                    .chain()
+                    .after(AiSystems)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                simulate_frozen_villagers
+                    .run_if(on_timer(Duration::from_secs(OFFSCREEN_TICK_SECS)))
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct VillagerAssets {
+    /// Facing right.
+    #[asset(path = "villager.png")]
+    pub villager: Handle<Image>,
+    /// Two frames of a villager bobbing up and down.
+    #[asset(texture_atlas_layout(tile_size_x = 16, tile_size_y = 16, columns = 2, rows = 1))]
+    pub villager_layout: Handle<TextureAtlasLayout>,
+}
+
+#[derive(Component, Clone, Debug)]
+#[require(Velocity, Freeze, Behavior<VillagerState>, YSort)]
+pub struct Villager {
+    /// The chunk of the village the villager lives in.
+    pub village: IVec2,
+    pub bed: Vec2,
+    pub work: Vec2,
+    /// Works the counter of the village shop rather than the field, and can be talked to.
+    pub keeps_shop: bool,
+}
+
+impl Villager {
+    /// The middle of the village, which the villager wanders around.
+    pub fn square(&self) -> Vec2 {
+        let middle = chunk_tile_to_world(self.village, (CHUNK_SIZE / 2).into());
+        tile_to_world_pos(middle)
+    }
+}
+
+/// What a [`Villager`] is doing, picked by the time of day.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VillagerState {
+    #[default]
+    Sleeping,
+    Working,
+    Wandering,
+    /// Back home for the evening.
+    Home,
+}
+
+impl VillagerState {
+    /// What villagers do at `time_of_day`.
+    pub fn scheduled(time_of_day: f32) -> Self {
+        if !(WAKE_TIME..BEDTIME).contains(&time_of_day) {
+            Self::Sleeping
+        } else if (WORK_START..WORK_END).contains(&time_of_day) {
+            Self::Working
+        } else if time_of_day >= HOME_TIME {
+            Self::Home
+        } else {
+            Self::Wandering
+        }
+    }
+}
+
+/// Whether the chunk at `chunk_pos` has a village built onto it.
+pub fn has_village(world_seed: u64, preset: &WorldgenPreset, chunk_pos: IVec2) -> bool {
+    chunk_hash(chunk_pos, world_seed, VILLAGE_SALT).is_multiple_of(VILLAGE_RARITY)
+        && biome_at_chunk(chunk_pos, world_seed, preset) == Biome::Plains
+}
+
+/// Builds the village of `chunk_pos`, if it has one, into `data`, its generated tiles.
+pub fn build_village(
+    world_seed: u64,
+    preset: &WorldgenPreset,
+    chunk_pos: IVec2,
+    data: &mut ChunkData,
+) {
+    if !has_village(world_seed, preset, chunk_pos) {
+        return;
+    }
+    for (row, line) in VILLAGE.iter().enumerate() {
+        let y = CHUNK_SIZE.y as usize - 1 - row;
+        for (x, symbol) in line.chars().enumerate() {
+            data.tiles[y * CHUNK_SIZE.x as usize + x] = match symbol {
+                '#' => TileKind::HouseWall,
+                'O' => TileKind::HouseWindow,
+                ',' | 'B' | 'K' => TileKind::HouseFloor,
+                'F' | 'W' => TileKind::Farmland,
+                _ => TileKind::Grass,
+            };
+        }
+    }
+}
+
+/// The tiles of the village layout marked with any of `symbols`, in the order they appear, with
+/// their symbol.
+fn village_tiles(symbols: &'static [char]) -> impl Iterator<Item = (UVec2, char)> {
+    VILLAGE.iter().enumerate().flat_map(move |(row, line)| {
+        let y = CHUNK_SIZE.y - 1 - row as u32;
+        line.match_indices(symbols)
+            .map(move |(x, symbol)| (UVec2::new(x as u32, y), symbol.chars().next().unwrap()))
+    })
+}
+
+/// The world position of the tile at `tile_pos` of the village at `village`.
+fn village_pos(village: IVec2, tile_pos: UVec2) -> Vec2 {
+    tile_to_world_pos(chunk_tile_to_world(village, tile_pos.into()))
+}
+
+/// The villagers living in the village at `village`, one for each bed.
+pub fn village_residents(village: IVec2) -> impl Iterator<Item = Villager> {
+    village_tiles(&['B'])
+        .zip(village_tiles(&['W', 'K']))
+        .map(move |((bed, _), (work, symbol))| Villager {
+            village,
+            bed: village_pos(village, bed),
+            work: village_pos(village, work),
+            keeps_shop: symbol == 'K',
+        })
+}
+
+pub fn spawn_villager(commands: &mut Commands, villager: Villager) {
+    let keeps_shop = villager.keeps_shop;
+    let mut entity = commands.spawn((
+        Name::new("Villager"),
+        Transform::from_translation(villager.bed.extend(0.9)),
+        villager,
+        DespawnOnExit(GameState::Playing),
+        TileCollider {
+            half_size: Vec2::new(4.0, 4.0),
+        },
+    ));
+    if keeps_shop {
+        entity.insert(Talker {
+            dialogue: SHOP_CLOSED_DIALOGUE.into(),
+        });
+    }
+}
+
+/// A window of a village house, lit while the villagers are home for the evening.
+#[derive(Component)]
+#[require(Freeze)]
+struct WindowLight;
+
+/// A trade in [`TRADES`].
+struct Trade {
+    event: &'static str,
+    /// The item and count the player pays.
+    pays: (&'static str, u32),
+    /// The item and count the player gets for it.
+    gets: (&'static str, u32),
+}
+
+fn add_villager_sprite(
+    add: On<Add, Villager>,
+    mut commands: Commands,
+    villager_assets: Res<VillagerAssets>,
+) {
+    commands.entity(add.entity).insert((
+        Sprite::from_atlas_image(
+            villager_assets.villager.clone(),
+            TextureAtlas::from(villager_assets.villager_layout.clone()),
+        ),
+        AnimatedSprite::looping(0, 2, VILLAGER_IDLE_FPS),
+    ));
+}
+
+fn spawn_villagers(
+    mut commands: Commands,
+    mut loaded: MessageReader<ChunkLoaded>,
+    (world_seed, preset): (Res<WorldSeed>, Res<WorldgenPreset>),
+    // Villagers frozen in another chunk still live in their village.
+    villagers: Query<&Villager, Allow<Disabled>>,
+    mut waypoints: ResMut<Waypoints>,
+) {
+    for ChunkLoaded(chunk_pos, _) in loaded.read().copied() {
+        if !has_village(world_seed.seed, &preset, chunk_pos) {
+            continue;
+        }
+        let middle = TilePos::new(CHUNK_SIZE.x / 2, CHUNK_SIZE.y / 2);
+        waypoints.mark(
+            &format!("village {chunk_pos}"),
+            "Village",
+            chunk_tile_to_world(chunk_pos, middle),
+        );
+        if villagers
+            .iter()
+            .any(|villager| villager.village == chunk_pos)
+        {
+            continue;
+        }
+        for villager in village_residents(chunk_pos) {
+            spawn_villager(&mut commands, villager);
+        }
+        for (tile_pos, _) in village_tiles(&['O']) {
+            commands.spawn((
+                Name::new("Window light"),
+                WindowLight,
+                DespawnOnExit(GameState::Playing),
+                Transform::from_translation(village_pos(chunk_pos, tile_pos).extend(0.0)),
+            ));
+        }
+    }
+}
+
+/// Sends villagers to bed, to work or off wandering when the schedule says so.
+fn follow_schedule(
+    mut commands: Commands,
+    clock: Res<GameClock>,
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+    mut villagers: Query<(Entity, &Villager, &mut Behavior<VillagerState>)>,
+) {
+    let scheduled = VillagerState::scheduled(clock.time_of_day());
+    for (entity, villager, mut behavior) in &mut villagers {
+        let goal = match scheduled {
+            VillagerState::Wandering => {
+                if *behavior.state() == scheduled && !behavior.timed_out() {
+                    continue;
+                }
+                behavior.enter_for(scheduled, global_rng.random_range(WANDER_SECS));
+                let offset = Vec2::new(
+                    global_rng.random_range(-1.0..1.0),
+                    global_rng.random_range(-1.0..1.0),
+                );
+                villager.square() + offset * WANDER_RADIUS
+            }
+            VillagerState::Sleeping | VillagerState::Working | VillagerState::Home => {
+                if !behavior.enter(scheduled) {
+                    continue;
+                }
+                if scheduled == VillagerState::Working {
+                    villager.work
+                } else {
+                    villager.bed
+                }
+            }
+        };
+        commands.entity(entity).insert(LongPath::to(goal));
+    }
+}
+
+/// Moves the villagers of unloaded chunks straight to where their schedule has them, without any
+/// walking or pathing, and thaws those that end up in a loaded chunk.
+fn simulate_frozen_villagers(
+    mut commands: Commands,
+    clock: Res<GameClock>,
+    mut chunk_manager: ResMut<ChunkManager>,
+    mut villagers: Query<
+        (
+            Entity,
+            &Villager,
+            &mut Behavior<VillagerState>,
+            &mut Transform,
+        ),
+        With<Disabled>,
+    >,
+) {
+    let scheduled = VillagerState::scheduled(clock.time_of_day());
+    for (entity, villager, mut behavior, mut transform) in &mut villagers {
+        if *behavior.state() == scheduled {
+            continue;
+        }
+        let spot = match scheduled {
+            VillagerState::Sleeping | VillagerState::Home => villager.bed,
+            VillagerState::Working => villager.work,
+            VillagerState::Wandering => villager.square(),
+        };
+        // Timed out, so a thawed villager picks somewhere to wander to straight away.
+        behavior.enter_for(scheduled, 0.0);
+        transform.translation = spot.extend(transform.translation.z);
+        let mut villager_entity = commands.entity(entity);
+        villager_entity.remove::<(LongPath, FindPath, Path)>();
+        if chunk_manager.move_frozen(entity, world_tile_to_chunk(world_pos_to_tile(spot)).0) {
+            villager_entity.remove::<Disabled>();
+        }
+    }
+}
+
+/// What [`move_villagers`] moves villagers with.
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct VillagerMovement {
+    path: Option<&'static mut Path>,
+    collider: &'static TileCollider,
+    velocity: &'static mut Velocity,
+    transform: &'static mut Transform,
+    sprite: &'static mut Sprite,
+}
+
+fn move_villagers(
+    time: Res<Time>,
+    mut villagers: Query<VillagerMovement, With<Villager>>,
+    tiles: WorldTiles,
+) {
+    let secs = time.delta_secs();
+    if secs == 0.0 {
+        return;
+    }
+    for mut villager in &mut villagers {
+        let pos = villager.transform.translation.xy();
+        let wanted = villager
+            .path
+            .as_mut()
+            .and_then(|path| path.next_waypoint(pos, WAYPOINT_REACHED))
+            .map_or(Vec2::ZERO, |waypoint| {
+                (waypoint - pos).normalize_or_zero() * VILLAGER_SPEED
+            });
+        let velocity = &mut villager.velocity.0;
+        let moved = walk(
+            &tiles,
+            pos,
+            velocity,
+            wanted,
+            VILLAGER_SPEED,
+            villager.collider,
+            secs,
+        );
+        let moving_x = velocity.x;
+        villager.transform.translation = moved.extend(villager.transform.translation.z);
+        if moving_x != 0.0 {
+            villager.sprite.flip_x = moving_x < 0.0;
+        }
+    }
+}
+
+/// Opens the shops while their shopkeepers are at work, and closes them otherwise.
+fn open_shops(mut shopkeepers: Query<(&Behavior<VillagerState>, &mut Talker), With<Villager>>) {
+    for (behavior, mut talker) in &mut shopkeepers {
+        let dialogue = if *behavior.state() == VillagerState::Working {
+            SHOP_OPEN_DIALOGUE
+        } else {
+            SHOP_CLOSED_DIALOGUE
+        };
+        if talker.dialogue != dialogue {
+            talker.dialogue = dialogue.into();
+        }
+    }
+}
+
+fn light_windows(
+    mut commands: Commands,
+    clock: Res<GameClock>,
+    windows: Query<(Entity, Has<LightSource>), With<WindowLight>>,
+) {
+    let lit = VillagerState::scheduled(clock.time_of_day()) == VillagerState::Home;
+    for (entity, has_light) in &windows {
+        if lit && !has_light {
+            commands.entity(entity).insert(LightSource {
+                level: WINDOW_LIGHT,
+            });
+        } else if !lit && has_light {
+            commands.entity(entity).remove::<LightSource>();
+        }
+    }
+}
+
+/// Makes the trades the player picks while talking to a shopkeeper.
+fn trade(
+    mut commands: Commands,
+    mut events: MessageReader<DialogueEvent>,
+    registry: Res<ItemRegistry>,
+    localization: Res<Localization>,
+    player: Single<(&Transform, &mut Inventory), With<Player>>,
+    shopkeepers: Query<&Villager>,
+) {
+    let (transform, mut inventory) = player.into_inner();
+    let pos = transform.translation.xy();
+    for DialogueEvent { speaker, event } in events.read() {
+        let Some(trade) = TRADES.iter().find(|trade| trade.event == event) else {
+            continue;
+        };
+        if !shopkeepers
+            .get(*speaker)
+            .is_ok_and(|villager| villager.keeps_shop)
+        {
+            continue;
+        }
+        let (pays, pay_count) = (ItemId::from(trade.pays.0), trade.pays.1);
+        if !inventory.consume(&pays, pay_count) {
+            let name = localization.item_name(&registry, &pays);
+            let text =
+                localization.format("trade-needs", &[("count", &pay_count), ("item", &name)]);
+            spawn_floating_text(&mut commands, pos, text, TRADE_COLOR);
+            continue;
+        }
+        let (gets, get_count) = (ItemId::from(trade.gets.0), trade.gets.1);
+        // Whatever doesn't fit in the inventory is dropped at the player's feet.
+        let left = inventory.add(&gets, get_count, &registry);
+        if left > 0 {
+            let stack = ItemStack {
+                item: gets,
+                count: left,
+            };
+            spawn_item_drop(&mut commands, &registry, stack, pos);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::generate_chunk;
+
+    #[test]
+    fn villages_house_villagers_who_keep_to_their_schedule() {
+        let preset = WorldgenPreset::default();
+        let village = (-40..40)
+            .flat_map(|y| (-40..40).map(move |x| IVec2::new(x, y)))
+            .find(|&chunk_pos| has_village(7, &preset, chunk_pos))
+            .unwrap();
+        let data = generate_chunk(7, &preset, village);
+        let tile_at = |pos: Vec2| {
+            let (chunk_pos, tile_pos) = world_tile_to_chunk(world_pos_to_tile(pos));
+            assert_eq!(chunk_pos, village);
+            data.tiles[(tile_pos.y * CHUNK_SIZE.x + tile_pos.x) as usize]
+        };
+        // Everyone has a bed in a house, and one of them keeps the shop in theirs.
+        let residents: Vec<Villager> = village_residents(village).collect();
+        assert_eq!(residents.len(), 2);
+        for villager in &residents {
+            assert_eq!(tile_at(villager.bed), TileKind::HouseFloor);
+            assert_eq!(tile_at(villager.square()), TileKind::Grass);
+        }
+        assert_eq!(tile_at(residents[0].work), TileKind::Farmland);
+        assert!(residents[1].keeps_shop && !residents[0].keeps_shop);
+        assert_eq!(tile_at(residents[1].work), TileKind::HouseFloor);
+        let windows = data
+            .tiles
+            .iter()
+            .filter(|&&tile| tile == TileKind::HouseWindow);
+        assert_eq!(windows.count(), village_tiles(&['O']).count());
+
+        let at = VillagerState::scheduled;
+        assert_eq!(at(0.0), VillagerState::Sleeping);
+        assert_eq!(at(WAKE_TIME), VillagerState::Wandering);
+        assert_eq!(at(0.5), VillagerState::Working);
+        assert_eq!(at(WORK_END), VillagerState::Wandering);
+        assert_eq!(at(HOME_TIME), VillagerState::Home);
+        assert_eq!(at(BEDTIME), VillagerState::Sleeping);
+    }
+}
@@ -0,0 +1,236 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+use crate::persistence::WorldSave;
+use crate::player::Player;
+use crate::tiles::{tile_to_world_pos, world_pos_to_tile};
+use crate::world_map::world_map_open;
+
+/// How far away a waypoint can be, in tiles, and still point the way on screen.
+pub const INDICATOR_RANGE: f32 = 160.0;
+
+/// How far in from the edges of the view the indicators of waypoints off screen sit, in points.
+const INDICATOR_MARGIN: f32 = 24.0;
+
+const PLACED_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 220, 90);
+const MARKED_COLOR: egui::Color32 = egui::Color32::from_rgb(120, 200, 255);
+
+/// Keeps the [`Waypoints`] of the world being played and points the way to each one within
+/// [`INDICATOR_RANGE`]: an arrow at the edge of the view for those off screen and a pin over
+/// those on it, named and with their distance. The player places their own on the world map, and
+/// the game marks places with [`Waypoints::mark`].
+pub struct WaypointsPlugin;
+
+impl Plugin for WaypointsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Waypoints>()
+            .add_systems(OnEnter(GameState::Playing), load_waypoints)
+            .add_systems(
+                EguiPrimaryContextPass,
+                waypoint_indicators
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(not(world_map_open)),
+            );
+    }
+}
+
+/// A named spot in the world.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Waypoint {
+    pub name: String,
+    pub world_tile: IVec2,
+    /// What marked it, for waypoints added by the game with [`Waypoints::mark`]. `None` for the
+    /// player's own.
+    #[serde(default)]
+    pub key: Option<String>,
+}
+
+impl Waypoint {
+    pub fn color(&self) -> egui::Color32 {
+        if self.key.is_some() {
+            MARKED_COLOR
+        } else {
+            PLACED_COLOR
+        }
+    }
+}
+
+/// The waypoints of the world being played. Saved with the world.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Waypoints {
+    pub waypoints: Vec<Waypoint>,
+}
+
+impl Waypoints {
+    /// Places one of the player's own waypoints.
+    pub fn place(&mut self, name: &str, world_tile: IVec2) {
+        self.waypoints.push(Waypoint {
+            name: name.to_string(),
+            world_tile,
+            key: None,
+        });
+    }
+
+    /// Marks `world_tile` as `name` for whatever `key` stands for, such as a quest or a structure,
+    /// moving the waypoint already marked for `key` if there is one.
+    pub fn mark(&mut self, key: &str, name: &str, world_tile: IVec2) {
+        let waypoint = Waypoint {
+            name: name.to_string(),
+            world_tile,
+            key: Some(key.to_string()),
+        };
+        match self
+            .waypoints
+            .iter_mut()
+            .find(|waypoint| waypoint.key.as_deref() == Some(key))
+        {
+            Some(marked) => *marked = waypoint,
+            None => self.waypoints.push(waypoint),
+        }
+    }
+
+    /// Removes the waypoint marked for `key`, if there is one.
+    pub fn unmark(&mut self, key: &str) {
+        self.waypoints
+            .retain(|waypoint| waypoint.key.as_deref() != Some(key));
+    }
+
+    /// Removes the closest of the player's own waypoints within `radius` tiles of `world_tile`,
+    /// returning whether there was one.
+    pub fn remove_placed_near(&mut self, world_tile: IVec2, radius: f32) -> bool {
+        let closest = self
+            .waypoints
+            .iter()
+            .enumerate()
+            .filter(|(_, waypoint)| waypoint.key.is_none())
+            .map(|(index, waypoint)| {
+                let distance = (waypoint.world_tile - world_tile).as_vec2().length();
+                (index, distance)
+            })
+            .filter(|&(_, distance)| distance <= radius)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+        if let Some((index, _)) = closest {
+            self.waypoints.remove(index);
+        }
+        closest.is_some()
+    }
+}
+
+/// Where the indicator of a waypoint at `target` goes in `view`: `None` if it is well inside
+/// the view, otherwise the point `margin` in from the edge on the way from the middle of the view
+/// towards it.
+pub fn edge_indicator(view: Rect, margin: f32, target: Vec2) -> Option<Vec2> {
+    let inner = view.inflate(-margin);
+    if inner.contains(target) {
+        return None;
+    }
+    let center = inner.center();
+    let direction = target - center;
+    let scale = (inner.half_size() / direction.abs()).min_element();
+    Some(center + direction * scale)
+}
+
+fn load_waypoints(mut commands: Commands, world_save: Option<Res<WorldSave>>) {
+    let waypoints = world_save
+        .and_then(|world_save| world_save.metadata.waypoints.clone())
+        .unwrap_or_default();
+    commands.insert_resource(waypoints);
+}
+
+fn waypoint_indicators(
+    mut contexts: EguiContexts,
+    waypoints: Res<Waypoints>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+    player: Single<&Transform, With<Player>>,
+) -> Result {
+    let (camera, camera_transform) = *camera;
+    let Some(view) = camera.logical_viewport_rect() else {
+        return Ok(());
+    };
+    let player_tile = world_pos_to_tile(player.translation.xy());
+    let painter = contexts.ctx_mut()?.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("waypoint_indicators"),
+    ));
+    for waypoint in &waypoints.waypoints {
+        let distance = (waypoint.world_tile - player_tile).as_vec2().length();
+        if distance > INDICATOR_RANGE {
+            continue;
+        }
+        let world_pos = tile_to_world_pos(waypoint.world_tile).extend(0.0);
+        let Ok(screen_pos) = camera.world_to_viewport(camera_transform, world_pos) else {
+            continue;
+        };
+        let color = waypoint.color();
+        let label = format!("{} ({distance:.0})", waypoint.name);
+        let (pos, label_anchor) = match edge_indicator(view, INDICATOR_MARGIN, screen_pos) {
+            Some(edge) => {
+                let direction = (screen_pos - edge).normalize_or_zero();
+                let tip = edge + direction * 8.0;
+                let side = direction.perp() * 5.0;
+                let [tip, left, right] =
+                    [tip, edge + side, edge - side].map(|point| egui::pos2(point.x, point.y));
+                painter.add(egui::Shape::convex_polygon(
+                    vec![tip, left, right],
+                    color,
+                    egui::Stroke::new(1.0, egui::Color32::BLACK),
+                ));
+                // Keep the label on the inside of the arrow.
+                (edge - direction * 10.0, egui::Align2::CENTER_CENTER)
+            }
+            None => {
+                let pin = egui::pos2(screen_pos.x, screen_pos.y);
+                painter.circle(
+                    pin,
+                    4.0,
+                    color,
+                    egui::Stroke::new(1.0, egui::Color32::BLACK),
+                );
+                (screen_pos - Vec2::Y * 8.0, egui::Align2::CENTER_BOTTOM)
+            }
+        };
+        painter.text(
+            egui::pos2(pos.x, pos.y),
+            label_anchor,
+            label,
+            egui::FontId::proportional(12.0),
+            color,
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_replace_each_other_and_only_placed_waypoints_are_removed() {
+        let mut waypoints = Waypoints::default();
+        waypoints.place("Home", IVec2::new(3, 4));
+        waypoints.mark("quest first_harvest", "First harvest", IVec2::new(10, 0));
+        waypoints.mark("quest first_harvest", "First harvest", IVec2::new(12, 0));
+        assert_eq!(waypoints.waypoints.len(), 2);
+        assert_eq!(waypoints.waypoints[1].world_tile, IVec2::new(12, 0));
+
+        assert!(!waypoints.remove_placed_near(IVec2::new(12, 0), 2.0));
+        assert!(waypoints.remove_placed_near(IVec2::new(4, 4), 2.0));
+        waypoints.unmark("quest first_harvest");
+        assert!(waypoints.waypoints.is_empty());
+    }
+
+    #[test]
+    fn indicators_off_screen_stick_to_the_edge_towards_the_waypoint() {
+        let view = Rect::new(0.0, 0.0, 200.0, 100.0);
+        assert_eq!(edge_indicator(view, 10.0, Vec2::new(50.0, 50.0)), None);
+        // Straight right of the middle, and far off below it.
+        assert_eq!(
+            edge_indicator(view, 10.0, Vec2::new(500.0, 50.0)),
+            Some(Vec2::new(190.0, 50.0))
+        );
+        let below = edge_indicator(view, 10.0, Vec2::new(100.0, 1000.0)).unwrap();
+        assert!((below - Vec2::new(100.0, 90.0)).length() < 1e-3);
+    }
+}
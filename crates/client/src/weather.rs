@@ -0,0 +1,369 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+use bevy_asset_loader::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use bevy_rand::prelude::*;
+use bevy_seedling::prelude::*;
+use rand::Rng;
+
+use crate::GameState;
+use crate::camera_shake::CameraShake;
+use crate::chunk::{ChunkManager, ChunkPosition};
+use crate::clock::Season;
+use crate::farming::Crop;
+use crate::haptics::Haptic;
+use crate::player::Player;
+use crate::tiles::{world_pos_to_tile, world_tile_to_chunk};
+use crate::wind::Wind;
+use crate::worldgen::{Biome, WorldSeed, WorldgenPreset, biome_at_chunk};
+
+/// Real-time seconds between chances for the weather to change.
+const SPELL_SECS: u64 = 90;
+
+/// How many particles fall at once in a storm. Rain and snow use fewer of them.
+const MAX_PARTICLES: usize = 160;
+
+/// How fast particles drift in the strongest wind, in screen percent per second.
+const PARTICLE_DRIFT: f32 = 40.0;
+
+/// Extra seconds of growth crops get per second of rain, so they grow twice as fast.
+const RAIN_GROWTH_BONUS: f64 = 1.0;
+
+/// Real-time seconds between chances of thunder in a storm.
+const THUNDER_SECS: u64 = 4;
+
+/// How likely thunder is to roll every [`THUNDER_SECS`].
+const THUNDER_CHANCE: f32 = 0.25;
+
+/// Rolls the [`Weather`] at the player every [`SPELL_SECS`], from the season and the biome they
+/// are in: rain can build up into a storm, and falls as snow in winter and in cold biomes. Rain,
+/// storms and snow fall as particles over the screen that drift with the [`Wind`], each with its
+/// own ambient loop, and crops in loaded chunks grow faster while it rains. Thunder in storms
+/// shakes the camera and rumbles the gamepad. The wind can be heard
+/// in clear weather too, and every loop swells as it gusts. The weather clears up whenever a
+/// world is entered.
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Weather>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (clear_weather, spawn_particles),
+            )
+            .add_systems(
+                Update,
+                (
+                    change_weather.run_if(on_timer(Duration::from_secs(SPELL_SECS))),
+                    play_ambience.run_if(resource_changed::<Weather>),
+                    gust_ambience,
+                    fall_particles,
+                    water_crops
+                        .run_if(resource_equals(Weather::Rain).or(resource_equals(Weather::Storm))),
+                    roll_thunder
+                        .run_if(on_timer(Duration::from_secs(THUNDER_SECS)))
+                        .run_if(resource_equals(Weather::Storm)),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct WeatherAssets {
+    /// Loops seamlessly, under both rain and storms.
+    #[asset(path = "sfx/weather/rain.wav")]
+    pub rain: Handle<AudioSample>,
+    /// Loops seamlessly, while it snows.
+    #[asset(path = "sfx/weather/wind.wav")]
+    pub wind: Handle<AudioSample>,
+}
+
+/// The weather at the player.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Weather {
+    #[default]
+    Clear,
+    Rain,
+    Storm,
+    Snow,
+}
+
+impl Weather {
+    /// The weather after the next spell, for a `roll` between 0 and 1, in `season` and `biome`.
+    pub fn next(self, season: Season, biome: Biome, roll: f32) -> Self {
+        let rain_chance = match season {
+            Season::Spring | Season::Autumn => 0.4,
+            Season::Summer => 0.15,
+            Season::Winter => 0.3,
+        };
+        let next = match self {
+            Self::Clear if roll < rain_chance => Self::Rain,
+            Self::Clear => Self::Clear,
+            Self::Rain | Self::Snow if roll < 0.2 => Self::Storm,
+            Self::Rain | Self::Snow if roll < 0.6 => Self::Clear,
+            Self::Rain | Self::Snow => Self::Rain,
+            Self::Storm if roll < 0.7 => Self::Rain,
+            Self::Storm => Self::Clear,
+        };
+        let cold = season == Season::Winter || matches!(biome, Biome::Tundra | Biome::Mountains);
+        if cold && next != Self::Clear {
+            Self::Snow
+        } else {
+            next
+        }
+    }
+
+    /// How many of the [`MAX_PARTICLES`] fall.
+    fn particles(self) -> usize {
+        match self {
+            Self::Clear => 0,
+            Self::Rain => MAX_PARTICLES / 2,
+            Self::Storm => MAX_PARTICLES,
+            Self::Snow => MAX_PARTICLES * 3 / 8,
+        }
+    }
+}
+
+/// A raindrop or snowflake, falling over the screen. Positions are in percent of the screen.
+#[derive(Component)]
+struct Particle {
+    pos: Vec2,
+    /// Scales how fast it falls, so the particles don't move in lockstep.
+    speed: f32,
+}
+
+/// The looping sound of the current weather, played at `volume` in a gentle wind.
+#[derive(Component)]
+struct Ambience {
+    volume: f32,
+}
+
+fn clear_weather(mut weather: ResMut<Weather>) {
+    *weather = Weather::Clear;
+}
+
+fn change_weather(
+    mut weather: ResMut<Weather>,
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+    (world_seed, preset, season): (Res<WorldSeed>, Res<WorldgenPreset>, Res<Season>),
+    player: Single<&Transform, With<Player>>,
+) {
+    let player_chunk = world_tile_to_chunk(world_pos_to_tile(player.translation.xy())).0;
+    let biome = biome_at_chunk(player_chunk, world_seed.seed, &preset);
+    let next = weather.next(*season, biome, global_rng.random());
+    if weather.set_if_neq(next) {
+        debug!("The weather turns to {next:?}");
+    }
+}
+
+fn roll_thunder(
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+    mut shakes: MessageWriter<CameraShake>,
+    mut haptics: MessageWriter<Haptic>,
+) {
+    if global_rng.random::<f32>() < THUNDER_CHANCE {
+        shakes.write(CameraShake::THUNDER);
+        haptics.write(Haptic::Thunder);
+    }
+}
+
+fn play_ambience(
+    mut commands: Commands,
+    weather: Res<Weather>,
+    weather_assets: Res<WeatherAssets>,
+    ambience: Query<Entity, With<Ambience>>,
+) {
+    for entity in &ambience {
+        commands.entity(entity).despawn();
+    }
+    let (sample, volume) = match *weather {
+        Weather::Clear => (&weather_assets.wind, 0.3),
+        Weather::Rain => (&weather_assets.rain, 0.6),
+        Weather::Storm => (&weather_assets.rain, 1.0),
+        Weather::Snow => (&weather_assets.wind, 0.7),
+    };
+    commands.spawn((
+        Name::new("Weather ambience"),
+        Ambience { volume },
+        DespawnOnExit(GameState::Playing),
+        SamplePlayer::new(sample.clone()).looping(),
+        sample_effects![VolumeNode {
+            volume: Volume::SILENT,
+            ..default()
+        }],
+    ));
+}
+
+/// Makes the ambience louder the harder the wind blows.
+fn gust_ambience(
+    wind: Res<Wind>,
+    ambience: Query<(&Ambience, &SampleEffects)>,
+    mut volume_nodes: Query<&mut VolumeNode>,
+) -> Result {
+    for (ambience, effects) in &ambience {
+        let volume = ambience.volume * (0.5 + wind.strength);
+        volume_nodes.get_effect_mut(effects)?.volume = Volume::Linear(volume);
+    }
+    Ok(())
+}
+
+fn spawn_particles(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Weather"),
+            DespawnOnExit(GameState::Playing),
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            // Just over the night tint, so the weather is as bright at night but under the HUD.
+            GlobalZIndex(i32::MIN + 1),
+            Pickable::IGNORE,
+        ))
+        .with_children(|layer| {
+            for i in 0..MAX_PARTICLES {
+                // Low-discrepancy sequences spread the particles evenly without any clumps.
+                let i = i as f32;
+                layer.spawn((
+                    Particle {
+                        pos: Vec2::new((i * 0.618_034).fract(), (i * 0.754_878).fract()) * 100.0,
+                        speed: 0.8 + 0.4 * (i * 0.569_840).fract(),
+                    },
+                    Node {
+                        position_type: PositionType::Absolute,
+                        ..default()
+                    },
+                    BackgroundColor(Color::NONE),
+                    Visibility::Hidden,
+                    Pickable::IGNORE,
+                ));
+            }
+        });
+}
+
+fn fall_particles(
+    time: Res<Time>,
+    (weather, wind): (Res<Weather>, Res<Wind>),
+    mut particles: Query<(
+        &mut Particle,
+        &mut Node,
+        &mut BackgroundColor,
+        &mut Visibility,
+    )>,
+) {
+    // Screen percent per second, and the size and color of each particle.
+    let (velocity, size, color) = match *weather {
+        Weather::Clear => (Vec2::ZERO, Vec2::ZERO, Color::NONE),
+        Weather::Rain => (
+            Vec2::new(0.0, 90.0),
+            Vec2::new(1.0, 10.0),
+            Color::srgba(0.6, 0.7, 1.0, 0.5),
+        ),
+        Weather::Storm => (
+            Vec2::new(0.0, 140.0),
+            Vec2::new(1.0, 14.0),
+            Color::srgba(0.6, 0.7, 1.0, 0.6),
+        ),
+        Weather::Snow => (
+            Vec2::new(0.0, 12.0),
+            Vec2::splat(3.0),
+            Color::srgba(1.0, 1.0, 1.0, 0.8),
+        ),
+    };
+    // Screen y points down, world y up.
+    let drift = wind.velocity() * Vec2::new(1.0, -1.0) * PARTICLE_DRIFT;
+    let count = weather.particles();
+    let mut index = 0;
+    for (mut particle, mut node, mut background, mut visibility) in &mut particles {
+        index += 1;
+        if index > count {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+        let mut step = (velocity + drift) * particle.speed * time.delta_secs();
+        if *weather == Weather::Snow {
+            // Flakes sway from side to side as they drift down.
+            step.x += (time.elapsed_secs() * particle.speed * 2.0).sin() * 0.1;
+        }
+        // Wrap around the screen, so the particles are spread out across it at all times.
+        particle.pos = (particle.pos + step).rem_euclid(Vec2::splat(100.0));
+        node.left = Val::Percent(particle.pos.x);
+        node.top = Val::Percent(particle.pos.y);
+        node.width = Val::Px(size.x);
+        node.height = Val::Px(size.y);
+        background.0 = color;
+    }
+}
+
+/// Makes rain count extra towards the growth of the crops in loaded chunks, and marks their
+/// chunks dirty so the growth is saved.
+fn water_crops(
+    time: Res<Time<Real>>,
+    mut chunk_manager: ResMut<ChunkManager>,
+    mut crops: Query<(&mut Crop, &TilemapId)>,
+    chunks: Query<&ChunkPosition>,
+) {
+    let bonus = time.delta_secs_f64() * RAIN_GROWTH_BONUS;
+    for (mut crop, tilemap_id) in &mut crops {
+        crop.planted_at -= bonus;
+        if let Ok(ChunkPosition(chunk_pos)) = chunks.get(tilemap_id.0) {
+            chunk_manager.dirty_chunks.insert(*chunk_pos);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weather_follows_the_seasons_and_biomes() {
+        // A low roll brings rain, which falls as snow in the cold.
+        assert_eq!(
+            Weather::Clear.next(Season::Spring, Biome::Plains, 0.1),
+            Weather::Rain
+        );
+        assert_eq!(
+            Weather::Clear.next(Season::Winter, Biome::Plains, 0.1),
+            Weather::Snow
+        );
+        assert_eq!(
+            Weather::Clear.next(Season::Summer, Biome::Tundra, 0.1),
+            Weather::Snow
+        );
+        // Summers are drier than springs.
+        assert_eq!(
+            Weather::Clear.next(Season::Summer, Biome::Plains, 0.3),
+            Weather::Clear
+        );
+        assert_eq!(
+            Weather::Clear.next(Season::Spring, Biome::Plains, 0.3),
+            Weather::Rain
+        );
+        // Rain can build into a storm, which always dies down again.
+        assert_eq!(
+            Weather::Rain.next(Season::Autumn, Biome::Forest, 0.1),
+            Weather::Storm
+        );
+        for roll in [0.0, 0.5, 0.99] {
+            assert_ne!(
+                Weather::Storm.next(Season::Autumn, Biome::Forest, roll),
+                Weather::Storm
+            );
+        }
+        // Whatever the weather was, the cold turns it to snow or clears it.
+        for weather in [Weather::Rain, Weather::Storm] {
+            for roll in [0.0, 0.5, 0.99] {
+                let next = weather.next(Season::Winter, Biome::Ocean, roll);
+                assert!(matches!(next, Weather::Snow | Weather::Clear));
+            }
+        }
+    }
+}
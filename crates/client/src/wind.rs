@@ -0,0 +1,229 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::render::render_resource::AsBindGroup;
+use bevy::shader::ShaderRef;
+use bevy_ecs_tilemap::prelude::*;
+use noisy_bevy::simplex_noise_2d_seeded;
+
+use crate::GameState;
+use crate::chunk::{ChunkLayer, TILE_SIZE};
+use crate::clock::Season;
+use crate::farming::CROP_STAGES;
+use crate::tiles::TileKind;
+use crate::weather::Weather;
+use crate::worldgen::WorldSeed;
+
+/// Seconds over which the wind turns around and picks up or dies down.
+const WIND_CHANGE_SECS: f32 = 40.0;
+
+/// How much more often gusts come and go than the wind turns.
+const GUSTINESS: f32 = 6.0;
+
+/// How far the tops of plants lean in the strongest wind, in world units.
+const MAX_SWAY: f32 = 1.5;
+
+/// How far apart gusts roll over the world, in world units.
+const GUST_LENGTH: f32 = 12.0 * TILE_SIZE.x;
+
+const SHADER_PATH: &str = "shaders/vegetation.wgsl";
+
+/// Blows a [`Wind`] over the world that turns and gusts with noise, blowing harder in bad
+/// [`Weather`]. Grass, undergrowth and crops sway with it, through the [`VegetationMaterial`]
+/// every chunk is drawn with, and weather and surface particles drift along with it. The same
+/// material ripples water and laps foam at its shorelines.
+pub struct WindPlugin;
+
+impl Plugin for WindPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialTilemapPlugin::<VegetationMaterial>::default())
+            .init_resource::<Wind>()
+            .add_systems(Startup, add_vegetation_material)
+            .add_systems(
+                Update,
+                (blow_wind, sway_vegetation)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// The wind over the world.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct Wind {
+    /// Where the wind blows towards.
+    pub direction: Vec2,
+    /// From 0 when it is still to 1 in the strongest storm.
+    pub strength: f32,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self {
+            direction: Vec2::X,
+            strength: 0.0,
+        }
+    }
+}
+
+impl Wind {
+    /// The wind `secs` into the game in a world with noise `seed`, blowing as hard as `weather`
+    /// makes it.
+    pub fn at(secs: f32, seed: f32, weather: Weather) -> Self {
+        let time = secs / WIND_CHANGE_SECS;
+        let angle = simplex_noise_2d_seeded(Vec2::new(time, 0.0), seed) * PI;
+        let gust = simplex_noise_2d_seeded(Vec2::new(time * GUSTINESS, 10.0), seed) * 0.5 + 0.5;
+        let calm = match weather {
+            Weather::Clear => 0.25,
+            Weather::Rain => 0.45,
+            Weather::Snow => 0.55,
+            Weather::Storm => 0.85,
+        };
+        Self {
+            direction: Vec2::from_angle(angle),
+            strength: (calm * (0.6 + 0.8 * gust)).clamp(0.0, 1.0),
+        }
+    }
+
+    /// The direction scaled by the strength.
+    pub fn velocity(&self) -> Vec2 {
+        self.direction * self.strength
+    }
+}
+
+/// Draws the tiles of every chunk, bending vegetation in the wind and rippling water, by
+/// `vegetation.wgsl`.
+#[derive(AsBindGroup, Asset, TypePath, Debug, Clone, Default)]
+pub struct VegetationMaterial {
+    /// How far the tops of plants lean in x, in tile widths, and the wave vector of the gusts.
+    #[uniform(0)]
+    wind: Vec4,
+    /// One bit for each texture index in `tiles.png` that sways in the wind.
+    #[uniform(1)]
+    swaying: UVec4,
+    /// One bit for each texture index in `tiles.png` that is water, with foam along the edges
+    /// its [`shoreline_color`](crate::tiles::shoreline_color) marks as land.
+    #[uniform(2)]
+    water: UVec4,
+}
+
+impl MaterialTilemap for VegetationMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+}
+
+/// The [`VegetationMaterial`] all chunks share.
+#[derive(Resource)]
+pub struct Vegetation(pub Handle<VegetationMaterial>);
+
+/// The bits in [`VegetationMaterial::swaying`] of grass, undergrowth and its flowers, and crops,
+/// in every season, frame of their [`animation`](TileKind::animation) and growth stage.
+fn swaying_textures() -> UVec4 {
+    let plants = [
+        (TileKind::Grass, ChunkLayer::Ground),
+        (TileKind::Forest, ChunkLayer::Ground),
+        (TileKind::Forest, ChunkLayer::Decoration),
+    ]
+    .into_iter()
+    .flat_map(|(kind, layer)| Season::ALL.map(move |season| (kind, layer, season)))
+    .flat_map(|(kind, layer, season)| {
+        let frames = kind
+            .animation(layer, season)
+            .map_or(0..0, |animation| animation.start..animation.end);
+        frames.chain(kind.layer_texture_index(layer, season))
+    });
+    let crops = (0..CROP_STAGES).map(|stage| TileKind::Crop.texture_index() + stage);
+    texture_bits(plants.chain(crops))
+}
+
+/// The bits in [`VegetationMaterial::water`] of water in every season.
+fn water_textures() -> UVec4 {
+    texture_bits(
+        Season::ALL
+            .into_iter()
+            .filter_map(|season| TileKind::Water.layer_texture_index(ChunkLayer::Ground, season)),
+    )
+}
+
+/// One bit for each of the texture `indices`, 32 to a component.
+fn texture_bits(indices: impl IntoIterator<Item = u32>) -> UVec4 {
+    let mut bits = UVec4::ZERO;
+    for index in indices {
+        bits[index as usize / 32] |= 1 << (index % 32);
+    }
+    bits
+}
+
+fn add_vegetation_material(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<VegetationMaterial>>,
+) {
+    let material = materials.add(VegetationMaterial {
+        wind: Vec4::ZERO,
+        swaying: swaying_textures(),
+        water: water_textures(),
+    });
+    commands.insert_resource(Vegetation(material));
+}
+
+fn blow_wind(
+    time: Res<Time>,
+    world_seed: Res<WorldSeed>,
+    weather: Res<Weather>,
+    mut wind: ResMut<Wind>,
+) {
+    let seed = (world_seed.seed % 1000) as f32;
+    wind.set_if_neq(Wind::at(time.elapsed_secs(), seed, *weather));
+}
+
+fn sway_vegetation(
+    wind: Res<Wind>,
+    vegetation: Res<Vegetation>,
+    mut materials: ResMut<Assets<VegetationMaterial>>,
+) {
+    let Some(material) = materials.get_mut(&vegetation.0) else {
+        return;
+    };
+    let lean = wind.velocity().x * MAX_SWAY / TILE_SIZE.x;
+    let gusts = wind.direction * 2.0 * PI / GUST_LENGTH;
+    material.wind = Vec4::new(lean, 0.0, gusts.x, gusts.y);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wind_turns_and_blows_harder_in_storms() {
+        let clear = Wind::at(100.0, 3.0, Weather::Clear);
+        let storm = Wind::at(100.0, 3.0, Weather::Storm);
+        assert_eq!(clear.direction, storm.direction);
+        assert!(storm.strength > clear.strength);
+        assert!((0.0..=1.0).contains(&storm.strength));
+        assert!((clear.direction.length() - 1.0).abs() < 1e-4);
+        // The wind changes over time, but smoothly.
+        let later = Wind::at(100.0 + WIND_CHANGE_SECS, 3.0, Weather::Clear);
+        assert_ne!(later, clear);
+        let soon = Wind::at(100.1, 3.0, Weather::Clear);
+        assert!(soon.direction.angle_to(clear.direction).abs() < 0.05);
+
+        let swaying = swaying_textures();
+        let sways = |index: u32| swaying[index as usize / 32] & (1 << (index % 32)) != 0;
+        assert!(sways(TileKind::Grass.texture_index()));
+        assert!(sways(
+            TileKind::Forest.seasonal_texture_index(Season::Winter)
+        ));
+        assert!(sways(TileKind::Crop.texture_index() + CROP_STAGES - 1));
+        let flowers = TileKind::Forest
+            .animation(ChunkLayer::Decoration, Season::Summer)
+            .unwrap();
+        assert!(sways(flowers.end - 1));
+        assert!(!sways(TileKind::Water.texture_index()));
+        assert!(!sways(TileKind::Stone.texture_index()));
+        assert_eq!(
+            water_textures(),
+            texture_bits([TileKind::Water.texture_index()])
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::image::ImageSampler;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_ecs_tilemap::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, EguiTextureHandle, egui};
+use bevy_enhanced_input::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+use crate::chunk::{CHUNK_SIZE, ChunkPosition};
+use crate::console::console_open;
+use crate::localization::Localization;
+use crate::map_export::{aged_color, map_color, map_fade};
+use crate::pause::{PauseState, paused};
+use crate::persistence::WorldSave;
+use crate::player::Player;
+use crate::settings::Settings;
+use crate::tiles::{TileKind, world_pos_to_tile, world_tile_to_chunk};
+use crate::waypoints::Waypoints;
+
+/// Screen points per tile when the map opens.
+const DEFAULT_ZOOM: f32 = 2.0;
+
+const MIN_ZOOM: f32 = 1.0;
+const MAX_ZOOM: f32 = 16.0;
+
+/// How much one notch of the scroll wheel zooms by.
+const ZOOM_STEP: f32 = 1.25;
+
+/// What the map shows where the player has never been.
+const UNEXPLORED_COLOR: [u8; 4] = [20, 18, 28, 255];
+
+/// Press M for a full-screen map of every chunk the player has been near, one pixel per tile.
+/// Areas not seen in a while fade towards sepia like on the exported map, unless
+/// [`Settings::map_aging`] is off. Drag to pan and scroll to zoom, and right-click to place or
+/// remove one of the player's [`Waypoints`]. The player's controls are off while it is open, and
+/// pausing closes it.
+pub struct WorldMapPlugin;
+
+impl Plugin for WorldMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ExploredMap>()
+            .init_resource::<WorldMapView>()
+            .add_systems(OnEnter(GameState::Playing), load_explored_map)
+            .add_systems(OnEnter(PauseState::Paused), close_world_map)
+            .add_systems(OnExit(GameState::Playing), close_world_map)
+            .add_systems(
+                Update,
+                (
+                    explore_tiles,
+                    toggle_world_map
+                        .run_if(not(paused.or(console_open)))
+                        .run_if(resource_exists::<ButtonInput<KeyCode>>),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                world_map_ui
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(world_map_open),
+            );
+    }
+}
+
+/// The tiles of every chunk the player has seen, as they last saw them, indexed like their
+/// [`TileStorage`]. Saved with the world.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ExploredMap {
+    pub chunks: HashMap<IVec2, Vec<TileKind>>,
+}
+
+impl ExploredMap {
+    /// The tile the player last saw at `world_tile`, or `None` if they never have.
+    pub fn tile(&self, world_tile: IVec2) -> Option<TileKind> {
+        let (chunk_pos, tile_pos) = world_tile_to_chunk(world_tile);
+        let tiles = self.chunks.get(&chunk_pos)?;
+        Some(tiles[tile_pos.to_index(&CHUNK_SIZE.into())])
+    }
+
+    /// Records that the tile at `tile_pos` of `chunk_pos` is `kind`. The rest of a chunk seen for
+    /// the first time starts as [`TileKind::default`] until its tiles come in.
+    pub fn record(&mut self, chunk_pos: IVec2, tile_pos: TilePos, kind: TileKind) {
+        let tiles = self
+            .chunks
+            .entry(chunk_pos)
+            .or_insert_with(|| vec![TileKind::default(); (CHUNK_SIZE.x * CHUNK_SIZE.y) as usize]);
+        tiles[tile_pos.to_index(&CHUNK_SIZE.into())] = kind;
+    }
+}
+
+/// Draws the `size` tiles of `map` around `center` as RGBA pixels, one per tile with north up.
+/// Each chunk is faded by [`aged_color`] with the amount `fade` returns for it.
+pub fn render_map_view(
+    map: &ExploredMap,
+    center: IVec2,
+    size: UVec2,
+    fade: impl Fn(IVec2) -> f32,
+) -> Vec<u8> {
+    let top_left = center + IVec2::new(-(size.x as i32) / 2, (size.y as i32) / 2);
+    let mut fades = HashMap::<IVec2, f32>::default();
+    let mut data = Vec::with_capacity((size.x * size.y * 4) as usize);
+    for y in 0..size.y as i32 {
+        for x in 0..size.x as i32 {
+            let world_tile = top_left + IVec2::new(x, -y);
+            let color = map.tile(world_tile).map_or(UNEXPLORED_COLOR, |kind| {
+                let chunk_pos = world_tile_to_chunk(world_tile).0;
+                let fade = *fades.entry(chunk_pos).or_insert_with(|| fade(chunk_pos));
+                aged_color(map_color(kind), fade)
+            });
+            data.extend(color);
+        }
+    }
+    data
+}
+
+/// Where the map is looking and what it last drew there.
+#[derive(Resource)]
+pub struct WorldMapView {
+    open: bool,
+    /// The world tile in the middle of the map, kept fractional so slow drags add up.
+    center: Vec2,
+    /// Screen points per tile.
+    zoom: f32,
+    image: Handle<Image>,
+    /// The center and size in tiles of what is drawn into `image`.
+    drawn: Option<(IVec2, UVec2)>,
+    /// What the next waypoint placed is called.
+    waypoint_name: String,
+}
+
+impl FromWorld for WorldMapView {
+    fn from_world(world: &mut World) -> Self {
+        let mut image = Image::new_fill(
+            Extent3d::default(),
+            TextureDimension::D2,
+            &UNEXPLORED_COLOR,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+        );
+        image.sampler = ImageSampler::nearest();
+        Self {
+            open: false,
+            center: Vec2::ZERO,
+            zoom: DEFAULT_ZOOM,
+            image: world.resource_mut::<Assets<Image>>().add(image),
+            drawn: None,
+            waypoint_name: String::new(),
+        }
+    }
+}
+
+fn load_explored_map(mut commands: Commands, world_save: Option<Res<WorldSave>>) {
+    let explored_map = world_save
+        .map(|world_save| world_save.explored_map.clone())
+        .unwrap_or_default();
+    commands.insert_resource(explored_map);
+}
+
+fn explore_tiles(
+    mut explored_map: ResMut<ExploredMap>,
+    tiles: Query<(&TileKind, &TilePos, &TilemapId), Changed<TileKind>>,
+    chunks: Query<&ChunkPosition>,
+) {
+    for (kind, tile_pos, tilemap_id) in &tiles {
+        if let Ok(ChunkPosition(chunk_pos)) = chunks.get(tilemap_id.0) {
+            explored_map.record(*chunk_pos, *tile_pos, *kind);
+        }
+    }
+}
+
+fn toggle_world_map(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut view: ResMut<WorldMapView>,
+    player: Single<(Entity, &Transform), With<Player>>,
+) {
+    if !keys.just_pressed(KeyCode::KeyM) {
+        return;
+    }
+    let (player, transform) = *player;
+    view.open = !view.open;
+    if view.open {
+        view.center = world_pos_to_tile(transform.translation.xy()).as_vec2();
+        view.drawn = None;
+        commands
+            .entity(player)
+            .insert(ContextActivity::<Player>::INACTIVE);
+    } else {
+        commands
+            .entity(player)
+            .insert(ContextActivity::<Player>::ACTIVE);
+    }
+}
+
+/// Whether the world map is open.
+pub fn world_map_open(view: Res<WorldMapView>) -> bool {
+    view.open
+}
+
+// Resuming or the next world turns the player's controls back on.
+fn close_world_map(mut view: ResMut<WorldMapView>) {
+    view.open = false;
+}
+
+fn world_map_ui(
+    mut contexts: EguiContexts,
+    mut view: ResMut<WorldMapView>,
+    (explored_map, mut waypoints): (Res<ExploredMap>, ResMut<Waypoints>),
+    (world_save, settings): (Option<Res<WorldSave>>, Res<Settings>),
+    (localization, mut images): (Res<Localization>, ResMut<Assets<Image>>),
+    player: Single<&Transform, With<Player>>,
+) -> Result {
+    let texture = contexts.add_image(EguiTextureHandle::Weak(view.image.id()));
+    let ctx = contexts.ctx_mut()?;
+    egui::TopBottomPanel::top("world_map_bar").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            ui.label(localization.get("map-waypoint-name"));
+            ui.text_edit_singleline(&mut view.waypoint_name);
+            ui.label(localization.get("map-hint"));
+        });
+    });
+    egui::CentralPanel::default().show(ctx, |ui| {
+        let (rect, response) =
+            ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+        let drag = response.drag_delta();
+        let pan = Vec2::new(-drag.x, drag.y) / view.zoom;
+        view.center += pan;
+        if response.hovered() {
+            let scroll = ui.input(|input| input.raw_scroll_delta.y);
+            if scroll != 0.0 {
+                view.zoom = (view.zoom * ZOOM_STEP.powf(scroll.signum())).clamp(MIN_ZOOM, MAX_ZOOM);
+            }
+        }
+
+        let center = view.center.floor().as_ivec2();
+        let size = UVec2::new(
+            (rect.width() / view.zoom).ceil() as u32,
+            (rect.height() / view.zoom).ceil() as u32,
+        )
+        .max(UVec2::ONE);
+        if explored_map.is_changed() || settings.is_changed() || view.drawn != Some((center, size))
+        {
+            if let Some(image) = images.get_mut(&view.image) {
+                image.resize(Extent3d {
+                    width: size.x,
+                    height: size.y,
+                    depth_or_array_layers: 1,
+                });
+                let fade = |chunk_pos| match &world_save {
+                    Some(world_save) if settings.map_aging => {
+                        map_fade(world_save.explored_age(chunk_pos))
+                    }
+                    _ => 0.0,
+                };
+                image.data = Some(render_map_view(&explored_map, center, size, fade));
+            }
+            view.drawn = Some((center, size));
+        }
+
+        // The drawn tiles overhang the panel by up to a tile, so line their center up with it.
+        let image_size = size.as_vec2() * view.zoom;
+        let image_rect =
+            egui::Rect::from_center_size(rect.center(), egui::vec2(image_size.x, image_size.y));
+        let top_left = center + IVec2::new(-(size.x as i32) / 2, (size.y as i32) / 2);
+        let zoom = view.zoom;
+        let to_screen = |world_tile: IVec2| {
+            let pixel = Vec2::new(
+                (world_tile.x - top_left.x) as f32,
+                (top_left.y - world_tile.y) as f32,
+            ) + 0.5;
+            image_rect.min + egui::vec2(pixel.x, pixel.y) * zoom
+        };
+
+        if response.secondary_clicked()
+            && let Some(pos) = response.interact_pointer_pos()
+        {
+            let pixel = ((pos - image_rect.min) / zoom).floor();
+            let world_tile = top_left + IVec2::new(pixel.x as i32, -(pixel.y as i32));
+            // Within a few screen points of a waypoint counts as clicking it.
+            if !waypoints.remove_placed_near(world_tile, (6.0 / zoom).max(1.0)) {
+                let name = match view.waypoint_name.trim() {
+                    "" => localization.format(
+                        "map-waypoint-default",
+                        &[("number", &(waypoints.waypoints.len() + 1))],
+                    ),
+                    name => name.to_string(),
+                };
+                waypoints.place(&name, world_tile);
+            }
+        }
+
+        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        let painter = ui.painter_at(rect);
+        painter.image(texture, image_rect, uv, egui::Color32::WHITE);
+        for waypoint in &waypoints.waypoints {
+            let pos = to_screen(waypoint.world_tile);
+            painter.circle(
+                pos,
+                4.0,
+                waypoint.color(),
+                egui::Stroke::new(1.0, egui::Color32::BLACK),
+            );
+            painter.text(
+                pos - egui::vec2(0.0, 6.0),
+                egui::Align2::CENTER_BOTTOM,
+                &waypoint.name,
+                egui::FontId::proportional(12.0),
+                waypoint.color(),
+            );
+        }
+        painter.circle(
+            to_screen(world_pos_to_tile(player.translation.xy())),
+            (zoom / 2.0).max(3.0),
+            egui::Color32::WHITE,
+            egui::Stroke::new(1.0, egui::Color32::BLACK),
+        );
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_view_is_north_up_around_its_center() {
+        let mut map = ExploredMap::default();
+        // The tile just north-west of the center, in the chunk west of the origin.
+        map.record(IVec2::new(-1, 0), TilePos::new(9, 1), TileKind::Water);
+        assert_eq!(map.tile(IVec2::new(-1, 1)), Some(TileKind::Water));
+        assert_eq!(map.tile(IVec2::ZERO), None);
+
+        let data = render_map_view(&map, IVec2::ZERO, UVec2::new(2, 2), |_| 0.0);
+        let pixels: Vec<&[u8]> = data.chunks_exact(4).collect();
+        assert_eq!(
+            pixels,
+            vec![
+                &map_color(TileKind::Water)[..],
+                &UNEXPLORED_COLOR[..],
+                &map_color(TileKind::default())[..],
+                &UNEXPLORED_COLOR[..],
+            ]
+        );
+
+        // Only the seen tiles age, by how long ago their chunk was seen.
+        let data = render_map_view(&map, IVec2::ZERO, UVec2::new(2, 2), |chunk_pos| {
+            if chunk_pos == IVec2::new(-1, 0) {
+                1.0
+            } else {
+                0.0
+            }
+        });
+        let pixels: Vec<&[u8]> = data.chunks_exact(4).collect();
+        assert_eq!(pixels[0], &aged_color(map_color(TileKind::Water), 1.0)[..]);
+        assert_ne!(pixels[0], &map_color(TileKind::Water)[..]);
+        assert_eq!(pixels[1], &UNEXPLORED_COLOR[..]);
+    }
+}
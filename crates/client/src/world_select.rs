@@ -0,0 +1,282 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use bevy_rand::prelude::*;
+use rand::RngCore;
+
+use crate::GameState;
+use crate::clock::GameClock;
+use crate::difficulty::Difficulty;
+use crate::health::PLAYER_MAX_HEALTH;
+use crate::localization::Localization;
+use crate::noise::NoiseBackend;
+use crate::paths::AppPaths;
+use crate::persistence::{WorldSave, list_worlds};
+use crate::temperature::SurvivalMode;
+use crate::worldgen::{WorldSeed, WorldgenPreset};
+
+/// Lists the saved worlds to open one, or lets the player create a new one, before entering
+/// [`GameState::Playing`]. The main menu picks which with [`WorldSelectPage`].
+pub struct WorldSelectPlugin;
+
+impl Plugin for WorldSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldSelect>()
+            .init_resource::<WorldSelectPage>()
+            .add_systems(OnEnter(GameState::WorldSelect), refresh_worlds)
+            .add_systems(
+                EguiPrimaryContextPass,
+                world_select_ui.run_if(in_state(GameState::WorldSelect)),
+            );
+    }
+}
+
+/// What the world select screen shows.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WorldSelectPage {
+    /// The saved worlds, to open one.
+    #[default]
+    Load,
+    /// The options for a new world.
+    New,
+}
+
+#[derive(Resource, Default)]
+struct WorldSelect {
+    worlds: Vec<WorldSave>,
+    new_world_name: String,
+    /// Seed for the new world; left empty for a random one.
+    new_world_seed: String,
+    new_world_preset: WorldgenPreset,
+    new_world_difficulty: Difficulty,
+    new_world_survival: bool,
+    error: Option<String>,
+}
+
+fn refresh_worlds(mut world_select: ResMut<WorldSelect>, paths: Res<AppPaths>) {
+    world_select.worlds = list_worlds(&paths.saves_dir());
+}
+
+fn world_select_ui(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    localization: Res<Localization>,
+    (mut world_select, page, paths): (ResMut<WorldSelect>, Res<WorldSelectPage>, Res<AppPaths>),
+    (mut world_seed, mut preset, mut difficulty, mut survival): (
+        ResMut<WorldSeed>,
+        ResMut<WorldgenPreset>,
+        ResMut<Difficulty>,
+        ResMut<SurvivalMode>,
+    ),
+    mut global_rng: Single<&mut WyRand, With<GlobalRng>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) -> Result {
+    let mut selected = None;
+    let mut create = false;
+    let mut back = false;
+
+    let title = match *page {
+        WorldSelectPage::Load => "worlds-title",
+        WorldSelectPage::New => "worlds-new-title",
+    };
+    egui::Window::new(localization.get(title))
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            if *page == WorldSelectPage::New {
+                create = new_world_ui(ui, &localization, &mut world_select);
+            } else {
+                selected = saved_worlds_ui(ui, &localization, &world_select.worlds);
+            }
+            if let Some(error) = &world_select.error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            back = ui.button(localization.get("worlds-back")).clicked();
+        });
+
+    let world_save = if let Some(index) = selected {
+        Some(world_select.worlds.swap_remove(index))
+    } else if create {
+        let seed = match world_select.new_world_seed.trim() {
+            "" => Ok(global_rng.next_u64()),
+            seed => seed.parse::<u64>(),
+        };
+        match seed {
+            Ok(seed) => {
+                let name = world_select.new_world_name.trim();
+                let new_preset = world_select.new_world_preset;
+                let new_difficulty = world_select.new_world_difficulty;
+                let new_survival = SurvivalMode(world_select.new_world_survival);
+                match WorldSave::create(
+                    &paths.saves_dir(),
+                    name,
+                    seed,
+                    new_preset,
+                    new_difficulty,
+                    new_survival,
+                ) {
+                    Ok(world_save) => Some(world_save),
+                    Err(err) => {
+                        world_select.error =
+                            Some(localization.format("worlds-create-failed", &[("error", &err)]));
+                        None
+                    }
+                }
+            }
+            Err(_) => {
+                world_select.error = Some(localization.get("worlds-bad-seed").to_string());
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    if let Some(world_save) = world_save {
+        info!("Opening world {:?}", world_save.dir);
+        world_seed.seed = world_save.metadata.seed;
+        *preset = world_save.metadata.preset;
+        *difficulty = world_save.metadata.difficulty;
+        *survival = world_save.metadata.survival;
+        commands.insert_resource(world_save);
+        world_select.error = None;
+        next_state.set(GameState::Playing);
+    } else if back {
+        world_select.error = None;
+        next_state.set(GameState::MainMenu);
+    }
+    Ok(())
+}
+
+/// The saved worlds, returning the index of the one the player picked to open.
+fn saved_worlds_ui(
+    ui: &mut egui::Ui,
+    localization: &Localization,
+    worlds: &[WorldSave],
+) -> Option<usize> {
+    let mut selected = None;
+    if worlds.is_empty() {
+        ui.label(localization.get("worlds-none"));
+    }
+    for (index, world) in worlds.iter().enumerate() {
+        let metadata = &world.metadata;
+        ui.horizontal(|ui| {
+            ui.vertical(|ui| {
+                ui.strong(&metadata.name);
+                let minutes = (metadata.playtime_secs / 60.0) as u64;
+                let playtime = localization.format(
+                    "worlds-playtime",
+                    &[("hours", &(minutes / 60)), ("minutes", &(minutes % 60))],
+                );
+                ui.label(localization.format(
+                    "worlds-details",
+                    &[
+                        ("seed", &metadata.seed),
+                        ("difficulty", &format!("{:?}", metadata.difficulty)),
+                        ("survival", &if metadata.survival.0 { "yes" } else { "no" }),
+                        ("created", &format_date(metadata.created)),
+                        ("playtime", &playtime),
+                    ],
+                ));
+                if let Some(days) = metadata.clock_days {
+                    ui.label(GameClock { days, ..default() }.date());
+                }
+                if let Some(health) = metadata.player_health {
+                    ui.label(localization.format(
+                        "worlds-health",
+                        &[
+                            ("health", &format!("{health:.0}")),
+                            ("max", &format!("{PLAYER_MAX_HEALTH:.0}")),
+                        ],
+                    ));
+                }
+            });
+            if ui.button(localization.get("worlds-play")).clicked() {
+                selected = Some(index);
+            }
+        });
+        ui.separator();
+    }
+    selected
+}
+
+/// The options for a new world, returning whether the player asked to create it.
+fn new_world_ui(
+    ui: &mut egui::Ui,
+    localization: &Localization,
+    world_select: &mut WorldSelect,
+) -> bool {
+    ui.horizontal(|ui| {
+        ui.label(localization.get("worlds-name"));
+        ui.text_edit_singleline(&mut world_select.new_world_name);
+    });
+    ui.horizontal(|ui| {
+        ui.label(localization.get("worlds-seed"));
+        ui.text_edit_singleline(&mut world_select.new_world_seed);
+    });
+    egui::ComboBox::from_label(localization.get("worlds-noise"))
+        .selected_text(format!("{:?}", world_select.new_world_preset.noise))
+        .show_ui(ui, |ui| {
+            for backend in NoiseBackend::ALL {
+                ui.selectable_value(
+                    &mut world_select.new_world_preset.noise,
+                    backend,
+                    format!("{backend:?}"),
+                );
+            }
+        });
+    egui::ComboBox::from_label(localization.get("worlds-difficulty"))
+        .selected_text(format!("{:?}", world_select.new_world_difficulty))
+        .show_ui(ui, |ui| {
+            for option in Difficulty::ALL {
+                ui.selectable_value(
+                    &mut world_select.new_world_difficulty,
+                    option,
+                    format!("{option:?}"),
+                );
+            }
+        });
+    ui.checkbox(
+        &mut world_select.new_world_survival,
+        localization.get("worlds-survival-mode"),
+    )
+    .on_hover_text(localization.get("worlds-survival-hint"));
+    let can_create = !world_select.new_world_name.trim().is_empty();
+    ui.add_enabled(
+        can_create,
+        egui::Button::new(localization.get("worlds-create")),
+    )
+    .clicked()
+}
+
+/// Formats seconds since the Unix epoch as a `YYYY-MM-DD` UTC date.
+pub fn format_date(unix_secs: u64) -> String {
+    // Civil-from-days conversion from Howard Hinnant's date algorithms.
+    let days = (unix_secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_791_936_000), "2026-10-14");
+    }
+}
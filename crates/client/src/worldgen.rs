@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::chunk::CHUNK_SIZE;
+use crate::noise::{NoiseBackend, NoiseSource};
+use crate::tiles::TileKind;
+
+#[derive(Default, Resource)]
+pub struct WorldSeed {
+    pub seed: u64,
+}
+
+/// Generation settings picked when a world is created and stored with it.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct WorldgenPreset {
+    pub noise: NoiseBackend,
+}
+
+/// Maps a world seed to the seed passed to the noise functions.
+pub fn noise_seed(seed: u64) -> f32 {
+    (seed % 10000) as f32 / 10000.0
+}
+
+// Stable FBM helper
+fn fbm_safe(
+    noise: &dyn NoiseSource,
+    pos: Vec2,
+    octaves: usize,
+    lacunarity: f32,
+    gain: f32,
+    seed: u64,
+) -> f32 {
+    let scaled_pos = pos / 10.0;
+    let seed_f = noise_seed(seed);
+    let mut sum = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+
+    for _ in 0..octaves {
+        let value = noise.sample(scaled_pos * frequency, seed_f);
+        sum += value * amplitude;
+        amplitude *= gain;
+        frequency *= lacunarity;
+    }
+
+    sum.clamp(-1.0, 1.0)
+}
+
+pub fn get_tile_type(world_x: i32, world_y: i32, seed: u64, preset: &WorldgenPreset) -> TileKind {
+    let noise = preset.noise.source();
+    let scale = 0.08;
+    let pos = Vec2::new(world_x as f32 * scale, world_y as f32 * scale);
+
+    let terrain = fbm_safe(noise, pos, 4, 2.0, 0.5, seed);
+    let moisture = fbm_safe(noise, pos + Vec2::splat(100.0), 3, 2.0, 0.5, seed + 1000);
+
+    if terrain < -0.25 {
+        TileKind::Water
+    } else if terrain < 0.0 {
+        if moisture > 0.3 {
+            TileKind::Grass
+        } else {
+            TileKind::Forest
+        }
+    } else if terrain < 0.3 {
+        if moisture > 0.1 {
+            TileKind::Grass
+        } else {
+            TileKind::Forest
+        }
+    } else if terrain < 0.55 {
+        if moisture < -0.2 {
+            TileKind::Gravel
+        } else {
+            TileKind::Stone
+        }
+    } else {
+        TileKind::Snow
+    }
+}
+
+/// Coarse region type used for content that spans many chunks, such as music and props.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Biome {
+    Ocean,
+    Plains,
+    Forest,
+    Mountains,
+    Tundra,
+}
+
+impl From<TileKind> for Biome {
+    fn from(kind: TileKind) -> Self {
+        match kind {
+            TileKind::Water => Biome::Ocean,
+            // Villages are only built on plains.
+            TileKind::Grass
+            | TileKind::Chest
+            | TileKind::Farmland
+            | TileKind::Crop
+            | TileKind::HouseWall
+            | TileKind::HouseFloor
+            | TileKind::HouseWindow
+            | TileKind::Torch
+            | TileKind::Campfire => Biome::Plains,
+            TileKind::Forest => Biome::Forest,
+            // Dungeons are only built into mountains.
+            TileKind::Stone
+            | TileKind::Gravel
+            | TileKind::DungeonWall
+            | TileKind::DungeonFloor
+            | TileKind::Door
+            | TileKind::SecretWall
+            | TileKind::PressurePlate => Biome::Mountains,
+            TileKind::Snow => Biome::Tundra,
+        }
+    }
+}
+
+/// A hash of `chunk_pos` and the world seed, for picking the chunks a structure is built into.
+/// Structures are told apart by their `salt`, so they don't all pick the same chunks.
+pub fn chunk_hash(chunk_pos: IVec2, seed: u64, salt: u32) -> u32 {
+    let seed = (seed ^ (seed >> 32)) as u32;
+    let mut h = (chunk_pos.x as u32).wrapping_mul(0x27d4_eb2d)
+        ^ (chunk_pos.y as u32).wrapping_mul(0x1656_67b1)
+        ^ seed.wrapping_mul(0x9e37_79b9)
+        ^ salt;
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h
+}
+
+/// The biome of a chunk, taken from the terrain at its center. Cheap enough to sample chunks
+/// far beyond the loaded area.
+pub fn biome_at_chunk(chunk_pos: IVec2, seed: u64, preset: &WorldgenPreset) -> Biome {
+    let center = chunk_pos * CHUNK_SIZE.as_ivec2() + (CHUNK_SIZE / 2).as_ivec2();
+    get_tile_type(center.x, center.y, seed, preset).into()
+}
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystems;
+
+use crate::GameState;
+use crate::chunk::{ChunkLayer, TILE_SIZE};
+use crate::clock::Season;
+use crate::tiles::{WorldTiles, tile_to_world_pos, world_pos_to_tile};
+
+/// How far in from the sides of a [`YSort`] entity its feet are, in world units, so an entity
+/// lined up with a column of tiles doesn't reach into the next one.
+const FEET_INSET: f32 = 1.0;
+
+/// Draws [`YSort`] entities behind the [`ChunkLayer::Object`] tiles just below them, so they walk
+/// behind chests, torches and campfires and in front of them from below. Only the
+/// [`GlobalTransform`] is moved back, like [`PixelSnapPlugin`](crate::pixel_snap::PixelSnapPlugin)
+/// does, so the entity's own `z` is kept for everything else.
+pub struct YSortPlugin;
+
+impl Plugin for YSortPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            sort_behind_objects
+                .after(TransformSystems::Propagate)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Marks a one tile tall entity that is drawn behind object tiles it stands above.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct YSort;
+
+/// The `z` of entities drawn behind objects, over the decoration layer but under the objects.
+fn behind_objects_z() -> f32 {
+    (ChunkLayer::Decoration.z() + ChunkLayer::Object.z()) / 2.0
+}
+
+fn sort_behind_objects(
+    season: Res<Season>,
+    tiles: WorldTiles,
+    mut sorted: Query<&mut GlobalTransform, With<YSort>>,
+) {
+    for mut transform in &mut sorted {
+        let pos = transform.translation().xy();
+        let half_width = TILE_SIZE.x / 2.0 - FEET_INSET;
+        let behind = [-half_width, half_width].into_iter().any(|side| {
+            let below = world_pos_to_tile(pos + Vec2::new(side, -TILE_SIZE.y / 2.0));
+            tile_to_world_pos(below).y < pos.y
+                && tiles.get_tile(below).is_some_and(|kind| {
+                    kind.layer_texture_index(ChunkLayer::Object, *season)
+                        .is_some()
+                })
+        });
+        if behind {
+            let mut affine = transform.affine();
+            affine.translation.z = behind_objects_z();
+            *transform = GlobalTransform::from(affine);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs_tilemap::prelude::*;
+
+    use super::*;
+    use crate::chunk::{CHUNK_SIZE, ChunkManager, ChunkMarker};
+    use crate::tiles::TileKind;
+
+    #[test]
+    fn entities_walk_behind_objects_below_them() {
+        let mut app = App::new();
+        app.init_resource::<ChunkManager>()
+            .init_resource::<Season>()
+            .add_systems(Update, sort_behind_objects);
+        let world = app.world_mut();
+        let mut storage = TileStorage::empty(CHUNK_SIZE.into());
+        for x in 0..CHUNK_SIZE.x {
+            for y in 0..CHUNK_SIZE.y {
+                let kind = if (x, y) == (4, 4) {
+                    TileKind::Campfire
+                } else {
+                    TileKind::Grass
+                };
+                storage.set(&TilePos::new(x, y), world.spawn(kind).id());
+            }
+        }
+        let chunk = world.spawn((ChunkMarker, storage)).id();
+        world
+            .resource_mut::<ChunkManager>()
+            .spawned_chunks
+            .insert(IVec2::ZERO, chunk);
+
+        let campfire = tile_to_world_pos(IVec2::new(4, 4));
+        let spawn = |world: &mut World, offset: Vec2| {
+            let pos = (campfire + offset).extend(1.0);
+            world
+                .spawn((YSort, GlobalTransform::from_translation(pos)))
+                .id()
+        };
+        let above = spawn(world, Vec2::new(5.0, 10.0));
+        let below = spawn(world, Vec2::new(0.0, -10.0));
+        let on_top = spawn(world, Vec2::ZERO);
+        let beside = spawn(world, Vec2::new(TILE_SIZE.x, 10.0));
+        app.update();
+
+        let z = |entity| {
+            app.world()
+                .get::<GlobalTransform>(entity)
+                .unwrap()
+                .translation()
+                .z
+        };
+        assert_eq!(z(above), behind_objects_z());
+        assert!(behind_objects_z() < ChunkLayer::Object.z());
+        for entity in [below, on_top, beside] {
+            assert_eq!(z(entity), 1.0);
+        }
+    }
+}
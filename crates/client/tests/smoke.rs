@@ -0,0 +1,255 @@
+//! Plays a world headlessly for several sessions of scripted input, saving and reloading between
+//! them, and checks that the world stays consistent.
+// GPU worldgen needs the render app, which a headless test doesn't have.
+#![cfg(not(feature = "gpu_worldgen"))]
+
+use std::time::Duration;
+
+use bevy::ecs::system::RunSystemOnce;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy_enhanced_input::prelude::*;
+use moonlit_client::autosave::{AUTOSAVE_INTERVAL, AutosavePlugin};
+use moonlit_client::biome_assets::{BiomeAssetGroup, BiomeAssets};
+use moonlit_client::chunk::{
+    CHUNK_RENDER_DISTANCE, CHUNK_SIZE, ChunkManager, ChunkMarker, ChunkPlugin, generate_chunk,
+};
+use moonlit_client::clock::{GameClock, Season};
+use moonlit_client::difficulty::Difficulty;
+use moonlit_client::haptics::HapticsPlugin;
+use moonlit_client::health::{Damage, Health, HealthPlugin, PLAYER_MAX_HEALTH};
+use moonlit_client::localization::Localization;
+use moonlit_client::notifications::Notifications;
+use moonlit_client::persistence::{PersistencePlugin, WorldSave};
+use moonlit_client::player::{Player, PlayerMovement, PlayerPlugin};
+use moonlit_client::quests::QuestLog;
+use moonlit_client::settings::Settings;
+use moonlit_client::temperature::SurvivalMode;
+use moonlit_client::tiles::{TileKind, WorldTiles, world_pos_to_tile, world_tile_to_chunk};
+use moonlit_client::waypoints::Waypoints;
+use moonlit_client::wind::Vegetation;
+use moonlit_client::world_map::ExploredMap;
+use moonlit_client::worldgen::{Biome, WorldSeed, WorldgenPreset};
+use moonlit_client::{GameAssets, GameState};
+
+const SEED: u64 = 1234;
+
+/// Each simulated day is one play session rather than a turn of the [`GameClock`]: walk a loop,
+/// autosave halfway, edit tiles and quit.
+const DAYS: u32 = 5;
+
+const STEP: Duration = Duration::from_millis(50);
+
+/// Updates spent walking in each direction of the loop.
+const LEG_UPDATES: u32 = 60;
+
+/// Updates after letting go of the input, long enough to stop on any surface.
+const SETTLE_UPDATES: u32 = 20;
+
+fn game(world_save: WorldSave) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin, EnhancedInputPlugin))
+        .add_plugins((
+            ChunkPlugin,
+            PlayerPlugin,
+            PersistencePlugin,
+            AutosavePlugin,
+            HealthPlugin,
+            HapticsPlugin,
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(STEP))
+        .insert_resource(Settings::default())
+        .insert_resource(GameAssets {
+            player: Handle::default(),
+            player_layout: Handle::default(),
+        })
+        .insert_resource(biome_assets())
+        .insert_resource(Vegetation(Handle::default()))
+        .insert_resource(WorldSeed {
+            seed: world_save.metadata.seed,
+        })
+        .insert_resource(world_save.metadata.preset)
+        .insert_resource(world_save.metadata.difficulty)
+        .init_resource::<GameClock>()
+        .init_resource::<Season>()
+        .init_resource::<QuestLog>()
+        .init_resource::<ExploredMap>()
+        .init_resource::<Waypoints>()
+        .init_resource::<Notifications>()
+        .init_resource::<Localization>()
+        .insert_resource(world_save)
+        .insert_state(GameState::Playing);
+    app
+}
+
+/// Without an asset server to preload them, every biome gets a group of placeholder handles.
+fn biome_assets() -> BiomeAssets {
+    let mut biome_assets = BiomeAssets::default();
+    for biome in [
+        Biome::Ocean,
+        Biome::Plains,
+        Biome::Forest,
+        Biome::Mountains,
+        Biome::Tundra,
+    ] {
+        biome_assets
+            .groups
+            .insert(biome, BiomeAssetGroup::default());
+    }
+    biome_assets
+}
+
+fn player_entity(app: &mut App) -> Entity {
+    let world = app.world_mut();
+    let mut players = world.query_filtered::<Entity, With<Player>>();
+    players.single(world).unwrap()
+}
+
+fn player_pos(app: &mut App) -> Vec2 {
+    let world = app.world_mut();
+    let mut players = world.query_filtered::<&Transform, With<Player>>();
+    players.single(world).unwrap().translation.xy()
+}
+
+fn walk(app: &mut App, direction: Vec2) {
+    let world = app.world_mut();
+    let mut actions = world.query_filtered::<Entity, With<Action<PlayerMovement>>>();
+    let action = actions.single(world).unwrap();
+    world.entity_mut(action).insert(ActionMock::new(
+        ActionState::Fired,
+        direction,
+        MockSpan::Manual,
+    ));
+    for _ in 0..LEG_UPDATES {
+        app.update();
+        assert_chunks_follow_player(app);
+    }
+    app.world_mut().entity_mut(action).remove::<ActionMock>();
+    // Let the player slide to a stop.
+    for _ in 0..SETTLE_UPDATES {
+        app.update();
+    }
+}
+
+/// Exactly the chunks within render distance of the player are loaded, once each.
+fn assert_chunks_follow_player(app: &mut App) {
+    let player_chunk = world_tile_to_chunk(world_pos_to_tile(player_pos(app))).0;
+    let world = app.world_mut();
+    let chunk_count = world
+        .query_filtered::<(), With<ChunkMarker>>()
+        .iter(world)
+        .count();
+    let chunk_manager = world.resource::<ChunkManager>();
+    let loaded_area = (CHUNK_RENDER_DISTANCE * 2 + UVec2::ONE).element_product() as usize;
+    assert_eq!(chunk_manager.spawned_chunks.len(), loaded_area);
+    assert_eq!(chunk_count, loaded_area);
+    for (chunk_pos, entity) in &chunk_manager.spawned_chunks {
+        // The streaming code rounds positions to chunks without the half-tile offset, so allow
+        // one extra chunk of slack.
+        let offset = (*chunk_pos - player_chunk).abs().as_uvec2();
+        assert!(
+            offset.cmple(CHUNK_RENDER_DISTANCE + UVec2::ONE).all(),
+            "chunk {chunk_pos} is loaded while the player is in {player_chunk}"
+        );
+        assert!(world.get_entity(*entity).is_ok());
+    }
+}
+
+/// The kind of a tile as it is stored on disk, whether or not it was ever edited.
+fn saved_tile(world_save: &mut WorldSave, world_tile: IVec2) -> TileKind {
+    let (chunk_pos, tile_pos) = world_tile_to_chunk(world_tile);
+    let chunk = world_save.load_chunk(chunk_pos).unwrap_or_else(|| {
+        let metadata = &world_save.metadata;
+        generate_chunk(metadata.seed, &metadata.preset, chunk_pos)
+    });
+    chunk.tiles[(tile_pos.y * CHUNK_SIZE.x + tile_pos.x) as usize]
+}
+
+#[test]
+fn worlds_stay_consistent_across_days_of_play() {
+    let saves_dir = std::env::temp_dir().join(format!("moonlit-smoke-{}", std::process::id()));
+    let mut world_save = WorldSave::create(
+        &saves_dir,
+        "Smoke test",
+        SEED,
+        WorldgenPreset::default(),
+        Difficulty::default(),
+        SurvivalMode::default(),
+    )
+    .unwrap();
+    let dir = world_save.dir.clone();
+    let mut edits = HashMap::<IVec2, TileKind>::default();
+    let mut last_playtime = 0.0;
+
+    for day in 0..DAYS {
+        let saved_pos = world_save.metadata.player_pos;
+        let saved_health = world_save.metadata.player_health;
+        assert!(world_save.metadata.playtime_secs >= last_playtime);
+        last_playtime = world_save.metadata.playtime_secs;
+
+        let mut app = game(world_save);
+        app.update();
+        assert_eq!(player_pos(&mut app), saved_pos);
+        let player = player_entity(&mut app);
+        assert_eq!(
+            Some(app.world().get::<Health>(player).unwrap().current),
+            saved_health.or(Some(PLAYER_MAX_HEALTH))
+        );
+
+        walk(&mut app, Vec2::X);
+        walk(&mut app, Vec2::Y);
+        // Jump far enough ahead for the autosave timer to fire.
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(AUTOSAVE_INTERVAL));
+        app.update();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(STEP));
+        walk(&mut app, Vec2::NEG_X);
+        walk(&mut app, Vec2::NEG_Y);
+
+        // Place a block and break another one next to wherever the player ended up.
+        let player_tile = world_pos_to_tile(player_pos(&mut app));
+        let placed = player_tile + IVec2::new(2, 1);
+        let broken = player_tile + IVec2::new(-2, -1);
+        app.world_mut()
+            .run_system_once(move |mut tiles: WorldTiles| {
+                assert!(tiles.set_tile(placed, TileKind::Stone));
+                assert!(tiles.set_tile(broken, TileKind::Grass));
+            })
+            .unwrap();
+        edits.insert(placed, TileKind::Stone);
+        edits.insert(broken, TileKind::Grass);
+        app.world_mut().write_message(Damage {
+            target: player,
+            amount: 1.0,
+            knockback: Vec2::ZERO,
+        });
+        app.update();
+
+        let exit_pos = player_pos(&mut app);
+        app.world_mut().write_message(AppExit::Success);
+        app.update();
+        drop(app.world_mut().remove_resource::<WorldSave>());
+        drop(app);
+
+        world_save = WorldSave::open(dir.clone()).unwrap();
+        assert_eq!(world_save.metadata.player_pos, exit_pos);
+        assert_eq!(
+            world_save.metadata.player_health,
+            Some(PLAYER_MAX_HEALTH - (day + 1) as f32)
+        );
+        for (&world_tile, &kind) in &edits {
+            assert_eq!(
+                saved_tile(&mut world_save, world_tile),
+                kind,
+                "edit at {world_tile} was lost after day {day}"
+            );
+        }
+    }
+
+    // Playtime follows real time, so it includes each day's skip to the autosave.
+    let min_playtime = DAYS as f64 * AUTOSAVE_INTERVAL.as_secs_f64();
+    assert!(world_save.metadata.playtime_secs >= min_playtime);
+    drop(world_save);
+    std::fs::remove_dir_all(saves_dir).unwrap();
+}